@@ -0,0 +1,295 @@
+use std::{
+	collections::{HashMap, HashSet},
+	mem,
+	path::Path,
+	sync::{Arc, Mutex},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use figment::value::magic::RelativePathBuf;
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use tokio::{select, time};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::Validator;
+
+const HOUR_SECONDS: u64 = 60 * 60;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+	/// Whether per-key usage should be recorded.
+	#[serde(default)]
+	enabled: bool,
+
+	/// Path to the SQLite database usage is rolled up into.
+	#[serde(default = "default_database")]
+	database: RelativePathBuf,
+
+	/// Interval, in seconds, between writes of recorded usage to the database.
+	#[serde(default = "default_interval")]
+	interval: u64,
+
+	/// API keys to record usage for. Requests made with other keys are not
+	/// recorded, and their usage can't be queried.
+	#[serde(default)]
+	keys: Vec<String>,
+}
+
+fn default_database() -> RelativePathBuf {
+	RelativePathBuf::from("analytics.sqlite")
+}
+
+fn default_interval() -> u64 {
+	60
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			database: default_database(),
+			interval: default_interval(),
+			keys: vec![],
+		}
+	}
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		if !self.enabled {
+			return;
+		}
+
+		validator.check("interval", self.interval > 0, "must be at least 1");
+		validator.check(
+			"keys",
+			!self.keys.is_empty(),
+			"at least one key is required",
+		);
+		let database = self.database.relative();
+		if let Some(parent) = database.parent() {
+			validator.writable_directory("database", parent);
+		}
+	}
+}
+
+/// Request counts for a key, within a single hour.
+#[derive(Debug)]
+pub struct Usage {
+	/// Start of the hour, in seconds since the unix epoch.
+	pub hour: u64,
+	pub route: String,
+	pub sheet: Option<String>,
+	pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct UsageKey {
+	key: String,
+	hour: u64,
+	route: String,
+	// Stored as an empty string when absent, as NULLs are never equal in SQLite
+	// unique constraints.
+	sheet: String,
+}
+
+/// Records per-key API usage, rolled up hourly.
+pub struct Analytics {
+	store: Option<Arc<Store>>,
+	keys: HashSet<String>,
+	interval: u64,
+}
+
+impl Analytics {
+	pub fn new(config: Config) -> Result<Self> {
+		let store = match config.enabled {
+			false => None,
+			true => Some(Arc::new(Store {
+				connection: Mutex::new(open(&config.database.relative())?),
+				pending: Default::default(),
+			})),
+		};
+
+		Ok(Self {
+			store,
+			keys: config.keys.into_iter().collect(),
+			interval: config.interval,
+		})
+	}
+
+	pub fn enabled(&self) -> bool {
+		self.store.is_some()
+	}
+
+	/// Whether usage is recorded for the given key.
+	pub fn tracks(&self, key: &str) -> bool {
+		self.keys.contains(key)
+	}
+
+	/// Record a request made with the given key. Requests are counted in memory,
+	/// and written to the database periodically. Requests made with keys that
+	/// aren't tracked are ignored.
+	pub fn record(&self, key: &str, route: &str, sheet: Option<&str>) {
+		let Some(store) = &self.store else {
+			return;
+		};
+		if !self.tracks(key) {
+			return;
+		}
+
+		let usage_key = UsageKey {
+			key: key.to_string(),
+			hour: current_hour(),
+			route: route.to_string(),
+			sheet: sheet.unwrap_or_default().to_string(),
+		};
+
+		*store
+			.pending
+			.lock()
+			.expect("poisoned")
+			.entry(usage_key)
+			.or_default() += 1;
+	}
+
+	/// Get the usage recorded for a key, for hours starting within the given range.
+	pub fn usage(&self, key: &str, since: u64, until: u64) -> Result<Vec<Usage>> {
+		let Some(store) = &self.store else {
+			return Ok(vec![]);
+		};
+
+		// Write out anything pending, so the response includes recent requests.
+		store.flush()?;
+
+		let connection = store.connection.lock().expect("poisoned");
+		let mut statement = connection.prepare_cached(
+			"SELECT hour, route, sheet, count FROM usage
+			WHERE key = ?1 AND hour >= ?2 AND hour < ?3
+			ORDER BY hour, route, sheet",
+		)?;
+
+		let usage = statement
+			.query_map(params![key, since, until], |row| {
+				let sheet: String = row.get(2)?;
+				Ok(Usage {
+					hour: row.get(0)?,
+					route: row.get(1)?,
+					sheet: (!sheet.is_empty()).then_some(sheet),
+					count: row.get(3)?,
+				})
+			})?
+			.collect::<Result<Vec<_>, _>>()?;
+
+		Ok(usage)
+	}
+
+	pub async fn start(&self, cancel: CancellationToken) -> Result<()> {
+		let Some(store) = &self.store else {
+			return Ok(());
+		};
+
+		let mut interval = time::interval(Duration::from_secs(self.interval));
+		interval.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+
+		loop {
+			select! {
+				_ = interval.tick() => {},
+				_ = cancel.cancelled() => break,
+			}
+
+			if let Err(error) = flush_blocking(store).await {
+				tracing::error!(?error, "failed to write usage analytics");
+			}
+		}
+
+		// Write out anything recorded since the last tick before shutting down.
+		flush_blocking(store).await
+	}
+}
+
+/// Usage database, along with counts yet to be written to it.
+struct Store {
+	connection: Mutex<Connection>,
+	pending: Mutex<HashMap<UsageKey, u64>>,
+}
+
+impl Store {
+	fn flush(&self) -> Result<()> {
+		let pending = mem::take(&mut *self.pending.lock().expect("poisoned"));
+		if pending.is_empty() {
+			return Ok(());
+		}
+
+		let result = write(&mut self.connection.lock().expect("poisoned"), &pending);
+
+		// Keep the counts around on failure, so they can be retried on the next flush.
+		if result.is_err() {
+			let mut current = self.pending.lock().expect("poisoned");
+			for (usage_key, count) in pending {
+				*current.entry(usage_key).or_default() += count;
+			}
+		}
+
+		result
+	}
+}
+
+async fn flush_blocking(store: &Arc<Store>) -> Result<()> {
+	let store = store.clone();
+	tokio::task::spawn_blocking(move || store.flush()).await?
+}
+
+fn write(connection: &mut Connection, pending: &HashMap<UsageKey, u64>) -> Result<()> {
+	let transaction = connection.transaction()?;
+	{
+		let mut statement = transaction.prepare_cached(
+			"INSERT INTO usage (key, hour, route, sheet, count) VALUES (?1, ?2, ?3, ?4, ?5)
+			ON CONFLICT (key, hour, route, sheet) DO UPDATE SET count = count + excluded.count",
+		)?;
+		for (usage_key, count) in pending {
+			statement.execute(params![
+				usage_key.key,
+				usage_key.hour,
+				usage_key.route,
+				usage_key.sheet,
+				count
+			])?;
+		}
+	}
+	transaction.commit()?;
+
+	tracing::debug!(rows = pending.len(), "wrote usage analytics");
+
+	Ok(())
+}
+
+fn open(path: &Path) -> Result<Connection> {
+	if let Some(parent) = path.parent() {
+		std::fs::create_dir_all(parent)?;
+	}
+
+	let connection = Connection::open(path)
+		.with_context(|| format!("failed to open analytics database {path:?}"))?;
+
+	connection.execute_batch(
+		"CREATE TABLE IF NOT EXISTS usage (
+			key TEXT NOT NULL,
+			hour INTEGER NOT NULL,
+			route TEXT NOT NULL,
+			sheet TEXT NOT NULL,
+			count INTEGER NOT NULL,
+			PRIMARY KEY (key, hour, route, sheet)
+		)",
+	)?;
+
+	Ok(connection)
+}
+
+fn current_hour() -> u64 {
+	let seconds = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_secs());
+	seconds - seconds % HOUR_SECONDS
+}
@@ -0,0 +1,3 @@
+mod analytics;
+
+pub use analytics::{Analytics, Config, Usage};
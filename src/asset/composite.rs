@@ -0,0 +1,34 @@
+use anyhow::Context;
+use image::{imageops, DynamicImage};
+
+use crate::data;
+
+use super::{
+	convert::{read_image, write_image},
+	error::Result,
+	format::Format,
+};
+
+/// Maximum number of layers a single composite may be built from.
+pub const MAX_LAYERS: usize = 8;
+
+/// Composite a stack of images into one, drawing each layer over those before
+/// it. The first layer determines the size of the output - later layers are
+/// centered over it, and cropped if larger.
+pub(super) fn composite(
+	data: &data::Version,
+	layers: &[String],
+	format: Format,
+) -> Result<Vec<u8>> {
+	let (first, rest) = layers.split_first().context("no layers to composite")?;
+
+	let mut canvas = read_image(data, first, format)?.to_rgba8();
+	for path in rest {
+		let layer = read_image(data, path, format)?.to_rgba8();
+		let x = (i64::from(canvas.width()) - i64::from(layer.width())) / 2;
+		let y = (i64::from(canvas.height()) - i64::from(layer.height())) / 2;
+		imageops::overlay(&mut canvas, &layer, x, y);
+	}
+
+	write_image(&DynamicImage::ImageRgba8(canvas), format)
+}
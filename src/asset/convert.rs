@@ -21,46 +21,55 @@ pub struct Image;
 
 impl Converter for Image {
 	fn convert(&self, data: &data::Version, path: &str, format: Format) -> Result<Vec<u8>> {
-		let extension = Path::new(path)
-			.extension()
-			.and_then(|extension| extension.to_str());
-
-		// TODO: add error handling case on this once more than one format exists.
-		let output_format = match format {
-			Format::Png => ImageFormat::Png,
-		};
-
-		// TODO: should i just pass IW to convert? is there any realistic expectation that a converter will need excel?
-		let ironworks = data.ironworks();
-
-		let buffer = match extension {
-			Some("tex") | Some("atex") => read_texture(&ironworks, path),
-
-			other => {
-				return Err(Error::InvalidConversion(
-					other.unwrap_or("(none)").into(),
-					format,
-				));
-			}
-		}?;
-
-		// TODO: are there any non-failure cases here?
-		let mut bytes = Cursor::new(vec![]);
-		buffer
-			.write_to(&mut bytes, output_format)
-			.context("failed to write output buffer")?;
-
-		Ok(bytes.into_inner())
+		let buffer = read_image(data, path, format)?;
+		write_image(&buffer, format)
 	}
 }
 
-fn read_texture(ironworks: &Ironworks, path: &str) -> Result<DynamicImage> {
-	let texture = match ironworks.file::<tex::Texture>(path) {
-		Ok(value) => value,
-		Err(ironworks::Error::NotFound(_)) => return Err(Error::NotFound(path.into())),
-		other => other.context("read file")?,
+pub(super) fn read_image(data: &data::Version, path: &str, format: Format) -> Result<DynamicImage> {
+	let extension = Path::new(path)
+		.extension()
+		.and_then(|extension| extension.to_str());
+
+	// TODO: should i just pass IW to convert? is there any realistic expectation that a converter will need excel?
+	let ironworks = data.ironworks();
+
+	match extension {
+		Some("tex") | Some("atex") => read_texture(&ironworks, path),
+
+		other => Err(Error::InvalidConversion(
+			other.unwrap_or("(none)").into(),
+			format,
+		)),
+	}
+}
+
+pub(super) fn write_image(buffer: &DynamicImage, format: Format) -> Result<Vec<u8>> {
+	// TODO: add error handling case on this once more than one format exists.
+	let output_format = match format {
+		Format::Png => ImageFormat::Png,
 	};
 
+	// TODO: are there any non-failure cases here?
+	let mut bytes = Cursor::new(vec![]);
+	buffer
+		.write_to(&mut bytes, output_format)
+		.context("failed to write output buffer")?;
+
+	Ok(bytes.into_inner())
+}
+
+pub(super) fn read_texture_file(ironworks: &Ironworks, path: &str) -> Result<tex::Texture> {
+	match ironworks.file::<tex::Texture>(path) {
+		Ok(value) => Ok(value),
+		Err(ironworks::Error::NotFound(_)) => Err(Error::NotFound(path.into())),
+		other => Ok(other.context("read file")?),
+	}
+}
+
+fn read_texture(ironworks: &Ironworks, path: &str) -> Result<DynamicImage> {
+	let texture = read_texture_file(ironworks, path)?;
+
 	if !matches!(texture.kind(), tex::TextureKind::D2) {
 		return Err(Error::UnsupportedSource(
 			path.into(),
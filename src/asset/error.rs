@@ -1,3 +1,5 @@
+use crate::error::{classify_failure, Classify, ErrorKind};
+
 use super::format::Format;
 
 #[derive(Debug, thiserror::Error)]
@@ -18,4 +20,16 @@ pub enum Error {
 	Failure(#[from] anyhow::Error),
 }
 
+impl Classify for Error {
+	fn kind(&self) -> ErrorKind {
+		match self {
+			Self::NotFound(..) => ErrorKind::NotFound,
+			Self::UnsupportedSource(..) | Self::UnknownFormat(..) | Self::InvalidConversion(..) => {
+				ErrorKind::Invalid
+			}
+			Self::Failure(inner) => classify_failure(inner),
+		}
+	}
+}
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,280 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::data;
+
+use super::{
+	error::{Error, Result},
+	reader::{read_file, Reader},
+};
+
+/// Glyph metrics of a game font, read from an `.fdt` file. Glyph images are
+/// stored in separate atlas textures.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Font {
+	/// Size of the font, in points.
+	pub size: f32,
+	pub line_height: u32,
+	pub ascent: u32,
+	/// Dimensions of each atlas texture.
+	pub texture_width: u16,
+	pub texture_height: u16,
+	pub glyphs: Vec<Glyph>,
+	pub kerning: Vec<Kerning>,
+}
+
+/// Position and metrics of a single character within the font's atlases.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Glyph {
+	pub character: String,
+	/// Index of the atlas containing the glyph. Atlases are packed into the
+	/// channels of the font's textures, four per texture.
+	pub texture_index: u16,
+	pub x: u16,
+	pub y: u16,
+	pub width: u8,
+	pub height: u8,
+	/// Adjustment to the advance following this glyph.
+	pub offset_x: i8,
+	/// Vertical offset the glyph should be drawn at.
+	pub offset_y: i8,
+}
+
+/// Horizontal adjustment applied between a specific pair of characters.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Kerning {
+	pub left: String,
+	pub right: String,
+	pub offset: i32,
+}
+
+impl Font {
+	pub(super) fn read(data: &data::Version, path: &str) -> Result<Self> {
+		if !path.ends_with(".fdt") {
+			return Err(Error::UnsupportedSource(
+				path.into(),
+				"fonts must be .fdt files".into(),
+			));
+		}
+
+		let bytes = read_file(data, path)?;
+
+		parse(&bytes).map_err(|error| Error::UnsupportedSource(path.into(), format!("{error:#}")))
+	}
+}
+
+fn parse(bytes: &[u8]) -> anyhow::Result<Font> {
+	let mut reader = Reader::new(bytes);
+
+	// File header is padded to 0x20, and the font table header to 0x24.
+	reader.magic(b"fcsv0100")?;
+	let font_table = reader.offset(0)?;
+	let kerning_table = reader.offset(0)?;
+
+	reader.seek(font_table);
+	reader.magic(b"fthd0100")?;
+	let glyph_count = reader.u32()?;
+	let kerning_count = reader.u32()?;
+	reader.bytes(4)?;
+	let texture_width = reader.u16()?;
+	let texture_height = reader.u16()?;
+	let size = reader.f32()?;
+	let line_height = reader.u32()?;
+	let ascent = reader.u32()?;
+
+	// Glyphs immediately follow the font table header.
+	let glyphs = (0..glyph_count)
+		.map(|_| -> anyhow::Result<_> {
+			let character = character(reader.u32()?);
+			// Shift-JIS representation of the character.
+			reader.u16()?;
+			Ok(Glyph {
+				character,
+				texture_index: reader.u16()?,
+				x: reader.u16()?,
+				y: reader.u16()?,
+				width: reader.u8()?,
+				height: reader.u8()?,
+				offset_x: reader.i8()?,
+				offset_y: reader.i8()?,
+			})
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+
+	// The font table header's kerning count is authoritative - the kerning
+	// table may be absent entirely when it is zero.
+	let mut kerning = Vec::new();
+	if kerning_count > 0 {
+		reader.seek(kerning_table);
+		reader.magic(b"knhd0100")?;
+		let count = reader.u32()?.min(kerning_count);
+		reader.seek(kerning_table + 0x10);
+		for _ in 0..count {
+			let left = character(reader.u32()?);
+			let right = character(reader.u32()?);
+			// Shift-JIS representations of the characters.
+			reader.u32()?;
+			kerning.push(Kerning {
+				left,
+				right,
+				offset: reader.i32()?,
+			});
+		}
+	}
+
+	Ok(Font {
+		size,
+		line_height,
+		ascent,
+		texture_width,
+		texture_height,
+		glyphs,
+		kerning,
+	})
+}
+
+/// Decode a character stored as its UTF-8 bytes, packed big-endian into an
+/// integer.
+fn character(packed: u32) -> String {
+	let bytes = packed.to_be_bytes();
+	let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(3);
+	String::from_utf8_lossy(&bytes[start..]).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+	use pretty_assertions::assert_eq;
+
+	use super::*;
+
+	const FONT_TABLE: u32 = 0x20;
+	const KERNING_TABLE: u32 = 0x80;
+
+	/// Build an `.fdt` file with the given glyphs and kerning pairs.
+	fn fixture(glyphs: &[(&str, u16, u16, u16)], kerning: &[(&str, &str, i32)]) -> Vec<u8> {
+		let mut bytes = vec![];
+		bytes.extend_from_slice(b"fcsv0100");
+		bytes.extend_from_slice(&FONT_TABLE.to_le_bytes());
+		bytes.extend_from_slice(&KERNING_TABLE.to_le_bytes());
+		bytes.resize(FONT_TABLE as usize, 0);
+
+		bytes.extend_from_slice(b"fthd0100");
+		bytes.extend_from_slice(&(glyphs.len() as u32).to_le_bytes());
+		bytes.extend_from_slice(&(kerning.len() as u32).to_le_bytes());
+		bytes.extend_from_slice(&[0; 4]);
+		bytes.extend_from_slice(&1024u16.to_le_bytes());
+		bytes.extend_from_slice(&512u16.to_le_bytes());
+		bytes.extend_from_slice(&12f32.to_le_bytes());
+		bytes.extend_from_slice(&16u32.to_le_bytes());
+		bytes.extend_from_slice(&13u32.to_le_bytes());
+
+		for (character, texture_index, x, y) in glyphs {
+			bytes.extend_from_slice(&packed(character).to_le_bytes());
+			bytes.extend_from_slice(&[0; 2]);
+			bytes.extend_from_slice(&texture_index.to_le_bytes());
+			bytes.extend_from_slice(&x.to_le_bytes());
+			bytes.extend_from_slice(&y.to_le_bytes());
+			bytes.extend_from_slice(&[10, 14, 0xFF, 2]);
+		}
+
+		assert!(bytes.len() <= KERNING_TABLE as usize, "too many glyphs");
+		bytes.resize(KERNING_TABLE as usize, 0);
+
+		bytes.extend_from_slice(b"knhd0100");
+		bytes.extend_from_slice(&(kerning.len() as u32).to_le_bytes());
+		bytes.extend_from_slice(&[0; 4]);
+		for (left, right, offset) in kerning {
+			bytes.extend_from_slice(&packed(left).to_le_bytes());
+			bytes.extend_from_slice(&packed(right).to_le_bytes());
+			bytes.extend_from_slice(&[0; 4]);
+			bytes.extend_from_slice(&offset.to_le_bytes());
+		}
+
+		bytes
+	}
+
+	fn packed(character: &str) -> u32 {
+		let mut bytes = [0; 4];
+		bytes[4 - character.len()..].copy_from_slice(character.as_bytes());
+		u32::from_be_bytes(bytes)
+	}
+
+	#[test]
+	fn parses_metrics() {
+		let font = parse(&fixture(
+			&[("A", 0, 1, 2), ("あ", 5, 100, 200)],
+			&[("A", "V", -2)],
+		))
+		.unwrap();
+
+		assert_eq!(font.size, 12.0);
+		assert_eq!(font.line_height, 16);
+		assert_eq!(font.ascent, 13);
+		assert_eq!((font.texture_width, font.texture_height), (1024, 512));
+
+		let glyphs = font
+			.glyphs
+			.iter()
+			.map(|glyph| {
+				(
+					glyph.character.as_str(),
+					glyph.texture_index,
+					glyph.x,
+					glyph.y,
+				)
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(glyphs, vec![("A", 0, 1, 2), ("あ", 5, 100, 200)]);
+
+		let glyph = &font.glyphs[0];
+		assert_eq!((glyph.width, glyph.height), (10, 14));
+		assert_eq!((glyph.offset_x, glyph.offset_y), (-1, 2));
+
+		let kerning = font
+			.kerning
+			.iter()
+			.map(|pair| (pair.left.as_str(), pair.right.as_str(), pair.offset))
+			.collect::<Vec<_>>();
+		assert_eq!(kerning, vec![("A", "V", -2)]);
+	}
+
+	#[test]
+	fn skips_absent_kerning_table() {
+		let mut bytes = fixture(&[("A", 0, 0, 0)], &[]);
+		bytes.truncate(KERNING_TABLE as usize);
+
+		let font = parse(&bytes).unwrap();
+		assert_eq!(font.glyphs.len(), 1);
+		assert!(font.kerning.is_empty());
+	}
+
+	#[test]
+	fn rejects_bad_magic() {
+		let mut bytes = fixture(&[], &[]);
+		bytes[0] = b'x';
+		assert!(parse(&bytes).is_err());
+	}
+
+	#[test]
+	fn rejects_truncated_files() {
+		let bytes = fixture(&[("A", 0, 0, 0), ("B", 0, 0, 0)], &[("A", "B", 1)]);
+		for length in [0, 0x10, FONT_TABLE as usize + 0x10, 0x50, bytes.len() - 1] {
+			assert!(parse(&bytes[..length]).is_err(), "parsed {length} bytes");
+		}
+	}
+
+	#[test]
+	fn rejects_out_of_bounds_offsets() {
+		let mut bytes = fixture(&[], &[]);
+		bytes[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+		assert!(parse(&bytes).is_err());
+	}
+
+	#[test]
+	fn rejects_counts_beyond_file() {
+		let mut bytes = fixture(&[("A", 0, 0, 0)], &[]);
+		let count = FONT_TABLE as usize + 8;
+		bytes[count..count + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+		assert!(parse(&bytes).is_err());
+	}
+}
@@ -11,7 +11,7 @@ use crate::utility::jsonschema::impl_jsonschema;
 
 use super::{convert, error::Error};
 
-#[derive(Debug, Clone, Copy, EnumIter)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
 pub enum Format {
 	Png,
 }
@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use crate::data;
+
+use super::{
+	convert::read_texture_file,
+	error::{Error, Result},
+};
+
+/// Metadata of a texture, as declared by its header.
+#[derive(Debug)]
+pub struct TextureInfo {
+	pub kind: String,
+	pub format: String,
+	pub width: u32,
+	pub height: u32,
+	pub depth: u32,
+	pub mip_levels: u32,
+	pub array_size: u32,
+}
+
+impl TextureInfo {
+	pub(super) fn read(data: &data::Version, path: &str) -> Result<Self> {
+		let extension = Path::new(path)
+			.extension()
+			.and_then(|extension| extension.to_str());
+
+		if !matches!(extension, Some("tex") | Some("atex")) {
+			return Err(Error::UnsupportedSource(
+				path.into(),
+				"only textures have texture metadata".into(),
+			));
+		}
+
+		let texture = read_texture_file(&data.ironworks(), path)?;
+
+		Ok(Self {
+			kind: format!("{:?}", texture.kind()),
+			format: format!("{:?}", texture.format()),
+			width: texture.width().into(),
+			height: texture.height().into(),
+			depth: texture.depth().into(),
+			mip_levels: texture.mip_levels().into(),
+			array_size: texture.array_size().into(),
+		})
+	}
+}
@@ -1,6 +1,20 @@
+mod composite;
 mod convert;
 mod error;
+mod font;
 mod format;
+mod info;
+mod package;
+mod reader;
 mod service;
+mod uld;
 
-pub use {error::Error, format::Format, service::Service};
+pub use {
+	composite::MAX_LAYERS as MAX_COMPOSITE_LAYERS,
+	error::Error,
+	font::Font,
+	format::Format,
+	info::TextureInfo,
+	service::{Config, Service},
+	uld::UiLayout,
+};
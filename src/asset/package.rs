@@ -0,0 +1,179 @@
+use std::{
+	collections::BTreeSet,
+	io::{Cursor, Write},
+};
+
+use anyhow::Context;
+use image::{DynamicImage, GrayImage, Luma, RgbaImage};
+use serde::Serialize;
+
+use crate::{data, read};
+
+use super::{
+	convert::{read_image, write_image},
+	error::{Error, Result},
+	font::Font,
+	format::Format,
+	uld::UiLayout,
+};
+
+/// Channel of an RGBA pixel holding each of the four atlases packed into a font
+/// texture, by atlas index within the texture.
+const FONT_ATLAS_CHANNELS: [usize; 4] = [2, 1, 0, 3];
+
+/// Package a font's metrics alongside its glyph atlases. Each atlas used by the
+/// font is extracted from its texture channel into a separate greyscale image,
+/// named by the atlas index glyphs refer to.
+pub(super) fn font(data: &data::Version, path: &str, format: Format) -> Result<Vec<u8>> {
+	let font = Font::read(data, path)?;
+
+	let mut package = Package::new();
+	package.json("font.json", &font)?;
+
+	let atlases = font
+		.glyphs
+		.iter()
+		.map(|glyph| glyph.texture_index)
+		.collect::<BTreeSet<_>>();
+
+	// Atlases are sorted, so each texture only needs to be read once.
+	let mut texture: Option<(u16, RgbaImage)> = None;
+	for atlas in atlases {
+		let texture_number = atlas / 4;
+		if texture.as_ref().map(|(number, _)| *number) != Some(texture_number) {
+			let texture_path = font_texture_path(path, texture_number);
+			let image = read_image(data, &texture_path, format)?.to_rgba8();
+			texture = Some((texture_number, image));
+		}
+		let (_, image) = texture.as_ref().expect("texture was just read");
+
+		let channel = FONT_ATLAS_CHANNELS[usize::from(atlas % 4)];
+		let atlas_image = GrayImage::from_fn(image.width(), image.height(), |x, y| {
+			Luma([image.get_pixel(x, y)[channel]])
+		});
+
+		package.file(
+			&format!("atlas_{atlas}.{}", format.extension()),
+			&write_image(&DynamicImage::ImageLuma8(atlas_image), format)?,
+		)?;
+	}
+
+	package.finish()
+}
+
+/// Game path of a font's atlas texture. Textures are stored alongside the font,
+/// numbered from 1, with separate sets for lobby and Korean fonts.
+fn font_texture_path(font_path: &str, texture_number: u16) -> String {
+	let (directory, file) = font_path.rsplit_once('/').unwrap_or(("", font_path));
+	let stem = file.strip_suffix(".fdt").unwrap_or(file);
+
+	let prefix = if stem.ends_with("_lobby") {
+		"font_lobby"
+	} else if stem.starts_with("KrnAXIS") {
+		"font_krn_"
+	} else {
+		"font"
+	};
+
+	let name = format!("{prefix}{}.tex", texture_number + 1);
+	match directory {
+		"" => name,
+		directory => format!("{directory}/{name}"),
+	}
+}
+
+/// Package a UI layout's parts alongside the textures they are cut from, named
+/// by the texture ID parts refer to. Textures missing from the game data are
+/// left out.
+pub(super) fn ui_layout(data: &data::Version, path: &str, format: Format) -> Result<Vec<u8>> {
+	let layout = UiLayout::read(data, path)?;
+
+	let mut package = Package::new();
+	package.json("layout.json", &layout)?;
+
+	for texture in &layout.textures {
+		let texture_path = match (texture.path.as_str(), texture.icon_id) {
+			("", 0) => continue,
+			("", icon_id) => format!("{}.tex", read::icon_path(icon_id)),
+			(path, _) => path.to_string(),
+		};
+
+		let image = match read_image(data, &texture_path, format) {
+			Ok(image) => image,
+			Err(Error::NotFound(_)) => continue,
+			Err(error) => return Err(error),
+		};
+
+		package.file(
+			&format!("texture_{}.{}", texture.id, format.extension()),
+			&write_image(&image, format)?,
+		)?;
+	}
+
+	package.finish()
+}
+
+/// Zip archive of the files making up a package.
+struct Package {
+	writer: zip::ZipWriter<Cursor<Vec<u8>>>,
+}
+
+impl Package {
+	fn new() -> Self {
+		Self {
+			writer: zip::ZipWriter::new(Cursor::new(Vec::new())),
+		}
+	}
+
+	fn file(&mut self, name: &str, bytes: &[u8]) -> Result<()> {
+		self.writer
+			.start_file(name, zip::write::SimpleFileOptions::default())
+			.context("failed to start package file")?;
+		self.writer
+			.write_all(bytes)
+			.context("failed to write package file")?;
+		Ok(())
+	}
+
+	fn json(&mut self, name: &str, value: &impl Serialize) -> Result<()> {
+		let bytes = serde_json::to_vec(value).context("failed to serialize package file")?;
+		self.file(name, &bytes)
+	}
+
+	fn finish(self) -> Result<Vec<u8>> {
+		let cursor = self.writer.finish().context("failed to finish package")?;
+		Ok(cursor.into_inner())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use pretty_assertions::assert_eq;
+
+	use super::*;
+
+	#[test]
+	fn font_textures_are_numbered_from_one() {
+		assert_eq!(
+			font_texture_path("common/font/AXIS_12.fdt", 0),
+			"common/font/font1.tex"
+		);
+		assert_eq!(
+			font_texture_path("common/font/AXIS_12.fdt", 6),
+			"common/font/font7.tex"
+		);
+	}
+
+	#[test]
+	fn font_textures_follow_font_set() {
+		assert_eq!(
+			font_texture_path("common/font/AXIS_12_lobby.fdt", 1),
+			"common/font/font_lobby2.tex"
+		);
+		assert_eq!(
+			font_texture_path("common/font/KrnAXIS_120.fdt", 0),
+			"common/font/font_krn_1.tex"
+		);
+		assert_eq!(font_texture_path("AXIS_12.fdt", 0), "font1.tex");
+	}
+}
@@ -0,0 +1,98 @@
+use anyhow::{bail, Context, Result};
+
+use crate::data;
+
+use super::error::Error;
+
+/// Read the raw bytes of a game file.
+pub fn read_file(data: &data::Version, path: &str) -> Result<Vec<u8>, Error> {
+	match data.ironworks().file::<Vec<u8>>(path) {
+		Ok(bytes) => Ok(bytes),
+		Err(ironworks::Error::NotFound(_)) => Err(Error::NotFound(path.into())),
+		Err(error) => Err(Error::Failure(error.into())),
+	}
+}
+
+/// Minimal little-endian reader over the bytes of a game file, for formats
+/// ironworks does not parse.
+pub struct Reader<'a> {
+	bytes: &'a [u8],
+	position: usize,
+}
+
+impl<'a> Reader<'a> {
+	pub fn new(bytes: &'a [u8]) -> Self {
+		Self { bytes, position: 0 }
+	}
+
+	pub fn seek(&mut self, position: usize) {
+		self.position = position;
+	}
+
+	pub fn position(&self) -> usize {
+		self.position
+	}
+
+	pub fn bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+		let end = self
+			.position
+			.checked_add(length)
+			.context("offset overflow")?;
+		let bytes = self.bytes.get(self.position..end).with_context(|| {
+			format!(
+				"unexpected end of file reading {length} bytes at {}",
+				self.position
+			)
+		})?;
+		self.position = end;
+		Ok(bytes)
+	}
+
+	pub fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+		Ok(self.bytes(N)?.try_into().expect("length matches"))
+	}
+
+	/// Read a magic value, failing if it does not match the expected value.
+	pub fn magic(&mut self, expected: &[u8]) -> Result<()> {
+		let position = self.position;
+		let magic = self.bytes(expected.len())?;
+		if magic != expected {
+			bail!(
+				"expected magic {:?} at {position}, got {:?}",
+				String::from_utf8_lossy(expected),
+				String::from_utf8_lossy(magic)
+			);
+		}
+		Ok(())
+	}
+
+	pub fn u8(&mut self) -> Result<u8> {
+		Ok(u8::from_le_bytes(self.array()?))
+	}
+
+	pub fn i8(&mut self) -> Result<i8> {
+		Ok(i8::from_le_bytes(self.array()?))
+	}
+
+	pub fn u16(&mut self) -> Result<u16> {
+		Ok(u16::from_le_bytes(self.array()?))
+	}
+
+	pub fn u32(&mut self) -> Result<u32> {
+		Ok(u32::from_le_bytes(self.array()?))
+	}
+
+	pub fn i32(&mut self) -> Result<i32> {
+		Ok(i32::from_le_bytes(self.array()?))
+	}
+
+	pub fn f32(&mut self) -> Result<f32> {
+		Ok(f32::from_le_bytes(self.array()?))
+	}
+
+	/// Read an offset value as a position within the file, relative to `base`.
+	pub fn offset(&mut self, base: usize) -> Result<usize> {
+		let offset = usize::try_from(self.u32()?).context("offset overflow")?;
+		base.checked_add(offset).context("offset overflow")
+	}
+}
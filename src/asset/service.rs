@@ -1,18 +1,58 @@
 use std::sync::Arc;
 
 use anyhow::Context;
+use serde::Deserialize;
 
-use crate::{data, version::VersionKey};
+use crate::{
+	cache::{Caches, TierConfig, TieredCache},
+	config::Validator,
+	data,
+	utility::anyhow::Anyhow,
+	version::VersionKey,
+};
 
-use super::{error::Result, format::Format};
+use super::{
+	composite, error::Result, font::Font, format::Format, info::TextureInfo, package, uld::UiLayout,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+	/// Limits of the converted asset cache, shared across all versions. Assets
+	/// are cached in memory only, up to 128MiB, if unset.
+	#[serde(default)]
+	cache: Option<TierConfig>,
+
+	/// Memory limit of the converted asset cache, as configured before caches
+	/// were tiered. Takes precedence over `cache.memory` if set.
+	#[serde(default)]
+	memory: Option<u64>,
+}
+
+impl Config {
+	pub fn validate(&self, _validator: &mut Validator) {}
+}
+
+type ConvertedKey = (VersionKey, String, Format);
 
 pub struct Service {
 	data: Arc<data::Data>,
+	converted: TieredCache<ConvertedKey>,
 }
 
 impl Service {
-	pub fn new(data: Arc<data::Data>) -> Self {
-		Self { data }
+	pub fn new(config: Config, caches: &Caches, data: Arc<data::Data>) -> anyhow::Result<Self> {
+		let mut cache = config
+			.cache
+			.unwrap_or_else(|| TierConfig::default().with_memory(128 * 1024 * 1024));
+		if let Some(memory) = config.memory {
+			tracing::warn!("asset.memory is deprecated, use asset.cache.memory");
+			cache = cache.with_memory(memory);
+		}
+
+		Ok(Self {
+			data,
+			converted: caches.build("asset", cache)?,
+		})
 	}
 
 	pub fn ready(&self) -> bool {
@@ -20,15 +60,141 @@ impl Service {
 		true
 	}
 
-	pub fn convert(&self, version: VersionKey, path: &str, format: Format) -> Result<Vec<u8>> {
-		// TODO: presumably this is where caching would be resolved
+	pub async fn convert(
+		&self,
+		version: VersionKey,
+		path: &str,
+		format: Format,
+	) -> Result<Vec<u8>> {
+		let data_version = self
+			.data
+			.version(version)
+			.with_context(|| format!("data for {version} not ready"))?;
+
+		let key = (version, path.to_string(), format);
+		if let Some(bytes) = self.converted.get_memory(&key) {
+			return Ok(bytes.to_vec());
+		}
+
+		// Conversion reads (and decodes) game files, and the disk tier of the
+		// conversion cache is blocking - run them on the blocking pool.
+		let converted = self.converted.clone();
+		let bytes = self
+			.data
+			.blocking(move || -> Result<_> {
+				if let Some(bytes) = converted.get_disk(&key) {
+					return Ok(bytes);
+				}
+
+				let converter = format.converter();
+				let bytes = Arc::<[u8]>::from(converter.convert(&data_version, &key.1, format)?);
+				converted.insert(key, bytes.clone());
+				Ok(bytes)
+			})
+			.await
+			.anyhow()??;
+
+		Ok(bytes.to_vec())
+	}
+
+	/// Composite a stack of images, bottom layer first, into a single image.
+	pub async fn composite(
+		&self,
+		version: VersionKey,
+		layers: Vec<String>,
+		format: Format,
+	) -> Result<Vec<u8>> {
+		let data_version = self
+			.data
+			.version(version)
+			.with_context(|| format!("data for {version} not ready"))?;
+
+		self.data
+			.blocking(move || composite::composite(&data_version, &layers, format))
+			.await
+			.anyhow()?
+	}
+
+	/// Read metadata of a texture, without decoding or converting its pixels.
+	pub async fn texture_info(&self, version: VersionKey, path: &str) -> Result<TextureInfo> {
+		let data_version = self
+			.data
+			.version(version)
+			.with_context(|| format!("data for {version} not ready"))?;
+
+		let path = path.to_string();
+		self.data
+			.blocking(move || TextureInfo::read(&data_version, &path))
+			.await
+			.anyhow()?
+	}
+
+	/// Read the glyph metrics of a font. Glyph images are available from the
+	/// font's atlas textures.
+	pub async fn font(&self, version: VersionKey, path: &str) -> Result<Font> {
+		let data_version = self
+			.data
+			.version(version)
+			.with_context(|| format!("data for {version} not ready"))?;
+
+		let path = path.to_string();
+		self.data
+			.blocking(move || Font::read(&data_version, &path))
+			.await
+			.anyhow()?
+	}
+
+	/// Package a font's glyph metrics alongside its atlases, extracted from the
+	/// font's textures and converted to the given format.
+	pub async fn font_package(
+		&self,
+		version: VersionKey,
+		path: &str,
+		format: Format,
+	) -> Result<Vec<u8>> {
+		let data_version = self
+			.data
+			.version(version)
+			.with_context(|| format!("data for {version} not ready"))?;
+
+		let path = path.to_string();
+		self.data
+			.blocking(move || package::font(&data_version, &path, format))
+			.await
+			.anyhow()?
+	}
+
+	/// Read the texture atlases and element rectangles referenced by a UI layout.
+	pub async fn ui_layout(&self, version: VersionKey, path: &str) -> Result<UiLayout> {
+		let data_version = self
+			.data
+			.version(version)
+			.with_context(|| format!("data for {version} not ready"))?;
+
+		let path = path.to_string();
+		self.data
+			.blocking(move || UiLayout::read(&data_version, &path))
+			.await
+			.anyhow()?
+	}
 
+	/// Package a UI layout's element rectangles alongside the textures they are
+	/// cut from, converted to the given format.
+	pub async fn ui_layout_package(
+		&self,
+		version: VersionKey,
+		path: &str,
+		format: Format,
+	) -> Result<Vec<u8>> {
 		let data_version = self
 			.data
 			.version(version)
 			.with_context(|| format!("data for {version} not ready"))?;
 
-		let converter = format.converter();
-		converter.convert(&data_version, path, format)
+		let path = path.to_string();
+		self.data
+			.blocking(move || package::ui_layout(&data_version, &path, format))
+			.await
+			.anyhow()?
 	}
 }
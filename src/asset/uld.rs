@@ -0,0 +1,315 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::data;
+
+use super::{
+	error::{Error, Result},
+	reader::{read_file, Reader},
+};
+
+/// Texture atlases referenced by a UI layout, read from a `.uld` file.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UiLayout {
+	pub textures: Vec<UiTexture>,
+	pub part_lists: Vec<UiPartList>,
+}
+
+/// Texture referenced by a UI layout, by path or icon ID.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UiTexture {
+	pub id: u32,
+	/// Game path of the texture. High resolution variants are found alongside it
+	/// with an `_hr1` suffix.
+	pub path: String,
+	pub icon_id: u32,
+}
+
+/// Group of UI elements cut out of the layout's textures.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UiPartList {
+	pub id: u32,
+	pub parts: Vec<UiPart>,
+}
+
+/// Rectangle of a texture making up a single UI element.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UiPart {
+	pub texture_id: u32,
+	pub x: u16,
+	pub y: u16,
+	pub width: u16,
+	pub height: u16,
+}
+
+impl UiLayout {
+	pub(super) fn read(data: &data::Version, path: &str) -> Result<Self> {
+		if !path.ends_with(".uld") {
+			return Err(Error::UnsupportedSource(
+				path.into(),
+				"UI layouts must be .uld files".into(),
+			));
+		}
+
+		let bytes = read_file(data, path)?;
+
+		parse(&bytes).map_err(|error| Error::UnsupportedSource(path.into(), format!("{error:#}")))
+	}
+}
+
+fn parse(bytes: &[u8]) -> anyhow::Result<UiLayout> {
+	let mut reader = Reader::new(bytes);
+
+	reader.magic(b"uldh0100")?;
+	let component_header = reader.offset(0)?;
+
+	// Offsets within the component header are relative to its start, and zero
+	// where the list is absent.
+	reader.seek(component_header);
+	reader.magic(b"atkh0100")?;
+	let asset_list = reader.u32()?;
+	let part_list = reader.u32()?;
+
+	let mut textures = Vec::new();
+	if asset_list != 0 {
+		reader.seek(component_header + usize::try_from(asset_list)?);
+		reader.magic(b"ashd")?;
+		let version = reader.array::<4>()?;
+		let count = reader.u32()?;
+		reader.u32()?;
+
+		for _ in 0..count {
+			let id = reader.u32()?;
+			let path = reader.bytes(44)?;
+			let icon_id = reader.u32()?;
+			if &version == b"0101" {
+				reader.u32()?;
+			}
+
+			let length = path
+				.iter()
+				.position(|byte| *byte == 0)
+				.unwrap_or(path.len());
+			textures.push(UiTexture {
+				id,
+				path: String::from_utf8_lossy(&path[..length]).into_owned(),
+				icon_id,
+			});
+		}
+	}
+
+	let mut part_lists = Vec::new();
+	if part_list != 0 {
+		reader.seek(component_header + usize::try_from(part_list)?);
+		reader.magic(b"tphd0100")?;
+		let count = reader.u32()?;
+		reader.u32()?;
+
+		for _ in 0..count {
+			let start = reader.position();
+			let id = reader.u32()?;
+			let part_count = reader.u32()?;
+			let next = reader.offset(start)?;
+
+			let parts = (0..part_count)
+				.map(|_| -> anyhow::Result<_> {
+					Ok(UiPart {
+						texture_id: reader.u32()?,
+						x: reader.u16()?,
+						y: reader.u16()?,
+						width: reader.u16()?,
+						height: reader.u16()?,
+					})
+				})
+				.collect::<anyhow::Result<Vec<_>>>()?;
+
+			part_lists.push(UiPartList { id, parts });
+			reader.seek(next);
+		}
+	}
+
+	Ok(UiLayout {
+		textures,
+		part_lists,
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use pretty_assertions::assert_eq;
+
+	use super::*;
+
+	const COMPONENT_HEADER: u32 = 0x10;
+
+	/// Build a `.uld` file with the given textures and part lists.
+	fn fixture(
+		version: &[u8; 4],
+		textures: &[(u32, &str, u32)],
+		part_lists: &[(u32, &[(u32, u16, u16, u16, u16)])],
+	) -> Vec<u8> {
+		let mut bytes = vec![];
+		bytes.extend_from_slice(b"uldh0100");
+		bytes.extend_from_slice(&COMPONENT_HEADER.to_le_bytes());
+		bytes.resize(COMPONENT_HEADER as usize, 0);
+
+		let mut assets = vec![];
+		assets.extend_from_slice(b"ashd");
+		assets.extend_from_slice(version);
+		assets.extend_from_slice(&(textures.len() as u32).to_le_bytes());
+		assets.extend_from_slice(&[0; 4]);
+		for (id, path, icon_id) in textures {
+			assets.extend_from_slice(&id.to_le_bytes());
+			let mut path_bytes = [0; 44];
+			path_bytes[..path.len()].copy_from_slice(path.as_bytes());
+			assets.extend_from_slice(&path_bytes);
+			assets.extend_from_slice(&icon_id.to_le_bytes());
+			if version == b"0101" {
+				assets.extend_from_slice(&[0; 4]);
+			}
+		}
+
+		let mut parts = vec![];
+		parts.extend_from_slice(b"tphd0100");
+		parts.extend_from_slice(&(part_lists.len() as u32).to_le_bytes());
+		parts.extend_from_slice(&[0; 4]);
+		for (id, list) in part_lists {
+			let size = 12 + list.len() * 12;
+			parts.extend_from_slice(&id.to_le_bytes());
+			parts.extend_from_slice(&(list.len() as u32).to_le_bytes());
+			parts.extend_from_slice(&(size as u32).to_le_bytes());
+			for (texture_id, x, y, width, height) in *list {
+				parts.extend_from_slice(&texture_id.to_le_bytes());
+				for value in [x, y, width, height] {
+					parts.extend_from_slice(&value.to_le_bytes());
+				}
+			}
+		}
+
+		let asset_list = 16u32;
+		let part_list = asset_list + assets.len() as u32;
+		bytes.extend_from_slice(b"atkh0100");
+		bytes.extend_from_slice(&asset_list.to_le_bytes());
+		bytes.extend_from_slice(&part_list.to_le_bytes());
+		bytes.extend_from_slice(&assets);
+		bytes.extend_from_slice(&parts);
+
+		bytes
+	}
+
+	fn textures(layout: &UiLayout) -> Vec<(u32, &str, u32)> {
+		layout
+			.textures
+			.iter()
+			.map(|texture| (texture.id, texture.path.as_str(), texture.icon_id))
+			.collect()
+	}
+
+	fn parts(layout: &UiLayout) -> Vec<(u32, Vec<(u32, u16, u16, u16, u16)>)> {
+		layout
+			.part_lists
+			.iter()
+			.map(|list| {
+				let parts = list
+					.parts
+					.iter()
+					.map(|part| (part.texture_id, part.x, part.y, part.width, part.height))
+					.collect();
+				(list.id, parts)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn parses_textures_and_parts() {
+		let bytes = fixture(
+			b"0100",
+			&[(1, "ui/uld/Parameter.tex", 0), (2, "", 60001)],
+			&[
+				(10, &[(1, 0, 0, 32, 32), (1, 32, 0, 16, 8)]),
+				(11, &[(2, 4, 4, 24, 24)]),
+			],
+		);
+		let layout = parse(&bytes).unwrap();
+
+		assert_eq!(
+			textures(&layout),
+			vec![(1, "ui/uld/Parameter.tex", 0), (2, "", 60001)]
+		);
+		assert_eq!(
+			parts(&layout),
+			vec![
+				(10, vec![(1, 0, 0, 32, 32), (1, 32, 0, 16, 8)]),
+				(11, vec![(2, 4, 4, 24, 24)]),
+			]
+		);
+	}
+
+	#[test]
+	fn parses_versioned_asset_entries() {
+		let bytes = fixture(
+			b"0101",
+			&[(1, "ui/uld/a.tex", 0), (2, "ui/uld/b.tex", 0)],
+			&[(10, &[(2, 1, 2, 3, 4)])],
+		);
+		let layout = parse(&bytes).unwrap();
+
+		assert_eq!(
+			textures(&layout),
+			vec![(1, "ui/uld/a.tex", 0), (2, "ui/uld/b.tex", 0)]
+		);
+		assert_eq!(parts(&layout), vec![(10, vec![(2, 1, 2, 3, 4)])]);
+	}
+
+	#[test]
+	fn skips_absent_lists() {
+		let mut bytes = fixture(b"0100", &[], &[]);
+		let lists = COMPONENT_HEADER as usize + 8;
+		bytes[lists..lists + 8].fill(0);
+
+		let layout = parse(&bytes).unwrap();
+		assert!(layout.textures.is_empty());
+		assert!(layout.part_lists.is_empty());
+	}
+
+	#[test]
+	fn rejects_bad_magic() {
+		let mut bytes = fixture(b"0100", &[], &[]);
+		bytes[COMPONENT_HEADER as usize] = b'x';
+		assert!(parse(&bytes).is_err());
+	}
+
+	#[test]
+	fn rejects_truncated_files() {
+		let bytes = fixture(
+			b"0100",
+			&[(1, "ui/uld/a.tex", 0)],
+			&[(10, &[(1, 0, 0, 8, 8)])],
+		);
+		for length in [
+			0,
+			0x0C,
+			COMPONENT_HEADER as usize + 12,
+			0x40,
+			bytes.len() - 1,
+		] {
+			assert!(parse(&bytes[..length]).is_err(), "parsed {length} bytes");
+		}
+	}
+
+	#[test]
+	fn rejects_out_of_bounds_offsets() {
+		let mut bytes = fixture(b"0100", &[], &[(10, &[])]);
+		let asset_list = COMPONENT_HEADER as usize + 8;
+		bytes[asset_list..asset_list + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+		assert!(parse(&bytes).is_err());
+	}
+
+	#[test]
+	fn rejects_counts_beyond_file() {
+		let mut bytes = fixture(b"0100", &[(1, "ui/uld/a.tex", 0)], &[]);
+		let count = COMPONENT_HEADER as usize + 16 + 8;
+		bytes[count..count + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+		assert!(parse(&bytes).is_err());
+	}
+}
@@ -0,0 +1,317 @@
+use std::{
+	hash::Hash,
+	mem,
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, RwLock,
+	},
+};
+
+use anyhow::{Context, Result};
+use figment::value::magic::RelativePathBuf;
+use mini_moka::sync as moka;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::config::Validator;
+
+use super::disk::DiskTier;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+	/// Directory disk tiers are stored in, with a subdirectory per cache.
+	#[serde(default = "default_directory")]
+	directory: RelativePathBuf,
+}
+
+fn default_directory() -> RelativePathBuf {
+	RelativePathBuf::from("cache")
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			directory: default_directory(),
+		}
+	}
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.writable_directory("directory", &self.directory.relative());
+	}
+}
+
+/// Size limits for the tiers of a single cache.
+#[derive(Debug, Default, Deserialize)]
+pub struct TierConfig {
+	/// Maximum size, in bytes, of entries to hold in memory.
+	memory: u64,
+
+	/// Maximum size, in bytes, of entries to hold on disk. Entries are kept on
+	/// disk across restarts, and after they've been evicted from memory. The disk
+	/// tier is disabled if 0.
+	#[serde(default)]
+	disk: u64,
+}
+
+impl TierConfig {
+	pub fn with_memory(self, memory: u64) -> Self {
+		Self { memory, ..self }
+	}
+}
+
+/// Keys usable with a tiered cache. Keys are serialized to name and identify
+/// entries stored on disk.
+pub trait CacheKey:
+	Hash + Eq + Clone + Serialize + DeserializeOwned + Send + Sync + 'static
+{
+}
+
+impl<T> CacheKey for T where
+	T: Hash + Eq + Clone + Serialize + DeserializeOwned + Send + Sync + 'static
+{
+}
+
+/// Point-in-time view of a cache's tiers.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheMetrics {
+	pub name: &'static str,
+	pub memory: TierMetrics,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub disk: Option<TierMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TierMetrics {
+	pub hits: u64,
+	pub misses: u64,
+	pub entries: u64,
+	/// Total size of held entries, in bytes.
+	pub size: u64,
+	/// Maximum size of held entries, in bytes.
+	pub capacity: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+	hits: AtomicU64,
+	misses: AtomicU64,
+}
+
+impl Counters {
+	fn record(&self, hit: bool) {
+		let counter = match hit {
+			true => &self.hits,
+			false => &self.misses,
+		};
+		counter.fetch_add(1, Ordering::Relaxed);
+	}
+
+	fn reset(&self) {
+		self.hits.store(0, Ordering::Relaxed);
+		self.misses.store(0, Ordering::Relaxed);
+	}
+
+	fn metrics(&self, entries: u64, size: u64, capacity: u64) -> TierMetrics {
+		TierMetrics {
+			hits: self.hits.load(Ordering::Relaxed),
+			misses: self.misses.load(Ordering::Relaxed),
+			entries,
+			size,
+			capacity,
+		}
+	}
+}
+
+/// Cache of byte values held in memory, and optionally backed by disk. Reads
+/// check memory first, then disk, promoting entries found on disk back into
+/// memory. Writes go to every tier.
+///
+/// Disk reads and writes are blocking - caches with a disk tier should only be
+/// used from blocking contexts, other than through `get_memory`.
+pub struct TieredCache<K> {
+	inner: Arc<Inner<K>>,
+}
+
+// Derived Clone would require K: Clone for no reason.
+impl<K> Clone for TieredCache<K> {
+	fn clone(&self) -> Self {
+		Self {
+			inner: self.inner.clone(),
+		}
+	}
+}
+
+struct Inner<K> {
+	name: &'static str,
+
+	memory: moka::Cache<K, Arc<[u8]>>,
+	memory_capacity: u64,
+	memory_counters: Counters,
+
+	disk: Option<DiskTier<K>>,
+	disk_counters: Counters,
+}
+
+impl<K: CacheKey> TieredCache<K> {
+	pub fn get(&self, key: &K) -> Option<Arc<[u8]>> {
+		self.get_memory(key).or_else(|| self.get_disk(key))
+	}
+
+	/// Read a value from the memory tier only. Never blocks, so may be used from
+	/// async contexts to skip a trip to the blocking pool for hot entries.
+	pub fn get_memory(&self, key: &K) -> Option<Arc<[u8]>> {
+		let inner = &self.inner;
+		let value = inner.memory.get(key);
+		inner.memory_counters.record(value.is_some());
+		value
+	}
+
+	/// Read a value from the disk tier, promoting it into memory if found.
+	pub fn get_disk(&self, key: &K) -> Option<Arc<[u8]>> {
+		let inner = &self.inner;
+		let disk = inner.disk.as_ref()?;
+		let value = disk.get(key);
+		inner.disk_counters.record(value.is_some());
+
+		let value = Arc::<[u8]>::from(value?);
+		inner.memory.insert(key.clone(), value.clone());
+		Some(value)
+	}
+
+	pub fn insert(&self, key: K, value: Arc<[u8]>) {
+		// Failing to write to disk only costs a later rebuild of the value - it's
+		// still held in memory.
+		if let Some(disk) = &self.inner.disk {
+			if let Err(error) = disk.insert(key.clone(), &value) {
+				tracing::warn!(
+					cache = self.inner.name,
+					?error,
+					"failed to write to disk tier"
+				);
+			}
+		}
+
+		self.inner.memory.insert(key, value);
+	}
+}
+
+/// Type-erased view of a cache, for the registry.
+trait Tiers: Send + Sync {
+	fn name(&self) -> &'static str;
+	fn metrics(&self) -> CacheMetrics;
+	fn flush(&self);
+}
+
+impl<K: CacheKey> Tiers for Inner<K> {
+	fn name(&self) -> &'static str {
+		self.name
+	}
+
+	fn metrics(&self) -> CacheMetrics {
+		CacheMetrics {
+			name: self.name,
+			memory: self.memory_counters.metrics(
+				self.memory.entry_count(),
+				self.memory.weighted_size(),
+				self.memory_capacity,
+			),
+			disk: self.disk.as_ref().map(|disk| {
+				let (entries, size) = disk.usage();
+				self.disk_counters.metrics(entries, size, disk.capacity())
+			}),
+		}
+	}
+
+	fn flush(&self) {
+		self.memory.invalidate_all();
+		self.memory_counters.reset();
+
+		if let Some(disk) = &self.disk {
+			disk.clear();
+			self.disk_counters.reset();
+		}
+	}
+}
+
+/// Registry of the tiered caches in use, providing a single point to inspect
+/// and flush them.
+pub struct Caches {
+	directory: PathBuf,
+	caches: RwLock<Vec<Arc<dyn Tiers>>>,
+}
+
+impl Caches {
+	pub fn new(config: Config) -> Self {
+		Self {
+			directory: config.directory.relative(),
+			caches: Default::default(),
+		}
+	}
+
+	/// Build a cache, registering it under the given name. Entries on disk are
+	/// stored in a directory of the same name, and are available immediately if
+	/// left by a previous run.
+	pub fn build<K: CacheKey>(
+		&self,
+		name: &'static str,
+		config: TierConfig,
+	) -> Result<TieredCache<K>> {
+		let memory = moka::Cache::builder()
+			.weigher(|key: &K, value: &Arc<[u8]>| {
+				// Keys own heap data (paths, names) - their serialized length is a
+				// close enough stand-in for it.
+				let key_size = serde_json::to_vec(key).map_or(0, |bytes| bytes.len());
+				let size = mem::size_of::<K>() + key_size + value.len();
+				u32::try_from(size).unwrap_or(u32::MAX)
+			})
+			.max_capacity(config.memory)
+			.build();
+
+		let disk = match config.disk {
+			0 => None,
+			capacity => Some(
+				DiskTier::open(self.directory.join(name), capacity)
+					.with_context(|| format!("failed to open disk tier of {name} cache"))?,
+			),
+		};
+
+		let inner = Arc::new(Inner {
+			name,
+			memory,
+			memory_capacity: config.memory,
+			memory_counters: Counters::default(),
+			disk,
+			disk_counters: Counters::default(),
+		});
+
+		self.caches.write().expect("poisoned").push(inner.clone());
+
+		Ok(TieredCache { inner })
+	}
+
+	pub fn metrics(&self) -> Vec<CacheMetrics> {
+		self.caches
+			.read()
+			.expect("poisoned")
+			.iter()
+			.map(|cache| cache.metrics())
+			.collect()
+	}
+
+	/// Drop every entry from the named cache, or from every cache if no name is
+	/// given. Returns false if no cache has the given name.
+	pub fn flush(&self, name: Option<&str>) -> bool {
+		let caches = self.caches.read().expect("poisoned");
+		let mut flushed = false;
+		for cache in caches.iter() {
+			if name.map_or(true, |name| name == cache.name()) {
+				tracing::info!(cache = cache.name(), "flushing cache");
+				cache.flush();
+				flushed = true;
+			}
+		}
+		flushed
+	}
+}
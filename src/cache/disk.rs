@@ -0,0 +1,301 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs,
+	io::{self, Read},
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Mutex,
+	},
+	time::SystemTime,
+};
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+
+use super::cache::CacheKey;
+
+/// Maximum length of an encoded key, guarding against damaged files.
+const KEY_LENGTH_MAX: usize = 64 * 1024;
+
+/// Size-bounded store of cache entries on disk, with a file per entry. Entries
+/// persist across restarts, and are evicted least-recently-used first once the
+/// capacity is exceeded.
+///
+/// Files hold the length-prefixed serialized key, followed by the
+/// length-prefixed value.
+pub struct DiskTier<K> {
+	directory: PathBuf,
+	capacity: u64,
+	state: Mutex<DiskState<K>>,
+	// Distinguishes the temporary files of concurrent writes.
+	writes: AtomicU64,
+}
+
+struct DiskState<K> {
+	entries: HashMap<K, DiskEntry>,
+	// Entries by the tick they were last used at, oldest first.
+	recency: BTreeMap<u64, K>,
+	size: u64,
+	tick: u64,
+}
+
+struct DiskEntry {
+	file: String,
+	size: u64,
+	used: u64,
+}
+
+impl<K: CacheKey> DiskState<K> {
+	fn touch(&mut self, key: &K) {
+		self.tick += 1;
+		let Some(entry) = self.entries.get_mut(key) else {
+			return;
+		};
+		self.recency.remove(&entry.used);
+		entry.used = self.tick;
+		self.recency.insert(entry.used, key.clone());
+	}
+
+	fn insert(&mut self, key: K, file: String, size: u64) {
+		self.remove(&key);
+		self.tick += 1;
+		self.recency.insert(self.tick, key.clone());
+		self.size += size;
+		self.entries.insert(
+			key,
+			DiskEntry {
+				file,
+				size,
+				used: self.tick,
+			},
+		);
+	}
+
+	fn remove(&mut self, key: &K) -> Option<DiskEntry> {
+		let entry = self.entries.remove(key)?;
+		self.recency.remove(&entry.used);
+		self.size -= entry.size;
+		Some(entry)
+	}
+}
+
+impl<K: CacheKey> DiskTier<K> {
+	/// Open the tier stored in the given directory, indexing any entries left by
+	/// a previous run. Unreadable files are removed.
+	pub fn open(directory: PathBuf, capacity: u64) -> Result<Self> {
+		fs::create_dir_all(&directory)?;
+
+		let mut found = vec![];
+		for item in fs::read_dir(&directory)? {
+			let item = item?;
+			let path = item.path();
+			let metadata = item.metadata()?;
+			if !metadata.is_file() {
+				continue;
+			}
+
+			match read_key::<K>(&path) {
+				Ok(key) => {
+					let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+					let file = item.file_name().to_string_lossy().into_owned();
+					found.push((modified, key, file, metadata.len()));
+				}
+				Err(error) => {
+					tracing::debug!(?path, ?error, "removing unreadable cache file");
+					fs::remove_file(&path)?;
+				}
+			}
+		}
+
+		// Files are touched as they're written, which is the closest thing to a
+		// record of use that survives a restart.
+		found.sort_unstable_by_key(|(modified, ..)| *modified);
+
+		let tier = Self {
+			directory,
+			capacity,
+			state: Mutex::new(DiskState {
+				entries: HashMap::new(),
+				recency: BTreeMap::new(),
+				size: 0,
+				tick: 0,
+			}),
+			writes: AtomicU64::new(0),
+		};
+
+		let mut state = tier.state.lock().expect("poisoned");
+		for (_modified, key, file, size) in found {
+			state.insert(key, file, size);
+		}
+		let evicted = tier.evict(&mut state);
+		drop(state);
+		tier.remove_files(evicted);
+
+		Ok(tier)
+	}
+
+	pub fn capacity(&self) -> u64 {
+		self.capacity
+	}
+
+	/// Number of entries and their total size, in bytes.
+	pub fn usage(&self) -> (u64, u64) {
+		let state = self.state.lock().expect("poisoned");
+		(state.entries.len() as u64, state.size)
+	}
+
+	pub fn get(&self, key: &K) -> Option<Vec<u8>> {
+		let file = {
+			let mut state = self.state.lock().expect("poisoned");
+			let file = state.entries.get(key)?.file.clone();
+			state.touch(key);
+			file
+		};
+
+		match read_value(&self.directory.join(&file), key) {
+			Ok(value) => Some(value),
+			Err(error) => {
+				// The file has gone missing or been damaged - forget it, and let the
+				// value be rebuilt.
+				tracing::debug!(%file, ?error, "discarding unreadable cache file");
+				let entry = self.state.lock().expect("poisoned").remove(key);
+				self.remove_files(entry.into_iter().map(|entry| entry.file).collect());
+				None
+			}
+		}
+	}
+
+	pub fn insert(&self, key: K, value: &[u8]) -> Result<()> {
+		let encoded_key = serde_json::to_vec(&key)?;
+		let size = (4 + encoded_key.len() + 8 + value.len()) as u64;
+		if encoded_key.len() > KEY_LENGTH_MAX || size > self.capacity {
+			return Ok(());
+		}
+
+		let file = file_name(&encoded_key);
+		let path = self.directory.join(&file);
+
+		// Write to a temporary file first, so that readers never see a partial
+		// entry. Each write gets its own file, so concurrent writes of the same key
+		// can't interleave. Leftovers are removed as unreadable on the next open.
+		let write = self.writes.fetch_add(1, Ordering::Relaxed);
+		let temporary = path.with_extension(format!("{write}.tmp"));
+		let mut contents = Vec::with_capacity(size as usize);
+		contents.extend_from_slice(&(encoded_key.len() as u32).to_le_bytes());
+		contents.extend_from_slice(&encoded_key);
+		contents.extend_from_slice(&(value.len() as u64).to_le_bytes());
+		contents.extend_from_slice(value);
+		fs::write(&temporary, &contents)?;
+		fs::rename(&temporary, &path)?;
+
+		let mut state = self.state.lock().expect("poisoned");
+		state.insert(key, file, size);
+		let evicted = self.evict(&mut state);
+		drop(state);
+		self.remove_files(evicted);
+
+		Ok(())
+	}
+
+	/// Remove entries matching the predicate.
+	pub fn invalidate_if(&self, predicate: impl Fn(&K) -> bool) {
+		let files = {
+			let mut state = self.state.lock().expect("poisoned");
+			let keys = state
+				.entries
+				.keys()
+				.filter(|key| predicate(key))
+				.cloned()
+				.collect::<Vec<_>>();
+			keys.iter()
+				.filter_map(|key| state.remove(key))
+				.map(|entry| entry.file)
+				.collect::<Vec<_>>()
+		};
+
+		self.remove_files(files);
+	}
+
+	pub fn clear(&self) {
+		self.invalidate_if(|_| true);
+	}
+
+	/// Evict the least recently used entries until the tier is within capacity,
+	/// returning the files to remove.
+	fn evict(&self, state: &mut DiskState<K>) -> Vec<String> {
+		let mut files = vec![];
+		while state.size > self.capacity {
+			let Some((_used, key)) = state.recency.pop_first() else {
+				break;
+			};
+			if let Some(entry) = state.entries.remove(&key) {
+				state.size -= entry.size;
+				files.push(entry.file);
+			}
+		}
+		files
+	}
+
+	fn remove_files(&self, files: Vec<String>) {
+		for file in files {
+			let path = self.directory.join(&file);
+			match fs::remove_file(&path) {
+				Ok(()) => {}
+				Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+				Err(error) => tracing::warn!(?path, ?error, "failed to remove cache file"),
+			}
+		}
+	}
+}
+
+/// Name of the file an entry is stored in, derived from its encoded key.
+fn file_name(encoded_key: &[u8]) -> String {
+	Sha256::digest(encoded_key)
+		.iter()
+		.map(|byte| format!("{byte:02x}"))
+		.collect()
+}
+
+fn read_encoded_key(reader: &mut impl Read) -> Result<Vec<u8>> {
+	let mut length = [0u8; 4];
+	reader.read_exact(&mut length)?;
+	let length = u32::from_le_bytes(length) as usize;
+	anyhow::ensure!(length <= KEY_LENGTH_MAX, "key length {length} is too long");
+
+	let mut encoded_key = vec![0u8; length];
+	reader.read_exact(&mut encoded_key)?;
+	Ok(encoded_key)
+}
+
+fn read_key<K: CacheKey>(path: &Path) -> Result<K> {
+	let mut file = fs::File::open(path)?;
+	let encoded_key = read_encoded_key(&mut file)?;
+	anyhow::ensure!(
+		path.file_name().and_then(|name| name.to_str()) == Some(file_name(&encoded_key).as_str()),
+		"file name does not match key"
+	);
+	Ok(serde_json::from_slice(&encoded_key)?)
+}
+
+fn read_value<K: CacheKey>(path: &Path, key: &K) -> Result<Vec<u8>> {
+	let mut file = fs::File::open(path)?;
+	let encoded_key = read_encoded_key(&mut file)?;
+	anyhow::ensure!(
+		encoded_key == serde_json::to_vec(key)?,
+		"cache file holds a different key"
+	);
+
+	let mut length = [0u8; 8];
+	file.read_exact(&mut length)?;
+	let length = u64::from_le_bytes(length);
+
+	let mut value = vec![];
+	file.read_to_end(&mut value)?;
+	anyhow::ensure!(
+		value.len() as u64 == length,
+		"cache file holds {} bytes of a {length} byte value",
+		value.len()
+	);
+	Ok(value)
+}
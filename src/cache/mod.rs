@@ -0,0 +1,4 @@
+mod cache;
+mod disk;
+
+pub use cache::{CacheKey, CacheMetrics, Caches, Config, TierConfig, TierMetrics, TieredCache};
@@ -0,0 +1,173 @@
+use std::{fmt, path::Path};
+
+use figment::Figment;
+use serde::de::DeserializeOwned;
+
+/// A single problem found while validating configuration.
+#[derive(Debug)]
+pub struct Problem {
+	pub key: String,
+	pub message: String,
+}
+
+impl fmt::Display for Problem {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(formatter, "{}: {}", self.key, self.message)
+	}
+}
+
+/// Every problem found during validation.
+#[derive(Debug)]
+pub struct Problems(pub Vec<Problem>);
+
+impl fmt::Display for Problems {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(
+			formatter,
+			"{} configuration problem(s) found:",
+			self.0.len()
+		)?;
+		for problem in &self.0 {
+			writeln!(formatter, "  - {problem}")?;
+		}
+		Ok(())
+	}
+}
+
+impl std::error::Error for Problems {}
+
+/// Collects configuration problems, so that all of them can be reported at once
+/// rather than failing on the first.
+#[derive(Debug, Default)]
+pub struct Validator {
+	path: Vec<String>,
+	problems: Vec<Problem>,
+}
+
+impl Validator {
+	/// Extract a configuration section, recording any deserialization errors as problems.
+	pub fn extract<T: DeserializeOwned>(&mut self, figment: &Figment, key: &str) -> Option<T> {
+		let errors = match figment.extract_inner::<T>(key) {
+			Ok(value) => return Some(value),
+			Err(errors) => errors,
+		};
+
+		for error in errors {
+			// Error paths may or may not already be rooted at the extracted key.
+			let mut path = key.split('.').map(String::from).collect::<Vec<_>>();
+			if error.path.starts_with(&path) {
+				path = error.path.clone();
+			} else {
+				path.extend(error.path.iter().cloned());
+			}
+
+			self.problems.push(Problem {
+				key: path.join("."),
+				message: error.kind.to_string(),
+			});
+		}
+
+		None
+	}
+
+	/// Extract a configuration section, falling back to its defaults if the
+	/// section is absent. Configs written before a section was added have no
+	/// entry for it.
+	pub fn extract_or_default<T: DeserializeOwned + Default>(
+		&mut self,
+		figment: &Figment,
+		key: &str,
+	) -> Option<T> {
+		match figment.contains(key) {
+			true => self.extract(figment, key),
+			false => Some(T::default()),
+		}
+	}
+
+	/// Run validation for a nested section of configuration.
+	pub fn scope(&mut self, key: &str, validate: impl FnOnce(&mut Self)) {
+		self.path.push(key.to_string());
+		validate(self);
+		self.path.pop();
+	}
+
+	/// Record a problem against the given key.
+	pub fn problem(&mut self, key: &str, message: impl fmt::Display) {
+		let key = self
+			.path
+			.iter()
+			.map(String::as_str)
+			.chain([key])
+			.collect::<Vec<_>>()
+			.join(".");
+
+		self.problems.push(Problem {
+			key,
+			message: message.to_string(),
+		});
+	}
+
+	/// Record a problem if the provided condition does not hold.
+	pub fn check(&mut self, key: &str, condition: bool, message: impl fmt::Display) {
+		if !condition {
+			self.problem(key, message);
+		}
+	}
+
+	/// Check that the value is a valid absolute URL.
+	pub fn url(&mut self, key: &str, value: &str) {
+		if let Err(error) = reqwest::Url::parse(value) {
+			self.problem(key, format!("invalid URL {value:?}: {error}"));
+		}
+	}
+
+	/// Check that the path exists.
+	pub fn exists(&mut self, key: &str, path: &Path) {
+		if !path.exists() {
+			self.problem(key, format!("{path:?} does not exist"));
+		}
+	}
+
+	/// Check that the path is a directory that is, or can be created, writable.
+	pub fn writable_directory(&mut self, key: &str, path: &Path) {
+		// Directories are created on demand - check the nearest one that exists.
+		let Some(existing) = path
+			.ancestors()
+			.map(|ancestor| match ancestor.as_os_str().is_empty() {
+				true => Path::new("."),
+				false => ancestor,
+			})
+			.find(|ancestor| ancestor.exists())
+		else {
+			self.problem(key, format!("{path:?} has no existing parent directory"));
+			return;
+		};
+
+		let metadata = match existing.metadata() {
+			Ok(metadata) => metadata,
+			Err(error) => {
+				self.problem(key, format!("could not read {existing:?}: {error}"));
+				return;
+			}
+		};
+
+		if !metadata.is_dir() {
+			self.problem(key, format!("{existing:?} is not a directory"));
+			return;
+		}
+
+		// NOTE: This only checks permission bits, it's not a guarantee that writes
+		// will succeed for the current user.
+		if metadata.permissions().readonly() {
+			self.problem(key, format!("{existing:?} is not writable"));
+		}
+	}
+
+	/// Finish validation, failing if any problems were recorded.
+	pub fn finish(self) -> Result<(), Problems> {
+		match self.problems.is_empty() {
+			true => Ok(()),
+			false => Err(Problems(self.problems)),
+		}
+	}
+}
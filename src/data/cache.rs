@@ -0,0 +1,100 @@
+use std::{
+	io::{self, Read, Seek},
+	sync::Arc,
+};
+
+use ironworks::Resource;
+
+use crate::{
+	cache::{Caches, TierConfig, TieredCache},
+	version::VersionKey,
+};
+
+type PageKey = (VersionKey, String);
+
+/// Cache of raw EXD page data, keyed by version and file path. File paths for
+/// pages encode the sheet, page start, and language.
+#[derive(Clone)]
+pub struct PageCache {
+	cache: TieredCache<PageKey>,
+}
+
+impl PageCache {
+	pub fn new(config: TierConfig, caches: &Caches) -> anyhow::Result<Self> {
+		Ok(Self {
+			cache: caches.build("page", config)?,
+		})
+	}
+}
+
+/// Resource wrapper that serves EXD pages through a shared page cache, reading
+/// them from the inner resource on first access.
+pub struct CachedResource<R> {
+	version: VersionKey,
+	cache: PageCache,
+	inner: R,
+}
+
+impl<R> CachedResource<R> {
+	pub fn new(version: VersionKey, cache: PageCache, inner: R) -> Self {
+		Self {
+			version,
+			cache,
+			inner,
+		}
+	}
+}
+
+impl<R: Resource> Resource for CachedResource<R> {
+	type File = CachedFile<R::File>;
+
+	fn version(&self, path: &str) -> ironworks::Result<String> {
+		self.inner.version(path)
+	}
+
+	fn file(&self, path: &str) -> ironworks::Result<Self::File> {
+		// Only EXD pages are cached - everything else is read once per sheet at most.
+		if !(path.starts_with("exd/") && path.ends_with(".exd")) {
+			return Ok(CachedFile::Uncached(self.inner.file(path)?));
+		}
+
+		let key = (self.version, path.to_string());
+		if let Some(data) = self.cache.cache.get(&key) {
+			return Ok(CachedFile::Cached(io::Cursor::new(data)));
+		}
+
+		let mut buffer = Vec::new();
+		self.inner
+			.file(path)?
+			.read_to_end(&mut buffer)
+			.map_err(|error| ironworks::Error::Resource(error.into()))?;
+		let data = Arc::<[u8]>::from(buffer);
+		self.cache.cache.insert(key, data.clone());
+
+		Ok(CachedFile::Cached(io::Cursor::new(data)))
+	}
+}
+
+#[derive(Debug)]
+pub enum CachedFile<F> {
+	Cached(io::Cursor<Arc<[u8]>>),
+	Uncached(F),
+}
+
+impl<F: Read> Read for CachedFile<F> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		match self {
+			Self::Cached(cursor) => cursor.read(buf),
+			Self::Uncached(file) => file.read(buf),
+		}
+	}
+}
+
+impl<F: Seek> Seek for CachedFile<F> {
+	fn seek(&mut self, position: io::SeekFrom) -> io::Result<u64> {
+		match self {
+			Self::Cached(cursor) => cursor.seek(position),
+			Self::Uncached(file) => file.seek(position),
+		}
+	}
+}
@@ -0,0 +1,47 @@
+use std::collections::BTreeSet;
+
+use ironworks::{excel::Excel, Ironworks};
+
+use crate::utility::anyhow::Anyhow;
+
+use super::{error::Result, path};
+
+/// Sheets that differ between two versions, by comparison of their headers.
+/// Sheets with identical headers may still have changed row data.
+#[derive(Debug, Default)]
+pub struct SheetChanges {
+	pub added: Vec<String>,
+	pub removed: Vec<String>,
+	/// Sheets present in both versions, with differing headers.
+	pub changed: Vec<String>,
+}
+
+pub fn build_sheet_changes(
+	(base_ironworks, base_excel): (&Ironworks, &Excel),
+	(ironworks, excel): (&Ironworks, &Excel),
+) -> Result<SheetChanges> {
+	let sheet_names = |excel: &Excel| -> Result<BTreeSet<String>> {
+		let list = excel.list().anyhow()?;
+		Ok(list.iter().map(|name| name.into_owned()).collect())
+	};
+	let base_names = sheet_names(base_excel)?;
+	let names = sheet_names(excel)?;
+
+	let header = |ironworks: &Ironworks, name: &str| -> Result<Vec<u8>> {
+		Ok(ironworks.file::<Vec<u8>>(&path::exh(name)).anyhow()?)
+	};
+
+	let mut changes = SheetChanges {
+		added: names.difference(&base_names).cloned().collect(),
+		removed: base_names.difference(&names).cloned().collect(),
+		changed: vec![],
+	};
+
+	for name in names.intersection(&base_names) {
+		if header(base_ironworks, name)? != header(ironworks, name)? {
+			changes.changed.push(name.clone());
+		}
+	}
+
+	Ok(changes)
+}
@@ -1,19 +1,84 @@
 use std::{
 	collections::{HashMap, HashSet},
-	sync::{Arc, RwLock},
+	path::PathBuf,
+	sync::{Arc, Mutex, RwLock},
 };
 
 use anyhow::Context;
-use ironworks::{excel::Excel, sqpack::SqPack, zipatch, Ironworks};
+use ironworks::{excel::Excel, Ironworks, Resource};
+use serde::Deserialize;
 use tokio::{
 	select,
 	sync::{broadcast, watch},
 };
 use tokio_util::sync::CancellationToken;
 
-use crate::version::{self, VersionKey, VersionMessage};
+use crate::{
+	cache::{self, Caches},
+	config::Validator,
+	version::{self, VersionKey, VersionMessage},
+};
+
+use super::{
+	cache::{CachedResource, PageCache},
+	changes::{build_sheet_changes, SheetChanges},
+	error::{Error, Result},
+	fixture::FixtureResource,
+	hash::{build_sheet_hashes, SheetHashes},
+	header::{read_sheet_header, SheetHeader},
+	pool::{self, Pool, PoolMetrics},
+	source::{DirectorySource, PatchSource, Source},
+	summary::{build_summary, Summary},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+	#[serde(default = "default_cache")]
+	cache: cache::TierConfig,
+
+	#[serde(default)]
+	pool: pool::Config,
+
+	/// Directory of JSON or CSV sheet fixtures. When set, all versions serve
+	/// excel data from the fixtures rather than from game data.
+	#[serde(default)]
+	fixture: Option<PathBuf>,
+
+	/// Directory of game files extracted ahead of time, containing a directory
+	/// per version key. When set, versions are read from this directory rather
+	/// than from their patches.
+	#[serde(default)]
+	extracted: Option<PathBuf>,
+}
+
+fn default_cache() -> cache::TierConfig {
+	cache::TierConfig::default().with_memory(256 * 1024 * 1024)
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			cache: default_cache(),
+			pool: pool::Config::default(),
+			fixture: None,
+			extracted: None,
+		}
+	}
+}
 
-use super::error::{Error, Result};
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.scope("pool", |validator| self.pool.validate(validator));
+
+		if let Some(fixture) = &self.fixture {
+			validator.exists("fixture", fixture);
+		}
+
+		if let Some(extracted) = &self.extracted {
+			validator.exists("extracted", extracted);
+		}
+	}
+}
 
 enum OnKnown {
 	Skip,
@@ -23,21 +88,36 @@ enum OnKnown {
 pub struct Data {
 	channel: watch::Sender<Vec<VersionKey>>,
 
-	// Root ZiPatch instance, acts as a LUT cache
-	zipatch: zipatch::ZiPatch,
+	// Backend providing the game files of each version.
+	source: Box<dyn Source>,
+
+	// Page cache shared between all versions.
+	page_cache: PageCache,
+
+	// Pool for blocking reads against version data.
+	pool: Pool,
 
 	versions: RwLock<HashMap<VersionKey, Arc<Version>>>,
 }
 
 impl Data {
-	pub fn new() -> Self {
+	pub fn new(config: Config, caches: &Caches) -> Result<Self> {
 		let (sender, _receiver) = watch::channel(vec![]);
 
-		Data {
+		// Fixtures take precedence over game data from any other source.
+		let source: Box<dyn Source> = match (config.fixture, config.extracted) {
+			(Some(directory), _) => Box::new(FixtureResource::load(&directory)?),
+			(None, Some(directory)) => Box::new(DirectorySource::new(directory)),
+			(None, None) => Box::<PatchSource>::default(),
+		};
+
+		Ok(Data {
 			channel: sender,
-			zipatch: zipatch::ZiPatch::new().with_persisted_lookups(),
+			source,
+			page_cache: PageCache::new(config.cache, caches)?,
+			pool: Pool::new(config.pool),
 			versions: Default::default(),
-		}
+		})
 	}
 
 	pub fn ready(&self) -> bool {
@@ -46,6 +126,19 @@ impl Data {
 		self.versions.read().expect("poisoned").len() > 0
 	}
 
+	/// Run blocking work, such as excel reads, on the data pool.
+	pub async fn blocking<F, T>(&self, function: F) -> Result<T>
+	where
+		F: FnOnce() -> T + Send + 'static,
+		T: Send + 'static,
+	{
+		self.pool.run(function).await
+	}
+
+	pub fn pool_metrics(&self) -> PoolMetrics {
+		self.pool.metrics()
+	}
+
 	pub fn subscribe(&self) -> watch::Receiver<Vec<VersionKey>> {
 		self.channel.subscribe()
 	}
@@ -122,34 +215,27 @@ impl Data {
 			.version(version_key)
 			.context("version does not exist")?;
 
-		let view = version
-			.repositories
-			.into_iter()
-			.map(|repository| zipatch::PatchRepository {
-				patches: repository
-					.patches
-					.into_iter()
-					.map(|patch| zipatch::Patch {
-						path: patch.path,
-						name: patch.name,
-					})
-					.collect(),
-			})
-			.zip(0u8..)
-			.fold(self.zipatch.view(), |builder, (repository, index)| {
-				builder.with_repository(index, repository)
-			})
-			.build();
+		// Version keys are derived from their content, so any pages cached for the
+		// key - including those kept on disk from a previous run - are still valid.
+		let version = self.build_version(version_key, version)?;
 
-		// Build a version and save it out to the struct.
-		let version = Version::new(view);
+		// Save the version out to the struct.
+		let version = Arc::new(version);
 		self.versions
 			.write()
 			.expect("poisoned")
-			.insert(version_key, Arc::new(version));
+			.insert(version_key, version.clone());
 
 		tracing::debug!(key = %version_key, "version prepared");
 
+		// Summaries touch every sheet header in the version - build it in the
+		// background now so it's cached by the time anyone asks for it.
+		tokio::task::spawn_blocking(move || {
+			if let Err(error) = version.summary() {
+				tracing::warn!(key = %version_key, ?error, "failed to build version summary");
+			}
+		});
+
 		// Broadcast the update.
 		// NOTE: This is performed after each version rather than when all versions
 		// are complete to allow other services to begin processing an early-completing
@@ -159,6 +245,16 @@ impl Data {
 		Ok(())
 	}
 
+	fn build_version(&self, key: VersionKey, version: version::Version) -> Result<Version> {
+		let resource = self.source.resource(key, version)?;
+		let version = match self.source.cached() {
+			true => Version::new(CachedResource::new(key, self.page_cache.clone(), resource)),
+			false => Version::new(resource),
+		};
+
+		Ok(version)
+	}
+
 	pub fn version(&self, version: VersionKey) -> Result<Arc<Version>> {
 		let versions = self.versions.read().expect("poisoned");
 
@@ -168,6 +264,26 @@ impl Data {
 			.cloned()
 	}
 
+	/// Compare the sheet headers of a known version against those of a version
+	/// that has not been prepared, such as the result of a planned update. All
+	/// of the candidate's patches must be available locally.
+	pub fn sheet_changes(
+		&self,
+		base: VersionKey,
+		candidate: version::Version,
+	) -> Result<SheetChanges> {
+		let base = self.version(base)?;
+
+		// The candidate is read once, bypassing the page cache.
+		let candidate_key = VersionKey::from(&candidate);
+		let candidate = Version::new(self.source.resource(candidate_key, candidate)?);
+
+		build_sheet_changes(
+			(&base.ironworks, &base.excel),
+			(&candidate.ironworks, &candidate.excel),
+		)
+	}
+
 	fn broadcast_version_list(&self) {
 		let versions = self.versions.read().expect("poisoned");
 		let keys = versions.keys().copied().collect::<Vec<_>>();
@@ -186,13 +302,20 @@ impl Data {
 pub struct Version {
 	ironworks: Arc<Ironworks>,
 	excel: Arc<Excel<'static>>,
+	summary: Mutex<Option<Arc<Summary>>>,
+	hashes: Mutex<Option<Arc<SheetHashes>>>,
 }
 
 impl Version {
-	fn new(view: zipatch::View) -> Self {
-		let ironworks = Arc::new(Ironworks::new().with_resource(SqPack::new(view)));
+	fn new(resource: impl Resource + Send + Sync + 'static) -> Self {
+		let ironworks = Arc::new(Ironworks::new().with_resource(resource));
 		let excel = Arc::new(Excel::new(ironworks.clone()));
-		Self { ironworks, excel }
+		Self {
+			ironworks,
+			excel,
+			summary: Default::default(),
+			hashes: Default::default(),
+		}
 	}
 
 	pub fn ironworks(&self) -> Arc<Ironworks> {
@@ -202,4 +325,41 @@ impl Version {
 	pub fn excel(&self) -> Arc<Excel<'static>> {
 		self.excel.clone()
 	}
+
+	/// Get a summary of the excel data in this version. The summary is built on
+	/// first access, and cached for the lifetime of the version.
+	pub fn summary(&self) -> Result<Arc<Summary>> {
+		// Holding the lock over the build is intentional, concurrent callers should
+		// wait on the in-progress build rather than duplicating the work.
+		let mut summary = self.summary.lock().expect("poisoned");
+		if let Some(summary) = summary.as_ref() {
+			return Ok(summary.clone());
+		}
+
+		let built = Arc::new(build_summary(&self.ironworks, &self.excel)?);
+		*summary = Some(built.clone());
+
+		Ok(built)
+	}
+
+	/// Get hashes of the data of every sheet in this version. This reads every
+	/// page of every sheet, so is only built on first access, and cached for the
+	/// lifetime of the version.
+	pub fn sheet_hashes(&self) -> Result<Arc<SheetHashes>> {
+		let mut hashes = self.hashes.lock().expect("poisoned");
+		if let Some(hashes) = hashes.as_ref() {
+			return Ok(hashes.clone());
+		}
+
+		let built = Arc::new(build_sheet_hashes(&self.ironworks, &self.excel)?);
+		*hashes = Some(built.clone());
+
+		Ok(built)
+	}
+
+	/// Read the header of a sheet, as both parsed and raw bytes. The sheet is
+	/// expected to exist.
+	pub fn sheet_header(&self, sheet: &str) -> Result<SheetHeader> {
+		read_sheet_header(&self.ironworks, sheet)
+	}
 }
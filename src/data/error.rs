@@ -1,4 +1,7 @@
-use crate::version::VersionKey;
+use crate::{
+	error::{classify_failure, Classify, ErrorKind},
+	version::VersionKey,
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -9,4 +12,13 @@ pub enum Error {
 	Failure(#[from] anyhow::Error),
 }
 
+impl Classify for Error {
+	fn kind(&self) -> ErrorKind {
+		match self {
+			Self::UnknownVersion(..) => ErrorKind::Invalid,
+			Self::Failure(inner) => classify_failure(inner),
+		}
+	}
+}
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
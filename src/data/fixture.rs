@@ -0,0 +1,426 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs, io,
+	path::Path,
+	sync::Arc,
+};
+
+use anyhow::Context;
+use ironworks::Resource;
+use serde::Deserialize;
+
+use crate::read::LanguageString;
+
+use super::path;
+
+/// Sheet fixture, as read from a JSON file named after the sheet, or built from
+/// CSV files named after the sheet and language.
+#[derive(Debug, Deserialize)]
+struct FixtureSheet {
+	#[serde(default)]
+	kind: FixtureKind,
+
+	columns: Vec<FixtureColumn>,
+
+	/// Rows of the sheet, keyed by language. Unlocalised sheets should use `none`.
+	rows: BTreeMap<String, Vec<FixtureRow>>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FixtureKind {
+	#[default]
+	Default,
+	Subrows,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, strum::EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+enum FixtureColumn {
+	String,
+	Bool,
+	I8,
+	U8,
+	I16,
+	U16,
+	I32,
+	U32,
+	F32,
+	I64,
+	U64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixtureRow {
+	id: u32,
+	#[serde(default)]
+	subrow: u16,
+	fields: Vec<serde_json::Value>,
+}
+
+impl FixtureColumn {
+	fn kind(&self) -> u16 {
+		match self {
+			Self::String => 0x0,
+			Self::Bool => 0x1,
+			Self::I8 => 0x2,
+			Self::U8 => 0x3,
+			Self::I16 => 0x4,
+			Self::U16 => 0x5,
+			Self::I32 => 0x6,
+			Self::U32 => 0x7,
+			Self::F32 => 0x9,
+			Self::I64 => 0xA,
+			Self::U64 => 0xB,
+		}
+	}
+
+	fn size(&self) -> u16 {
+		match self {
+			Self::Bool | Self::I8 | Self::U8 => 1,
+			Self::I16 | Self::U16 => 2,
+			Self::String | Self::I32 | Self::U32 | Self::F32 => 4,
+			Self::I64 | Self::U64 => 8,
+		}
+	}
+}
+
+/// Resource serving excel data built from a directory of sheet fixtures, for
+/// running the stack without game data. Fixtures are encoded into the game's
+/// excel formats when loaded.
+///
+/// Fixtures may be JSON files named after their sheet, or CSV files named
+/// `<sheet>.<language>.csv` (or `<sheet>.csv` for unlocalised sheets). The
+/// header of a CSV fixture is `id`, then `subrow` for subrow sheets, followed by
+/// the kind of each column, i.e. `id,u32,string`.
+#[derive(Clone)]
+pub struct FixtureResource {
+	files: Arc<HashMap<String, Arc<[u8]>>>,
+}
+
+impl FixtureResource {
+	pub fn load(directory: &Path) -> anyhow::Result<Self> {
+		let mut sheets = BTreeMap::<String, FixtureSheet>::new();
+
+		for entry in fs::read_dir(directory)
+			.with_context(|| format!("failed to read fixture directory {directory:?}"))?
+		{
+			let path = entry?.path();
+			let extension = path.extension().and_then(|extension| extension.to_str());
+			if !matches!(extension, Some("json" | "csv")) {
+				continue;
+			}
+
+			let stem = path
+				.file_stem()
+				.and_then(|stem| stem.to_str())
+				.context("fixture file name is not valid UTF-8")?;
+
+			if extension == Some("json") {
+				let sheet: FixtureSheet = serde_json::from_reader(fs::File::open(&path)?)
+					.with_context(|| format!("failed to parse fixture {path:?}"))?;
+				if sheets.insert(stem.to_string(), sheet).is_some() {
+					anyhow::bail!("fixture {stem} is defined more than once");
+				}
+				continue;
+			}
+
+			let (name, language) = stem.split_once('.').unwrap_or((stem, "none"));
+			let (kind, columns, rows) =
+				read_csv(&path).with_context(|| format!("failed to parse fixture {path:?}"))?;
+
+			let sheet = sheets
+				.entry(name.to_string())
+				.or_insert_with(|| FixtureSheet {
+					kind,
+					columns: columns.clone(),
+					rows: BTreeMap::new(),
+				});
+			if sheet.kind != kind || sheet.columns != columns {
+				anyhow::bail!("fixture {name} has mismatched columns across files");
+			}
+			if sheet.rows.insert(language.to_string(), rows).is_some() {
+				anyhow::bail!("fixture {name} is defined more than once for {language}");
+			}
+		}
+
+		let mut files = HashMap::new();
+		for (name, sheet) in &sheets {
+			encode_sheet(name, sheet, &mut files)
+				.with_context(|| format!("failed to encode fixture {name}"))?;
+		}
+
+		// Sheets are already in name order, courtesy of the map.
+		let mut list = String::from("EXLT,2\r\n");
+		for (index, name) in sheets.keys().enumerate() {
+			list.push_str(&format!("{name},{index}\r\n"));
+		}
+		files.insert("exd/root.exl".to_string(), list.into_bytes().into());
+
+		Ok(Self {
+			files: Arc::new(files),
+		})
+	}
+}
+
+/// Read the rows of a single language from a CSV fixture.
+fn read_csv(path: &Path) -> anyhow::Result<(FixtureKind, Vec<FixtureColumn>, Vec<FixtureRow>)> {
+	let mut reader = csv::Reader::from_path(path)?;
+
+	let headers = reader.headers()?.clone();
+	let mut headers = headers.iter();
+	if headers.next() != Some("id") {
+		anyhow::bail!("first column must be id");
+	}
+	let mut headers = headers.peekable();
+	let kind = match headers.next_if_eq(&"subrow") {
+		Some(_) => FixtureKind::Subrows,
+		None => FixtureKind::Default,
+	};
+	let columns = headers
+		.map(|header| {
+			header
+				.parse::<FixtureColumn>()
+				.with_context(|| format!("unknown column kind {header:?}"))
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+
+	let rows = reader
+		.records()
+		.map(|record| -> anyhow::Result<_> {
+			let record = record?;
+			let mut cells = record.iter();
+			let mut next = || cells.next().context("record is missing fields");
+
+			let id = next()?.parse::<u32>().context("invalid row id")?;
+			let subrow = match kind {
+				FixtureKind::Default => 0,
+				FixtureKind::Subrows => next()?.parse::<u16>().context("invalid subrow id")?,
+			};
+			let fields = columns
+				.iter()
+				.map(|column| {
+					let cell = next()?;
+					parse_csv_field(*column, cell)
+						.with_context(|| format!("invalid field {cell:?} in row {id}:{subrow}"))
+				})
+				.collect::<anyhow::Result<Vec<_>>>()?;
+
+			Ok(FixtureRow { id, subrow, fields })
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+
+	Ok((kind, columns, rows))
+}
+
+/// Parse a CSV cell into the JSON value a fixture field of the column's kind
+/// would hold.
+fn parse_csv_field(column: FixtureColumn, cell: &str) -> anyhow::Result<serde_json::Value> {
+	use FixtureColumn as C;
+	let value = match column {
+		C::String => cell.into(),
+		C::Bool => cell.parse::<bool>()?.into(),
+		C::I8 | C::I16 | C::I32 | C::I64 => cell.parse::<i64>()?.into(),
+		C::U8 | C::U16 | C::U32 | C::U64 => cell.parse::<u64>()?.into(),
+		C::F32 => cell.parse::<f64>()?.into(),
+	};
+	Ok(value)
+}
+
+impl Resource for FixtureResource {
+	type File = io::Cursor<Arc<[u8]>>;
+
+	fn version(&self, _path: &str) -> ironworks::Result<String> {
+		Ok("fixture".to_string())
+	}
+
+	fn file(&self, path: &str) -> ironworks::Result<Self::File> {
+		self.files
+			.get(path)
+			.map(|data| io::Cursor::new(data.clone()))
+			.ok_or_else(|| ironworks::Error::NotFound(ironworks::ErrorValue::Path(path.into())))
+	}
+}
+
+fn encode_sheet(
+	name: &str,
+	sheet: &FixtureSheet,
+	files: &mut HashMap<String, Arc<[u8]>>,
+) -> anyhow::Result<()> {
+	// Lay columns out sequentially, padding the row to a 4-byte boundary.
+	let mut offsets = Vec::with_capacity(sheet.columns.len());
+	let mut row_size = 0u16;
+	for column in &sheet.columns {
+		offsets.push(row_size);
+		row_size += column.size();
+	}
+	let row_size = row_size.next_multiple_of(4);
+
+	let languages = sheet
+		.rows
+		.keys()
+		.map(|language| {
+			language
+				.parse::<LanguageString>()
+				.map(ironworks::excel::Language::from)
+				.map_err(anyhow::Error::from)
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+
+	// All languages are expected to share the same row IDs - use the first as
+	// the source of truth for the page definition.
+	let mut row_ids = sheet
+		.rows
+		.values()
+		.next()
+		.map(|rows| rows.iter().map(|row| row.id).collect::<Vec<_>>())
+		.unwrap_or_default();
+	row_ids.sort_unstable();
+	row_ids.dedup();
+	let row_count = u32::try_from(row_ids.len())?;
+
+	// Pages span the full ID range from their first row to their last, whether
+	// or not every ID in between is present.
+	let (start_id, page_row_count) = match (row_ids.first(), row_ids.last()) {
+		(Some(first), Some(last)) => (*first, last - first + 1),
+		_ => (0, 0),
+	};
+
+	// Header.
+	let mut header = Vec::new();
+	header.extend_from_slice(b"EXHF");
+	header.extend_from_slice(&3u16.to_be_bytes());
+	header.extend_from_slice(&row_size.to_be_bytes());
+	header.extend_from_slice(&u16::try_from(sheet.columns.len())?.to_be_bytes());
+	header.extend_from_slice(&1u16.to_be_bytes());
+	header.extend_from_slice(&u16::try_from(languages.len())?.to_be_bytes());
+	header.extend_from_slice(&[0, 0, 0]);
+	header.push(match sheet.kind {
+		FixtureKind::Default => 1,
+		FixtureKind::Subrows => 2,
+	});
+	header.extend_from_slice(&[0, 0]);
+	header.extend_from_slice(&row_count.to_be_bytes());
+	header.extend_from_slice(&[0; 8]);
+	for (column, offset) in sheet.columns.iter().zip(&offsets) {
+		header.extend_from_slice(&column.kind().to_be_bytes());
+		header.extend_from_slice(&offset.to_be_bytes());
+	}
+	header.extend_from_slice(&start_id.to_be_bytes());
+	header.extend_from_slice(&page_row_count.to_be_bytes());
+	for language in &languages {
+		// Languages are the only little-endian values in the header.
+		header.extend_from_slice(&(*language as u16).to_le_bytes());
+	}
+	files.insert(path::exh(name), header.into());
+
+	// Pages - one per language.
+	for (language, rows) in languages.iter().zip(sheet.rows.values()) {
+		let data = encode_page(sheet, &offsets, row_size, rows)?;
+		files.insert(path::exd(name, start_id, *language), data.into());
+	}
+
+	Ok(())
+}
+
+fn encode_page(
+	sheet: &FixtureSheet,
+	offsets: &[u16],
+	row_size: u16,
+	rows: &[FixtureRow],
+) -> anyhow::Result<Vec<u8>> {
+	// Group subrows under their parent row.
+	let mut grouped = BTreeMap::<u32, Vec<&FixtureRow>>::new();
+	for row in rows {
+		grouped.entry(row.id).or_default().push(row);
+	}
+
+	let mut row_data = Vec::new();
+	let mut index = Vec::new();
+	let header_size = 0x20 + 8 * u32::try_from(grouped.len())?;
+
+	for (row_id, mut subrows) in grouped {
+		subrows.sort_by_key(|row| row.subrow);
+		if sheet.kind == FixtureKind::Default && subrows.len() > 1 {
+			anyhow::bail!("row {row_id} specified multiple times in non-subrow sheet");
+		}
+
+		let mut fixed = Vec::new();
+		let mut strings = Vec::new();
+		for row in &subrows {
+			if sheet.kind == FixtureKind::Subrows {
+				fixed.extend_from_slice(&row.subrow.to_be_bytes());
+			}
+			let mut data = vec![0u8; usize::from(row_size)];
+			for ((column, offset), value) in sheet.columns.iter().zip(offsets).zip(&row.fields) {
+				let offset = usize::from(*offset);
+				let bytes = encode_field(*column, value, &mut strings)
+					.with_context(|| format!("invalid field in row {row_id}:{}", row.subrow))?;
+				data[offset..offset + bytes.len()].copy_from_slice(&bytes);
+			}
+			fixed.extend_from_slice(&data);
+		}
+
+		let mut body = fixed;
+		body.extend_from_slice(&strings);
+		while body.len() % 4 != 0 {
+			body.push(0);
+		}
+
+		let offset = header_size + u32::try_from(row_data.len())?;
+		index.extend_from_slice(&row_id.to_be_bytes());
+		index.extend_from_slice(&offset.to_be_bytes());
+
+		row_data.extend_from_slice(&u32::try_from(body.len())?.to_be_bytes());
+		row_data.extend_from_slice(&u16::try_from(subrows.len())?.to_be_bytes());
+		row_data.extend_from_slice(&body);
+	}
+
+	let mut page = Vec::new();
+	page.extend_from_slice(b"EXDF");
+	page.extend_from_slice(&2u16.to_be_bytes());
+	page.extend_from_slice(&[0, 0]);
+	page.extend_from_slice(&u32::try_from(index.len())?.to_be_bytes());
+	page.extend_from_slice(&u32::try_from(row_data.len())?.to_be_bytes());
+	page.extend_from_slice(&[0; 16]);
+	page.extend_from_slice(&index);
+	page.extend_from_slice(&row_data);
+
+	Ok(page)
+}
+
+fn encode_field(
+	column: FixtureColumn,
+	value: &serde_json::Value,
+	strings: &mut Vec<u8>,
+) -> anyhow::Result<Vec<u8>> {
+	let int = || value.as_i64().context("expected integer");
+	let uint = || value.as_u64().context("expected unsigned integer");
+
+	let bytes = match column {
+		FixtureColumn::String => {
+			let string = value.as_str().context("expected string")?;
+			// String fields point into the string section following the fixed row data.
+			let offset = u32::try_from(strings.len())?;
+			strings.extend_from_slice(string.as_bytes());
+			strings.push(0);
+			offset.to_be_bytes().to_vec()
+		}
+		FixtureColumn::Bool => vec![u8::from(value.as_bool().context("expected bool")?)],
+		FixtureColumn::I8 => i8::try_from(int()?)?.to_be_bytes().to_vec(),
+		FixtureColumn::U8 => u8::try_from(uint()?)?.to_be_bytes().to_vec(),
+		FixtureColumn::I16 => i16::try_from(int()?)?.to_be_bytes().to_vec(),
+		FixtureColumn::U16 => u16::try_from(uint()?)?.to_be_bytes().to_vec(),
+		FixtureColumn::I32 => i32::try_from(int()?)?.to_be_bytes().to_vec(),
+		FixtureColumn::U32 => u32::try_from(uint()?)?.to_be_bytes().to_vec(),
+		FixtureColumn::I64 => int()?.to_be_bytes().to_vec(),
+		FixtureColumn::U64 => uint()?.to_be_bytes().to_vec(),
+		FixtureColumn::F32 => {
+			let float = value.as_f64().context("expected number")? as f32;
+			float.to_be_bytes().to_vec()
+		}
+	};
+
+	Ok(bytes)
+}
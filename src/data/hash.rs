@@ -0,0 +1,45 @@
+use std::{collections::BTreeMap, hash::Hasher};
+
+use ironworks::{
+	excel::{Excel, Language},
+	file::exh,
+	Ironworks,
+};
+use seahash::SeaHasher;
+
+use crate::utility::anyhow::Anyhow;
+
+use super::{error::Result, path};
+
+/// Hashes of the raw data of each sheet in a version, keyed by sheet name.
+/// Hashes are stable, a sheet with identical data in two versions will share a hash.
+pub type SheetHashes = BTreeMap<String, u64>;
+
+pub fn build_sheet_hashes(ironworks: &Ironworks, excel: &Excel) -> Result<SheetHashes> {
+	let list = excel.list().anyhow()?;
+
+	list.iter()
+		.map(|name| Ok((name.to_string(), hash_sheet(ironworks, &name)?)))
+		.collect()
+}
+
+fn hash_sheet(ironworks: &Ironworks, name: &str) -> Result<u64> {
+	let mut hasher = SeaHasher::new();
+
+	let header_path = path::exh(name);
+	hasher.write(&ironworks.file::<Vec<u8>>(&header_path).anyhow()?);
+
+	// Header languages are unordered - sort them so the page order is stable.
+	let header = ironworks.file::<exh::ExcelHeader>(&header_path).anyhow()?;
+	let mut languages = header.languages().iter().copied().collect::<Vec<_>>();
+	languages.sort_by_key(|language| *language as u8);
+
+	for page in header.pages() {
+		for language in &languages {
+			let page_path = path::exd(name, page.start_id(), *language);
+			hasher.write(&ironworks.file::<Vec<u8>>(&page_path).anyhow()?);
+		}
+	}
+
+	Ok(hasher.finish())
+}
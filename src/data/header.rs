@@ -0,0 +1,21 @@
+use ironworks::{file::exh, Ironworks};
+
+use crate::utility::anyhow::Anyhow;
+
+use super::{error::Result, path};
+
+/// Header of a sheet, alongside the raw bytes it was parsed from.
+#[derive(Debug)]
+pub struct SheetHeader {
+	pub header: exh::ExcelHeader,
+	pub raw: Vec<u8>,
+}
+
+pub fn read_sheet_header(ironworks: &Ironworks, name: &str) -> Result<SheetHeader> {
+	let header_path = path::exh(name);
+
+	Ok(SheetHeader {
+		header: ironworks.file::<exh::ExcelHeader>(&header_path).anyhow()?,
+		raw: ironworks.file::<Vec<u8>>(&header_path).anyhow()?,
+	})
+}
@@ -1,7 +1,22 @@
+mod cache;
+mod changes;
 mod data;
 mod error;
+mod fixture;
+mod hash;
+mod header;
+mod path;
+mod pool;
+mod source;
+mod summary;
 
 pub use {
-	data::{Data, Version},
+	changes::SheetChanges,
+	data::{Config, Data, Version},
 	error::Error,
+	hash::SheetHashes,
+	header::SheetHeader,
+	pool::PoolMetrics,
+	source::{Source, SourceFile, SourceResource},
+	summary::{SheetSummary, Summary},
 };
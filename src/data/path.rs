@@ -0,0 +1,21 @@
+use ironworks::excel::Language;
+
+/// Path to the header file of a sheet.
+pub fn exh(sheet: &str) -> String {
+	format!("exd/{sheet}.exh")
+}
+
+/// Path to the page file of a sheet starting at the given row, for a language.
+pub fn exd(sheet: &str, start_id: u32, language: Language) -> String {
+	let suffix = match language {
+		Language::None => "",
+		Language::Japanese => "_ja",
+		Language::English => "_en",
+		Language::German => "_de",
+		Language::French => "_fr",
+		Language::ChineseSimplified => "_chs",
+		Language::ChineseTraditional => "_cht",
+		Language::Korean => "_ko",
+	};
+	format!("exd/{sheet}_{start_id}{suffix}.exd")
+}
@@ -0,0 +1,116 @@
+use std::sync::{
+	atomic::{AtomicU64, AtomicUsize, Ordering},
+	Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+use crate::config::Validator;
+
+use super::error::Result;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+	/// Maximum number of blocking tasks that may run concurrently.
+	#[serde(default = "default_size")]
+	size: usize,
+}
+
+fn default_size() -> usize {
+	16
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			size: default_size(),
+		}
+	}
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.check("size", self.size > 0, "must be at least 1");
+	}
+}
+
+/// Point-in-time view of the blocking pool's load.
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolMetrics {
+	pub size: usize,
+	pub active: usize,
+	pub queued: usize,
+	pub completed: u64,
+}
+
+/// Bounded pool for blocking work such as excel reads, keeping slow disk IO off
+/// the async runtime's worker threads.
+pub struct Pool {
+	size: usize,
+	semaphore: Arc<Semaphore>,
+	queued: AtomicUsize,
+	completed: Arc<AtomicU64>,
+}
+
+impl Pool {
+	pub fn new(config: Config) -> Self {
+		Self {
+			size: config.size,
+			semaphore: Arc::new(Semaphore::new(config.size)),
+			queued: AtomicUsize::new(0),
+			completed: Default::default(),
+		}
+	}
+
+	/// Run a blocking function on the pool, waiting for a free slot if the pool
+	/// is at capacity.
+	pub async fn run<F, T>(&self, function: F) -> Result<T>
+	where
+		F: FnOnce() -> T + Send + 'static,
+		T: Send + 'static,
+	{
+		let queued = Queued::new(&self.queued);
+		let permit = self.semaphore.clone().acquire_owned().await;
+		drop(queued);
+		let permit = permit.expect("pool semaphore should never be closed");
+
+		let completed = self.completed.clone();
+		let handle = tokio::task::spawn_blocking(move || {
+			let output = function();
+			completed.fetch_add(1, Ordering::Relaxed);
+			drop(permit);
+			output
+		});
+
+		let output = handle.await.map_err(anyhow::Error::from)?;
+		Ok(output)
+	}
+
+	pub fn metrics(&self) -> PoolMetrics {
+		PoolMetrics {
+			size: self.size,
+			active: self.size - self.semaphore.available_permits(),
+			queued: self.queued.load(Ordering::Relaxed),
+			completed: self.completed.load(Ordering::Relaxed),
+		}
+	}
+}
+
+/// Counts a caller as queued for as long as it's held. Callers may be dropped
+/// while waiting for a slot - i.e. on client disconnect - so the count is
+/// released on drop rather than after the wait.
+struct Queued<'a>(&'a AtomicUsize);
+
+impl<'a> Queued<'a> {
+	fn new(counter: &'a AtomicUsize) -> Self {
+		counter.fetch_add(1, Ordering::Relaxed);
+		Self(counter)
+	}
+}
+
+impl Drop for Queued<'_> {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::Relaxed);
+	}
+}
@@ -0,0 +1,196 @@
+use std::{
+	fs,
+	io::{self, Read, Seek},
+	path::{Component, Path, PathBuf},
+};
+
+use ironworks::{sqpack::SqPack, zipatch, Resource};
+
+use crate::version::{self, VersionKey};
+
+use super::{error::Result, fixture::FixtureResource};
+
+/// Backend providing the game files of each version. Sources are responsible
+/// for mapping a version onto a resource - be that by reading its patches, or
+/// by some other means entirely.
+pub trait Source: Send + Sync {
+	/// Build a resource serving the files of a version.
+	fn resource(&self, key: VersionKey, version: version::Version) -> Result<SourceResource>;
+
+	/// Whether files read from this source should be held in the shared page
+	/// cache. Sources that already hold their files in memory can opt out.
+	fn cached(&self) -> bool {
+		true
+	}
+}
+
+/// Resource provided by a source. Source implementations may use differing
+/// resource types, this erases them to a single type usable by the data layer.
+pub struct SourceResource(Box<dyn ErasedResource>);
+
+impl SourceResource {
+	pub fn new<R>(resource: R) -> Self
+	where
+		R: Resource + Send + Sync + 'static,
+		R::File: Send + 'static,
+	{
+		Self(Box::new(resource))
+	}
+}
+
+impl Resource for SourceResource {
+	type File = Box<dyn SourceFile>;
+
+	fn version(&self, path: &str) -> ironworks::Result<String> {
+		self.0.version(path)
+	}
+
+	fn file(&self, path: &str) -> ironworks::Result<Self::File> {
+		self.0.file(path)
+	}
+}
+
+pub trait SourceFile: Read + Seek + Send {}
+impl<T: Read + Seek + Send> SourceFile for T {}
+
+trait ErasedResource: Send + Sync {
+	fn version(&self, path: &str) -> ironworks::Result<String>;
+	fn file(&self, path: &str) -> ironworks::Result<Box<dyn SourceFile>>;
+}
+
+impl<R> ErasedResource for R
+where
+	R: Resource + Send + Sync,
+	R::File: Send + 'static,
+{
+	fn version(&self, path: &str) -> ironworks::Result<String> {
+		Resource::version(self, path)
+	}
+
+	fn file(&self, path: &str) -> ironworks::Result<Box<dyn SourceFile>> {
+		Ok(Box::new(Resource::file(self, path)?))
+	}
+}
+
+/// Source reading game files from the patches of each version.
+pub struct PatchSource {
+	// Root ZiPatch instance, acts as a LUT cache
+	zipatch: zipatch::ZiPatch,
+}
+
+impl Default for PatchSource {
+	fn default() -> Self {
+		Self {
+			zipatch: zipatch::ZiPatch::new().with_persisted_lookups(),
+		}
+	}
+}
+
+impl Source for PatchSource {
+	fn resource(&self, _key: VersionKey, version: version::Version) -> Result<SourceResource> {
+		let view = version
+			.repositories
+			.into_iter()
+			// Repositories without an sqpack index (i.e. boot) don't contain game data.
+			.filter_map(|repository| {
+				let index = repository.slot.sqpack_index()?;
+				let patches = repository
+					.patches
+					.into_iter()
+					.map(|patch| zipatch::Patch {
+						path: patch.path,
+						name: patch.name,
+					})
+					.collect();
+				Some((index, zipatch::PatchRepository { patches }))
+			})
+			.fold(self.zipatch.view(), |builder, (index, repository)| {
+				builder.with_repository(index, repository)
+			})
+			.build();
+
+		Ok(SourceResource::new(SqPack::new(view)))
+	}
+}
+
+/// Source reading game files that have already been extracted to disk, laid
+/// out by their game path within a directory named after each version's key.
+pub struct DirectorySource {
+	directory: PathBuf,
+}
+
+impl DirectorySource {
+	pub fn new(directory: PathBuf) -> Self {
+		Self { directory }
+	}
+}
+
+impl Source for DirectorySource {
+	fn resource(&self, key: VersionKey, _version: version::Version) -> Result<SourceResource> {
+		let directory = self.directory.join(key.to_string());
+		if !directory.is_dir() {
+			return Err(
+				anyhow::anyhow!("no extracted files for version {key} at {directory:?}").into(),
+			);
+		}
+
+		// Canonicalised up front, so that resolved file paths can be checked against it.
+		let directory = directory.canonicalize().map_err(anyhow::Error::from)?;
+
+		Ok(SourceResource::new(DirectoryResource { directory }))
+	}
+}
+
+struct DirectoryResource {
+	directory: PathBuf,
+}
+
+impl Resource for DirectoryResource {
+	type File = io::BufReader<fs::File>;
+
+	fn version(&self, _path: &str) -> ironworks::Result<String> {
+		Ok("extracted".to_string())
+	}
+
+	fn file(&self, path: &str) -> ironworks::Result<Self::File> {
+		let not_found = || ironworks::Error::NotFound(ironworks::ErrorValue::Path(path.into()));
+
+		// Paths come from requests - anything that could escape the version's
+		// directory is treated as not existing.
+		let relative = Path::new(path);
+		let escapes = relative
+			.components()
+			.any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+		if escapes {
+			return Err(not_found());
+		}
+
+		let resolved = match self.directory.join(relative).canonicalize() {
+			Ok(resolved) => resolved,
+			Err(error) if error.kind() == io::ErrorKind::NotFound => return Err(not_found()),
+			Err(error) => return Err(ironworks::Error::Resource(error.into())),
+		};
+
+		// Symlinks within the directory may still point outside of it.
+		if !resolved.starts_with(&self.directory) {
+			return Err(not_found());
+		}
+
+		match fs::File::open(resolved) {
+			Ok(file) => Ok(io::BufReader::new(file)),
+			Err(error) if error.kind() == io::ErrorKind::NotFound => Err(not_found()),
+			Err(error) => Err(ironworks::Error::Resource(error.into())),
+		}
+	}
+}
+
+// Fixtures stand in for game data wholesale - every version serves the same files.
+impl Source for FixtureResource {
+	fn resource(&self, _key: VersionKey, _version: version::Version) -> Result<SourceResource> {
+		Ok(SourceResource::new(self.clone()))
+	}
+
+	fn cached(&self) -> bool {
+		false
+	}
+}
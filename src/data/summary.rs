@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+
+use ironworks::{
+	excel::{Excel, Language},
+	file::exh,
+	Ironworks,
+};
+
+use crate::utility::anyhow::Anyhow;
+
+use super::{error::Result, path};
+
+/// Aggregate metadata about the excel sheets present in a version.
+#[derive(Debug)]
+pub struct Summary {
+	pub sheets: Vec<SheetSummary>,
+}
+
+impl Summary {
+	/// Total number of rows across all sheets in the version. Subrows are not
+	/// counted individually.
+	pub fn row_count(&self) -> u64 {
+		self.sheets
+			.iter()
+			.map(|sheet| u64::from(sheet.row_count))
+			.sum()
+	}
+
+	/// Estimated total size, in bytes, of the fixed-size portion of row data
+	/// across all sheets and languages.
+	pub fn data_size(&self) -> u64 {
+		self.sheets.iter().map(SheetSummary::data_size).sum()
+	}
+
+	/// Union of all languages provided by at least one sheet.
+	pub fn languages(&self) -> HashSet<Language> {
+		self.sheets
+			.iter()
+			.flat_map(|sheet| sheet.languages.iter().copied())
+			.collect()
+	}
+}
+
+#[derive(Debug)]
+pub struct SheetSummary {
+	pub name: String,
+	pub kind: exh::SheetKind,
+	pub languages: Vec<Language>,
+	pub column_count: usize,
+	pub row_count: u32,
+	pub row_size: u16,
+}
+
+impl SheetSummary {
+	pub fn data_size(&self) -> u64 {
+		let languages = u64::try_from(self.languages.len()).unwrap_or(u64::MAX);
+		u64::from(self.row_count) * u64::from(self.row_size) * languages
+	}
+}
+
+pub fn build_summary(ironworks: &Ironworks, excel: &Excel) -> Result<Summary> {
+	let list = excel.list().anyhow()?;
+
+	let mut sheets = list
+		.iter()
+		.map(|name| build_sheet_summary(ironworks, excel, &name))
+		.collect::<Result<Vec<_>>>()?;
+
+	sheets.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+	Ok(Summary { sheets })
+}
+
+fn build_sheet_summary(ironworks: &Ironworks, excel: &Excel, name: &str) -> Result<SheetSummary> {
+	let sheet = excel.sheet(name).anyhow()?;
+
+	// The sheet API doesn't expose pagination, read the header directly for the row counts.
+	// TODO: this is reading the header a second time - IW will have it cached internally, would be nice to share that.
+	let header = ironworks
+		.file::<exh::ExcelHeader>(&path::exh(name))
+		.anyhow()?;
+	let row_count = header.pages().iter().map(|page| page.row_count()).sum();
+
+	Ok(SheetSummary {
+		name: name.to_string(),
+		kind: sheet.kind().anyhow()?,
+		languages: sheet.languages().anyhow()?,
+		column_count: sheet.columns().anyhow()?.len(),
+		row_count,
+		row_size: header.row_size(),
+	})
+}
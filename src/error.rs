@@ -0,0 +1,109 @@
+use std::{fmt, io};
+
+/// Broad classification of an error, shared between modules. Callers can use
+/// this to decide how to report an error, and whether it is worth retrying,
+/// without needing to know about each module's error type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+	/// The requested resource does not exist.
+	NotFound,
+	/// The request is malformed, or cannot be satisfied as specified.
+	Invalid,
+	/// The resource exists, but is not available yet - i.e. still being
+	/// prepared, or the operation was cancelled.
+	Unavailable,
+	/// A failure that may not recur, such as a network or disk error.
+	Transient,
+	/// A failure that will recur until something is changed.
+	Failure,
+}
+
+impl ErrorKind {
+	/// Whether the error was caused by the request, rather than the server.
+	pub fn is_user(self) -> bool {
+		matches!(self, Self::NotFound | Self::Invalid)
+	}
+
+	/// Whether retrying the same operation later may succeed.
+	pub fn is_retriable(self) -> bool {
+		matches!(self, Self::Unavailable | Self::Transient)
+	}
+}
+
+/// Errors that can be classified by [`ErrorKind`].
+pub trait Classify {
+	fn kind(&self) -> ErrorKind;
+}
+
+/// Error type shared across module boundaries, carrying the classification of
+/// the underlying error alongside it.
+#[derive(Debug)]
+pub struct Error {
+	kind: ErrorKind,
+	inner: anyhow::Error,
+}
+
+impl Error {
+	pub fn new(kind: ErrorKind, inner: impl Into<anyhow::Error>) -> Self {
+		Self {
+			kind,
+			inner: inner.into(),
+		}
+	}
+
+	pub fn kind(&self) -> ErrorKind {
+		self.kind
+	}
+
+	pub fn into_inner(self) -> anyhow::Error {
+		self.inner
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(&self.inner, formatter)
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		self.inner.source()
+	}
+}
+
+impl<E> From<E> for Error
+where
+	E: Classify + std::error::Error + Send + Sync + 'static,
+{
+	fn from(error: E) -> Self {
+		Self::new(error.kind(), error)
+	}
+}
+
+impl From<anyhow::Error> for Error {
+	fn from(error: anyhow::Error) -> Self {
+		Self {
+			kind: classify_failure(&error),
+			inner: error,
+		}
+	}
+}
+
+/// Classify an otherwise untyped failure. IO and network errors anywhere in the
+/// chain are assumed to be transient.
+pub fn classify_failure(error: &anyhow::Error) -> ErrorKind {
+	let transient = error.chain().any(|cause| {
+		cause.downcast_ref::<io::Error>().is_some()
+			|| cause
+				.downcast_ref::<reqwest::Error>()
+				.map_or(false, |error| error.is_timeout() || error.is_connect())
+	});
+
+	match transient {
+		true => ErrorKind::Transient,
+		false => ErrorKind::Failure,
+	}
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
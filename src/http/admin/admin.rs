@@ -1,21 +1,44 @@
+use std::sync::Arc;
+
 use axum::{middleware, Router};
 use serde::Deserialize;
 
-use crate::http::service;
+use crate::{config::Validator, http::service};
 
 use super::{
 	auth::{basic_auth, BasicAuth},
-	version, versions,
+	cache,
+	idempotency::{self, idempotency, Idempotency},
+	plan, schema, version, versions,
 };
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
 	auth: BasicAuth,
+
+	#[serde(default)]
+	idempotency: idempotency::Config,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.scope("auth", |validator| self.auth.validate(validator));
+		validator.scope("idempotency", |validator| {
+			self.idempotency.validate(validator)
+		});
+	}
 }
 
 pub fn router(config: Config) -> Router<service::State> {
 	Router::new()
 		.merge(versions::router())
 		.merge(version::router())
+		.merge(plan::router())
+		.merge(schema::router())
+		.merge(cache::router())
+		.layer(middleware::from_fn_with_state(
+			Arc::new(Idempotency::new(config.idempotency)),
+			idempotency,
+		))
 		.layer(middleware::from_fn_with_state(config.auth, basic_auth))
 }
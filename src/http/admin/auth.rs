@@ -10,12 +10,21 @@ use axum_extra::{
 };
 use serde::Deserialize;
 
+use crate::config::Validator;
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct BasicAuth {
 	username: String,
 	password: String,
 }
 
+impl BasicAuth {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.check("username", !self.username.is_empty(), "must not be empty");
+		validator.check("password", !self.password.is_empty(), "must not be empty");
+	}
+}
+
 pub async fn basic_auth(
 	State(expected): State<BasicAuth>,
 	authorization: Option<TypedHeader<Authorization<Basic>>>,
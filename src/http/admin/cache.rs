@@ -0,0 +1,105 @@
+use anyhow::anyhow;
+use axum::{
+	debug_handler,
+	extract::{OriginalUri, State},
+	response::{IntoResponse, Redirect},
+	routing::get,
+	Form, Router,
+};
+use maud::{html, Markup, Render};
+use serde::Deserialize;
+
+use crate::{cache::TierMetrics, http::service};
+
+use super::{base::BaseTemplate, error::Result};
+
+const MIB: u64 = 1024 * 1024;
+
+pub fn router() -> Router<service::State> {
+	Router::new().route("/cache", get(get_cache).post(post_cache))
+}
+
+#[debug_handler(state = service::State)]
+async fn get_cache(
+	OriginalUri(uri): OriginalUri,
+	State(cache): State<service::Cache>,
+) -> Result<impl IntoResponse> {
+	let metrics = cache.metrics();
+	let action = uri.to_string();
+
+	Ok((BaseTemplate {
+		title: "caches".to_string(),
+		content: html! {
+			table {
+				thead {
+					tr {
+						th { "cache" }
+						th { "tier" }
+						th { "entries" }
+						th { "size" }
+						th { "hits" }
+						th { "misses" }
+						th {}
+					}
+				}
+				tbody {
+					@for cache in &metrics {
+						// Tiers of a cache are flushed together - only offer it once.
+						(tier_row(cache.name, "memory", &cache.memory, Some(&action)))
+						@if let Some(disk) = &cache.disk {
+							(tier_row(cache.name, "disk", disk, None))
+						}
+					}
+				}
+			}
+
+			form action=(action) method="post" {
+				button type="submit" { "flush all" };
+			}
+		},
+	})
+	.render())
+}
+
+fn tier_row(name: &str, tier: &str, metrics: &TierMetrics, flush: Option<&str>) -> Markup {
+	html! {
+		tr {
+			td { (name) }
+			td { (tier) }
+			td { (metrics.entries) }
+			td { (metrics.size / MIB) " / " (metrics.capacity / MIB) " MiB" }
+			td { (metrics.hits) }
+			td { (metrics.misses) }
+			td {
+				@if let Some(action) = flush {
+					form action=(action) method="post" {
+						input type="hidden" name="name" value=(name);
+						button type="submit" { "flush" };
+					}
+				}
+			}
+		}
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct CachePostRequest {
+	/// Cache to flush. All caches are flushed if omitted.
+	name: Option<String>,
+}
+
+#[debug_handler(state = service::State)]
+async fn post_cache(
+	OriginalUri(uri): OriginalUri,
+	State(cache): State<service::Cache>,
+	Form(request): Form<CachePostRequest>,
+) -> Result<impl IntoResponse> {
+	// Disk tiers remove their files while flushing.
+	let name = request.name.clone();
+	let flushed = tokio::task::spawn_blocking(move || cache.flush(name.as_deref())).await?;
+	if !flushed {
+		return Err(anyhow!("unknown cache {:?}", request.name.unwrap_or_default()).into());
+	}
+
+	Ok(Redirect::to(&uri.to_string()))
+}
@@ -0,0 +1,255 @@
+use std::{
+	collections::{hash_map::DefaultHasher, HashMap},
+	hash::{Hash, Hasher},
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use axum::{
+	body::{self, Body, Bytes},
+	extract::{Request, State},
+	http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+	middleware::Next,
+	response::{IntoResponse, Response},
+};
+use mini_moka::sync as moka;
+use serde::Deserialize;
+
+use crate::config::Validator;
+
+/// Header clients may set to make a mutation safe to retry.
+const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Header set on responses replayed from a previous request.
+const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Maximum length of an idempotency key.
+const KEY_LENGTH_MAX: usize = 255;
+
+/// Maximum size of a request or response body that will be retained for
+/// comparison and replay. Admin forms and pages are well below this.
+const BODY_LIMIT: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+	/// Time, in seconds, a completed request's response is retained for replay.
+	#[serde(default = "default_ttl")]
+	ttl: u64,
+
+	/// Maximum number of completed responses to retain.
+	#[serde(default = "default_capacity")]
+	capacity: u64,
+}
+
+fn default_ttl() -> u64 {
+	60 * 60 * 24
+}
+
+fn default_capacity() -> u64 {
+	1024
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			ttl: default_ttl(),
+			capacity: default_capacity(),
+		}
+	}
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.check("ttl", self.ttl > 0, "must be greater than 0");
+		validator.check("capacity", self.capacity > 0, "must be greater than 0");
+	}
+}
+
+/// Hash identifying the request an idempotency key was first used with. Keys
+/// may only be reused to retry the exact same request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fingerprint(u64);
+
+impl Fingerprint {
+	fn new(method: &Method, path: &str, body: &[u8]) -> Self {
+		let mut hasher = DefaultHasher::new();
+		method.hash(&mut hasher);
+		path.hash(&mut hasher);
+		body.hash(&mut hasher);
+		Self(hasher.finish())
+	}
+}
+
+#[derive(Debug, Clone)]
+struct Completed {
+	fingerprint: Fingerprint,
+	status: StatusCode,
+	headers: HeaderMap,
+	body: Bytes,
+}
+
+impl Completed {
+	fn replay(&self) -> Response {
+		let mut response = Response::new(Body::from(self.body.clone()));
+		*response.status_mut() = self.status;
+		*response.headers_mut() = self.headers.clone();
+		response
+			.headers_mut()
+			.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+		response
+	}
+}
+
+/// Tracks admin mutations made with an idempotency key, so that a retried
+/// request receives the original response rather than repeating the work.
+pub struct Idempotency {
+	in_flight: Mutex<HashMap<String, Fingerprint>>,
+	completed: moka::Cache<String, Arc<Completed>>,
+}
+
+impl Idempotency {
+	pub fn new(config: Config) -> Self {
+		Self {
+			in_flight: Default::default(),
+			completed: moka::Cache::builder()
+				.max_capacity(config.capacity)
+				.time_to_live(Duration::from_secs(config.ttl))
+				.build(),
+		}
+	}
+}
+
+/// Removes a key from the in-flight set when the request it guards finishes,
+/// including if the handler is cancelled or panics.
+struct InFlight<'a> {
+	idempotency: &'a Idempotency,
+	key: &'a str,
+}
+
+impl Drop for InFlight<'_> {
+	fn drop(&mut self) {
+		self.idempotency
+			.in_flight
+			.lock()
+			.expect("poisoned")
+			.remove(self.key);
+	}
+}
+
+/// Middleware honouring the `Idempotency-Key` header on mutating requests.
+/// The first request with a key is executed as normal and its response
+/// retained; retries with the same key and request receive that response
+/// again. Reusing a key for a different request, or while the original is
+/// still running, is rejected.
+pub async fn idempotency(
+	State(idempotency): State<Arc<Idempotency>>,
+	request: Request,
+	next: Next,
+) -> Response {
+	if matches!(
+		*request.method(),
+		Method::GET | Method::HEAD | Method::OPTIONS
+	) {
+		return next.run(request).await;
+	}
+
+	let key = match request.headers().get(IDEMPOTENCY_KEY) {
+		None => return next.run(request).await,
+		Some(value) => match value.to_str() {
+			Ok(key) if !key.is_empty() && key.len() <= KEY_LENGTH_MAX => key.to_string(),
+			_ => return invalid_key(),
+		},
+	};
+
+	// The body is needed to tell a retry apart from a different request reusing
+	// the key, so buffer it and rebuild the request around it.
+	let (parts, body) = request.into_parts();
+	let body = match body::to_bytes(body, BODY_LIMIT).await {
+		Ok(body) => body,
+		Err(error) => {
+			return (
+				StatusCode::PAYLOAD_TOO_LARGE,
+				format!("error: failed to read request body: {error}"),
+			)
+				.into_response()
+		}
+	};
+	let fingerprint = Fingerprint::new(&parts.method, parts.uri.path(), &body);
+
+	{
+		// Responses are retained before the key leaves the in-flight set, so
+		// checking both under the lock can't miss a request that just finished.
+		let mut in_flight = idempotency.in_flight.lock().expect("poisoned");
+		if let Some(completed) = idempotency.completed.get(&key) {
+			return match completed.fingerprint == fingerprint {
+				true => completed.replay(),
+				false => mismatch(),
+			};
+		}
+		if let Some(existing) = in_flight.get(&key) {
+			return match *existing == fingerprint {
+				true => (
+					StatusCode::CONFLICT,
+					"error: a request with this idempotency key is still in progress",
+				)
+					.into_response(),
+				false => mismatch(),
+			};
+		}
+		in_flight.insert(key.clone(), fingerprint);
+	}
+
+	let _guard = InFlight {
+		idempotency: &idempotency,
+		key: &key,
+	};
+
+	let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+	// Server errors are not retained, so that a retry can succeed once whatever
+	// caused the failure has been resolved.
+	if response.status().is_server_error() {
+		return response;
+	}
+
+	let (parts, body) = response.into_parts();
+	let body = match body::to_bytes(body, BODY_LIMIT).await {
+		Ok(body) => body,
+		Err(error) => {
+			tracing::warn!(?error, "failed to buffer response for idempotent replay");
+			return (
+				StatusCode::INTERNAL_SERVER_ERROR,
+				"error: failed to buffer response",
+			)
+				.into_response();
+		}
+	};
+
+	let completed = Completed {
+		fingerprint,
+		status: parts.status,
+		headers: parts.headers.clone(),
+		body: body.clone(),
+	};
+	idempotency
+		.completed
+		.insert(key.clone(), Arc::new(completed));
+
+	Response::from_parts(parts, Body::from(body))
+}
+
+fn invalid_key() -> Response {
+	(
+		StatusCode::BAD_REQUEST,
+		format!("error: idempotency key must be 1-{KEY_LENGTH_MAX} visible ASCII characters"),
+	)
+		.into_response()
+}
+
+fn mismatch() -> Response {
+	(
+		StatusCode::UNPROCESSABLE_ENTITY,
+		"error: idempotency key has already been used for a different request",
+	)
+		.into_response()
+}
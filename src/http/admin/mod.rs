@@ -1,7 +1,11 @@
 mod admin;
 mod auth;
 mod base;
+mod cache;
 mod error;
+mod idempotency;
+mod plan;
+mod schema;
 mod version;
 mod versions;
 
@@ -0,0 +1,104 @@
+use axum::{debug_handler, extract::State, response::IntoResponse, routing::get, Router};
+use maud::{html, Render};
+
+use crate::http::service;
+
+use super::{base::BaseTemplate, error::Result};
+
+const MIB: u64 = 1024 * 1024;
+
+pub fn router() -> Router<service::State> {
+	Router::new().route("/plan", get(plan))
+}
+
+/// Resolve what a version update would entail, without running it.
+#[debug_handler(state = service::State)]
+async fn plan(
+	State(data): State<service::Data>,
+	State(version): State<service::Version>,
+) -> Result<impl IntoResponse> {
+	let plan = version.plan().await?;
+	let download_size = plan
+		.downloads
+		.iter()
+		.map(|download| download.size)
+		.sum::<u64>();
+
+	// Headers can only be compared once every patch of the planned version is
+	// available locally.
+	let latest = version.resolve(None);
+	let changes = match (latest, plan.known, plan.downloads.is_empty()) {
+		(Some(latest), false, true) => {
+			let candidate = plan.version;
+			let task_data = data.clone();
+			Some(
+				data.blocking(move || task_data.sheet_changes(latest, candidate))
+					.await??,
+			)
+		}
+		_ => None,
+	};
+
+	Ok((BaseTemplate {
+		title: "update plan".to_string(),
+		content: html! {
+			p {
+				"an update would produce version " code { (plan.key) }
+				@if plan.known { ", which is already known - no changes would be made" }
+			}
+
+			h2 { "downloads" }
+			p {
+				(plan.downloads.len()) " patches, " (download_size / MIB) " MiB"
+			}
+			@if !plan.downloads.is_empty() {
+				table {
+					thead {
+						tr {
+							th { "repository" }
+							th { "patch" }
+							th { "size" }
+						}
+					}
+					tbody {
+						@for download in &plan.downloads {
+							tr {
+								td { (download.repository) }
+								td { (download.patch) }
+								td { (download.size / MIB) " MiB" }
+							}
+						}
+					}
+				}
+			}
+
+			h2 { "sheets" }
+			@match &changes {
+				Some(changes) => {
+					p {
+						"compared against latest: "
+						(changes.added.len()) " added, "
+						(changes.removed.len()) " removed, "
+						(changes.changed.len()) " with changed headers"
+					}
+					@for (label, sheets) in [("added", &changes.added), ("removed", &changes.removed), ("changed", &changes.changed)] {
+						@if !sheets.is_empty() {
+							details {
+								summary { (label) " (" (sheets.len()) ")" }
+								ul {
+									@for sheet in sheets {
+										li { (sheet) }
+									}
+								}
+							}
+						}
+					}
+				}
+				None => {
+					p { "sheet changes can only be determined for a new version once all of its patches are available locally" }
+				}
+			}
+		},
+	})
+	.render())
+}
@@ -0,0 +1,152 @@
+use anyhow::Context;
+use axum::{
+	debug_handler,
+	extract::{Query, State},
+	response::IntoResponse,
+	routing::get,
+	Router,
+};
+use maud::{html, Markup, Render};
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{http::service, read, schema};
+
+use super::{base::BaseTemplate, error::Result};
+
+/// Number of rows sampled when comparing schemas.
+const SAMPLE_DEFAULT: usize = 100;
+
+pub fn router() -> Router<service::State> {
+	Router::new().route("/schema", get(compare))
+}
+
+#[derive(Debug, Deserialize)]
+struct CompareQuery {
+	sheet: Option<String>,
+	base: Option<String>,
+	target: Option<String>,
+	sample: Option<usize>,
+}
+
+/// Compare a sample of a sheet's rows under two schemas against the latest
+/// version, to check a schema upgrade for breaking changes before switching
+/// the default to it.
+#[debug_handler(state = service::State)]
+async fn compare(
+	Query(query): Query<CompareQuery>,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+	State(schema_provider): State<service::Schema>,
+	State(version): State<service::Version>,
+) -> Result<impl IntoResponse> {
+	let specifier = |value: &Option<String>| {
+		value
+			.as_deref()
+			.filter(|value| !value.is_empty())
+			.and_then(|value| value.parse::<schema::Specifier>().ok())
+	};
+
+	let comparison = match (&query.sheet, specifier(&query.target)) {
+		(Some(sheet), Some(target)) if !sheet.is_empty() => {
+			let version_key = version.resolve(None).context("no latest version")?;
+			let excel = data.version(version_key)?.excel();
+			let base = schema_provider.canonicalize(specifier(&query.base), version_key)?;
+			let target = schema_provider.canonicalize(Some(target), version_key)?;
+
+			let sheet = sheet.clone();
+			let sample = query.sample.unwrap_or(SAMPLE_DEFAULT);
+			let (task_base, task_target) = (base.clone(), target.clone());
+			let diff = data
+				.blocking(move || -> anyhow::Result<_> {
+					let base = schema_provider.schema(task_base)?;
+					let target = schema_provider.schema(task_target)?;
+					let diff = read.compare_schemas(
+						&excel,
+						base.as_ref(),
+						target.as_ref(),
+						&sheet,
+						sample,
+						read.default_language(),
+						&|_| true,
+						&CancellationToken::new(),
+					)?;
+					Ok(diff)
+				})
+				.await??;
+
+			Some((base, target, diff))
+		}
+		_ => None,
+	};
+
+	Ok((BaseTemplate {
+		title: "schema comparison".to_string(),
+		content: html! {
+			form method="get" {
+				input type="text" name="sheet" placeholder="sheet" value=[&query.sheet];
+				input type="text" name="base" placeholder="base schema (default)" value=[&query.base];
+				input type="text" name="target" placeholder="target schema" value=[&query.target];
+				input type="number" name="sample" min="1" value=(query.sample.unwrap_or(SAMPLE_DEFAULT));
+				button type="submit" { "compare" };
+			}
+
+			@if let Some((base, target, diff)) = &comparison {
+				(diff_table(base, target, diff))
+			}
+		},
+	})
+	.render())
+}
+
+fn diff_table(
+	base: &schema::CanonicalSpecifier,
+	target: &schema::CanonicalSpecifier,
+	diff: &read::SchemaDiff,
+) -> Markup {
+	html! {
+		p {
+			"compared " code { (base.to_string()) } " to " code { (target.to_string()) }
+			" across " (diff.rows) " rows: "
+			(diff.added.len()) " added, "
+			(diff.removed.len()) " removed, "
+			(diff.changed.len()) " changed, "
+			(diff.moved.len()) " moved"
+		}
+
+		@if !diff.moved.is_empty() {
+			h2 { "moved" }
+			table {
+				thead { tr { th { "from" } th { "to" } } }
+				tbody {
+					@for field_move in &diff.moved {
+						tr { td { code { (field_move.from) } } td { code { (field_move.to) } } }
+					}
+				}
+			}
+		}
+
+		@if !diff.changed.is_empty() {
+			h2 { "changed" }
+			table {
+				thead { tr { th { "field" } th { "base" } th { "target" } } }
+				tbody {
+					@for change in &diff.changed {
+						tr { td { code { (change.path) } } td { (change.from) } td { (change.to) } }
+					}
+				}
+			}
+		}
+
+		@for (heading, paths) in [("removed", &diff.removed), ("added", &diff.added)] {
+			@if !paths.is_empty() {
+				h2 { (heading) }
+				ul {
+					@for path in paths {
+						li { code { (path) } }
+					}
+				}
+			}
+		}
+	}
+}
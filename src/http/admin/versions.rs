@@ -12,6 +12,9 @@ use crate::{http::service, version::VersionKey};
 
 use super::{base::BaseTemplate, error::Result};
 
+const KIB: u64 = 1024;
+const MIB: u64 = 1024 * KIB;
+
 pub fn router() -> Router<service::State> {
 	Router::new().route("/", get(versions))
 }
@@ -49,9 +52,45 @@ async fn versions(
 		.map(version_info)
 		.collect::<Result<Vec<_>>>()?;
 
+	let downloads = version.status().downloads;
+
 	Ok((BaseTemplate {
 		title: "versions".to_string(),
 		content: html! {
+			@if !downloads.is_empty() {
+				h2 { "downloads" }
+				table {
+					thead {
+						tr {
+							th { "repository" }
+							th { "patch" }
+							th { "progress" }
+							th { "rate" }
+							th { "eta" }
+						}
+					}
+					tbody {
+						@for download in &downloads {
+							tr {
+								td { (download.repository) }
+								td { (download.patch) }
+								td {
+									(download.bytes / MIB) " / " (download.total / MIB) " MiB "
+									"(" (download.bytes * 100 / download.total.max(1)) "%)"
+								}
+								td { (download.rate / KIB) " KiB/s" }
+								td {
+									@match download.eta {
+										Some(eta) => { (eta.as_secs()) "s" }
+										None => { "unknown" }
+									}
+								}
+							}
+						}
+					}
+				}
+			}
+
 			@for version in versions {
 				h2 {
 					a href={ (uri) "/" (version.key) } {
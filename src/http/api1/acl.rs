@@ -0,0 +1,175 @@
+use std::{collections::HashMap, convert::Infallible, sync::Arc};
+
+use aide::OperationIo;
+use axum::{
+	async_trait,
+	extract::{FromRequestParts, RawPathParams, Request, State},
+	http::request::Parts,
+	middleware::Next,
+	response::{IntoResponse, Response},
+	RequestExt,
+};
+use serde::Deserialize;
+
+use crate::config::Validator;
+
+use super::{error::Error, extract::ApiKey};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+	/// Sheets that may be accessed. All sheets are allowed if unset.
+	allow: Option<Vec<String>>,
+
+	/// Sheets that may not be accessed, taking precedence over `allow`.
+	#[serde(default)]
+	deny: Vec<String>,
+
+	/// Rules for specific API keys, replacing the global rules for requests
+	/// made with that key.
+	#[serde(default)]
+	key: HashMap<String, Rules>,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validate_patterns(validator, "allow", self.allow.iter().flatten());
+		validate_patterns(validator, "deny", &self.deny);
+
+		for (key, rules) in &self.key {
+			validator.scope(&format!("key.{key}"), |validator| {
+				validate_patterns(validator, "allow", rules.allow.iter().flatten());
+				validate_patterns(validator, "deny", &rules.deny);
+			});
+		}
+	}
+}
+
+fn validate_patterns<'a>(
+	validator: &mut Validator,
+	key: &str,
+	patterns: impl IntoIterator<Item = &'a String>,
+) {
+	for pattern in patterns {
+		let name = pattern.strip_suffix('*').unwrap_or(pattern);
+		validator.check(
+			key,
+			!name.contains('*'),
+			format_args!("pattern {pattern:?} may only contain a trailing *"),
+		);
+	}
+}
+
+/// Sheet access rules. Patterns match a sheet name exactly, or match any sheet
+/// name starting with the pattern if it ends in `*`.
+#[derive(Debug, Default, Deserialize)]
+struct Rules {
+	allow: Option<Vec<String>>,
+
+	#[serde(default)]
+	deny: Vec<String>,
+}
+
+impl Rules {
+	fn allows(&self, sheet: &str) -> bool {
+		let allowed = self.allow.as_ref().map_or(true, |allow| {
+			allow.iter().any(|pattern| matches(pattern, sheet))
+		});
+
+		allowed && !self.deny.iter().any(|pattern| matches(pattern, sheet))
+	}
+}
+
+fn matches(pattern: &str, sheet: &str) -> bool {
+	match pattern.strip_suffix('*') {
+		Some(prefix) => sheet.starts_with(prefix),
+		None => pattern == sheet,
+	}
+}
+
+/// Sheet access rules configured for the API.
+pub struct Acl {
+	global: Arc<Rules>,
+	keys: HashMap<String, Arc<Rules>>,
+}
+
+impl Acl {
+	pub fn new(config: Config) -> Self {
+		Self {
+			global: Arc::new(Rules {
+				allow: config.allow,
+				deny: config.deny,
+			}),
+			keys: config
+				.key
+				.into_iter()
+				.map(|(key, rules)| (key, Arc::new(rules)))
+				.collect(),
+		}
+	}
+
+	fn access(&self, key: Option<&str>) -> SheetAccess {
+		let rules = key
+			.and_then(|key| self.keys.get(key))
+			.unwrap_or(&self.global);
+
+		SheetAccess(Some(rules.clone()))
+	}
+}
+
+/// Middleware resolving the sheet access rules for a request, and rejecting
+/// requests for sheets that are not accessible.
+pub async fn restrict(State(acl): State<Arc<Acl>>, mut request: Request, next: Next) -> Response {
+	let ApiKey(key) = match request.extract_parts::<ApiKey>().await {
+		Ok(key) => key,
+		Err(infallible) => match infallible {},
+	};
+	let access = acl.access(key.as_deref());
+
+	let params = request.extract_parts::<RawPathParams>().await.ok();
+	let sheet = params
+		.as_ref()
+		.and_then(|params| params.iter().find(|(name, _)| *name == "sheet"))
+		.map(|(_, value)| value);
+
+	if let Some(sheet) = sheet {
+		if let Err(error) = access.check(sheet) {
+			return error.into_response();
+		}
+	}
+
+	request.extensions_mut().insert(access);
+
+	next.run(request).await
+}
+
+/// Sheet access rules applicable to the current request.
+#[derive(Clone, OperationIo)]
+pub struct SheetAccess(Option<Arc<Rules>>);
+
+impl SheetAccess {
+	pub fn allows(&self, sheet: &str) -> bool {
+		self.0.as_ref().map_or(true, |rules| rules.allows(sheet))
+	}
+
+	pub fn check(&self, sheet: &str) -> Result<(), Error> {
+		match self.allows(sheet) {
+			true => Ok(()),
+			false => Err(Error::Forbidden(format!(
+				"access to sheet \"{sheet}\" is not permitted"
+			))),
+		}
+	}
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for SheetAccess {
+	type Rejection = Infallible;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		Ok(parts
+			.extensions
+			.get::<SheetAccess>()
+			.cloned()
+			.unwrap_or(SheetAccess(None)))
+	}
+}
@@ -1,45 +1,140 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use aide::{
 	axum::ApiRouter,
 	openapi::{self, Tag},
 	transform::TransformOpenApi,
 };
-use axum::{debug_handler, response::IntoResponse, routing::get, Extension, Json, Router};
+use axum::{
+	debug_handler, middleware, response::IntoResponse, routing::get, Extension, Json, Router,
+};
 use git_version::git_version;
 use maud::{html, DOCTYPE};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tower_http::cors::CorsLayer;
 
-use crate::http::service;
+use crate::{config::Validator, http::service};
 
-use super::{asset, extract::RouterPath, sheet, version};
+use super::{
+	acl, asset, deadline, default_version, envelope, eorzea, extract::RouterPath, pretty, resolve,
+	sheet, signing, tenant, upstream, usage, version, view, weather,
+};
 
 const OPENAPI_JSON_ROUTE: &str = "/openapi.json";
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
 	sheet: sheet::Config,
+
+	#[serde(default)]
+	tenant: HashMap<String, tenant::TenantConfig>,
+
+	#[serde(default)]
+	acl: acl::Config,
+
+	#[serde(default)]
+	default_version: default_version::Config,
+
+	#[serde(default)]
+	deadline: deadline::Config,
+
+	#[serde(default)]
+	upstream: upstream::Config,
+
+	#[serde(default)]
+	envelope: envelope::Config,
+
+	#[serde(default)]
+	signing: signing::Config,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.scope("sheet", |validator| self.sheet.validate(validator));
+		validator.scope("tenant", |validator| {
+			tenant::validate(&self.tenant, validator)
+		});
+		validator.scope("acl", |validator| self.acl.validate(validator));
+		validator.scope("default_version", |validator| {
+			self.default_version.validate(validator)
+		});
+		validator.scope("deadline", |validator| self.deadline.validate(validator));
+		validator.scope("upstream", |validator| self.upstream.validate(validator));
+		validator.scope("envelope", |validator| self.envelope.validate(validator));
+		validator.scope("signing", |validator| self.signing.validate(validator));
+	}
 }
 
 pub fn router(config: Config) -> Router<service::State> {
 	let mut openapi = openapi::OpenApi::default();
+	let signing = Arc::new(signing::Signing::new(config.signing));
 
 	ApiRouter::new()
 		.nest(
 			"/asset",
 			asset::router().with_path_items(|item| item.tag("assets")),
 		)
+		.nest(
+			"/eorzea",
+			eorzea::router().with_path_items(|item| item.tag("computed")),
+		)
+		.nest(
+			"/resolve",
+			resolve::router(config.sheet.clone()).with_path_items(|item| item.tag("sheets")),
+		)
 		.nest(
 			"/sheet",
 			sheet::router(config.sheet).with_path_items(|item| item.tag("sheets")),
 		)
+		.nest(
+			"/sign",
+			signing::router(signing.clone()).with_path_items(|item| item.tag("signing")),
+		)
+		.nest(
+			"/usage",
+			usage::router().with_path_items(|item| item.tag("usage")),
+		)
 		.nest(
 			"/version",
 			version::router().with_path_items(|item| item.tag("versions")),
 		)
+		.nest(
+			"/view",
+			view::router().with_path_items(|item| item.tag("views")),
+		)
+		.nest(
+			"/weather",
+			weather::router().with_path_items(|item| item.tag("computed")),
+		)
 		.finish_api_with(&mut openapi, api_docs)
+		// Documentation routes below are available regardless of tenant or sheet access.
+		.route_layer(middleware::from_fn_with_state(
+			Arc::new(acl::Acl::new(config.acl)),
+			acl::restrict,
+		))
+		.route_layer(middleware::from_fn_with_state(signing, signing::restrict))
+		.route_layer(middleware::from_fn_with_state(
+			Arc::new(tenant::Tenants::new(config.tenant)),
+			tenant::resolve,
+		))
+		.route_layer(middleware::from_fn_with_state(
+			Arc::new(default_version::DefaultVersion::new(config.default_version)),
+			default_version::apply,
+		))
+		.route_layer(middleware::from_fn_with_state(
+			Arc::new(envelope::Envelope::new(config.envelope)),
+			envelope::apply,
+		))
+		.route_layer(middleware::from_fn_with_state(
+			Arc::new(upstream::Upstream::new(config.upstream)),
+			upstream::forward,
+		))
+		.route_layer(middleware::from_fn_with_state(
+			Arc::new(deadline::Deadline::new(config.deadline)),
+			deadline::apply,
+		))
+		.route_layer(middleware::from_fn(pretty::apply))
 		.layer(CorsLayer::permissive())
 		.route(OPENAPI_JSON_ROUTE, get(openapi_json))
 		.route("/docs", get(scalar))
@@ -50,20 +145,41 @@ fn api_docs(api: TransformOpenApi) -> TransformOpenApi {
 	let mut api = api
 		.title("boilmaster")
 		.version(git_version!(prefix = "1-", fallback = "unknown"))
+		.description("Successful JSON responses may be wrapped in a `{data, meta, warnings}` envelope by passing `envelope=true` in the query string, or returned bare with `envelope=false`. The default depends on the instance's configuration. JSON responses may be pretty-printed, with object keys sorted throughout, by passing `pretty=true`.")
 		.tag(Tag {
 			name: "assets".into(),
 			description: Some("Endpoints for accessing game data on a file-by-file basis. Commonly useful for fetching icons or other textures to display on the web.".into()),
 			..Default::default()
 		})
+		.tag(Tag {
+			name: "computed".into(),
+			description: Some("Endpoints for values commonly derived from game data, such as weather forecasts and Eorzean time, saving every consumer from reimplementing the calculations.".into()),
+			..Default::default()
+		})
 		.tag(Tag {
 			name: "sheets".into(),
-			description: Some("Endpoints for reading data from the game's static relational data store.".into()),
+			description: Some("Endpoints for reading data from the game's static relational data store. Row data is returned as JSON by default, or as MessagePack (`application/msgpack`) or CBOR (`application/cbor`) when requested via the `Accept` header.".into()),
+			..Default::default()
+		})
+		.tag(Tag {
+			name: "signing".into(),
+			description: Some("Endpoints for signing URLs, granting temporary access to routes restricted on this deployment without sharing an API key.".into()),
+			..Default::default()
+		})
+		.tag(Tag {
+			name: "usage".into(),
+			description: Some("Endpoints for querying the usage recorded against an API key.".into()),
 			..Default::default()
 		})
 		.tag(Tag {
 			name: "versions".into(),
 			description: Some("Endpoints for querying metadata about the versions recorded by the boilmaster system.".into()),
 			..Default::default()
+		})
+		.tag(Tag {
+			name: "views".into(),
+			description: Some("Endpoints for reading materialized views - result sets derived from sheets that are pre-computed and refreshed in the background, for fast access to popular data.".into()),
+			..Default::default()
 		});
 
 	let openapi = api.inner_mut();
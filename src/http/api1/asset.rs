@@ -9,7 +9,13 @@ use aide::{
 	transform::TransformOperation,
 	NoApi,
 };
-use axum::{debug_handler, extract::State, http::header, response::IntoResponse};
+use axum::{
+	debug_handler,
+	extract::State,
+	http::header,
+	response::{IntoResponse, Response},
+	Json,
+};
 use axum_extra::{
 	headers::{ContentType, ETag, IfNoneMatch},
 	TypedHeader,
@@ -17,13 +23,17 @@ use axum_extra::{
 use reqwest::StatusCode;
 use schemars::JsonSchema;
 use seahash::SeaHasher;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 
-use crate::{asset::Format, http::service, version::VersionKey};
+use crate::{
+	asset::{Font, Format, TextureInfo, UiLayout, MAX_COMPOSITE_LAYERS},
+	http::service,
+	version::VersionKey,
+};
 
 use super::{
-	error::Result,
+	error::{Error, Result},
 	extract::{Path, Query, VersionQuery},
 };
 
@@ -31,7 +41,221 @@ use super::{
 const ASSET_ETAG_VERSION: usize = 2;
 
 pub fn router() -> ApiRouter<service::State> {
-	ApiRouter::new().api_route("/*path", get_with(asset, asset_docs))
+	ApiRouter::new()
+		.api_route("/info", get_with(info, info_docs))
+		.api_route("/font", get_with(font, font_docs))
+		.api_route("/font/package", get_with(font_package, font_package_docs))
+		.api_route("/layout", get_with(layout, layout_docs))
+		.api_route(
+			"/layout/package",
+			get_with(layout_package, layout_package_docs),
+		)
+		.api_route("/composite", get_with(composite, composite_docs))
+		.api_route("/*path", get_with(asset, asset_docs))
+}
+
+/// Query parameters accepted by the asset metadata endpoints.
+#[derive(Deserialize, JsonSchema)]
+struct FileQuery {
+	/// Game path of the file to read.
+	#[schemars(example = "example_path")]
+	path: String,
+}
+
+/// Metadata of a texture asset.
+#[derive(Serialize, JsonSchema)]
+struct InfoResponse {
+	/// Dimensionality of the texture, i.e. `D2` or `Cube`.
+	kind: String,
+	/// Pixel format the texture data is stored in.
+	format: String,
+	width: u32,
+	height: u32,
+	depth: u32,
+	/// Number of mipmap levels stored, including the full size image.
+	mip_levels: u32,
+	/// Number of array layers stored.
+	array_size: u32,
+}
+
+impl From<TextureInfo> for InfoResponse {
+	fn from(info: TextureInfo) -> Self {
+		Self {
+			kind: info.kind,
+			format: info.format,
+			width: info.width,
+			height: info.height,
+			depth: info.depth,
+			mip_levels: info.mip_levels,
+			array_size: info.array_size,
+		}
+	}
+}
+
+fn info_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("read texture metadata")
+		.description("Read the metadata of a texture at the specified path, such as its dimensions and format, without converting it. Useful for deciding whether, or how, to fetch the texture itself.")
+		.response_with::<200, Json<InfoResponse>, _>(|response| {
+			response.example(InfoResponse {
+				kind: "D2".into(),
+				format: "Dxt5".into(),
+				width: 80,
+				height: 80,
+				depth: 1,
+				mip_levels: 7,
+				array_size: 1,
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn info(
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<FileQuery>,
+	State(asset): State<service::Asset>,
+) -> Result<impl IntoApiResponse> {
+	let info = asset.texture_info(version_key, &query.path).await?;
+	Ok(Json(InfoResponse::from(info)))
+}
+
+fn font_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("read font metrics")
+		.description("Read the glyph metrics and kerning of a font (`.fdt` file) at the specified path. Glyph images are packed into the channels of the font's atlas textures, four atlases per texture, which may be read via the asset endpoint, or extracted alongside the metrics via the font package endpoint.")
+		.response::<200, Json<Font>>()
+}
+
+#[debug_handler(state = service::State)]
+async fn font(
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<FileQuery>,
+	State(asset): State<service::Asset>,
+) -> Result<impl IntoApiResponse> {
+	let font = asset.font(version_key, &query.path).await?;
+	Ok(Json(font))
+}
+
+/// Query parameters accepted by the asset package endpoints.
+#[derive(Deserialize, JsonSchema)]
+struct PackageQuery {
+	/// Game path of the file to package.
+	path: String,
+
+	/// Format that packaged images should be converted into.
+	#[schemars(example = "example_format")]
+	format: Format,
+}
+
+fn font_package_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("package a font")
+		.description("Package a font (`.fdt` file) at the specified path as a zip bundle, containing its metrics as `font.json`, and each glyph atlas it uses as a greyscale image named `atlas_<index>`, where the index matches the `texture_index` of glyphs.")
+		.response_with::<200, Vec<u8>, _>(|mut response| {
+			response.inner().content = [(
+				"application/zip".to_string(),
+				openapi::MediaType::default(),
+			)]
+			.into_iter()
+			.collect();
+			response
+		})
+		.response_with::<304, (), _>(|res| res.description("not modified"))
+}
+
+#[debug_handler(state = service::State)]
+async fn font_package(
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<PackageQuery>,
+	NoApi(header_if_none_match): NoApi<Option<TypedHeader<IfNoneMatch>>>,
+	State(asset): State<service::Asset>,
+) -> Result<impl IntoApiResponse> {
+	let etag = etag(&query.path, query.format, version_key);
+
+	if let Some(TypedHeader(if_none_match)) = header_if_none_match {
+		if !if_none_match.precondition_passes(&etag) {
+			return Ok(StatusCode::NOT_MODIFIED.into_response());
+		}
+	}
+
+	let bytes = asset
+		.font_package(version_key, &query.path, query.format)
+		.await?;
+
+	Ok(package_response_body(&query.path, etag, bytes))
+}
+
+fn layout_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("read UI layout atlases")
+		.description("Read the textures referenced by a UI layout (`.uld` file) at the specified path, along with the rectangles of each texture making up its UI elements. Textures may be read via the asset endpoint, or bundled alongside the layout via the layout package endpoint.")
+		.response::<200, Json<UiLayout>>()
+}
+
+#[debug_handler(state = service::State)]
+async fn layout(
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<FileQuery>,
+	State(asset): State<service::Asset>,
+) -> Result<impl IntoApiResponse> {
+	let layout = asset.ui_layout(version_key, &query.path).await?;
+	Ok(Json(layout))
+}
+
+fn layout_package_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("package a UI layout")
+		.description("Package a UI layout (`.uld` file) at the specified path as a zip bundle, containing its textures and part lists as `layout.json`, and each texture it references as an image named `texture_<id>`. Textures missing from the game data are omitted.")
+		.response_with::<200, Vec<u8>, _>(|mut response| {
+			response.inner().content = [(
+				"application/zip".to_string(),
+				openapi::MediaType::default(),
+			)]
+			.into_iter()
+			.collect();
+			response
+		})
+		.response_with::<304, (), _>(|res| res.description("not modified"))
+}
+
+#[debug_handler(state = service::State)]
+async fn layout_package(
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<PackageQuery>,
+	NoApi(header_if_none_match): NoApi<Option<TypedHeader<IfNoneMatch>>>,
+	State(asset): State<service::Asset>,
+) -> Result<impl IntoApiResponse> {
+	let etag = etag(&query.path, query.format, version_key);
+
+	if let Some(TypedHeader(if_none_match)) = header_if_none_match {
+		if !if_none_match.precondition_passes(&etag) {
+			return Ok(StatusCode::NOT_MODIFIED.into_response());
+		}
+	}
+
+	let bytes = asset
+		.ui_layout_package(version_key, &query.path, query.format)
+		.await?;
+
+	Ok(package_response_body(&query.path, etag, bytes))
+}
+
+fn package_response_body(path: &str, etag: ETag, bytes: Vec<u8>) -> Response {
+	let filepath = std::path::Path::new(path).with_extension("zip");
+	let disposition = match filepath.file_name().and_then(OsStr::to_str) {
+		Some(name) => format!("attachment; filename=\"{name}\""),
+		None => "attachment".to_string(),
+	};
+
+	(
+		[
+			(header::CONTENT_TYPE, "application/zip".to_string()),
+			(header::CONTENT_DISPOSITION, disposition),
+		],
+		TypedHeader(etag),
+		bytes,
+	)
+		.into_response()
 }
 
 /// Path variables accepted by the asset endpoint.
@@ -94,7 +318,7 @@ async fn asset(
 		}
 	}
 
-	let bytes = asset.convert(version_key, &path, format)?;
+	let bytes = asset.convert(version_key, &path, format).await?;
 
 	let filepath = std::path::Path::new(&path).with_extension(format.extension());
 	let disposition = match filepath.file_name().and_then(OsStr::to_str) {
@@ -128,3 +352,73 @@ fn etag(path: &str, format: Format, version: VersionKey) -> ETag {
 		.parse()
 		.expect("malformed etag")
 }
+
+/// Query parameters accepted by the asset composite endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct CompositeQuery {
+	/// Game paths of the images to composite, as a comma-separated list ordered
+	/// from the bottom layer up.
+	layers: String,
+
+	/// Format that the composite should be converted into.
+	#[schemars(example = "example_format")]
+	format: Format,
+}
+
+fn composite_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("composite assets")
+		.description("Experimental. Composite multiple images from the game into one, such as an icon with its frame, or the layers of a plate. Layers are drawn from the bottom up, centered over the first layer, which determines the size of the output.")
+		.response_with::<200, Vec<u8>, _>(|mut response| {
+			response.inner().content = Format::iter()
+				.map(|format| {
+					(
+						format_mime(format).to_string(),
+						openapi::MediaType::default(),
+					)
+				})
+				.collect();
+			response
+		})
+		.response_with::<304, (), _>(|res| res.description("not modified"))
+}
+
+#[debug_handler(state = service::State)]
+async fn composite(
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<CompositeQuery>,
+	NoApi(header_if_none_match): NoApi<Option<TypedHeader<IfNoneMatch>>>,
+	State(asset): State<service::Asset>,
+) -> Result<impl IntoApiResponse> {
+	let format = query.format;
+
+	let layers = query
+		.layers
+		.split(',')
+		.filter(|layer| !layer.is_empty())
+		.map(str::to_string)
+		.collect::<Vec<_>>();
+
+	if layers.is_empty() || layers.len() > MAX_COMPOSITE_LAYERS {
+		return Err(Error::Invalid(format!(
+			"composites must have between 1 and {MAX_COMPOSITE_LAYERS} layers"
+		)));
+	}
+
+	let etag = etag(&query.layers, format, version_key);
+
+	if let Some(TypedHeader(if_none_match)) = header_if_none_match {
+		if !if_none_match.precondition_passes(&etag) {
+			return Ok(StatusCode::NOT_MODIFIED.into_response());
+		}
+	}
+
+	let bytes = asset.composite(version_key, layers, format).await?;
+
+	Ok((
+		TypedHeader(ContentType::from(format_mime(format))),
+		TypedHeader(etag),
+		bytes,
+	)
+		.into_response())
+}
@@ -0,0 +1,191 @@
+use std::{
+	convert::Infallible,
+	pin::Pin,
+	sync::Arc,
+	task::{Context, Poll},
+	time::Duration,
+};
+
+use aide::OperationIo;
+use axum::{
+	async_trait,
+	body::{Body, Bytes},
+	extract::{FromRequestParts, Request, State},
+	http::request::Parts,
+	middleware::Next,
+	response::{IntoResponse, Response},
+};
+use http_body::{Frame, SizeHint};
+use serde::Deserialize;
+use tokio::time;
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::config::Validator;
+
+use super::error::Error;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+	/// Maximum time a request may take, in seconds. Requests are not limited if
+	/// unset. Long-polling endpoints are subject to this limit as well.
+	timeout: Option<u64>,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		if let Some(timeout) = self.timeout {
+			validator.check("timeout", timeout > 0, "must be at least 1");
+		}
+	}
+}
+
+pub struct Deadline {
+	timeout: Option<Duration>,
+}
+
+impl Deadline {
+	pub fn new(config: Config) -> Self {
+		Self {
+			timeout: config.timeout.map(Duration::from_secs),
+		}
+	}
+}
+
+/// Middleware cancelling the work of a request once its response is no longer
+/// wanted - either because the client disconnected, or the timeout elapsed
+/// before a response was ready. Responses streamed from background work keep
+/// the request alive until their body has been sent.
+pub async fn apply(
+	State(deadline): State<Arc<Deadline>>,
+	mut request: Request,
+	next: Next,
+) -> Response {
+	let cancel = CancellationToken::new();
+	request
+		.extensions_mut()
+		.insert(Cancellation(cancel.clone()));
+
+	// A client disconnecting drops this future, which in turn drops the guard.
+	// Once a response is ready, the guard moves into its body instead.
+	let guard = cancel.clone().drop_guard();
+
+	let Some(timeout) = deadline.timeout else {
+		return guard_body(next.run(request).await, guard);
+	};
+
+	match time::timeout(timeout, next.run(request)).await {
+		Ok(response) => guard_body(response, guard),
+		Err(_elapsed) => {
+			cancel.cancel();
+			Error::Unavailable(format!(
+				"request exceeded the time limit of {} seconds",
+				timeout.as_secs()
+			))
+			.into_response()
+		}
+	}
+}
+
+fn guard_body(response: Response, guard: DropGuard) -> Response {
+	response.map(|body| {
+		Body::new(GuardedBody {
+			inner: body,
+			_guard: guard,
+		})
+	})
+}
+
+/// Response body holding a request's cancellation guard, so that work writing
+/// to the body is cancelled only once the body has been sent or dropped.
+struct GuardedBody {
+	inner: Body,
+	_guard: DropGuard,
+}
+
+impl http_body::Body for GuardedBody {
+	type Data = Bytes;
+	type Error = axum::Error;
+
+	fn poll_frame(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+		Pin::new(&mut self.inner).poll_frame(cx)
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.inner.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.inner.size_hint()
+	}
+}
+
+/// Token cancelled when the current request is abandoned. Blocking work on
+/// behalf of the request should stop once it has been cancelled.
+#[derive(Clone, OperationIo)]
+pub struct Cancellation(pub CancellationToken);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Cancellation {
+	type Rejection = Infallible;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		// Requests outside the middleware are never cancelled.
+		let cancellation = parts
+			.extensions
+			.get::<Self>()
+			.cloned()
+			.unwrap_or_else(|| Self(CancellationToken::new()));
+
+		Ok(cancellation)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::io::Write;
+
+	use axum::{body, middleware, routing::get, Router};
+	use pretty_assertions::assert_eq;
+	use tower::ServiceExt;
+
+	use super::{super::negotiate, *};
+
+	/// Enough rows to fill the body's buffer, so that writing continues after
+	/// the response has left the middleware.
+	const ROWS: u32 = 200_000;
+
+	async fn stream(Cancellation(cancel): Cancellation) -> Body {
+		let (mut writer, body) = negotiate::body_writer();
+		tokio::task::spawn_blocking(move || {
+			let result = (0..ROWS).try_for_each(|row| {
+				anyhow::ensure!(!cancel.is_cancelled(), "cancelled");
+				writeln!(writer, "{row}")?;
+				Ok(())
+			});
+			writer.finish(result);
+		});
+		body
+	}
+
+	#[tokio::test]
+	async fn streamed_body_finishes() {
+		let deadline = Arc::new(Deadline::new(Config { timeout: Some(60) }));
+		let router = Router::new()
+			.route("/", get(stream))
+			.layer(middleware::from_fn_with_state(deadline, apply));
+
+		let response = router
+			.oneshot(axum::http::Request::get("/").body(Body::empty()).unwrap())
+			.await
+			.unwrap();
+		let bytes = body::to_bytes(response.into_body(), usize::MAX)
+			.await
+			.expect("body should not be cancelled");
+
+		let expected = (0..ROWS).map(|row| format!("{row}\n")).collect::<String>();
+		assert_eq!(String::from_utf8(bytes.to_vec()).unwrap(), expected);
+	}
+}
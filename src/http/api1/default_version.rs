@@ -0,0 +1,130 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+	extract::{Request, State},
+	http::HeaderValue,
+	middleware::Next,
+	response::Response,
+};
+use serde::Deserialize;
+
+use crate::{
+	config::Validator,
+	data::Data,
+	version::{self, VersionKey},
+};
+
+use super::error::Error;
+
+/// Header declaring the key of the version that served a request.
+pub const VERSION_KEY_HEADER: &str = "x-version-key";
+
+/// Version used by requests that specify none, and have no tenant default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Config {
+	/// Reject requests that do not specify a version.
+	Reject,
+
+	/// Use the version tagged as latest, even if its data is still being prepared.
+	#[default]
+	Latest,
+
+	/// Use the version tagged as latest once its data has been prepared, falling
+	/// back to the newest version that has been prepared until then.
+	LatestReady,
+
+	/// Use the named version.
+	Pin(String),
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		if let Self::Pin(name) = self {
+			validator.check("pin", !name.is_empty(), "version name must not be empty");
+		}
+	}
+}
+
+pub struct DefaultVersion {
+	config: Config,
+}
+
+impl DefaultVersion {
+	pub fn new(config: Config) -> Self {
+		Self { config }
+	}
+
+	/// Resolve the default version for a request.
+	pub fn resolve(&self, version: &version::Manager, data: &Data) -> Result<VersionKey, Error> {
+		let unknown = |name: &str| Error::Invalid(format!("unknown version \"{name}\""));
+
+		match &self.config {
+			Config::Reject => Err(Error::Invalid(
+				"a version must be specified for this request".into(),
+			)),
+
+			Config::Latest => version.resolve(None).ok_or_else(|| unknown("latest")),
+
+			Config::LatestReady => {
+				// Readiness is derived from the prepared versions rather than
+				// remembered, so that it survives a restart while latest is still
+				// being prepared.
+				let prepared = data.subscribe().borrow().clone();
+				if let Some(key) = version.resolve(None).filter(|key| prepared.contains(key)) {
+					return Ok(key);
+				}
+
+				// Patches are named chronologically - the newest prepared version is
+				// the one with the latest patches.
+				prepared
+					.into_iter()
+					.max_by_key(|key| {
+						version.version(*key).map(|version| {
+							version
+								.repositories
+								.iter()
+								.map(|repository| repository.latest().name.clone())
+								.collect::<Vec<_>>()
+						})
+					})
+					.ok_or_else(|| Error::Invalid("no version has finished preparing yet".into()))
+			}
+
+			Config::Pin(name) => version.resolve(Some(name)).ok_or_else(|| unknown(name)),
+		}
+	}
+}
+
+/// Version that served the current request, recorded by the version extractor.
+#[derive(Clone, Default)]
+pub struct ServedVersion(Arc<Mutex<Option<VersionKey>>>);
+
+impl ServedVersion {
+	pub fn record(&self, key: VersionKey) {
+		*self.0.lock().expect("poisoned") = Some(key);
+	}
+}
+
+/// Middleware making the default version available to requests, and declaring
+/// the version that served each request in its response headers.
+pub async fn apply(
+	State(default_version): State<Arc<DefaultVersion>>,
+	mut request: Request,
+	next: Next,
+) -> Response {
+	let served = ServedVersion::default();
+	request.extensions_mut().insert(default_version);
+	request.extensions_mut().insert(served.clone());
+
+	let mut response = next.run(request).await;
+
+	let key = *served.0.lock().expect("poisoned");
+	if let Some(key) = key {
+		let value =
+			HeaderValue::from_str(&key.to_string()).expect("version keys are valid header values");
+		response.headers_mut().insert(VERSION_KEY_HEADER, value);
+	}
+
+	response
+}
@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::{
+	body::{self, Body},
+	extract::{Query, Request, State},
+	http::{header, HeaderValue},
+	middleware::Next,
+	response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::config::Validator;
+
+use super::{default_version::VERSION_KEY_HEADER, error::Error};
+
+/// Maximum size of a response body that will be buffered to wrap in an
+/// envelope. Well above the size of a sheet response at the configured limits.
+pub(super) const BODY_LIMIT: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+	/// Whether responses are wrapped in an envelope by default.
+	#[serde(default)]
+	default: bool,
+
+	/// Route path prefixes whose responses are wrapped by default, i.e.
+	/// `/sheet`, regardless of the above.
+	#[serde(default)]
+	routes: Vec<String>,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		for route in &self.routes {
+			validator.check(
+				"routes",
+				route.starts_with('/'),
+				format_args!("route prefix \"{route}\" must start with /"),
+			);
+		}
+	}
+}
+
+/// Query parameters accepted by every endpoint, controlling response wrapping.
+#[derive(Deserialize)]
+struct EnvelopeQuery {
+	/// Whether to wrap the response in an envelope, overriding the route's default.
+	envelope: Option<bool>,
+}
+
+/// Warnings about a request that did not prevent it from succeeding. Handlers
+/// may add these to response extensions, they are reported in enveloped
+/// responses.
+#[derive(Clone, Default)]
+pub struct Warnings(pub Vec<String>);
+
+#[derive(Serialize)]
+struct EnvelopeBody {
+	data: Box<RawValue>,
+	meta: EnvelopeMeta,
+	warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct EnvelopeMeta {
+	/// Key of the version that served the request, if any.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	version: Option<String>,
+}
+
+pub struct Envelope {
+	default: bool,
+	routes: Vec<String>,
+}
+
+impl Envelope {
+	pub fn new(config: Config) -> Self {
+		Self {
+			default: config.default,
+			routes: config.routes,
+		}
+	}
+
+	fn enabled(&self, path: &str) -> bool {
+		self.default
+			|| self
+				.routes
+				.iter()
+				.any(|route| path.starts_with(route.as_str()))
+	}
+}
+
+/// Middleware wrapping successful JSON responses in a `{data, meta, warnings}`
+/// envelope, when enabled by configuration or the `envelope` query parameter.
+/// Errors and non-JSON responses are left as-is.
+pub async fn apply(
+	State(envelope): State<Arc<Envelope>>,
+	request: Request,
+	next: Next,
+) -> Response {
+	let requested = match Query::<EnvelopeQuery>::try_from_uri(request.uri()) {
+		Ok(Query(query)) => query.envelope,
+		Err(rejection) => return Error::Invalid(rejection.body_text()).into_response(),
+	};
+	if !requested.unwrap_or_else(|| envelope.enabled(request.uri().path())) {
+		return next.run(request).await;
+	}
+
+	let response = next.run(request).await;
+
+	let is_json = response
+		.headers()
+		.get(header::CONTENT_TYPE)
+		.and_then(|value| value.to_str().ok())
+		.map_or(false, |value| value.starts_with("application/json"));
+	if !response.status().is_success() || !is_json {
+		return response;
+	}
+
+	let (mut parts, body) = response.into_parts();
+	let body = match body::to_bytes(body, BODY_LIMIT)
+		.await
+		.context("failed to buffer response for envelope")
+	{
+		Ok(body) => body,
+		Err(error) => return Error::Other(error).into_response(),
+	};
+	// The body is spliced in as-is, rather than parsed to a value, so that field
+	// order and number formatting are left untouched.
+	let data = match serde_json::from_slice::<Box<RawValue>>(&body) {
+		Ok(data) => data,
+		Err(error) => return Error::Other(error.into()).into_response(),
+	};
+
+	let envelope_body = EnvelopeBody {
+		data,
+		meta: EnvelopeMeta {
+			version: parts
+				.headers
+				.get(VERSION_KEY_HEADER)
+				.and_then(|value| value.to_str().ok())
+				.map(String::from),
+		},
+		warnings: parts.extensions.remove::<Warnings>().unwrap_or_default().0,
+	};
+
+	let body = match serde_json::to_vec(&envelope_body) {
+		Ok(body) => body,
+		Err(error) => return Error::Other(error.into()).into_response(),
+	};
+
+	// The wrapped body has a different length, and any validator for the bare
+	// body no longer applies.
+	parts.headers.remove(header::CONTENT_LENGTH);
+	parts.headers.remove(header::ETAG);
+	parts.headers.insert(
+		header::CONTENT_TYPE,
+		HeaderValue::from_static("application/json"),
+	);
+
+	Response::from_parts(parts, Body::from(body))
+}
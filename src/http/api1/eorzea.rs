@@ -0,0 +1,247 @@
+use std::{
+	str::FromStr,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use aide::{
+	axum::{routing::get_with, ApiRouter, IntoApiResponse},
+	transform::TransformOperation,
+};
+use axum::{debug_handler, Json};
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::{http::service, utility::eorzea};
+
+use super::{
+	error::{Error, Result},
+	extract::Query,
+};
+
+/// Maximum number of window occurrences returned by a single request.
+const WINDOW_COUNT_MAX: u32 = 50;
+
+pub fn router() -> ApiRouter<service::State> {
+	ApiRouter::new()
+		.api_route("/time", get_with(time, time_docs))
+		.api_route("/real", get_with(real, real_docs))
+		.api_route("/window", get_with(window, window_docs))
+}
+
+fn out_of_range(parameter: &str) -> Error {
+	Error::Invalid(format!("{parameter} is out of the supported range"))
+}
+
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_secs())
+}
+
+/// Query parameters accepted by the Eorzea time endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct TimeQuery {
+	/// Time to convert, in seconds since the unix epoch. Defaults to the current time.
+	at: Option<u64>,
+}
+
+/// Response structure for the Eorzea time endpoint.
+#[derive(Serialize, JsonSchema)]
+struct TimeResponse {
+	/// Converted time, in seconds since the unix epoch.
+	unix: u64,
+
+	/// Eorzean time at the converted time.
+	eorzea: EorzeaTime,
+}
+
+/// Point in Eorzean time. Eorzean years are 12 moons of 32 suns (days), each 24
+/// bells (hours) long.
+#[derive(Serialize, JsonSchema)]
+struct EorzeaTime {
+	/// Seconds since the Eorzean epoch.
+	timestamp: u64,
+	year: u64,
+	moon: u8,
+	sun: u8,
+	bell: u8,
+	minute: u8,
+}
+
+impl From<eorzea::EorzeaTime> for EorzeaTime {
+	fn from(time: eorzea::EorzeaTime) -> Self {
+		Self {
+			timestamp: time.timestamp,
+			year: time.year,
+			moon: time.moon,
+			sun: time.sun,
+			bell: time.bell,
+			minute: time.minute,
+		}
+	}
+}
+
+fn time_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("convert to eorzean time")
+		.description("Convert a real time into Eorzean time.")
+		.response_with::<200, Json<TimeResponse>, _>(|response| {
+			response.example(TimeResponse {
+				unix: 1700000000,
+				eorzea: eorzea::EorzeaTime::from_timestamp(
+					eorzea::to_eorzea(1700000000).expect("example is in range"),
+				)
+				.into(),
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn time(Query(query): Query<TimeQuery>) -> Result<impl IntoApiResponse> {
+	let unix = query.at.unwrap_or_else(now);
+	let eorzea = eorzea::to_eorzea(unix).ok_or_else(|| out_of_range("at"))?;
+	let eorzea = eorzea::EorzeaTime::from_timestamp(eorzea);
+
+	Ok(Json(TimeResponse {
+		unix,
+		eorzea: eorzea.into(),
+	}))
+}
+
+/// Query parameters accepted by the real time endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct RealQuery {
+	/// Eorzean time to convert, in seconds since the Eorzean epoch.
+	eorzea: u64,
+}
+
+/// Response structure for the real time endpoint.
+#[derive(Serialize, JsonSchema)]
+struct RealResponse {
+	/// Earliest time at which the Eorzean time is reached, in seconds since the
+	/// unix epoch.
+	unix: u64,
+
+	/// The converted Eorzean time.
+	eorzea: EorzeaTime,
+}
+
+fn real_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("convert to real time")
+		.description("Convert an Eorzean time into the earliest real time at which it is reached.")
+		.response_with::<200, Json<RealResponse>, _>(|response| {
+			response.example(RealResponse {
+				unix: 1700000000,
+				eorzea: eorzea::EorzeaTime::from_timestamp(
+					eorzea::to_eorzea(1700000000).expect("example is in range"),
+				)
+				.into(),
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn real(Query(query): Query<RealQuery>) -> Result<impl IntoApiResponse> {
+	Ok(Json(RealResponse {
+		unix: eorzea::to_real(query.eorzea).ok_or_else(|| out_of_range("eorzea"))?,
+		eorzea: eorzea::EorzeaTime::from_timestamp(query.eorzea).into(),
+	}))
+}
+
+/// Query parameters accepted by the Eorzea window endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct WindowQuery {
+	/// Eorzean time of day the window starts at, as `HH:MM`.
+	#[schemars(with = "String")]
+	start: TimeOfDay,
+
+	/// Eorzean time of day the window ends at, as `HH:MM`. Windows ending at or
+	/// before their start span midnight.
+	#[schemars(with = "String")]
+	end: TimeOfDay,
+
+	/// Time to search for the window from, in seconds since the unix epoch.
+	/// Defaults to the current time.
+	at: Option<u64>,
+
+	/// Number of consecutive occurrences of the window to return.
+	count: Option<u32>,
+}
+
+/// Time of day, as Eorzean seconds since midnight.
+#[derive(Clone, Copy)]
+struct TimeOfDay(u64);
+
+impl FromStr for TimeOfDay {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		let invalid = || format!("invalid time of day \"{input}\", expected HH:MM");
+
+		let (hours, minutes) = input.split_once(':').ok_or_else(invalid)?;
+		let hours = hours.parse::<u64>().map_err(|_| invalid())?;
+		let minutes = minutes.parse::<u64>().map_err(|_| invalid())?;
+		if hours > 24 || minutes > 59 || (hours == 24 && minutes > 0) {
+			return Err(invalid());
+		}
+
+		Ok(Self((hours * 60 + minutes) * 60))
+	}
+}
+
+impl<'de> Deserialize<'de> for TimeOfDay {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let raw = String::deserialize(deserializer)?;
+		raw.parse().map_err(de::Error::custom)
+	}
+}
+
+/// Response structure for the Eorzea window endpoint.
+#[derive(Serialize, JsonSchema)]
+struct WindowResponse {
+	/// Occurrences of the window, starting with the next or current occurrence.
+	windows: Vec<Window>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Window {
+	/// Start of the window, in seconds since the unix epoch.
+	start: u64,
+
+	/// End of the window, in seconds since the unix epoch.
+	end: u64,
+}
+
+fn window_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("find eorzean time window")
+		.description("Find the next occurrences of a daily window of Eorzean time, such as the spawn window of a gathering node, as real times. A window already in progress is included as the first occurrence.")
+		.response_with::<200, Json<WindowResponse>, _>(|response| {
+			response.example(WindowResponse {
+				windows: vec![Window {
+					start: 1700000292,
+					end: 1700000467,
+				}],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn window(Query(query): Query<WindowQuery>) -> Result<impl IntoApiResponse> {
+	let count = query.count.unwrap_or(1).clamp(1, WINDOW_COUNT_MAX);
+
+	let mut at = query.at.unwrap_or_else(now);
+	let mut windows = Vec::new();
+	for _ in 0..count {
+		let (start, end) = eorzea::next_window(at, query.start.0, query.end.0)
+			.ok_or_else(|| out_of_range("at"))?;
+		windows.push(Window { start, end });
+		at = end;
+	}
+
+	Ok(Json(WindowResponse { windows }))
+}
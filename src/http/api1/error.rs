@@ -1,20 +1,19 @@
 use aide::{openapi::Response as AideResponse, transform::TransformResponse, OperationOutput};
 use axum::{
-	extract::rejection::{PathRejection, QueryRejection},
-	http::StatusCode,
+	extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+	http::{header, StatusCode},
 	response::{IntoResponse, Response as AxumResponse},
 	Json,
 };
 use schemars::JsonSchema;
 use serde::Serialize;
 
-use crate::{
-	asset,
-	data,
-	read,
-	schema,
-	// search
-};
+use crate::{asset, data, error, read, schema};
+
+use super::upstream::UnknownVersion;
+
+/// Seconds clients are asked to wait before retrying an unavailable request.
+const RETRY_AFTER_SECONDS: &str = "30";
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -24,73 +23,57 @@ pub enum Error {
 	#[error("invalid request: {0}")]
 	Invalid(String),
 
-	// #[error("unavailable: {0}")]
-	// Unavailable(String),
-	//
+	/// The requested version is not known to this instance. Such requests may be
+	/// forwarded to an upstream instance, if configured.
+	#[error("invalid request: unknown version \"{0}\"")]
+	UnknownVersion(String),
+
+	#[error("forbidden: {0}")]
+	Forbidden(String),
+
+	#[error("too many requests: {0}")]
+	TooManyRequests(String),
+
+	#[error("unavailable: {0}")]
+	Unavailable(String),
+
 	#[error("internal server error")]
 	Other(#[from] anyhow::Error),
 }
 
-impl From<asset::Error> for Error {
-	fn from(error: asset::Error) -> Self {
-		use asset::Error as AE;
-		match error {
-			AE::NotFound(value) => Self::NotFound(value),
-			AE::UnsupportedSource(..) | AE::InvalidConversion(..) | AE::UnknownFormat(..) => {
-				Self::Invalid(error.to_string())
+impl From<error::Error> for Error {
+	fn from(value: error::Error) -> Self {
+		use error::ErrorKind as EK;
+		match value.kind() {
+			EK::NotFound => Self::NotFound(value.to_string()),
+			EK::Invalid => Self::Invalid(value.to_string()),
+			EK::Unavailable => Self::Unavailable(value.to_string()),
+			// Transient failures are still failures of the server, their details
+			// shouldn't be shown - but they're worth retrying.
+			EK::Transient => {
+				tracing::warn!("{:?}", value.into_inner());
+				Self::Unavailable("temporary failure, please retry".into())
 			}
-			AE::Failure(inner) => Self::Other(inner),
+			EK::Failure => Self::Other(value.into_inner()),
 		}
 	}
 }
 
-impl From<data::Error> for Error {
-	fn from(error: data::Error) -> Self {
-		use data::Error as DE;
-		match error {
-			DE::UnknownVersion(..) => Self::Invalid(error.to_string()),
-			DE::Failure(inner) => Self::Other(inner),
-		}
-	}
-}
-
-impl From<read::Error> for Error {
-	fn from(error: read::Error) -> Self {
-		use read::Error as RE;
-		match error {
-			RE::NotFound(..) => Self::NotFound(error.to_string()),
-			RE::FilterSchemaMismatch(..) | RE::SchemaGameMismatch(..) | RE::InvalidLanguage(..) => {
-				Self::Invalid(error.to_string())
+// Module errors are mapped to responses by their classification.
+macro_rules! impl_from_classified {
+	($source:ty) => {
+		impl From<$source> for Error {
+			fn from(value: $source) -> Self {
+				error::Error::from(value).into()
 			}
-			RE::Failure(inner) => Self::Other(inner),
-		}
-	}
-}
-
-impl From<schema::Error> for Error {
-	fn from(error: schema::Error) -> Self {
-		use schema::Error as SE;
-		match error {
-			SE::UnknownSource(..) | SE::InvalidVersion(..) => Self::Invalid(error.to_string()),
-			SE::Failure(inner) => Self::Other(inner),
 		}
-	}
+	};
 }
 
-// impl From<search::Error> for Error {
-// 	fn from(error: search::Error) -> Self {
-// 		use search::Error as SE;
-// 		match error {
-// 			SE::FieldType(..)
-// 			| SE::MalformedQuery(..)
-// 			| SE::QuerySchemaMismatch(..)
-// 			| SE::QueryGameMismatch(..)
-// 			| SE::SchemaGameMismatch(..)
-// 			| SE::UnknownCursor(..) => Self::Invalid(error.to_string()),
-// 			SE::Failure(inner) => Self::Other(inner),
-// 		}
-// 	}
-// }
+impl_from_classified!(asset::Error);
+impl_from_classified!(data::Error);
+impl_from_classified!(read::Error);
+impl_from_classified!(schema::Error);
 
 impl From<PathRejection> for Error {
 	fn from(value: PathRejection) -> Self {
@@ -112,6 +95,17 @@ impl From<QueryRejection> for Error {
 	}
 }
 
+impl From<JsonRejection> for Error {
+	fn from(value: JsonRejection) -> Self {
+		match value {
+			JsonRejection::JsonDataError(..)
+			| JsonRejection::JsonSyntaxError(..)
+			| JsonRejection::MissingJsonContentType(..) => Self::Invalid(value.body_text()),
+			other => Self::Other(other.into()),
+		}
+	}
+}
+
 /// General purpose error response structure.
 #[derive(Serialize, JsonSchema)]
 pub struct ErrorResponse {
@@ -132,8 +126,10 @@ impl From<Error> for ErrorResponse {
 		// TODO: INCREDIBLY IMPORTANT: work out how to worm IM_A_TEAPOT into this
 		let status_code = match value {
 			Error::NotFound(..) => StatusCode::NOT_FOUND,
-			Error::Invalid(..) => StatusCode::BAD_REQUEST,
-			// Error::Unavailable(..) => StatusCode::SERVICE_UNAVAILABLE,
+			Error::Invalid(..) | Error::UnknownVersion(..) => StatusCode::BAD_REQUEST,
+			Error::Forbidden(..) => StatusCode::FORBIDDEN,
+			Error::TooManyRequests(..) => StatusCode::TOO_MANY_REQUESTS,
+			Error::Unavailable(..) => StatusCode::SERVICE_UNAVAILABLE,
 			Error::Other(..) => StatusCode::INTERNAL_SERVER_ERROR,
 		};
 
@@ -151,9 +147,26 @@ impl IntoResponse for Error {
 			tracing::error!("{error:?}")
 		}
 
+		let unknown_version = matches!(self, Self::UnknownVersion(..));
 		let response = ErrorResponse::from(self);
 
-		(response.code, Json(response)).into_response()
+		// Unavailability is transient - data still being prepared, or an overloaded
+		// server. Let clients know when it's worth trying again.
+		if response.code == StatusCode::SERVICE_UNAVAILABLE {
+			return (
+				response.code,
+				[(header::RETRY_AFTER, RETRY_AFTER_SECONDS)],
+				Json(response),
+			)
+				.into_response();
+		}
+
+		let mut response = (response.code, Json(response)).into_response();
+		if unknown_version {
+			response.extensions_mut().insert(UnknownVersion);
+		}
+
+		response
 	}
 }
 
@@ -1,9 +1,9 @@
-use std::convert::Infallible;
+use std::{convert::Infallible, sync::Arc};
 
 use aide::OperationIo;
 use axum::{
 	async_trait,
-	extract::{FromRef, FromRequestParts, OriginalUri},
+	extract::{FromRef, FromRequest, FromRequestParts, OriginalUri},
 	http::{request::Parts, Uri},
 	RequestPartsExt,
 };
@@ -12,7 +12,11 @@ use serde::Deserialize;
 
 use crate::{http::service, version::VersionKey};
 
-use super::error::Error;
+use super::{
+	default_version::{DefaultVersion, ServedVersion},
+	error::Error,
+	tenant::CurrentTenant,
+};
 
 /// # VersionQuery
 /// Query parameters accepted by endpoints that interact with versioned game data.
@@ -30,6 +34,7 @@ pub struct VersionQuery(pub VersionKey);
 impl<S> FromRequestParts<S> for VersionQuery
 where
 	S: Send + Sync,
+	service::Data: FromRef<S>,
 	service::Version: FromRef<S>,
 {
 	type Rejection = Error;
@@ -42,19 +47,41 @@ where
 
 		let version = service::Version::from_ref(state);
 
-		let version_name = params.version.as_deref();
-		let version_key = version.resolve(version_name).ok_or_else(|| {
-			Error::Invalid(format!(
-				"unknown version \"{}\"",
-				version_name.unwrap_or("(none)")
-			))
-		})?;
+		// Requests without an explicit version use their tenant's default, if any,
+		// falling back to the configured default.
+		let tenant = match parts.extract::<CurrentTenant>().await {
+			Ok(tenant) => tenant,
+			Err(infallible) => match infallible {},
+		};
+		let version_key = match tenant.version(params.version.as_deref()) {
+			Some(version_name) => version
+				.resolve(Some(version_name))
+				.ok_or_else(|| Error::UnknownVersion(version_name.into()))?,
+			None => match parts.extensions.get::<Arc<DefaultVersion>>() {
+				Some(default_version) => {
+					let data = service::Data::from_ref(state);
+					default_version.resolve(&version, &data)?
+				}
+				None => version
+					.resolve(None)
+					.ok_or_else(|| Error::Invalid("unknown version \"latest\"".into()))?,
+			},
+		};
+
+		if let Some(served) = parts.extensions.get::<ServedVersion>() {
+			served.record(version_key);
+		}
+
+		// Record the version against the request, for context in logs and error reports.
+		tracing::Span::current().record("version_key", tracing::field::display(version_key));
+		sentry::configure_scope(|scope| scope.set_tag("version_key", version_key));
 
 		Ok(Self(version_key))
 	}
 }
 
 // This cursed garbage courtesy of trying to get the path of the parent router. Fun.
+#[derive(OperationIo)]
 pub struct RouterPath(pub String);
 
 #[async_trait]
@@ -71,6 +98,30 @@ impl<S> FromRequestParts<S> for RouterPath {
 	}
 }
 
+/// Header carrying the API key a request is made with.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// API key the request was made with, if any. Keys are not authenticated, they
+/// only identify the caller.
+#[derive(OperationIo)]
+pub struct ApiKey(pub Option<String>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ApiKey {
+	type Rejection = Infallible;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		let key = parts
+			.headers
+			.get(API_KEY_HEADER)
+			.and_then(|value| value.to_str().ok())
+			.filter(|value| !value.is_empty())
+			.map(String::from);
+
+		Ok(Self(key))
+	}
+}
+
 #[derive(FromRequestParts, OperationIo)]
 #[from_request(via(axum::extract::Path), rejection(Error))]
 #[aide(input_with = "axum::extract::Path<T>", json_schema)]
@@ -80,3 +131,8 @@ pub struct Path<T>(pub T);
 #[from_request(via(axum::extract::Query), rejection(Error))]
 #[aide(input_with = "axum::extract::Query<T>", json_schema)]
 pub struct Query<T>(pub T);
+
+#[derive(FromRequest, OperationIo)]
+#[from_request(via(axum::Json), rejection(Error))]
+#[aide(input_with = "axum::Json<T>", json_schema)]
+pub struct JsonBody<T>(pub T);
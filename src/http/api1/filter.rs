@@ -33,12 +33,12 @@ use super::error;
 /// select _all_ `b` fields of structs within the array `a`, however `a.b` will
 /// select nothing.
 #[derive(Debug, Clone, JsonSchema)]
-pub struct FilterString(#[schemars(with = "String")] Vec<Path>);
+pub struct FilterString(#[schemars(with = "String")] pub(super) Vec<Path>);
 
-type Path = Vec<Entry>;
+pub(super) type Path = Vec<Entry>;
 
 #[derive(Debug, Clone)]
-enum Entry {
+pub(super) enum Entry {
 	Key(String, Option<excel::Language>),
 	Index,
 }
@@ -1,10 +1,30 @@
+mod acl;
 mod api;
 mod asset;
+mod deadline;
+mod default_version;
+mod envelope;
+mod eorzea;
 mod error;
 mod extract;
 mod filter;
+mod negotiate;
+mod pretty;
+mod provenance;
+mod query;
+mod range;
+mod resolve;
 mod sheet;
+mod signing;
+mod tenant;
+mod upstream;
+mod usage;
 mod value;
 mod version;
+mod view;
+mod weather;
 
-pub use api::{router, Config};
+pub use {
+	api::{router, Config},
+	usage::record as record_usage,
+};
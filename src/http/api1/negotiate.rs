@@ -0,0 +1,240 @@
+use std::{
+	convert::Infallible,
+	io::{self, Write},
+	mem,
+};
+
+use aide::{openapi, OperationIo, OperationOutput};
+use anyhow::Context;
+use axum::{
+	async_trait,
+	body::{Body, Bytes},
+	extract::FromRequestParts,
+	http::{header, request::Parts},
+	response::{IntoResponse, Response},
+	Json,
+};
+use futures::stream;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+/// Size of the chunks response bodies are sent in, in bytes.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Number of encoded chunks buffered ahead of the client. Encoding pauses while
+/// the buffer is full, bounding the memory used by each response.
+const CHUNK_BUFFER: usize = 4;
+
+/// Formats response bodies may be serialized as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyFormat {
+	#[default]
+	Json,
+	MessagePack,
+	Cbor,
+}
+
+impl BodyFormat {
+	fn from_media_type(media_type: &str) -> Option<Self> {
+		let format = match media_type {
+			"application/json" | "application/*" | "*/*" => Self::Json,
+			"application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+				Self::MessagePack
+			}
+			"application/cbor" => Self::Cbor,
+			_ => return None,
+		};
+		Some(format)
+	}
+
+	fn content_type(&self) -> &'static str {
+		match self {
+			Self::Json => "application/json",
+			Self::MessagePack => "application/msgpack",
+			Self::Cbor => "application/cbor",
+		}
+	}
+
+	/// Pick the format most preferred by an `Accept` header. Unsupported media
+	/// types are ignored, falling back to JSON if nothing else matches.
+	fn negotiate(accept: &str) -> Self {
+		let mut best = (Self::default(), 0.0);
+
+		for range in accept.split(',') {
+			let mut parts = range.split(';').map(str::trim);
+			let media_type = parts.next().unwrap_or("").to_ascii_lowercase();
+			let Some(format) = Self::from_media_type(&media_type) else {
+				continue;
+			};
+
+			let quality = parts
+				.find_map(|parameter| parameter.strip_prefix("q="))
+				.and_then(|quality| quality.parse::<f32>().ok())
+				.unwrap_or(1.0);
+
+			if quality > best.1 {
+				best = (format, quality);
+			}
+		}
+
+		best.0
+	}
+}
+
+/// Response body format requested by the client via the `Accept` header.
+#[derive(OperationIo)]
+pub struct Negotiated(pub BodyFormat);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Negotiated {
+	type Rejection = Infallible;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		let format = parts
+			.headers
+			.get(header::ACCEPT)
+			.and_then(|value| value.to_str().ok())
+			.map(BodyFormat::negotiate)
+			.unwrap_or_default();
+
+		Ok(Self(format))
+	}
+}
+
+/// Response body serialized in a negotiated format. Documented as JSON, which
+/// the binary formats mirror structurally.
+pub struct Encoded<T>(pub BodyFormat, pub T);
+
+impl<T> IntoResponse for Encoded<T>
+where
+	T: Serialize + Send + 'static,
+{
+	fn into_response(self) -> Response {
+		let Self(format, value) = self;
+
+		// Bodies are serialized incrementally on the blocking pool. Deep reads can
+		// produce very large bodies - this avoids holding a full copy of the
+		// encoded body alongside the value tree it was encoded from.
+		let (mut writer, body) = body_writer();
+		tokio::task::spawn_blocking(move || {
+			let result = encode(format, &value, &mut writer);
+			writer.finish(result);
+		});
+
+		(
+			[
+				(header::CONTENT_TYPE, format.content_type()),
+				(header::VARY, "accept"),
+			],
+			body,
+		)
+			.into_response()
+	}
+}
+
+fn encode(
+	format: BodyFormat,
+	value: &impl Serialize,
+	writer: &mut ChunkWriter,
+) -> anyhow::Result<()> {
+	match format {
+		BodyFormat::Json => {
+			serde_json::to_writer(writer, value).context("failed to encode JSON response")
+		}
+
+		// Named encoding keeps struct field names, matching the JSON structure.
+		BodyFormat::MessagePack => rmp_serde::encode::write_named(writer, value)
+			.context("failed to encode MessagePack response"),
+
+		BodyFormat::Cbor => {
+			ciborium::into_writer(value, writer).context("failed to encode CBOR response")
+		}
+	}
+}
+
+/// Create a response body that is written incrementally from blocking code,
+/// with only a bounded number of chunks buffered ahead of the client.
+pub fn body_writer() -> (ChunkWriter, Body) {
+	let (sender, receiver) = mpsc::channel(CHUNK_BUFFER);
+
+	let body = stream::unfold(receiver, |mut receiver| async move {
+		receiver.recv().await.map(|chunk| (chunk, receiver))
+	});
+
+	let writer = ChunkWriter {
+		buffer: Vec::with_capacity(CHUNK_SIZE),
+		sender,
+	};
+
+	(writer, Body::from_stream(body))
+}
+
+/// Writer sending encoded bytes to a response body in fixed-size chunks.
+/// Writes block while the body's buffer is full.
+pub struct ChunkWriter {
+	buffer: Vec<u8>,
+	sender: mpsc::Sender<anyhow::Result<Bytes>>,
+}
+
+impl ChunkWriter {
+	/// Complete the body with the result of writing it. Remaining bytes are
+	/// flushed, and failures are passed on to abort the body.
+	pub fn finish(mut self, result: anyhow::Result<()>) {
+		let result = result.and_then(|_| self.flush().context("failed to flush response body"));
+
+		// A broken pipe indicates the client has gone away, and there's nobody
+		// left to report to.
+		if let Err(error) = result {
+			if !self.sender.is_closed() {
+				tracing::warn!(?error, "failed to write response body");
+				let _ = self.sender.blocking_send(Err(error));
+			}
+		}
+	}
+
+	fn send(&mut self) -> io::Result<()> {
+		let chunk = mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_SIZE));
+		self.sender
+			.blocking_send(Ok(Bytes::from(chunk)))
+			.map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "response body dropped"))
+	}
+}
+
+impl io::Write for ChunkWriter {
+	fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+		self.buffer.extend_from_slice(bytes);
+		if self.buffer.len() >= CHUNK_SIZE {
+			self.send()?;
+		}
+		Ok(bytes.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		if !self.buffer.is_empty() {
+			self.send()?;
+		}
+		Ok(())
+	}
+}
+
+impl<T> OperationOutput for Encoded<T>
+where
+	T: JsonSchema,
+{
+	type Inner = T;
+
+	fn operation_response(
+		ctx: &mut aide::gen::GenContext,
+		operation: &mut openapi::Operation,
+	) -> Option<openapi::Response> {
+		Json::<T>::operation_response(ctx, operation)
+	}
+
+	fn inferred_responses(
+		ctx: &mut aide::gen::GenContext,
+		operation: &mut openapi::Operation,
+	) -> Vec<(Option<u16>, openapi::Response)> {
+		Json::<T>::inferred_responses(ctx, operation)
+	}
+}
@@ -0,0 +1,115 @@
+use axum::{
+	body::Body,
+	extract::{Query, Request},
+	http::header,
+	middleware::Next,
+	response::{IntoResponse, Response},
+};
+use futures::{stream, StreamExt};
+use serde::Deserialize;
+
+use super::{envelope::BODY_LIMIT, error::Error};
+
+/// Query parameters accepted by every endpoint, controlling JSON formatting.
+#[derive(Deserialize)]
+struct PrettyQuery {
+	/// Whether to pretty-print JSON responses with keys sorted throughout.
+	#[serde(default)]
+	pretty: bool,
+}
+
+/// Middleware re-formatting JSON responses for human consumption when the
+/// `pretty` query parameter is set. Object keys are sorted at every level of
+/// the payload, so that responses can be compared by eye or with a diff.
+/// Responses are left compact and as-serialized otherwise, or when they are too
+/// large to buffer.
+pub async fn apply(request: Request, next: Next) -> Response {
+	let pretty = match Query::<PrettyQuery>::try_from_uri(request.uri()) {
+		Ok(Query(query)) => query.pretty,
+		Err(rejection) => return Error::Invalid(rejection.body_text()).into_response(),
+	};
+
+	let response = next.run(request).await;
+	if !pretty {
+		return response;
+	}
+
+	let is_json = response
+		.headers()
+		.get(header::CONTENT_TYPE)
+		.and_then(|value| value.to_str().ok())
+		.map_or(false, |value| value.starts_with("application/json"));
+	if !is_json {
+		return response;
+	}
+
+	let (mut parts, body) = response.into_parts();
+	let body = match buffer(body).await {
+		Ok(Buffered::Complete(body)) => body,
+		Ok(Buffered::Oversized(body)) => return Response::from_parts(parts, body),
+		Err(error) => return Error::Other(error.into()).into_response(),
+	};
+	let value = match serde_json::from_slice::<serde_json::Value>(&body) {
+		Ok(value) => sort_keys(value),
+		Err(error) => return Error::Other(error.into()).into_response(),
+	};
+	let body = match serde_json::to_vec_pretty(&value) {
+		Ok(body) => body,
+		Err(error) => return Error::Other(error.into()).into_response(),
+	};
+
+	// The formatted body has a different length, and any validator for the
+	// compact body no longer applies.
+	parts.headers.remove(header::CONTENT_LENGTH);
+	parts.headers.remove(header::ETAG);
+
+	Response::from_parts(parts, Body::from(body))
+}
+
+enum Buffered {
+	Complete(Vec<u8>),
+	Oversized(Body),
+}
+
+/// Buffer a body up to [`BODY_LIMIT`]. Larger bodies are rebuilt from the
+/// chunks read so far and the remainder of the body, unchanged.
+async fn buffer(body: Body) -> Result<Buffered, axum::Error> {
+	let mut data = body.into_data_stream();
+	let mut chunks = vec![];
+	let mut length = 0;
+
+	while let Some(chunk) = data.next().await {
+		let chunk = chunk?;
+		length += chunk.len();
+		chunks.push(chunk);
+
+		if length > BODY_LIMIT {
+			let read = stream::iter(chunks.into_iter().map(Ok));
+			return Ok(Buffered::Oversized(Body::from_stream(read.chain(data))));
+		}
+	}
+
+	Ok(Buffered::Complete(chunks.concat()))
+}
+
+/// Sort object keys throughout a value. Entries are rebuilt in sorted order
+/// rather than relying on the map implementation, which may preserve insertion
+/// order depending on enabled features.
+fn sort_keys(value: serde_json::Value) -> serde_json::Value {
+	use serde_json::Value as V;
+
+	match value {
+		V::Object(map) => {
+			let mut entries = map.into_iter().collect::<Vec<_>>();
+			entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+			V::Object(
+				entries
+					.into_iter()
+					.map(|(key, value)| (key, sort_keys(value)))
+					.collect(),
+			)
+		}
+		V::Array(values) => V::Array(values.into_iter().map(sort_keys).collect()),
+		other => other,
+	}
+}
@@ -0,0 +1,77 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	schema,
+	version::{self, VersionKey},
+};
+
+use super::error::{Error, Result};
+
+/// Query parameters accepted by endpoints that can report data provenance.
+#[derive(Deserialize, JsonSchema)]
+pub struct ProvenanceQuery {
+	/// Include a `provenance` object in the response, describing exactly which
+	/// game data and schema the response was derived from.
+	#[serde(default)]
+	provenance: bool,
+}
+
+/// Provenance of the data within a response. Consumers caching responses can
+/// record this to identify precisely what their data was derived from.
+#[derive(Serialize, JsonSchema)]
+pub struct Provenance {
+	/// Key of the version the data was read from.
+	#[schemars(with = "String")]
+	version: VersionKey,
+
+	/// Latest patch of each repository in the version.
+	patches: Vec<ProvenancePatch>,
+
+	/// Schema provider the data was structured with.
+	schema_source: String,
+
+	/// Commit, or equivalent revision, of the schema provider.
+	schema_version: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ProvenancePatch {
+	/// Name of the repository.
+	repository: String,
+
+	/// Name of the repository's latest patch.
+	patch: String,
+}
+
+impl ProvenanceQuery {
+	/// Build provenance for a response, if it was requested.
+	pub fn build(
+		&self,
+		version: &version::Manager,
+		version_key: VersionKey,
+		schema: &schema::CanonicalSpecifier,
+	) -> Result<Option<Provenance>> {
+		if !self.provenance {
+			return Ok(None);
+		}
+
+		let repositories = version
+			.version(version_key)
+			.ok_or_else(|| Error::UnknownVersion(version_key.to_string()))?
+			.repositories;
+
+		Ok(Some(Provenance {
+			version: version_key,
+			patches: repositories
+				.into_iter()
+				.map(|repository| ProvenancePatch {
+					patch: repository.latest().name.clone(),
+					repository: repository.name,
+				})
+				.collect(),
+			schema_source: schema.source.clone(),
+			schema_version: schema.version.clone(),
+		}))
+	}
+}
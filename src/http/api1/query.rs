@@ -0,0 +1,496 @@
+use std::str::FromStr;
+
+use ironworks::excel;
+use nom::{
+	branch::alt,
+	bytes::complete::{tag, take_till, take_while1},
+	character::complete::{char, digit1, multispace0, multispace1},
+	combinator::{all_consuming, map, map_res, not, opt, success, value as nom_value},
+	multi::separated_list1,
+	number::complete::double,
+	sequence::{delimited, pair, preceded, terminated, tuple},
+	Finish, IResult,
+};
+use schemars::{
+	gen::SchemaGenerator,
+	schema::{InstanceType, Schema, SchemaObject},
+};
+use serde::{de, Deserialize};
+
+use crate::{read, utility::jsonschema::impl_jsonschema};
+
+use super::{
+	error,
+	filter::{self, FilterString},
+};
+
+/// A query string for selecting rows by their field values.
+///
+/// Queries are comprised of whitespace-separated clauses, each optionally
+/// prefixed with `+` (must match) or `-` (must not match). Clauses without a
+/// prefix are optional, however at least one must match if there are no `+`
+/// clauses. Clauses may be grouped with parentheses, i.e. `+(a b) -c`.
+///
+/// A clause targets a field by name with an optional `@` language suffix, i.e.
+/// `Name@ja`, or every element of an array with `[]`. Nested fields, array
+/// elements, and references are targeted with dot notation, i.e.
+/// `ItemResult.Name` or `Tags.[]`.
+///
+/// The targeted value may be compared with `=`, i.e. `ClassJobLevel=50` or
+/// `Name="Potion"`, or matched against a partial, case-insensitive string, i.e.
+/// `Name"potion"`. Clauses without a field target match against any field.
+#[derive(Debug, Clone)]
+pub struct QueryString {
+	raw: String,
+	group: Group,
+}
+
+#[derive(Debug, Clone)]
+struct Group {
+	clauses: Vec<(Occur, Node)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Occur {
+	Must,
+	MustNot,
+	Should,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+	Group(Group),
+	Leaf(Leaf),
+}
+
+#[derive(Debug, Clone)]
+struct Leaf {
+	field: Option<FieldSpecifier>,
+	operation: Operation,
+}
+
+#[derive(Debug, Clone)]
+enum FieldSpecifier {
+	Struct(String, Option<excel::Language>),
+	Array,
+}
+
+#[derive(Debug, Clone)]
+enum Operation {
+	Relation(Box<Node>),
+	Equal(Value),
+	Match(String),
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+	U64(u64),
+	I64(i64),
+	F64(f64),
+	String(String),
+}
+
+impl QueryString {
+	pub fn as_str(&self) -> &str {
+		&self.raw
+	}
+
+	/// Build a filter selecting every field the query reads.
+	pub fn to_filter(&self, default_language: excel::Language) -> error::Result<read::Filter> {
+		let mut paths = vec![];
+		self.group.collect_paths(&[], &mut paths);
+		FilterString(paths).to_filter(default_language)
+	}
+
+	/// Check if a row's fields, read with this query's filter, match the query.
+	pub fn matches(&self, value: &read::Value, default_language: excel::Language) -> bool {
+		self.group.matches(value, default_language)
+	}
+}
+
+impl Group {
+	fn collect_paths(&self, prefix: &[filter::Entry], paths: &mut Vec<filter::Path>) {
+		for (_occur, node) in &self.clauses {
+			node.collect_paths(prefix, paths);
+		}
+	}
+
+	fn matches(&self, value: &read::Value, language: excel::Language) -> bool {
+		let mut has_must = false;
+		let mut should = None;
+
+		for (occur, node) in &self.clauses {
+			let matches = node.matches(value, language);
+			match occur {
+				Occur::Must if !matches => return false,
+				Occur::MustNot if matches => return false,
+				Occur::Must => has_must = true,
+				Occur::MustNot => {}
+				Occur::Should => should = Some(should.unwrap_or(false) || matches),
+			}
+		}
+
+		// Optional clauses only become required when nothing else is.
+		has_must || should.unwrap_or(true)
+	}
+}
+
+impl Node {
+	fn collect_paths(&self, prefix: &[filter::Entry], paths: &mut Vec<filter::Path>) {
+		match self {
+			Self::Group(group) => group.collect_paths(prefix, paths),
+			Self::Leaf(leaf) => leaf.collect_paths(prefix, paths),
+		}
+	}
+
+	fn matches(&self, value: &read::Value, language: excel::Language) -> bool {
+		match self {
+			Self::Group(group) => group.matches(value, language),
+			Self::Leaf(leaf) => leaf.matches(value, language),
+		}
+	}
+}
+
+impl Leaf {
+	fn collect_paths(&self, prefix: &[filter::Entry], paths: &mut Vec<filter::Path>) {
+		let mut path = prefix.to_vec();
+		match &self.field {
+			Some(FieldSpecifier::Struct(name, language)) => {
+				path.push(filter::Entry::Key(name.clone(), *language))
+			}
+			Some(FieldSpecifier::Array) => path.push(filter::Entry::Index),
+			None => {}
+		}
+
+		match &self.operation {
+			Operation::Relation(node) => node.collect_paths(&path, paths),
+			Operation::Equal(_) | Operation::Match(_) => paths.push(path),
+		}
+	}
+
+	fn matches(&self, value: &read::Value, language: excel::Language) -> bool {
+		match &self.field {
+			None => self.operation.matches(value, language),
+
+			Some(FieldSpecifier::Struct(name, specified_language)) => {
+				let read::Value::Struct(fields) = value else {
+					return false;
+				};
+				let key = read::StructKey {
+					name: name.clone(),
+					language: specified_language.unwrap_or(language),
+				};
+				fields
+					.get(&key)
+					.is_some_and(|value| self.operation.matches(value, language))
+			}
+
+			Some(FieldSpecifier::Array) => {
+				let read::Value::Array(values) = value else {
+					return false;
+				};
+				values
+					.iter()
+					.any(|value| self.operation.matches(value, language))
+			}
+		}
+	}
+}
+
+impl Operation {
+	fn matches(&self, value: &read::Value, language: excel::Language) -> bool {
+		match self {
+			Self::Relation(node) => match value {
+				read::Value::Struct(_) | read::Value::Array(_) => node.matches(value, language),
+				read::Value::Reference(read::Reference::Populated { fields, .. }) => {
+					node.matches(fields, language)
+				}
+				_ => false,
+			},
+			Self::Equal(target) => equal(value, target),
+			Self::Match(target) => string_match(value, &target.to_lowercase()),
+		}
+	}
+}
+
+fn equal(value: &read::Value, target: &Value) -> bool {
+	match value {
+		read::Value::Struct(fields) => fields.values().any(|value| equal(value, target)),
+		read::Value::Array(values) => values.iter().any(|value| equal(value, target)),
+		read::Value::Scalar(excel::Field::String(string)) => match target {
+			Value::String(target) => &string.to_string() == target,
+			_ => false,
+		},
+		other => {
+			let number = match target {
+				Value::U64(number) => *number as f64,
+				Value::I64(number) => *number as f64,
+				Value::F64(number) => *number,
+				Value::String(_) => return false,
+			};
+			other.numeric() == Some(number)
+		}
+	}
+}
+
+fn string_match(value: &read::Value, target: &str) -> bool {
+	match value {
+		read::Value::Struct(fields) => fields.values().any(|value| string_match(value, target)),
+		read::Value::Array(values) => values.iter().any(|value| string_match(value, target)),
+		read::Value::Reference(read::Reference::Populated { fields, .. }) => {
+			string_match(fields, target)
+		}
+		read::Value::Scalar(excel::Field::String(string)) => {
+			string.to_string().to_lowercase().contains(target)
+		}
+		_ => false,
+	}
+}
+
+impl_jsonschema!(QueryString, querystring_schema);
+fn querystring_schema(_generator: &mut SchemaGenerator) -> Schema {
+	Schema::Object(SchemaObject {
+		instance_type: Some(InstanceType::String.into()),
+		..Default::default()
+	})
+}
+
+impl<'de> Deserialize<'de> for QueryString {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		let raw = String::deserialize(deserializer)?;
+		raw.parse().map_err(de::Error::custom)
+	}
+}
+
+impl FromStr for QueryString {
+	type Err = error::Error;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		let (_, group) = all_consuming(delimited(multispace0, group, multispace0))(input)
+			.finish()
+			.map_err(|error| error::Error::Invalid(error.to_string()))?;
+
+		Ok(Self {
+			raw: input.into(),
+			group,
+		})
+	}
+}
+
+fn group(input: &str) -> IResult<&str, Group> {
+	map(separated_list1(multispace1, pair(occur, node)), |clauses| {
+		Group { clauses }
+	})(input)
+}
+
+fn occur(input: &str) -> IResult<&str, Occur> {
+	alt((
+		nom_value(Occur::Must, char('+')),
+		nom_value(Occur::MustNot, char('-')),
+		success(Occur::Should),
+	))(input)
+}
+
+fn node(input: &str) -> IResult<&str, Node> {
+	alt((
+		map(delimited(char('('), group, char(')')), Node::Group),
+		map(leaf, Node::Leaf),
+	))(input)
+}
+
+fn leaf(input: &str) -> IResult<&str, Leaf> {
+	map(
+		tuple((opt(field_specifier), operation)),
+		|(field, operation)| Leaf { field, operation },
+	)(input)
+}
+
+fn field_specifier(input: &str) -> IResult<&str, FieldSpecifier> {
+	terminated(
+		alt((field_specifier_struct, field_specifier_array)),
+		opt(char(':')),
+	)(input)
+}
+
+fn field_specifier_struct(input: &str) -> IResult<&str, FieldSpecifier> {
+	map(
+		pair(alphanumeric, opt(preceded(char('@'), language))),
+		|(name, language)| FieldSpecifier::Struct(name.into(), language),
+	)(input)
+}
+
+fn field_specifier_array(input: &str) -> IResult<&str, FieldSpecifier> {
+	nom_value(FieldSpecifier::Array, tag("[]"))(input)
+}
+
+fn language(input: &str) -> IResult<&str, excel::Language> {
+	map_res(alphanumeric, |string: &str| {
+		string
+			.parse::<read::LanguageString>()
+			.map(excel::Language::from)
+	})(input)
+}
+
+fn alphanumeric(input: &str) -> IResult<&str, &str> {
+	take_while1(|c: char| c.is_ascii_alphanumeric())(input)
+}
+
+fn operation(input: &str) -> IResult<&str, Operation> {
+	alt((
+		map(preceded(char('.'), node), |node| {
+			Operation::Relation(node.into())
+		}),
+		map(preceded(char('='), value), Operation::Equal),
+		// An un-adorned string acts as a match. This needs to be last to ensure other sigils take priority.
+		map(string, Operation::Match),
+	))(input)
+}
+
+fn value(input: &str) -> IResult<&str, Value> {
+	alt((
+		// Try to parse the number as a potentially-signed integer. If it's followed by `.`, it'll fall through to the float check.
+		terminated(
+			alt((
+				map(map_res(digit1, str::parse), Value::U64),
+				map(map_res(take_while1(is_signed), str::parse), Value::I64),
+			)),
+			not(char('.')),
+		),
+		map(double, Value::F64),
+		map(string, Value::String),
+	))(input)
+}
+
+fn string(input: &str) -> IResult<&str, String> {
+	map(
+		delimited(char('"'), take_till(|c| c == '"'), char('"')),
+		|string: &str| string.to_string(),
+	)(input)
+}
+
+fn is_signed(char: char) -> bool {
+	char.is_ascii_digit() || char == '-'
+}
+
+#[cfg(test)]
+mod test {
+	use std::collections::HashMap;
+
+	use pretty_assertions::assert_eq;
+
+	use super::*;
+
+	fn test_query(input: &str) -> QueryString {
+		input.parse::<QueryString>().expect("parse should not fail")
+	}
+
+	fn test_struct(entries: impl IntoIterator<Item = (impl ToString, read::Value)>) -> read::Value {
+		read::Value::Struct(
+			entries
+				.into_iter()
+				.map(|(name, value)| {
+					(
+						read::StructKey {
+							name: name.to_string(),
+							language: excel::Language::English,
+						},
+						value,
+					)
+				})
+				.collect::<HashMap<_, _>>(),
+		)
+	}
+
+	fn test_string(value: &str) -> read::Value {
+		read::Value::Scalar(excel::Field::String(value.into()))
+	}
+
+	fn test_row() -> read::Value {
+		test_struct([
+			("Name", test_string("Hi-Potion")),
+			("Level", read::Value::Scalar(excel::Field::U16(50))),
+			(
+				"Tags",
+				read::Value::Array(vec![test_string("Medicine"), test_string("Tradeable")]),
+			),
+			(
+				"Category",
+				read::Value::Reference(read::Reference::Populated {
+					value: 44,
+					sheet: "ItemUICategory".into(),
+					row_id: 44,
+					display: None,
+					fields: Box::new(test_struct([("Name", test_string("Medicine"))])),
+				}),
+			),
+		])
+	}
+
+	fn test_matches(input: &str) -> bool {
+		test_query(input).matches(&test_row(), excel::Language::English)
+	}
+
+	#[test]
+	fn parse_invalid() {
+		assert!("Name=".parse::<QueryString>().is_err());
+		assert!("(Name=1".parse::<QueryString>().is_err());
+		assert!("".parse::<QueryString>().is_err());
+	}
+
+	#[test]
+	fn match_equal() {
+		assert_eq!(test_matches("Level=50"), true);
+		assert_eq!(test_matches("Level=50.0"), true);
+		assert_eq!(test_matches("Level=49"), false);
+		assert_eq!(test_matches(r#"Name="Hi-Potion""#), true);
+		assert_eq!(test_matches(r#"Name="hi-potion""#), false);
+	}
+
+	#[test]
+	fn match_string() {
+		assert_eq!(test_matches(r#"Name"potion""#), true);
+		assert_eq!(test_matches(r#"Name"ether""#), false);
+		assert_eq!(test_matches(r#""potion""#), true);
+	}
+
+	#[test]
+	fn match_array() {
+		assert_eq!(test_matches(r#"Tags.[]="Tradeable""#), true);
+		assert_eq!(test_matches(r#"Tags.[]="Unique""#), false);
+	}
+
+	#[test]
+	fn match_relation() {
+		assert_eq!(test_matches(r#"Category.Name="Medicine""#), true);
+		assert_eq!(test_matches("Category=44"), true);
+		assert_eq!(test_matches(r#"Category.Name="Meal""#), false);
+	}
+
+	#[test]
+	fn match_occur() {
+		assert_eq!(test_matches(r#"+Level=50 -Name"ether""#), true);
+		assert_eq!(test_matches(r#"+Level=50 -Name"potion""#), false);
+		assert_eq!(test_matches(r#"Level=1 Name"potion""#), true);
+		assert_eq!(test_matches(r#"Level=1 Name"ether""#), false);
+		assert_eq!(test_matches(r#"+Level=50 Name"ether""#), true);
+		assert_eq!(test_matches(r#"+(Level=1 Level=50) +Name"potion""#), true);
+	}
+
+	#[test]
+	fn filter_paths() {
+		let filter = test_query(r#"+Level=50 Category.Name@ja"x" Tags.[]="y""#)
+			.to_filter(excel::Language::English)
+			.expect("conversion should not fail");
+
+		let expected = r#"Level,Category.Name@ja,Tags[]"#
+			.parse::<FilterString>()
+			.expect("parse should not fail")
+			.to_filter(excel::Language::English)
+			.expect("conversion should not fail");
+
+		assert_eq!(filter, expected);
+	}
+}
@@ -0,0 +1,212 @@
+use std::convert::Infallible;
+
+use aide::OperationIo;
+use axum::{
+	async_trait,
+	body::Bytes,
+	extract::FromRequestParts,
+	http::{header, request::Parts, StatusCode},
+	response::{IntoResponse, Response},
+};
+use axum_extra::{
+	headers::{AcceptRanges, ContentRange, ETag, HeaderMapExt, IfRange},
+	TypedHeader,
+};
+
+/// Byte range of a response body requested by the client via the `Range` and
+/// `If-Range` headers, used to resume interrupted downloads.
+#[derive(OperationIo)]
+pub struct RequestedRange {
+	range: Option<String>,
+	if_range: Option<IfRange>,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestedRange {
+	type Rejection = Infallible;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		// The typed range header drops unsatisfiable specs while decoding, which
+		// would hide them from the 416 check - keep the raw value instead.
+		let range = parts
+			.headers
+			.get(header::RANGE)
+			.and_then(|value| value.to_str().ok())
+			.map(str::to_string);
+
+		Ok(Self {
+			range,
+			if_range: parts.headers.typed_get(),
+		})
+	}
+}
+
+impl RequestedRange {
+	/// Whether the client requested a range of the body. Streamed responses
+	/// need to be buffered to serve one.
+	pub fn is_requested(&self) -> bool {
+		self.range.is_some()
+	}
+
+	/// Build a response serving the requested range of a body. The full body is
+	/// served if no range was requested, if the `If-Range` validator no longer
+	/// matches the body's entity tag, or if multiple ranges were requested.
+	pub fn respond(self, etag: ETag, body: Vec<u8>) -> Response {
+		let Self { range, if_range } = self;
+		let body = Bytes::from(body);
+		let length = body.len() as u64;
+
+		// A stale validator means the client's partial copy is outdated - it needs
+		// the full body rather than the remainder of a different one.
+		let range = range.filter(|_| {
+			if_range
+				.as_ref()
+				.map_or(true, |if_range| !if_range.is_modified(Some(&etag), None))
+		});
+
+		let headers = (TypedHeader(AcceptRanges::bytes()), TypedHeader(etag));
+
+		let (start, end) = match range.map_or(Resolved::Full, |range| resolve(&range, length)) {
+			Resolved::Full => return (headers, body).into_response(),
+			Resolved::Unsatisfiable => {
+				return (
+					StatusCode::RANGE_NOT_SATISFIABLE,
+					headers,
+					TypedHeader(ContentRange::unsatisfied_bytes(length)),
+				)
+					.into_response()
+			}
+			Resolved::Partial(start, end) => (start, end),
+		};
+
+		let content_range =
+			ContentRange::bytes(start..end, length).expect("range is within the body");
+
+		(
+			StatusCode::PARTIAL_CONTENT,
+			headers,
+			TypedHeader(content_range),
+			body.slice(start as usize..end as usize),
+		)
+			.into_response()
+	}
+}
+
+#[derive(Debug, PartialEq)]
+enum Resolved {
+	Full,
+	Partial(u64, u64),
+	Unsatisfiable,
+}
+
+/// Resolve a range header against the length of the body. Malformed headers are
+/// ignored, as are requests for multiple ranges unless none of them can be
+/// satisfied.
+fn resolve(header: &str, length: u64) -> Resolved {
+	let Some(specs) = parse(header) else {
+		return Resolved::Full;
+	};
+
+	let multiple = specs.len() > 1;
+	let mut satisfiable = specs
+		.into_iter()
+		.filter_map(|spec| satisfiable_range(spec, length));
+
+	match satisfiable.next() {
+		None => Resolved::Unsatisfiable,
+		Some(_) if multiple => Resolved::Full,
+		Some((start, end)) => Resolved::Partial(start, end),
+	}
+}
+
+/// A single `first-last` byte range spec. Suffix ranges (`-length`) have no
+/// first position.
+type Spec = (Option<u64>, Option<u64>);
+
+fn parse(header: &str) -> Option<Vec<Spec>> {
+	let specs = header.trim().strip_prefix("bytes=")?;
+
+	specs
+		.split(',')
+		.map(|spec| {
+			let (first, last) = spec.trim().split_once('-')?;
+			let first = match first {
+				"" => None,
+				first => Some(first.parse::<u64>().ok()?),
+			};
+			let last = match last {
+				"" => None,
+				last => Some(last.parse::<u64>().ok()?),
+			};
+
+			match (first, last) {
+				(None, None) => None,
+				(Some(first), Some(last)) if last < first => None,
+				spec => Some(spec),
+			}
+		})
+		.collect()
+}
+
+/// Half-open byte range a spec selects from the body, if it overlaps it at all.
+fn satisfiable_range(spec: Spec, length: u64) -> Option<(u64, u64)> {
+	match spec {
+		(Some(first), last) => {
+			if first >= length {
+				return None;
+			}
+			let end = last.map_or(length, |last| last.saturating_add(1).min(length));
+			Some((first, end))
+		}
+		(None, Some(suffix)) => {
+			if suffix == 0 || length == 0 {
+				return None;
+			}
+			Some((length.saturating_sub(suffix), length))
+		}
+		(None, None) => None,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use pretty_assertions::assert_eq;
+
+	use super::*;
+
+	#[test]
+	fn resolves_bounded_range() {
+		assert_eq!(resolve("bytes=0-9", 100), Resolved::Partial(0, 10));
+		assert_eq!(resolve("bytes=90-200", 100), Resolved::Partial(90, 100));
+	}
+
+	#[test]
+	fn resolves_open_and_suffix_ranges() {
+		assert_eq!(resolve("bytes=40-", 100), Resolved::Partial(40, 100));
+		assert_eq!(resolve("bytes=-10", 100), Resolved::Partial(90, 100));
+		assert_eq!(resolve("bytes=-500", 100), Resolved::Partial(0, 100));
+	}
+
+	#[test]
+	fn rejects_unsatisfiable_ranges() {
+		assert_eq!(resolve("bytes=100-", 100), Resolved::Unsatisfiable);
+		assert_eq!(resolve("bytes=500-600", 100), Resolved::Unsatisfiable);
+		assert_eq!(resolve("bytes=-0", 100), Resolved::Unsatisfiable);
+		assert_eq!(resolve("bytes=0-", 0), Resolved::Unsatisfiable);
+		assert_eq!(resolve("bytes=100-110, 200-", 100), Resolved::Unsatisfiable);
+	}
+
+	#[test]
+	fn serves_full_body_for_multiple_ranges() {
+		assert_eq!(resolve("bytes=0-9, 20-29", 100), Resolved::Full);
+		assert_eq!(resolve("bytes=0-9, 200-", 100), Resolved::Full);
+	}
+
+	#[test]
+	fn ignores_malformed_ranges() {
+		assert_eq!(resolve("items=0-9", 100), Resolved::Full);
+		assert_eq!(resolve("bytes=9-0", 100), Resolved::Full);
+		assert_eq!(resolve("bytes=a-b", 100), Resolved::Full);
+		assert_eq!(resolve("bytes=-", 100), Resolved::Full);
+	}
+}
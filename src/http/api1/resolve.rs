@@ -0,0 +1,204 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use aide::{
+	axum::{routing::post_with, ApiRouter, IntoApiResponse},
+	transform::TransformOperation,
+};
+use axum::{debug_handler, extract::State, Extension, Json};
+use ironworks::excel;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{http::service, read, schema};
+
+use super::{
+	acl::SheetAccess,
+	deadline::Cancellation,
+	error::{Error, Result},
+	extract::{JsonBody, Query, VersionQuery},
+	filter::FilterString,
+	negotiate::{Encoded, Negotiated},
+	sheet,
+	tenant::CurrentTenant,
+	value::ValueString,
+};
+
+pub fn router(config: sheet::Config) -> ApiRouter<service::State> {
+	ApiRouter::new()
+		.api_route("/", post_with(resolve, resolve_docs))
+		.layer(Extension(config))
+}
+
+/// Query parameters accepted by the resolve endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct ResolveQuery {
+	/// Language to use for data with no language otherwise specified in the fields filter.
+	language: Option<read::LanguageString>,
+
+	/// Schema that row data should be read with.
+	schema: Option<schema::Specifier>,
+
+	/// Data fields to read for each referenced row.
+	fields: Option<FilterString>,
+}
+
+/// Request body accepted by the resolve endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct ResolveRequest {
+	/// References to resolve, matching the `sheet` and `row_id` of reference values.
+	references: Vec<ReferenceTarget>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, JsonSchema)]
+struct ReferenceTarget {
+	/// Name of the sheet the reference targets.
+	sheet: String,
+
+	/// ID of the row the reference targets.
+	row_id: u32,
+}
+
+/// Response structure for the resolve endpoint.
+#[derive(Serialize, JsonSchema)]
+struct ResolveResponse {
+	/// The canonical specifier for the schema used in this response.
+	#[schemars(with = "String")]
+	schema: schema::CanonicalSpecifier,
+
+	/// Field values of resolved references, keyed by sheet name, then row ID.
+	rows: BTreeMap<String, BTreeMap<u32, ValueString>>,
+
+	/// References that could not be resolved, as their target does not exist.
+	missing: Vec<ReferenceTarget>,
+}
+
+fn resolve_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("resolve references")
+		.description("Read the fields of multiple referenced rows in a single request. Intended for expanding shallow references, such as those beyond the depth limit of a row read. References to subrow sheets are resolved to their first subrow.")
+		.response_with::<200, Json<ResolveResponse>, _>(|response| {
+			response.example(ResolveResponse {
+				schema: schema::CanonicalSpecifier {
+					source: "source".into(),
+					version: "version".into(),
+				},
+				rows: BTreeMap::from([(
+					"Item".into(),
+					BTreeMap::from([(
+						1,
+						ValueString(
+							read::Value::Struct(HashMap::from([(
+								read::StructKey {
+									name: "FieldName".into(),
+									language: excel::Language::English,
+								},
+								read::Value::Scalar(excel::Field::U32(14)),
+							)])),
+							excel::Language::English,
+						),
+					)]),
+				)]),
+				missing: vec![ReferenceTarget {
+					sheet: "Item".into(),
+					row_id: 99999,
+				}],
+			})
+		})
+}
+
+#[allow(clippy::too_many_arguments)]
+#[debug_handler(state = service::State)]
+async fn resolve(
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<ResolveQuery>,
+	tenant: CurrentTenant,
+	access: SheetAccess,
+	Cancellation(cancel): Cancellation,
+	Negotiated(format): Negotiated,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+	State(schema_provider): State<service::Schema>,
+	Extension(config): Extension<sheet::Config>,
+	JsonBody(request): JsonBody<ResolveRequest>,
+) -> Result<impl IntoApiResponse> {
+	// Deduplicate up front, so repeated references don't count towards the limit.
+	let targets = request.references.into_iter().collect::<BTreeSet<_>>();
+	if targets.len() > config.limit_max() {
+		return Err(Error::Invalid(format!(
+			"too many references: {} requested, at most {} may be resolved at once",
+			targets.len(),
+			config.limit_max()
+		)));
+	}
+
+	for target in &targets {
+		access.check(&target.sheet)?;
+	}
+
+	let excel = data.version(version_key)?.excel();
+
+	let language = query
+		.language
+		.map(excel::Language::from)
+		.unwrap_or_else(|| read.default_language());
+
+	let schema_specifier =
+		schema_provider.canonicalize(tenant.schema(query.schema), version_key)?;
+
+	let filter = query
+		.fields
+		.or_else(|| config.entry_filter(&schema_specifier.source))
+		.map(|filter_string| filter_string.to_filter(language))
+		.unwrap_or(Ok(read::Filter::All))?;
+
+	let response_specifier = schema_specifier.clone();
+	let (rows, missing) = data
+		.blocking(move || -> Result<_> {
+			let schema = schema_provider.schema(schema_specifier)?;
+
+			let mut rows = BTreeMap::<String, BTreeMap<u32, ValueString>>::new();
+			let mut missing = vec![];
+
+			for target in targets {
+				// Resolved rows are not expanded any further - clients can resolve
+				// any references within them with a further request.
+				let result = read.read(
+					&excel,
+					schema.as_ref(),
+					&target.sheet,
+					target.row_id,
+					0,
+					language,
+					&filter,
+					0,
+					&|sheet| access.allows(sheet),
+					&cancel,
+				);
+
+				let fields = match result {
+					Ok(fields) => fields,
+					Err(read::Error::NotFound(..)) => {
+						missing.push(target);
+						continue;
+					}
+					Err(error) => Err(error)?,
+				};
+
+				rows.entry(target.sheet)
+					.or_default()
+					.insert(target.row_id, ValueString(fields, language));
+			}
+
+			Ok((rows, missing))
+		})
+		.await??;
+
+	Ok(Encoded(
+		format,
+		ResolveResponse {
+			schema: response_specifier,
+			rows,
+			missing,
+		},
+	))
+}
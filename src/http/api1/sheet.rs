@@ -1,10 +1,30 @@
-use std::{collections::HashMap, num::ParseIntError, str::FromStr};
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap},
+	hash::{Hash, Hasher},
+	io::{self, Write},
+	iter,
+	num::ParseIntError,
+	str::FromStr,
+	time::Duration,
+};
 
 use aide::{
 	axum::{routing::get_with, ApiRouter, IntoApiResponse},
+	openapi,
 	transform::TransformOperation,
+	NoApi,
+};
+use axum::{
+	debug_handler,
+	extract::State,
+	http::{header, StatusCode},
+	response::IntoResponse,
+	Extension, Json,
+};
+use axum_extra::{
+	headers::{AcceptRanges, ETag, IfNoneMatch},
+	TypedHeader,
 };
-use axum::{debug_handler, extract::State, Extension, Json};
 use either::Either;
 use ironworks::{excel, file::exh};
 use schemars::{
@@ -12,26 +32,54 @@ use schemars::{
 	schema::{InstanceType, Schema, SchemaObject, StringValidation},
 	JsonSchema,
 };
-use serde::{de, Deserialize, Deserializer, Serialize};
+use seahash::SeaHasher;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use tokio::time::{self, Instant};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
+	asset::Format,
+	config::Validator,
 	http::service,
 	read, schema,
 	utility::{anyhow::Anyhow, jsonschema::impl_jsonschema},
+	version::{Slot, VersionKey},
 };
 
 use super::{
+	acl::SheetAccess,
+	deadline::Cancellation,
+	envelope::Warnings,
 	error::{Error, Result},
-	extract::{Path, Query, VersionQuery},
+	extract::{Path, Query, RouterPath, VersionQuery},
 	filter::FilterString,
+	negotiate::{self, Encoded, Negotiated},
+	provenance::{Provenance, ProvenanceQuery},
+	query::QueryString,
+	range::RequestedRange,
+	tenant::CurrentTenant,
 	value::ValueString,
 };
 
+/// Default time, in seconds, a watch request will wait for changes.
+const WATCH_TIMEOUT_DEFAULT: u64 = 30;
+/// Maximum time, in seconds, a watch request may wait for changes.
+const WATCH_TIMEOUT_MAX: u64 = 120;
+
+/// Default number of rows sampled when comparing schemas.
+const SCHEMA_DIFF_SAMPLE_DEFAULT: usize = 100;
+/// Maximum number of rows that may be sampled when comparing schemas.
+const SCHEMA_DIFF_SAMPLE_MAX: usize = 1000;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
 	limit: LimitConfig,
 
 	filter: HashMap<String, FilterConfig>,
+
+	/// Row ID ranges introduced by each expansion, keyed by sheet name.
+	#[serde(default)]
+	expansion: HashMap<String, Vec<ExpansionRange>>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -47,11 +95,87 @@ struct FilterConfig {
 	entry: Option<FilterString>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct ExpansionRange {
+	expansion: Slot,
+	/// First row ID of the range, inclusive.
+	start: u32,
+	/// Last row ID of the range, exclusive. Unbounded if unset.
+	end: Option<u32>,
+}
+
+impl ExpansionRange {
+	fn contains(&self, row_id: u32) -> bool {
+		row_id >= self.start && self.end.map_or(true, |end| row_id < end)
+	}
+}
+
+impl Config {
+	/// Default fields filter for single row reads with the given schema source.
+	pub(super) fn entry_filter(&self, source: &str) -> Option<FilterString> {
+		self.filter
+			.get(source)
+			.and_then(|filter_config| filter_config.entry.clone())
+	}
+
+	/// Maximum number of rows that may be read in a single request.
+	pub(super) fn limit_max(&self) -> usize {
+		self.limit.max
+	}
+
+	pub fn validate(&self, validator: &mut Validator) {
+		let limit = &self.limit;
+		validator.check("limit.max", limit.max > 0, "must be at least 1");
+		validator.check(
+			"limit.default",
+			limit.default > 0 && limit.default <= limit.max,
+			format_args!("must be between 1 and limit.max ({})", limit.max),
+		);
+
+		for (sheet, ranges) in &self.expansion {
+			for range in ranges {
+				let key = format!("expansion.{sheet}");
+				validator.check(
+					&key,
+					range.expansion != Slot::Boot,
+					"boot is not an expansion",
+				);
+				validator.check(
+					&key,
+					range.end.map_or(true, |end| end > range.start),
+					format_args!("range starting at {} must end after it starts", range.start),
+				);
+			}
+		}
+	}
+}
+
 pub fn router(config: Config) -> ApiRouter<service::State> {
 	ApiRouter::new()
 		.api_route("/", get_with(list, list_docs))
+		.api_route("/tree", get_with(tree, tree_docs))
+		.api_route("/languages", get_with(languages, languages_docs))
 		.api_route("/:sheet", get_with(sheet, sheet_docs))
+		.api_route("/:sheet/strings", get_with(strings, strings_docs))
+		.api_route(
+			"/:sheet/strings/diff",
+			get_with(strings_diff, strings_diff_docs),
+		)
+		.api_route("/:sheet/watch", get_with(watch, watch_docs))
+		.api_route("/:sheet/stats", get_with(stats, stats_docs))
+		.api_route("/:sheet/header", get_with(header, header_docs))
+		.api_route(
+			"/:sheet/schema-diff",
+			get_with(schema_diff, schema_diff_docs),
+		)
+		.api_route("/:sheet/join", get_with(join, join_docs))
+		.api_route("/:sheet/export", get_with(export, export_docs))
 		.api_route("/:sheet/:row", get_with(row, row_docs))
+		.api_route("/:sheet/:row/assets", get_with(row_assets, row_assets_docs))
+		.api_route(
+			"/:sheet/:row/history",
+			get_with(row_history, row_history_docs),
+		)
 		// Using Extension so I don't need to worry about nested state destructuring.
 		.layer(Extension(config))
 }
@@ -68,20 +192,169 @@ fn list_docs(operation: TransformOperation) -> TransformOperation {
 #[debug_handler(state = service::State)]
 async fn list(
 	VersionQuery(version_key): VersionQuery,
+	access: SheetAccess,
 	State(data): State<service::Data>,
 ) -> Result<impl IntoApiResponse> {
 	let excel = data.version(version_key)?.excel();
 
-	let list = excel.list().anyhow()?;
-	let mut names = list
-		.iter()
-		.map(|name| name.into_owned())
-		.collect::<Vec<_>>();
-	names.sort();
+	let names = data
+		.blocking(move || -> Result<_> {
+			let list = excel.list().anyhow()?;
+			let mut names = list
+				.iter()
+				.filter(|name| access.allows(name))
+				.map(|name| name.into_owned())
+				.collect::<Vec<_>>();
+			names.sort();
+			Ok(names)
+		})
+		.await??;
 
 	Ok(Json(names))
 }
 
+/// Query parameters accepted by the sheet tree endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct TreeQuery {
+	/// Folder to list, as a prefix of sheet names, i.e. `quest/000/`. Lists the root folder if unset.
+	prefix: Option<String>,
+}
+
+/// Response structure for the sheet tree endpoint.
+#[derive(Serialize, JsonSchema)]
+struct TreeResponse {
+	/// Folder that was listed.
+	prefix: String,
+
+	/// Folders directly within the listed folder.
+	folders: Vec<TreeFolder>,
+
+	/// Names of sheets directly within the listed folder.
+	sheets: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct TreeFolder {
+	/// Prefix of the folder, which may be passed as the `prefix` of a further listing.
+	prefix: String,
+
+	/// Number of sheets within the folder, including those in nested folders.
+	count: usize,
+}
+
+fn tree_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("list sheets as a tree")
+		.description("List known excel sheets within a folder, grouping sheets in nested folders by their name prefix. Sheet names use `/` to separate folders, i.e. `quest/000/ClsArc000_00001`.")
+		.response_with::<200, Json<TreeResponse>, _>(|response| {
+			response.example(TreeResponse {
+				prefix: "".into(),
+				folders: vec![TreeFolder {
+					prefix: "quest/".into(),
+					count: 3000,
+				}],
+				sheets: vec!["Action".into(), "Item".into()],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn tree(
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<TreeQuery>,
+	access: SheetAccess,
+	State(data): State<service::Data>,
+) -> Result<impl IntoApiResponse> {
+	let excel = data.version(version_key)?.excel();
+
+	// Accept folder prefixes with or without their trailing separator.
+	let prefix = match query.prefix {
+		Some(prefix) if !prefix.is_empty() && !prefix.ends_with('/') => format!("{prefix}/"),
+		other => other.unwrap_or_default(),
+	};
+
+	let response = data
+		.blocking(move || -> Result<_> {
+			let list = excel.list().anyhow()?;
+
+			let mut folders = BTreeMap::<String, usize>::new();
+			let mut sheets = vec![];
+			for name in list.iter().filter(|name| access.allows(name)) {
+				let Some(remainder) = name.strip_prefix(prefix.as_str()) else {
+					continue;
+				};
+
+				match remainder.split_once('/') {
+					Some((folder, _rest)) => {
+						*folders.entry(format!("{prefix}{folder}/")).or_default() += 1;
+					}
+					None => sheets.push(name.into_owned()),
+				}
+			}
+			sheets.sort();
+
+			Ok(TreeResponse {
+				prefix,
+				folders: folders
+					.into_iter()
+					.map(|(prefix, count)| TreeFolder { prefix, count })
+					.collect(),
+				sheets,
+			})
+		})
+		.await??;
+
+	Ok(Json(response))
+}
+
+fn languages_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("list sheet languages")
+		.description("List the languages provided by each known excel sheet, keyed by sheet name. Sheets without localised data provide only `none`. Reading a sheet in a language it does not provide will fall back to its language-less data, if any.")
+		.response_with::<200, Json<BTreeMap<String, Vec<read::LanguageString>>>, _>(|response| {
+			response.example(BTreeMap::from([
+				(
+					"Item".to_string(),
+					vec![
+						excel::Language::Japanese.into(),
+						excel::Language::English.into(),
+						excel::Language::German.into(),
+						excel::Language::French.into(),
+					],
+				),
+				("ItemLevel".to_string(), vec![excel::Language::None.into()]),
+			]))
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn languages(
+	VersionQuery(version_key): VersionQuery,
+	access: SheetAccess,
+	State(data): State<service::Data>,
+) -> Result<impl IntoApiResponse> {
+	let data_version = data.version(version_key)?;
+
+	// Languages are read from each sheet's header, reuse the cached version summary.
+	let summary = data.blocking(move || data_version.summary()).await??;
+
+	let languages = summary
+		.sheets
+		.iter()
+		.filter(|sheet| access.allows(&sheet.name))
+		.map(|sheet| {
+			let languages = sheet
+				.languages
+				.iter()
+				.map(|language| read::LanguageString::from(*language))
+				.collect::<Vec<_>>();
+			(sheet.name.clone(), languages)
+		})
+		.collect::<BTreeMap<_, _>>();
+
+	Ok(Json(languages))
+}
+
 /// Path variables accepted by the sheet endpoint.
 #[derive(Deserialize, JsonSchema)]
 struct SheetPath {
@@ -163,6 +436,52 @@ struct SheetQuery {
 
 	/// Fetch rows after the specified row. Behavior is undefined if both `rows` and `after` are provided.
 	after: Option<RowSpecifier>,
+
+	/// Only return rows introduced by the specified expansions, as a comma-separated list of `ffxiv`, `ex1`, `ex2`, etc. Only available for sheets with configured expansion ranges.
+	#[serde(default, deserialize_with = "deserialize_expansions")]
+	#[schemars(schema_with = "expansions_schema")]
+	expansion: Option<Vec<Slot>>,
+
+	/// Only return rows matching the specified query. Rows are filtered before the `limit` is applied, so a page may scan many rows to fill.
+	query: Option<QueryString>,
+}
+
+fn deserialize_expansions<'de, D>(deserializer: D) -> Result<Option<Vec<Slot>>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	let maybe_raw = Option::<String>::deserialize(deserializer)?;
+	let raw = match maybe_raw.as_deref() {
+		None | Some("") => return Ok(None),
+		Some(value) => value,
+	};
+
+	let parsed = raw
+		.split(',')
+		.map(|expansion| {
+			expansion
+				.parse::<Slot>()
+				.ok()
+				.filter(|slot| *slot != Slot::Boot)
+				.ok_or_else(|| de::Error::custom(format!("unknown expansion \"{expansion}\"")))
+		})
+		.collect::<Result<_, D::Error>>()?;
+
+	Ok(Some(parsed))
+}
+
+fn expansions_schema(_generator: &mut SchemaGenerator) -> Schema {
+	Schema::Object(SchemaObject {
+		instance_type: Some(InstanceType::String.into()),
+		string: Some(
+			StringValidation {
+				pattern: Some("^(ffxiv|ex\\d)(,(ffxiv|ex\\d))*$".into()),
+				..Default::default()
+			}
+			.into(),
+		),
+		..Default::default()
+	})
 }
 
 // TODO: this can probably be made as a general purpose "comma seperated" deserializer struct
@@ -209,6 +528,10 @@ struct SheetResponse {
 
 	/// Array of rows retrieved by the query.
 	rows: Vec<RowResult>,
+
+	/// Provenance of the data in this response, if requested.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	provenance: Option<Provenance>,
 }
 
 // TODO: ideally this structure is equivalent to the relation metadata from read:: - to the point honestly it probably _should_ be that. yet another thing to consider when reworking read::.
@@ -236,18 +559,26 @@ fn sheet_docs(operation: TransformOperation) -> TransformOperation {
 					version: "version".into(),
 				},
 				rows: vec![row_result_example(1), row_result_example(2)],
+				provenance: None,
 			})
 		})
 }
 
+#[allow(clippy::too_many_arguments)]
 #[debug_handler(state = service::State)]
 async fn sheet(
 	Path(path): Path<SheetPath>,
 	VersionQuery(version_key): VersionQuery,
 	Query(query): Query<SheetQuery>,
+	Query(provenance_query): Query<ProvenanceQuery>,
+	tenant: CurrentTenant,
+	access: SheetAccess,
+	Cancellation(cancel): Cancellation,
+	Negotiated(format): Negotiated,
 	State(data): State<service::Data>,
 	State(read): State<service::Read>,
 	State(schema_provider): State<service::Schema>,
+	State(version): State<service::Version>,
 	Extension(config): Extension<Config>,
 ) -> Result<impl IntoApiResponse> {
 	// Resolve arguments with the services.
@@ -259,7 +590,9 @@ async fn sheet(
 		.unwrap_or_else(|| read.default_language());
 
 	// TODO: Consider extractor for this.
-	let schema_specifier = schema_provider.canonicalize(query.schema, version_key)?;
+	let schema_specifier =
+		schema_provider.canonicalize(tenant.schema(query.schema), version_key)?;
+	let provenance = provenance_query.build(&version, version_key, &schema_specifier)?;
 
 	let filter = query
 		.fields
@@ -272,23 +605,103 @@ async fn sheet(
 		.map(|filter_string| filter_string.to_filter(language))
 		.unwrap_or(Ok(read::Filter::All))?;
 
-	let schema = schema_provider.schema(schema_specifier.clone())?;
+	let row_query = query
+		.query
+		.map(|row_query| -> Result<_> {
+			let query_filter = row_query.to_filter(language)?;
+			Ok((row_query, query_filter))
+		})
+		.transpose()?;
+
+	// Everything past this point reads from disk - run it on the blocking pool.
+	let response_specifier = schema_specifier.clone();
+	let (rows, warnings) = data
+		.blocking(move || -> Result<_> {
+			let schema = schema_provider.schema(schema_specifier)?;
+			let rows = read_sheet_rows(
+				&excel,
+				schema.as_ref(),
+				&read,
+				&path.sheet,
+				query.rows,
+				query.after,
+				query.limit,
+				query.expansion,
+				row_query
+					.as_ref()
+					.map(|(row_query, query_filter)| (row_query, query_filter)),
+				language,
+				&filter,
+				&config,
+				&access,
+				&cancel,
+			)?;
+			let warnings = language_warnings(&excel, &path.sheet, language)?;
+			Ok((rows, warnings))
+		})
+		.await??;
+
+	let response = SheetResponse {
+		schema: response_specifier,
+		rows,
+		provenance,
+	};
+
+	let mut response = Encoded(format, response).into_response();
+	response.extensions_mut().insert(warnings);
+	Ok(response)
+}
+
+/// Warn about reading a sheet in a language it does not provide. Sheets without
+/// localised data lack every language, and are not warned about.
+fn language_warnings(
+	excel: &excel::Excel,
+	sheet: &str,
+	language: excel::Language,
+) -> Result<Warnings> {
+	let languages = excel.sheet(sheet).anyhow()?.languages().anyhow()?;
+	let localised = languages
+		.iter()
+		.any(|language| *language != excel::Language::None);
+
+	let mut warnings = Warnings::default();
+	if localised && !languages.contains(&language) {
+		warnings.0.push(format!(
+			"sheet \"{sheet}\" does not provide language \"{}\"",
+			read::LanguageString::from(language)
+		));
+	}
+
+	Ok(warnings)
+}
 
+#[allow(clippy::too_many_arguments)]
+fn read_sheet_rows(
+	excel: &excel::Excel,
+	schema: &dyn schema::Schema,
+	read: &read::Read,
+	sheet_name: &str,
+	rows: Option<Vec<RowSpecifier>>,
+	after: Option<RowSpecifier>,
+	limit: Option<usize>,
+	expansions: Option<Vec<Slot>>,
+	query: Option<(&QueryString, &read::Filter)>,
+	language: excel::Language,
+	filter: &read::Filter,
+	config: &Config,
+	access: &SheetAccess,
+	cancel: &CancellationToken,
+) -> Result<Vec<RowResult>> {
 	// Get a reference to the sheet we'll be reading from.
 	// TODO: should this be in super::error as a default extract? minus the sheet specialised case, that is
-	let sheet = excel.sheet(&path.sheet).map_err(|error| match error {
-		ironworks::Error::NotFound(ironworks::ErrorValue::Sheet(..)) => {
-			Error::NotFound(error.to_string())
-		}
-		other => Error::Other(other.into()),
-	})?;
+	let sheet = excel.sheet(sheet_name).map_err(not_found)?;
 
 	// Iterate over the sheet, building row results.
 	// TODO: look into changing the row builder in iw so this assignment isn't required - moving to an owned value would also possibly allow me to move this builder into the None case below.
 	let mut builder = sheet.with();
 	builder.language(language);
 
-	let sheet_iterator = match query.rows {
+	let sheet_iterator = match rows {
 		// One or more row specifiers were provided, iterate over those specifically.
 		Some(specifiers) => Either::Left(specifiers.into_iter()),
 
@@ -300,53 +713,420 @@ async fn sheet(
 		})),
 	};
 
-	// Paginate the results.
-	let limit = query
-		.limit
-		.unwrap_or(config.limit.default)
-		.min(config.limit.max);
+	// Restrict to rows within the requested expansions' ranges.
+	let ranges = match expansions {
+		None => None,
+		Some(expansions) => {
+			let ranges = config.expansion.get(sheet_name).ok_or_else(|| {
+				Error::Invalid(format!(
+					"sheet \"{sheet_name}\" has no configured expansion ranges"
+				))
+			})?;
+			Some(
+				ranges
+					.iter()
+					.filter(|range| expansions.contains(&range.expansion))
+					.collect::<Vec<_>>(),
+			)
+		}
+	};
+	let sheet_iterator = sheet_iterator.filter(|specifier| {
+		ranges.as_ref().map_or(true, |ranges| {
+			ranges.iter().any(|range| range.contains(specifier.row_id))
+		})
+	});
+
+	// Skip to the requested page start.
 	let sheet_iterator = sheet_iterator
 		// TODO: Improve this - introducing an explicit "after" method on a sheet iterator would allow skipping a lot of busywork. As-is, this is fetching every single row's data.
-		.skip_while(|specifier| Some(specifier) <= query.after.as_ref())
-		.take(limit);
+		.skip_while(|specifier| Some(specifier) <= after.as_ref());
+
+	let read_fields = |row_id, subrow_id, filter: &read::Filter| {
+		read.read(
+			excel,
+			schema,
+			sheet_name,
+			row_id,
+			subrow_id,
+			language,
+			filter,
+			config.limit.depth,
+			&|sheet| access.allows(sheet),
+			cancel,
+		)
+	};
 
 	// Build Results for the targeted rows.
 	let sheet_kind = sheet.kind().anyhow()?;
-	let sheet_iterator = sheet_iterator.map(|specifier| {
+	let sheet_iterator = sheet_iterator.map(|specifier| -> Result<_> {
 		let row_id = specifier.row_id;
 		let subrow_id = specifier.subrow_id;
 
+		// Rows are checked against the query with its own filter, so the
+		// returned fields need not include the fields being queried.
+		if let Some((query, query_filter)) = query {
+			let query_fields = read_fields(row_id, subrow_id, query_filter)?;
+			if !query.matches(&query_fields, language) {
+				return Ok(None);
+			}
+		}
+
 		// TODO: This is pretty wasteful to call inside a loop, revisit actual read logic.
 		// TODO: at the moment, an unknown row specifier will cause excel to error with a NotFound (which is fine), however read:: then squashes that with anyhow, meaning the error gets hidden in a 500 ISE. revisit error handling in read:: while i'm at it ref. the above.
-		let fields = read.read(
-			&excel,
-			schema.as_ref(),
-			&path.sheet,
-			row_id,
-			subrow_id,
-			language,
-			&filter,
-			config.limit.depth,
-		)?;
+		let fields = read_fields(row_id, subrow_id, filter)?;
 
-		Ok(RowResult {
+		Ok(Some(RowResult {
 			row_id,
 			subrow_id: match sheet_kind {
 				exh::SheetKind::Subrows => Some(subrow_id),
 				_ => None,
 			},
 			fields: ValueString(fields, language),
+		}))
+	});
+
+	// Paginate the results. Rows not matching the query do not count towards the limit.
+	let limit = limit.unwrap_or(config.limit.default).min(config.limit.max);
+	let sheet_iterator = sheet_iterator.filter_map(Result::transpose).take(limit);
+
+	sheet_iterator.collect::<Result<Vec<_>>>()
+}
+
+/// Query parameters accepted by the sheet export endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct ExportQuery {
+	/// Format of the export.
+	#[serde(default)]
+	format: ExportFormat,
+
+	/// Language to use for data with no language otherwise specified in the fields filter.
+	language: Option<read::LanguageString>,
+
+	/// Schema that row data should be read with.
+	schema: Option<schema::Specifier>,
+
+	/// Data fields to read for each row.
+	fields: Option<FilterString>,
+
+	/// Query that rows must match to be exported. Fields referenced by the query
+	/// are read independently of the fields filter.
+	query: Option<QueryString>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum ExportFormat {
+	#[default]
+	Ndjson,
+	Csv,
+}
+
+impl ExportFormat {
+	fn extension(&self) -> &'static str {
+		match self {
+			Self::Ndjson => "ndjson",
+			Self::Csv => "csv",
+		}
+	}
+
+	fn content_type(&self) -> &'static str {
+		match self {
+			Self::Ndjson => "application/x-ndjson",
+			Self::Csv => "text/csv",
+		}
+	}
+}
+
+fn export_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("export a sheet")
+		.description("Export every row of a sheet, optionally limited to rows matching a query, streamed as newline-delimited JSON or CSV. Each NDJSON line is a row as returned by the sheet endpoint. CSV exports have `row_id`, `subrow_id`, and `fields` columns, with fields embedded as JSON. Interrupted downloads may be resumed with a `Range` request, guarded by `If-Range`.")
+		.response_with::<200, String, _>(|mut response| {
+			response.inner().content = [ExportFormat::Ndjson, ExportFormat::Csv]
+				.into_iter()
+				.map(|format| {
+					(
+						format.content_type().to_string(),
+						openapi::MediaType::default(),
+					)
+				})
+				.collect();
+			response
+		})
+		.response_with::<206, String, _>(|res| res.description("partial content"))
+		.response_with::<304, (), _>(|res| res.description("not modified"))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[debug_handler(state = service::State)]
+async fn export(
+	Path(path): Path<SheetPath>,
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<ExportQuery>,
+	NoApi(header_if_none_match): NoApi<Option<TypedHeader<IfNoneMatch>>>,
+	requested_range: RequestedRange,
+	tenant: CurrentTenant,
+	access: SheetAccess,
+	Cancellation(cancel): Cancellation,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+	State(schema_provider): State<service::Schema>,
+	Extension(config): Extension<Config>,
+) -> Result<impl IntoApiResponse> {
+	let excel = data.version(version_key)?.excel();
+
+	let language = query
+		.language
+		.map(excel::Language::from)
+		.unwrap_or_else(|| read.default_language());
+
+	let schema_specifier =
+		schema_provider.canonicalize(tenant.schema(query.schema), version_key)?;
+
+	let filter = query
+		.fields
+		.or_else(|| {
+			config
+				.filter
+				.get(&schema_specifier.source)
+				.and_then(|filter_config| filter_config.list.clone())
+		})
+		.map(|filter_string| filter_string.to_filter(language))
+		.unwrap_or(Ok(read::Filter::All))?;
+
+	let row_query = query
+		.query
+		.map(|row_query| -> Result<_> {
+			let query_filter = row_query.to_filter(language)?;
+			Ok((row_query, query_filter))
 		})
+		.transpose()?;
+
+	let etag = export_etag(
+		&path.sheet,
+		query.format,
+		&schema_specifier,
+		language,
+		&filter,
+		row_query.as_ref().map(|(row_query, _)| row_query),
+		version_key,
+	);
+
+	if let Some(TypedHeader(if_none_match)) = header_if_none_match {
+		if !if_none_match.precondition_passes(&etag) {
+			return Ok(StatusCode::NOT_MODIFIED.into_response());
+		}
+	}
+
+	// Check the sheet exists before starting the body, so that a missing sheet
+	// is reported as such rather than as a broken stream.
+	let check_excel = excel.clone();
+	let sheet_name = path.sheet.clone();
+	data.blocking(move || -> Result<_> {
+		check_excel.sheet(&sheet_name).map_err(not_found)?;
+		Ok(())
+	})
+	.await??;
+
+	let format = query.format;
+	let filename = format!("{}.{}", path.sheet.replace('/', "_"), format.extension());
+	let headers = [
+		(header::CONTENT_TYPE, format.content_type().to_string()),
+		(
+			header::CONTENT_DISPOSITION,
+			format!("attachment; filename=\"{filename}\""),
+		),
+	];
+
+	// Resuming a download needs the length of the full body - buffer the export
+	// when a range is requested.
+	if requested_range.is_requested() {
+		let sheet_name = path.sheet.clone();
+		let bytes = data
+			.blocking(move || -> Result<_> {
+				let schema = schema_provider.schema(schema_specifier)?;
+				let mut bytes = vec![];
+				write_export(
+					&mut bytes,
+					format,
+					&excel,
+					schema.as_ref(),
+					&read,
+					&sheet_name,
+					language,
+					&filter,
+					row_query
+						.as_ref()
+						.map(|(row_query, query_filter)| (row_query, query_filter)),
+					config.limit.depth,
+					&access,
+					&cancel,
+				)?;
+				Ok(bytes)
+			})
+			.await??;
+
+		return Ok((headers, requested_range.respond(etag, bytes)).into_response());
+	}
+
+	// Otherwise, rows are read and written out as the client consumes them,
+	// rather than buffering the entire sheet.
+	let (mut writer, body) = negotiate::body_writer();
+	let sheet_name = path.sheet.clone();
+	let stream_data = data.clone();
+	tokio::spawn(async move {
+		let result = stream_data
+			.blocking(move || {
+				let result = schema_provider
+					.schema(schema_specifier)
+					.map_err(Error::from)
+					.and_then(|schema| {
+						write_export(
+							&mut writer,
+							format,
+							&excel,
+							schema.as_ref(),
+							&read,
+							&sheet_name,
+							language,
+							&filter,
+							row_query
+								.as_ref()
+								.map(|(row_query, query_filter)| (row_query, query_filter)),
+							config.limit.depth,
+							&access,
+							&cancel,
+						)
+					});
+				writer.finish(result.map_err(anyhow::Error::from));
+			})
+			.await;
+
+		if let Err(error) = result {
+			tracing::warn!(?error, "failed to run sheet export");
+		}
 	});
 
-	let rows = sheet_iterator.collect::<Result<Vec<_>>>()?;
+	Ok((
+		headers,
+		TypedHeader(AcceptRanges::bytes()),
+		TypedHeader(etag),
+		body,
+	)
+		.into_response())
+}
 
-	let response = SheetResponse {
-		schema: schema_specifier,
-		rows,
+/// Entity tag identifying a sheet export. Exports are read deterministically
+/// from version data, so the same tag always refers to the same bytes.
+fn export_etag(
+	sheet: &str,
+	format: ExportFormat,
+	schema: &schema::CanonicalSpecifier,
+	language: excel::Language,
+	filter: &read::Filter,
+	query: Option<&QueryString>,
+	version: VersionKey,
+) -> ETag {
+	let mut hasher = SeaHasher::new();
+	sheet.hash(&mut hasher);
+	format.extension().hash(&mut hasher);
+	schema.to_string().hash(&mut hasher);
+	language.hash(&mut hasher);
+	hash_filter(filter, &mut hasher);
+	query.map(QueryString::as_str).hash(&mut hasher);
+	let resource_hash = hasher.finish();
+
+	format!("\"{resource_hash:016x}.{version}\"")
+		.parse()
+		.expect("malformed etag")
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_export(
+	writer: &mut impl Write,
+	format: ExportFormat,
+	excel: &excel::Excel,
+	schema: &dyn schema::Schema,
+	read: &read::Read,
+	sheet_name: &str,
+	language: excel::Language,
+	filter: &read::Filter,
+	query: Option<(&QueryString, &read::Filter)>,
+	depth: u8,
+	access: &SheetAccess,
+	cancel: &CancellationToken,
+) -> Result<()> {
+	let sheet = excel.sheet(sheet_name).anyhow()?;
+	let subrows = sheet.kind().anyhow()? == exh::SheetKind::Subrows;
+
+	let mut builder = sheet.with();
+	builder.language(language);
+
+	let read_fields = |row_id, subrow_id, filter: &read::Filter| {
+		read.read(
+			excel,
+			schema,
+			sheet_name,
+			row_id,
+			subrow_id,
+			language,
+			filter,
+			depth,
+			&|sheet| access.allows(sheet),
+			cancel,
+		)
 	};
 
-	Ok(Json(response))
+	let rows = builder.iter().map(|row| -> Result<_> {
+		let row_id = row.row_id();
+		let subrow_id = row.subrow_id();
+
+		// Rows are checked against the query with its own filter, so the
+		// exported fields need not include the fields being queried.
+		if let Some((query, query_filter)) = query {
+			let query_fields = read_fields(row_id, subrow_id, query_filter)?;
+			if !query.matches(&query_fields, language) {
+				return Ok(None);
+			}
+		}
+
+		let fields = read_fields(row_id, subrow_id, filter)?;
+
+		Ok(Some(RowResult {
+			row_id,
+			subrow_id: subrows.then_some(subrow_id),
+			fields: ValueString(fields, language),
+		}))
+	});
+	let rows = rows.filter_map(Result::transpose);
+
+	match format {
+		ExportFormat::Ndjson => {
+			for row in rows {
+				serde_json::to_writer(&mut *writer, &row?).anyhow()?;
+				writer.write_all(b"\n").anyhow()?;
+			}
+		}
+
+		ExportFormat::Csv => {
+			// Field data is nested - embed it as a JSON cell.
+			let mut csv = csv::Writer::from_writer(writer);
+			csv.write_record(["row_id", "subrow_id", "fields"])
+				.anyhow()?;
+			for row in rows {
+				let row = row?;
+				csv.write_record([
+					row.row_id.to_string(),
+					row.subrow_id.map(|id| id.to_string()).unwrap_or_default(),
+					serde_json::to_string(&row.fields).anyhow()?,
+				])
+				.anyhow()?;
+			}
+			csv.flush().anyhow()?;
+		}
+	}
+
+	Ok(())
 }
 
 /// Path variables accepted by the row endpoint.
@@ -380,6 +1160,10 @@ struct RowResponse {
 
 	#[serde(flatten)]
 	row: RowResult,
+
+	/// Provenance of the data in this response, if requested.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	provenance: Option<Provenance>,
 }
 
 fn row_docs(operation: TransformOperation) -> TransformOperation {
@@ -395,6 +1179,7 @@ fn row_docs(operation: TransformOperation) -> TransformOperation {
 					version: "version".into(),
 				},
 				row: row_result_example(1),
+				provenance: None,
 			})
 		})
 }
@@ -416,14 +1201,21 @@ fn row_result_example(row_id: u32) -> RowResult {
 	}
 }
 
+#[allow(clippy::too_many_arguments)]
 #[debug_handler(state = service::State)]
 async fn row(
 	Path(path): Path<RowPath>,
 	VersionQuery(version_key): VersionQuery,
 	Query(query): Query<RowQuery>,
+	Query(provenance_query): Query<ProvenanceQuery>,
+	tenant: CurrentTenant,
+	access: SheetAccess,
+	Cancellation(cancel): Cancellation,
+	Negotiated(format): Negotiated,
 	State(data): State<service::Data>,
 	State(read): State<service::Read>,
 	State(schema_provider): State<service::Schema>,
+	State(version): State<service::Version>,
 	Extension(config): Extension<Config>,
 ) -> Result<impl IntoApiResponse> {
 	let excel = data.version(version_key)?.excel();
@@ -433,50 +1225,1646 @@ async fn row(
 		.map(excel::Language::from)
 		.unwrap_or_else(|| read.default_language());
 
-	let schema_specifier = schema_provider.canonicalize(query.schema, version_key)?;
+	let schema_specifier =
+		schema_provider.canonicalize(tenant.schema(query.schema), version_key)?;
+	let provenance = provenance_query.build(&version, version_key, &schema_specifier)?;
 
 	let filter = query
 		.fields
-		.or_else(|| {
-			config
-				.filter
-				.get(&schema_specifier.source)
-				.and_then(|filter_config| filter_config.entry.clone())
-		})
+		.or_else(|| config.entry_filter(&schema_specifier.source))
 		.map(|filter_string| filter_string.to_filter(language))
 		.unwrap_or(Ok(read::Filter::All))?;
 
-	let schema = schema_provider.schema(schema_specifier.clone())?;
+	let row_id = path.row.row_id;
+	let subrow_id = path.row.subrow_id;
+
+	// Reading the row hits disk - run it on the blocking pool.
+	let response_specifier = schema_specifier.clone();
+	let (row, warnings) = data
+		.blocking(move || -> Result<_> {
+			let schema = schema_provider.schema(schema_specifier)?;
+
+			let fields = read.read(
+				&excel,
+				schema.as_ref(),
+				&path.sheet,
+				row_id,
+				subrow_id,
+				language,
+				&filter,
+				config.limit.depth,
+				&|sheet| access.allows(sheet),
+				&cancel,
+			)?;
+
+			// Check the kind of the sheet to determine if we should report a subrow id.
+			// TODO: this is theoretically wasteful, though IW will have cached it anyway.
+			let result_subrow_id = match excel.sheet(&path.sheet).anyhow()?.kind().anyhow()? {
+				exh::SheetKind::Subrows => Some(subrow_id),
+				_ => None,
+			};
+
+			let warnings = language_warnings(&excel, &path.sheet, language)?;
+
+			Ok((
+				RowResult {
+					row_id,
+					subrow_id: result_subrow_id,
+					fields: ValueString(fields, language),
+				},
+				warnings,
+			))
+		})
+		.await??;
+
+	let response = RowResponse {
+		schema: response_specifier,
+		row,
+		provenance,
+	};
+
+	let mut response = Encoded(format, response).into_response();
+	response.extensions_mut().insert(warnings);
+	Ok(response)
+}
+
+/// Response structure for the row history endpoint.
+#[derive(Serialize, JsonSchema)]
+struct RowHistoryResponse {
+	/// The row in each retained version, ordered oldest to newest.
+	versions: Vec<RowHistoryEntry>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct RowHistoryEntry {
+	/// Key of the version.
+	#[schemars(with = "String")]
+	version: VersionKey,
+
+	/// Names the version is known by.
+	names: Vec<String>,
+
+	/// The canonical specifier for the schema the row was read with in this version.
+	#[schemars(with = "String")]
+	schema: schema::CanonicalSpecifier,
+
+	/// Fields of the row in this version, or `null` if the row did not exist.
+	fields: Option<serde_json::Value>,
+
+	/// Top-level fields that differ from the previous entry. Empty for the oldest entry.
+	changed: Vec<String>,
+}
+
+fn row_history_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("read sheet row history")
+		.description("Read a single sheet row from every version retained by this deployment, ordered oldest to newest, with the fields that changed between each. References to other rows are not followed, so changes to related rows are not reported.")
+		.response_with::<200, Json<RowHistoryResponse>, _>(|response| {
+			response.example(RowHistoryResponse {
+				versions: vec![RowHistoryEntry {
+					version: VersionKey::from_patches(["2024.01.01.0000.0000"]),
+					names: vec!["latest".into()],
+					schema: schema::CanonicalSpecifier {
+						source: "source".into(),
+						version: "version".into(),
+					},
+					fields: Some(serde_json::json!({ "FieldName": 14 })),
+					changed: vec!["FieldName".into()],
+				}],
+			})
+		})
+}
+
+#[allow(clippy::too_many_arguments)]
+#[debug_handler(state = service::State)]
+async fn row_history(
+	Path(path): Path<RowPath>,
+	Query(query): Query<RowQuery>,
+	tenant: CurrentTenant,
+	access: SheetAccess,
+	Cancellation(cancel): Cancellation,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+	State(schema_provider): State<service::Schema>,
+	State(version): State<service::Version>,
+	Extension(config): Extension<Config>,
+) -> Result<impl IntoApiResponse> {
+	let language = query
+		.language
+		.map(excel::Language::from)
+		.unwrap_or_else(|| read.default_language());
+
+	// Only versions that have been prepared can be read - order them by the
+	// patches they contain, which are named chronologically.
+	let mut version_keys = data.subscribe().borrow().clone();
+	version_keys.sort_by_cached_key(|key| {
+		version.version(*key).map(|version| {
+			version
+				.repositories
+				.iter()
+				.map(|repository| repository.latest().name.clone())
+				.collect::<Vec<_>>()
+		})
+	});
+
+	let requested_schema = tenant.schema(query.schema);
+	let mut versions = Vec::with_capacity(version_keys.len());
+	for version_key in version_keys {
+		let excel = data.version(version_key)?.excel();
+		let schema_specifier =
+			schema_provider.canonicalize(requested_schema.clone(), version_key)?;
+		let names = version.names(version_key).unwrap_or_default();
+		versions.push((version_key, names, excel, schema_specifier));
+	}
+
+	let filter_string = query.fields.or_else(|| {
+		versions
+			.last()
+			.and_then(|(.., schema_specifier)| config.entry_filter(&schema_specifier.source))
+	});
+	let filter = filter_string
+		.map(|filter_string| filter_string.to_filter(language))
+		.unwrap_or(Ok(read::Filter::All))?;
 
 	let row_id = path.row.row_id;
 	let subrow_id = path.row.subrow_id;
 
-	let fields = read.read(
-		&excel,
-		schema.as_ref(),
-		&path.sheet,
-		row_id,
-		subrow_id,
-		language,
-		&filter,
-		config.limit.depth,
+	// Reading the row hits disk - run it on the blocking pool.
+	let entries = data
+		.blocking(move || -> Result<_> {
+			let mut entries = Vec::<RowHistoryEntry>::with_capacity(versions.len());
+			for (version_key, names, excel, schema_specifier) in versions {
+				let schema = schema_provider.schema(schema_specifier.clone())?;
+
+				let fields = match read.read(
+					&excel,
+					schema.as_ref(),
+					&path.sheet,
+					row_id,
+					subrow_id,
+					language,
+					&filter,
+					0,
+					&|sheet| access.allows(sheet),
+					&cancel,
+				) {
+					Ok(value) => Some(serde_json::to_value(ValueString(value, language)).anyhow()?),
+					// The sheet or row may not exist in every version.
+					Err(read::Error::NotFound(_)) => None,
+					Err(error) => return Err(error.into()),
+				};
+
+				let changed = match entries.last() {
+					Some(previous) => changed_fields(previous.fields.as_ref(), fields.as_ref()),
+					None => vec![],
+				};
+
+				entries.push(RowHistoryEntry {
+					version: version_key,
+					names,
+					schema: schema_specifier,
+					fields,
+					changed,
+				});
+			}
+
+			Ok(entries)
+		})
+		.await??;
+
+	if entries.iter().all(|entry| entry.fields.is_none()) {
+		return Err(Error::NotFound(format!(
+			"row {row_id}:{subrow_id} was not found in any retained version"
+		)));
+	}
+
+	Ok(Json(RowHistoryResponse { versions: entries }))
+}
+
+/// Names of the top-level fields that differ between two serialised rows. A
+/// missing row is treated as having no fields.
+fn changed_fields(
+	previous: Option<&serde_json::Value>,
+	current: Option<&serde_json::Value>,
+) -> Vec<String> {
+	let empty = serde_json::Map::new();
+	let fields = |value: Option<&serde_json::Value>| {
+		value
+			.and_then(serde_json::Value::as_object)
+			.unwrap_or(&empty)
+	};
+	let (previous, current) = (fields(previous), fields(current));
+
+	previous
+		.keys()
+		.chain(current.keys())
+		.collect::<BTreeSet<_>>()
+		.into_iter()
+		.filter(|name| previous.get(*name) != current.get(*name))
+		.cloned()
+		.collect()
+}
+
+/// Query parameters accepted by the sheet strings endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct StringsQuery {
+	/// Format of the per-language files within the bundle.
+	#[serde(default)]
+	format: StringsFormat,
+
+	/// Offset of a string column to sort rows by, using the collation rules of
+	/// each file's language. Rows are ordered by row specifier if unset.
+	sort: Option<u16>,
+
+	/// If specified, only this language will be included in the bundle. Sheets without the language fall back to the language-less variant, if available.
+	language: Option<read::LanguageString>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum StringsFormat {
+	#[default]
+	Json,
+	Csv,
+}
+
+impl StringsFormat {
+	fn extension(&self) -> &'static str {
+		match self {
+			Self::Json => "json",
+			Self::Csv => "csv",
+		}
+	}
+}
+
+fn strings_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("export sheet strings")
+		.description("Export every string column of a sheet as a zip bundle, containing one file per language the sheet provides. Rows are keyed by row specifier, and values by column offset. Rows may be sorted by the text of a column, following the collation rules of each language. Interrupted downloads may be resumed with a `Range` request, guarded by `If-Range`.")
+		.response_with::<200, Vec<u8>, _>(|mut response| {
+			response.inner().content = [(
+				"application/zip".to_string(),
+				openapi::MediaType::default(),
+			)]
+			.into_iter()
+			.collect();
+			response
+		})
+		.response_with::<206, Vec<u8>, _>(|res| res.description("partial content"))
+		.response_with::<304, (), _>(|res| res.description("not modified"))
+}
+
+#[debug_handler(state = service::State)]
+async fn strings(
+	Path(path): Path<SheetPath>,
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<StringsQuery>,
+	NoApi(header_if_none_match): NoApi<Option<TypedHeader<IfNoneMatch>>>,
+	requested_range: RequestedRange,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+) -> Result<impl IntoApiResponse> {
+	let format = query.format;
+	let sort = query.sort;
+	let language = query.language.map(excel::Language::from);
+
+	let etag = strings_etag(&path.sheet, format, sort, language, version_key);
+
+	if let Some(TypedHeader(if_none_match)) = header_if_none_match {
+		if !if_none_match.precondition_passes(&etag) {
+			return Ok(StatusCode::NOT_MODIFIED.into_response());
+		}
+	}
+
+	let excel = data.version(version_key)?.excel();
+
+	let sheet_name = path.sheet.clone();
+	let bytes = data
+		.blocking(move || -> Result<_> {
+			let mut strings = read.strings(&excel, &sheet_name, language)?;
+			if let Some(column) = sort {
+				sort_strings(&mut strings, column)?;
+			}
+			Ok(build_strings_bundle(&strings, format).anyhow()?)
+		})
+		.await??;
+
+	let filename = format!("{}.strings.zip", path.sheet.replace('/', "_"));
+
+	Ok((
+		[
+			(header::CONTENT_TYPE, "application/zip".to_string()),
+			(
+				header::CONTENT_DISPOSITION,
+				format!("attachment; filename=\"{filename}\""),
+			),
+		],
+		requested_range.respond(etag, bytes),
+	)
+		.into_response())
+}
+
+/// Entity tag identifying a strings bundle. Bundles are built deterministically
+/// from version data, so the same tag always refers to the same bytes - which
+/// range requests rely on to resume a download.
+fn strings_etag(
+	sheet: &str,
+	format: StringsFormat,
+	sort: Option<u16>,
+	language: Option<excel::Language>,
+	version: VersionKey,
+) -> ETag {
+	let mut hasher = SeaHasher::new();
+	sheet.hash(&mut hasher);
+	format.extension().hash(&mut hasher);
+	sort.hash(&mut hasher);
+	language.hash(&mut hasher);
+	let resource_hash = hasher.finish();
+
+	format!("\"{resource_hash:016x}.{version}\"")
+		.parse()
+		.expect("malformed etag")
+}
+
+/// Hash a filter in a stable order - the maps it's built from iterate in a
+/// different order each time they're created.
+fn hash_filter(filter: &read::Filter, hasher: &mut impl Hasher) {
+	match filter {
+		read::Filter::All => 0u8.hash(hasher),
+		read::Filter::Array(inner) => {
+			1u8.hash(hasher);
+			hash_filter(inner, hasher);
+		}
+		read::Filter::Struct(fields) => {
+			2u8.hash(hasher);
+			let mut fields = fields.iter().collect::<Vec<_>>();
+			fields.sort_unstable_by_key(|(name, _)| *name);
+			for (name, languages) in fields {
+				name.hash(hasher);
+				let mut languages = languages.iter().collect::<Vec<_>>();
+				languages.sort_unstable_by_key(|(read::Language(language), _)| *language as u8);
+				for (language, inner) in languages {
+					language.hash(hasher);
+					hash_filter(inner, hasher);
+				}
+			}
+		}
+	}
+}
+
+fn sort_strings(strings: &mut read::SheetStrings, column: u16) -> Result<()> {
+	let index = strings
+		.columns
+		.iter()
+		.position(|offset| *offset == column)
+		.ok_or_else(|| Error::Invalid(format!("column {column} is not a string column")))?;
+
+	for language in &mut strings.languages {
+		let collator = read::Collator::new(language.language);
+		language
+			.rows
+			.sort_by_cached_key(|row| collator.key(&row.values[index]));
+	}
+
+	Ok(())
+}
+
+fn build_strings_bundle(
+	strings: &read::SheetStrings,
+	format: StringsFormat,
+) -> io::Result<Vec<u8>> {
+	let mut writer = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+	let options = zip::write::SimpleFileOptions::default();
+
+	for language in &strings.languages {
+		let name = format!(
+			"{}.{}",
+			read::LanguageString::from(language.language),
+			format.extension()
+		);
+		writer.start_file(name, options)?;
+
+		match format {
+			StringsFormat::Json => write_strings_json(&mut writer, &strings.columns, language)?,
+			StringsFormat::Csv => write_strings_csv(&mut writer, &strings.columns, language)?,
+		}
+	}
+
+	Ok(writer.finish()?.into_inner())
+}
+
+fn strings_row_key(row: &read::StringRow) -> String {
+	match row.subrow_id {
+		0 => row.row_id.to_string(),
+		subrow_id => format!("{}:{subrow_id}", row.row_id),
+	}
+}
+
+fn write_strings_json(
+	writer: &mut impl Write,
+	columns: &[u16],
+	language: &read::LanguageStrings,
+) -> io::Result<()> {
+	// Empty strings are omitted - they make up the bulk of most sheets and are of
+	// no interest to translation tooling.
+	let rows = language
+		.rows
+		.iter()
+		.map(|row| {
+			let values = columns
+				.iter()
+				.zip(&row.values)
+				.filter(|(_, value)| !value.is_empty())
+				.collect::<BTreeMap<_, _>>();
+			(strings_row_key(row), values)
+		})
+		.filter(|(_, values)| !values.is_empty());
+
+	// Serialising as a map directly to retain row order.
+	let mut serializer = serde_json::Serializer::pretty(writer);
+	serializer.collect_map(rows)?;
+	Ok(())
+}
+
+fn write_strings_csv(
+	writer: &mut impl Write,
+	columns: &[u16],
+	language: &read::LanguageStrings,
+) -> io::Result<()> {
+	let mut writer = csv::Writer::from_writer(writer);
+
+	writer.write_record(
+		iter::once("key".to_string()).chain(columns.iter().map(|column| column.to_string())),
 	)?;
 
-	// Check the kind of the sheet to determine if we should report a subrow id.
-	// TODO: this is theoretically wasteful, though IW will have cached it anyway.
-	let result_subrow_id = match excel.sheet(&path.sheet).anyhow()?.kind().anyhow()? {
-		exh::SheetKind::Subrows => Some(subrow_id),
-		_ => None,
+	for row in &language.rows {
+		writer.write_record(
+			iter::once(strings_row_key(row).as_str()).chain(row.values.iter().map(String::as_str)),
+		)?;
+	}
+
+	writer.flush()
+}
+
+/// Query parameters accepted by the sheet strings diff endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct StringsDiffQuery {
+	/// Name or key of the version to compare from.
+	from: String,
+
+	/// Name or key of the version to compare to.
+	to: String,
+
+	/// If specified, only changes in this language will be reported.
+	language: Option<read::LanguageString>,
+}
+
+/// Response structure for the sheet strings diff endpoint.
+#[derive(Serialize, JsonSchema)]
+struct StringsDiffResponse {
+	/// Strings that were added, removed, or changed between the two versions.
+	changes: Vec<StringChange>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct StringChange {
+	/// ID of the row containing the string.
+	row_id: u32,
+
+	/// Subrow ID of the row containing the string, if relevant.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	subrow_id: Option<u16>,
+
+	/// Offset of the column containing the string.
+	column: u16,
+
+	/// Language of the string.
+	language: String,
+
+	/// Value of the string in the `from` version. Omitted if the string was added.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	before: Option<String>,
+
+	/// Value of the string in the `to` version. Omitted if the string was removed.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	after: Option<String>,
+}
+
+impl From<read::StringChange> for StringChange {
+	fn from(change: read::StringChange) -> Self {
+		Self {
+			row_id: change.row_id,
+			subrow_id: (change.subrow_id != 0).then_some(change.subrow_id),
+			column: change.column,
+			language: read::LanguageString::from(change.language).to_string(),
+			before: change.before,
+			after: change.after,
+		}
+	}
+}
+
+fn strings_diff_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("diff sheet strings")
+		.description("List string columns of a sheet that differ between two versions, with their values before and after the change for each language. Empty strings are treated as absent.")
+		.response_with::<200, Json<StringsDiffResponse>, _>(|response| {
+			response.example(StringsDiffResponse {
+				changes: vec![StringChange {
+					row_id: 1,
+					subrow_id: None,
+					column: 0,
+					language: "en".into(),
+					before: Some("Old Name".into()),
+					after: Some("New Name".into()),
+				}],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn strings_diff(
+	Path(path): Path<SheetPath>,
+	Query(query): Query<StringsDiffQuery>,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+	State(version): State<service::Version>,
+) -> Result<impl IntoApiResponse> {
+	let changes = diff_strings(
+		&data,
+		read,
+		resolve_name_or_key(&version, &query.from)?,
+		resolve_name_or_key(&version, &query.to)?,
+		path.sheet,
+		query.language.map(excel::Language::from),
+	)
+	.await?
+	.into_iter()
+	.map(StringChange::from)
+	.collect();
+
+	Ok(Json(StringsDiffResponse { changes }))
+}
+
+/// Resolve a version by name, or by key. Keys are accepted so that versions
+/// returned by endpoints such as watch can be passed straight back in.
+fn resolve_name_or_key(version: &service::Version, name: &str) -> Result<VersionKey> {
+	if let Some(key) = version.resolve(Some(name)) {
+		return Ok(key);
+	}
+
+	name.parse::<VersionKey>()
+		.ok()
+		.filter(|key| version.version(*key).is_some())
+		.ok_or_else(|| Error::UnknownVersion(name.into()))
+}
+
+async fn diff_strings(
+	data: &service::Data,
+	read: service::Read,
+	from: VersionKey,
+	to: VersionKey,
+	sheet: String,
+	language: Option<excel::Language>,
+) -> Result<Vec<read::StringChange>> {
+	let excel_from = data.version(from)?.excel();
+	let excel_to = data.version(to)?.excel();
+
+	let changes = data
+		.blocking(move || -> Result<_> {
+			let strings_from = read.strings(&excel_from, &sheet, language)?;
+			let strings_to = read.strings(&excel_to, &sheet, language)?;
+			Ok(strings_from.diff(&strings_to))
+		})
+		.await??;
+
+	Ok(changes)
+}
+
+/// Query parameters accepted by the sheet watch endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct WatchQuery {
+	/// Name or key of the version the client currently holds. Changes are reported relative to this version.
+	since: String,
+
+	/// Name of the version to watch for updates. Defaults to `latest`.
+	version: Option<String>,
+
+	/// Rows to watch, as a comma-separated list. If omitted, the entire sheet is watched.
+	#[serde(default, deserialize_with = "deserialize_rows")]
+	#[schemars(schema_with = "rows_schema")]
+	rows: Option<Vec<RowSpecifier>>,
+
+	/// If specified, only changes in this language will be reported.
+	language: Option<read::LanguageString>,
+
+	/// Maximum time, in seconds, to wait for a change before responding. Defaults to 30, capped at 120.
+	timeout: Option<u64>,
+}
+
+/// Response structure for the sheet watch endpoint.
+#[derive(Serialize, JsonSchema)]
+struct WatchResponse {
+	/// Version that changes are reported up to. Pass this as `since` to continue watching.
+	version: String,
+
+	/// Strings of watched rows that changed since the requested version. Empty if no changes occurred before the timeout.
+	changes: Vec<StringChange>,
+}
+
+fn watch_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("watch sheet for changes")
+		.description("Wait for a new version that changes strings in the sheet, or in the specified rows. Responds as soon as a change is available, or with no changes once the timeout elapses. Clients should pass the returned version as `since` in their next request to continue watching.")
+		.response_with::<200, Json<WatchResponse>, _>(|response| {
+			response.example(WatchResponse {
+				version: "c8ef5a8c4b6fe1a6".into(),
+				changes: vec![StringChange {
+					row_id: 1,
+					subrow_id: None,
+					column: 0,
+					language: "en".into(),
+					before: Some("Old Name".into()),
+					after: Some("New Name".into()),
+				}],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn watch(
+	Path(path): Path<SheetPath>,
+	Query(query): Query<WatchQuery>,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+	State(version): State<service::Version>,
+) -> Result<impl IntoApiResponse> {
+	let mut current = resolve_name_or_key(&version, &query.since)?;
+
+	let timeout = query
+		.timeout
+		.unwrap_or(WATCH_TIMEOUT_DEFAULT)
+		.min(WATCH_TIMEOUT_MAX);
+	let deadline = Instant::now() + Duration::from_secs(timeout);
+	let language = query.language.map(excel::Language::from);
+
+	// Subscribe before checking for updates, so a version prepared in between isn't missed.
+	let mut receiver = data.subscribe();
+
+	loop {
+		// Versions are only usable once data has prepared them, which may lag
+		// behind the version name being updated.
+		let latest = version.resolve(query.version.as_deref());
+		if let Some(latest) = latest.filter(|&key| key != current && data.version(key).is_ok()) {
+			let changes = diff_strings(
+				&data,
+				read.clone(),
+				current,
+				latest,
+				path.sheet.clone(),
+				language,
+			)
+			.await?
+			.into_iter()
+			.filter(|change| {
+				query.rows.as_ref().map_or(true, |rows| {
+					rows.iter()
+						.any(|row| row.row_id == change.row_id && row.subrow_id == change.subrow_id)
+				})
+			})
+			.map(StringChange::from)
+			.collect::<Vec<_>>();
+
+			// A new version that doesn't touch the watched rows still moves the
+			// client forward, so the next diff doesn't need to repeat this one.
+			current = latest;
+			if !changes.is_empty() {
+				return Ok(Json(WatchResponse {
+					version: current.to_string(),
+					changes,
+				}));
+			}
+		}
+
+		// Wake on data preparing a version. A timeout, or data shutting down, ends the wait.
+		match time::timeout_at(deadline, receiver.changed()).await {
+			Ok(Ok(())) => continue,
+			Ok(Err(_)) | Err(_) => break,
+		}
+	}
+
+	Ok(Json(WatchResponse {
+		version: current.to_string(),
+		changes: vec![],
+	}))
+}
+
+/// Query parameters accepted by the sheet stats endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct StatsQuery {
+	/// Language to read string columns in. Falls back to the sheet's language-less data if unavailable.
+	language: Option<read::LanguageString>,
+
+	/// If `true`, include guesses at the purpose of integer columns, such as
+	/// icon IDs or references to other sheets. Checks every sheet in the version,
+	/// and may be slow.
+	#[serde(default)]
+	guess: bool,
+}
+
+/// Response structure for the sheet stats endpoint.
+#[derive(Serialize, JsonSchema)]
+struct StatsResponse {
+	/// Language the statistics were computed from.
+	language: String,
+
+	/// Number of rows in the sheet. Subrows are counted individually.
+	row_count: u64,
+
+	/// Per-column statistics, ordered by column offset.
+	columns: Vec<ColumnStats>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ColumnStats {
+	/// Byte offset of the column within a row.
+	offset: u16,
+
+	/// Data type of the column.
+	kind: String,
+
+	/// Number of distinct values in the column.
+	distinct: usize,
+
+	/// If `true`, the column has too many distinct values to track, and `distinct` is a lower bound.
+	#[serde(skip_serializing_if = "std::ops::Not::not")]
+	distinct_capped: bool,
+
+	/// Minimum value of numeric columns.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	min: Option<f64>,
+
+	/// Maximum value of numeric columns.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	max: Option<f64>,
+
+	/// Ratio of zero, false, or empty string values to the total number of rows.
+	zero_ratio: f64,
+
+	/// Most common values in the column, by number of occurrences.
+	top: Vec<TopValue>,
+
+	/// Guesses at the purpose of the column, if requested.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	guesses: Vec<ColumnGuess>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ColumnGuess {
+	/// Values appear to be icon IDs.
+	Icon,
+
+	/// Values appear to be row IDs of the specified sheet.
+	Reference { sheet: String },
+}
+
+impl From<read::ColumnGuess> for ColumnGuess {
+	fn from(guess: read::ColumnGuess) -> Self {
+		match guess {
+			read::ColumnGuess::Icon => Self::Icon,
+			read::ColumnGuess::Reference(sheet) => Self::Reference { sheet },
+		}
+	}
+}
+
+#[derive(Serialize, JsonSchema)]
+struct TopValue {
+	/// Value, formatted as a string.
+	value: String,
+
+	/// Number of rows with this value.
+	count: u64,
+}
+
+fn stats_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("sheet column statistics")
+		.description("Compute statistics for every column of a sheet, to help identify the purpose of columns not yet described by a schema. Statistics are computed with a full pass over the sheet, and may be slow for large sheets.")
+		.response_with::<200, Json<StatsResponse>, _>(|response| {
+			response.example(StatsResponse {
+				language: "en".into(),
+				row_count: 100,
+				columns: vec![ColumnStats {
+					offset: 0,
+					kind: "UInt16".into(),
+					distinct: 3,
+					distinct_capped: false,
+					min: Some(0.0),
+					max: Some(2.0),
+					zero_ratio: 0.9,
+					top: vec![
+						TopValue {
+							value: "0".into(),
+							count: 90,
+						},
+						TopValue {
+							value: "1".into(),
+							count: 8,
+						},
+					],
+					guesses: vec![ColumnGuess::Reference {
+						sheet: "ClassJob".into(),
+					}],
+				}],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn stats(
+	Path(path): Path<SheetPath>,
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<StatsQuery>,
+	access: SheetAccess,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+) -> Result<impl IntoApiResponse> {
+	let data_version = data.version(version_key)?;
+
+	let language = query
+		.language
+		.map(excel::Language::from)
+		.unwrap_or_else(|| read.default_language());
+
+	let (stats, guesses) = data
+		.blocking(move || -> Result<_> {
+			let excel = data_version.excel();
+			let stats = read.stats(&excel, &path.sheet, language)?;
+			let guesses = match query.guess {
+				true => read.guess_columns(&data_version.ironworks(), &excel, &stats)?,
+				false => vec![],
+			};
+			Ok((stats, guesses))
+		})
+		.await??;
+
+	let row_count = stats.row_count;
+	let response = StatsResponse {
+		language: read::LanguageString::from(stats.language).to_string(),
+		row_count,
+		columns: stats
+			.columns
+			.into_iter()
+			.zip(guesses.into_iter().chain(iter::repeat_with(Vec::new)))
+			.map(|(column, guesses)| ColumnStats {
+				offset: column.offset,
+				kind: format!("{:?}", column.kind),
+				distinct: column.distinct,
+				distinct_capped: column.distinct_capped,
+				min: column.min,
+				max: column.max,
+				zero_ratio: match row_count {
+					0 => 0.0,
+					count => column.zero_count as f64 / count as f64,
+				},
+				top: column
+					.top
+					.into_iter()
+					.map(|(value, count)| TopValue { value, count })
+					.collect(),
+				guesses: guesses
+					.into_iter()
+					.filter(|guess| match guess {
+						read::ColumnGuess::Reference(sheet) => access.allows(sheet),
+						read::ColumnGuess::Icon => true,
+					})
+					.map(ColumnGuess::from)
+					.collect(),
+			})
+			.collect(),
 	};
 
-	let response = RowResponse {
-		schema: schema_specifier,
-		row: RowResult {
-			row_id,
-			subrow_id: result_subrow_id,
-			fields: ValueString(fields, language),
-		},
+	Ok(Json(response))
+}
+
+/// Query parameters accepted by the sheet header endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct HeaderQuery {
+	/// If `true`, include the raw bytes of the header file, hex encoded.
+	#[serde(default)]
+	raw: bool,
+}
+
+/// Response structure for the sheet header endpoint.
+#[derive(Serialize, JsonSchema)]
+struct HeaderResponse {
+	/// Variant of the sheet, i.e. whether rows contain subrows.
+	kind: String,
+
+	/// Size of the fixed-size portion of each row, in bytes.
+	row_size: u16,
+
+	/// Column definitions, in the order they are declared in the header.
+	columns: Vec<HeaderColumn>,
+
+	/// Pages the sheet's rows are split across, in the order they are declared
+	/// in the header.
+	pages: Vec<HeaderPage>,
+
+	/// Languages the sheet provides data for.
+	languages: Vec<String>,
+
+	/// Raw bytes of the header file, as a hexadecimal string. Only present if
+	/// requested with the `raw` query parameter.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	raw: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct HeaderColumn {
+	/// Byte offset of the column within a row.
+	offset: u16,
+
+	/// Data type of the column.
+	kind: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct HeaderPage {
+	/// ID of the first row in the page.
+	start_id: u32,
+
+	/// Number of rows the page spans.
+	row_count: u32,
+}
+
+fn header_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("sheet header")
+		.description("Read the parsed header of a sheet, describing its pagination, column layout, and variant, as read by this instance. Intended as a reference for tooling validating its own parsing of the game's excel files.")
+		.response_with::<200, Json<HeaderResponse>, _>(|response| {
+			response.example(HeaderResponse {
+				kind: "Default".into(),
+				row_size: 8,
+				columns: vec![
+					HeaderColumn {
+						offset: 0,
+						kind: "String".into(),
+					},
+					HeaderColumn {
+						offset: 4,
+						kind: "UInt16".into(),
+					},
+				],
+				pages: vec![HeaderPage {
+					start_id: 0,
+					row_count: 500,
+				}],
+				languages: vec!["de".into(), "en".into(), "fr".into(), "ja".into()],
+				raw: None,
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn header(
+	Path(path): Path<SheetPath>,
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<HeaderQuery>,
+	State(data): State<service::Data>,
+) -> Result<impl IntoApiResponse> {
+	let data_version = data.version(version_key)?;
+
+	let sheet_header = data
+		.blocking(move || -> Result<_> {
+			// Check the sheet exists first, so unknown sheets are reported as such.
+			data_version
+				.excel()
+				.sheet(&path.sheet)
+				.map_err(read::Error::from)?;
+			Ok(data_version.sheet_header(&path.sheet)?)
+		})
+		.await??;
+
+	let header = &sheet_header.header;
+	let mut languages = header
+		.languages()
+		.iter()
+		.map(|language| read::LanguageString::from(*language).to_string())
+		.collect::<Vec<_>>();
+	languages.sort_unstable();
+
+	let response = HeaderResponse {
+		kind: format!("{:?}", header.kind()),
+		row_size: header.row_size(),
+		columns: header
+			.columns()
+			.iter()
+			.map(|column| HeaderColumn {
+				offset: column.offset(),
+				kind: format!("{:?}", column.kind()),
+			})
+			.collect(),
+		pages: header
+			.pages()
+			.iter()
+			.map(|page| HeaderPage {
+				start_id: page.start_id(),
+				row_count: page.row_count(),
+			})
+			.collect(),
+		languages,
+		raw: query.raw.then(|| {
+			sheet_header
+				.raw
+				.iter()
+				.map(|byte| format!("{byte:02x}"))
+				.collect()
+		}),
 	};
 
 	Ok(Json(response))
 }
+
+/// Query parameters accepted by the sheet schema diff endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct SchemaDiffQuery {
+	/// Schema to compare from. Defaults to the schema used when none is specified.
+	base: Option<schema::Specifier>,
+
+	/// Schema to compare against the base.
+	target: schema::Specifier,
+
+	/// Number of rows to sample, spread evenly across the sheet. Defaults to 100, up to a maximum of 1000.
+	sample: Option<usize>,
+
+	/// Language to read rows in.
+	language: Option<read::LanguageString>,
+}
+
+/// Response structure for the sheet schema diff endpoint.
+#[derive(Serialize, JsonSchema)]
+struct SchemaDiffResponse {
+	/// The canonical specifier of the base schema.
+	#[schemars(with = "String")]
+	base: schema::CanonicalSpecifier,
+
+	/// The canonical specifier of the target schema.
+	#[schemars(with = "String")]
+	target: schema::CanonicalSpecifier,
+
+	/// Number of rows that were sampled.
+	rows: usize,
+
+	/// Field paths only present under the target schema.
+	added: Vec<String>,
+
+	/// Field paths only present under the base schema.
+	removed: Vec<String>,
+
+	/// Field paths present under both schemas, with a different shape.
+	changed: Vec<SchemaFieldChange>,
+
+	/// Fields that have likely been renamed or moved, as their values match
+	/// across every sampled row.
+	moved: Vec<SchemaFieldMove>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SchemaFieldChange {
+	/// Path of the field.
+	path: String,
+	/// Shape of the field under the base schema.
+	from: &'static str,
+	/// Shape of the field under the target schema.
+	to: &'static str,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SchemaFieldMove {
+	/// Path of the field under the base schema.
+	from: String,
+	/// Path of the field under the target schema.
+	to: String,
+}
+
+fn schema_diff_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("compare sheet schemas")
+		.description("Read a sample of rows from a sheet under two schemas, and report structural differences between the results. Useful for checking a schema upgrade for breaking changes before switching to it. Moves are detected by matching values, and may be missed for fields with few distinct values.")
+		.response_with::<200, Json<SchemaDiffResponse>, _>(|response| {
+			response.example(SchemaDiffResponse {
+				base: schema::CanonicalSpecifier {
+					source: "source".into(),
+					version: "base".into(),
+				},
+				target: schema::CanonicalSpecifier {
+					source: "source".into(),
+					version: "target".into(),
+				},
+				rows: 100,
+				added: vec!["Unknown3".into()],
+				removed: vec![],
+				changed: vec![SchemaFieldChange {
+					path: "Icon".into(),
+					from: "scalar",
+					to: "icon",
+				}],
+				moved: vec![SchemaFieldMove {
+					from: "Unknown0".into(),
+					to: "ClassJobLevel".into(),
+				}],
+			})
+		})
+}
+
+#[allow(clippy::too_many_arguments)]
+#[debug_handler(state = service::State)]
+async fn schema_diff(
+	Path(path): Path<SheetPath>,
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<SchemaDiffQuery>,
+	tenant: CurrentTenant,
+	access: SheetAccess,
+	Cancellation(cancel): Cancellation,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+	State(schema_provider): State<service::Schema>,
+) -> Result<impl IntoApiResponse> {
+	let excel = data.version(version_key)?.excel();
+
+	let language = query
+		.language
+		.map(excel::Language::from)
+		.unwrap_or_else(|| read.default_language());
+
+	let base_specifier = schema_provider.canonicalize(tenant.schema(query.base), version_key)?;
+	let target_specifier = schema_provider.canonicalize(Some(query.target), version_key)?;
+
+	let sample = query
+		.sample
+		.unwrap_or(SCHEMA_DIFF_SAMPLE_DEFAULT)
+		.min(SCHEMA_DIFF_SAMPLE_MAX);
+
+	let (base, target) = (base_specifier.clone(), target_specifier.clone());
+	let diff = data
+		.blocking(move || -> Result<_> {
+			let base = schema_provider.schema(base)?;
+			let target = schema_provider.schema(target)?;
+			let diff = read.compare_schemas(
+				&excel,
+				base.as_ref(),
+				target.as_ref(),
+				&path.sheet,
+				sample,
+				language,
+				&|sheet| access.allows(sheet),
+				&cancel,
+			)?;
+			Ok(diff)
+		})
+		.await??;
+
+	Ok(Json(SchemaDiffResponse {
+		base: base_specifier,
+		target: target_specifier,
+		rows: diff.rows,
+		added: diff.added,
+		removed: diff.removed,
+		changed: diff
+			.changed
+			.into_iter()
+			.map(|change| SchemaFieldChange {
+				path: change.path,
+				from: change.from,
+				to: change.to,
+			})
+			.collect(),
+		moved: diff
+			.moved
+			.into_iter()
+			.map(|field_move| SchemaFieldMove {
+				from: field_move.from,
+				to: field_move.to,
+			})
+			.collect(),
+	}))
+}
+
+/// Query parameters accepted by the row assets endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct RowAssetsQuery {
+	/// Schema that row data should be read with.
+	schema: Option<schema::Specifier>,
+
+	/// Format that asset URLs should request. Defaults to `png`.
+	format: Option<Format>,
+
+	// Retained to forward to asset URLs. Resolution is handled by VersionQuery.
+	#[serde(default)]
+	#[schemars(skip)]
+	version: Option<String>,
+}
+
+/// Response structure for the row assets endpoint.
+#[derive(Serialize, JsonSchema)]
+struct RowAssetsResponse {
+	/// The canonical specifier for the schema used in this response.
+	#[schemars(with = "String")]
+	schema: schema::CanonicalSpecifier,
+
+	/// Assets referenced by the row and its related rows, ordered by ID.
+	assets: Vec<RowAsset>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct RowAsset {
+	/// ID of the icon.
+	id: u32,
+
+	/// Game path of the icon texture.
+	path: String,
+
+	/// Game path of the high resolution icon texture.
+	path_hr1: String,
+
+	/// URL of the converted icon, served by the asset endpoint.
+	url: String,
+}
+
+fn row_assets_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("list row assets")
+		.description("List every icon referenced by a sheet row and its related data, with URLs to fetch them from the asset endpoint. Useful for prefetching all imagery associated with a row.")
+		.response_with::<200, Json<RowAssetsResponse>, _>(|response| {
+			response.example(RowAssetsResponse {
+				schema: schema::CanonicalSpecifier {
+					source: "source".into(),
+					version: "version".into(),
+				},
+				assets: vec![RowAsset {
+					id: 20650,
+					path: "ui/icon/020000/020650.tex".into(),
+					path_hr1: "ui/icon/020000/020650_hr1.tex".into(),
+					url: "/api/1/asset/ui/icon/020000/020650.tex?format=png".into(),
+				}],
+			})
+		})
+}
+
+#[allow(clippy::too_many_arguments)]
+#[debug_handler(state = service::State)]
+async fn row_assets(
+	Path(path): Path<RowPath>,
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<RowAssetsQuery>,
+	RouterPath(router_path): RouterPath,
+	tenant: CurrentTenant,
+	access: SheetAccess,
+	Cancellation(cancel): Cancellation,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+	State(schema_provider): State<service::Schema>,
+	Extension(config): Extension<Config>,
+) -> Result<impl IntoApiResponse> {
+	let excel = data.version(version_key)?.excel();
+	let language = read.default_language();
+	let schema_specifier =
+		schema_provider.canonicalize(tenant.schema(query.schema), version_key)?;
+
+	let response_specifier = schema_specifier.clone();
+	let icons = data
+		.blocking(move || -> Result<_> {
+			let schema = schema_provider.schema(schema_specifier)?;
+			let value = read.read(
+				&excel,
+				schema.as_ref(),
+				&path.sheet,
+				path.row.row_id,
+				path.row.subrow_id,
+				language,
+				&read::Filter::All,
+				config.limit.depth,
+				&|sheet| access.allows(sheet),
+				&cancel,
+			)?;
+
+			let mut icons = BTreeSet::new();
+			collect_icons(&value, &mut icons);
+			Ok(icons)
+		})
+		.await??;
+
+	// The asset router is a sibling of this one - swap out our own mount point.
+	let asset_root = match router_path.strip_suffix("/sheet") {
+		Some(api_root) => format!("{api_root}/asset"),
+		None => "/api/1/asset".to_string(),
+	};
+	let format = query.format.unwrap_or(Format::Png);
+	let version_suffix = match query.version {
+		Some(version) => {
+			let encoded = serde_urlencoded::to_string([("version", version)]).anyhow()?;
+			format!("&{encoded}")
+		}
+		None => String::new(),
+	};
+
+	let assets = icons
+		.into_iter()
+		.map(|id| {
+			let path = read::icon_path(id);
+			RowAsset {
+				id,
+				url: format!(
+					"{asset_root}/{path}.tex?format={}{version_suffix}",
+					format.extension()
+				),
+				path: format!("{path}.tex"),
+				path_hr1: format!("{path}_hr1.tex"),
+			}
+		})
+		.collect();
+
+	Ok(Json(RowAssetsResponse {
+		schema: response_specifier,
+		assets,
+	}))
+}
+
+fn collect_icons(value: &read::Value, icons: &mut BTreeSet<u32>) {
+	match value {
+		read::Value::Icon(id) => {
+			// Icon 0 is used throughout the game data to represent "no icon".
+			if *id != 0 {
+				icons.insert(*id);
+			}
+		}
+		read::Value::Array(values) => {
+			for value in values {
+				collect_icons(value, icons)
+			}
+		}
+		read::Value::Struct(fields) => {
+			for value in fields.values() {
+				collect_icons(value, icons)
+			}
+		}
+		read::Value::Reference(read::Reference::Populated {
+			display, fields, ..
+		}) => {
+			if let Some(display) = display {
+				collect_icons(display, icons)
+			}
+			collect_icons(fields, icons)
+		}
+		read::Value::Reference(read::Reference::Shallow {
+			display: Some(display),
+			..
+		}) => collect_icons(display, icons),
+		read::Value::Reference(read::Reference::Scalar(..) | read::Reference::Shallow { .. })
+		| read::Value::Scalar(..) => {}
+	}
+}
+
+/// Query parameters accepted by the sheet join endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct JoinQuery {
+	// Join specification
+	/// Offset of the column in the base sheet holding the row IDs to join on.
+	column: u16,
+
+	/// Sheet that base rows are joined to.
+	target: String,
+
+	/// Data fields to read for joined target rows.
+	target_fields: Option<FilterString>,
+
+	// Data resolution
+	/// Language to use for data with no language otherwise specified in the fields filters.
+	language: Option<read::LanguageString>,
+
+	/// Schema that row data should be read with.
+	schema: Option<schema::Specifier>,
+
+	/// Data fields to read for base rows.
+	fields: Option<FilterString>,
+
+	// ID pagination/filtering
+	/// Base rows to fetch, as a comma-separated list. Behavior is undefined if both `rows` and `after` are provided.
+	#[serde(default, deserialize_with = "deserialize_rows")]
+	#[schemars(schema_with = "rows_schema")]
+	rows: Option<Vec<RowSpecifier>>,
+
+	/// Maximum number of base rows to return. To paginate, provide the last returned row to the next request's `after` parameter.
+	limit: Option<usize>,
+
+	/// Fetch base rows after the specified row. Behavior is undefined if both `rows` and `after` are provided.
+	after: Option<RowSpecifier>,
+}
+
+/// Response structure for the sheet join endpoint.
+#[derive(Serialize, JsonSchema)]
+struct JoinResponse {
+	/// The canonical specifier for the schema used in this response.
+	#[schemars(with = "String")]
+	schema: schema::CanonicalSpecifier,
+
+	/// Array of base rows, each paired with the target row it joins to.
+	rows: Vec<JoinResult>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct JoinResult {
+	#[serde(flatten)]
+	row: RowResult,
+
+	/// Target row whose ID matches the base row's join column. Absent if the
+	/// column holds no valid row ID, or the target sheet has no such row.
+	joined: Option<RowResult>,
+}
+
+fn join_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("join rows across sheets")
+		.description("Read rows from a sheet, each combined with the row of a target sheet identified by one of its columns. Saves fetching both sheets and matching rows client-side.")
+		.response_with::<200, Json<JoinResponse>, _>(|response| {
+			response.example(JoinResponse {
+				schema: schema::CanonicalSpecifier {
+					source: "source".into(),
+					version: "version".into(),
+				},
+				rows: vec![JoinResult {
+					row: row_result_example(1),
+					joined: Some(row_result_example(14)),
+				}],
+			})
+		})
+}
+
+#[allow(clippy::too_many_arguments)]
+#[debug_handler(state = service::State)]
+async fn join(
+	Path(path): Path<SheetPath>,
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<JoinQuery>,
+	tenant: CurrentTenant,
+	access: SheetAccess,
+	Cancellation(cancel): Cancellation,
+	Negotiated(format): Negotiated,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+	State(schema_provider): State<service::Schema>,
+	Extension(config): Extension<Config>,
+) -> Result<impl IntoApiResponse> {
+	// The base sheet is checked by the access middleware, but the target is not.
+	access.check(&query.target)?;
+
+	let excel = data.version(version_key)?.excel();
+
+	let language = query
+		.language
+		.map(excel::Language::from)
+		.unwrap_or_else(|| read.default_language());
+
+	let schema_specifier =
+		schema_provider.canonicalize(tenant.schema(query.schema), version_key)?;
+
+	// Both sides of the join fall back to the configured list filter.
+	let list_filter = config
+		.filter
+		.get(&schema_specifier.source)
+		.and_then(|filter_config| filter_config.list.clone());
+	let to_filter = |fields: Option<FilterString>| {
+		fields
+			.or_else(|| list_filter.clone())
+			.map(|filter_string| filter_string.to_filter(language))
+			.unwrap_or(Ok(read::Filter::All))
+	};
+	let filter = to_filter(query.fields)?;
+	let target_filter = to_filter(query.target_fields)?;
+
+	let response_specifier = schema_specifier.clone();
+	let rows = data
+		.blocking(move || -> Result<_> {
+			let schema = schema_provider.schema(schema_specifier)?;
+
+			let base_sheet = excel.sheet(&path.sheet).map_err(not_found)?;
+			let column = base_sheet
+				.columns()
+				.anyhow()?
+				.into_iter()
+				.find(|column| column.offset() == query.column)
+				.ok_or_else(|| {
+					Error::Invalid(format!(
+						"sheet \"{}\" has no column at offset {}",
+						path.sheet, query.column
+					))
+				})?;
+
+			// Rows are joined by row ID alone - a subrow sheet has no single row to join to.
+			let target_sheet = excel.sheet(&query.target).map_err(not_found)?;
+			if target_sheet.kind().anyhow()? == exh::SheetKind::Subrows {
+				return Err(Error::Invalid(format!(
+					"target sheet \"{}\" has subrows, and cannot be joined to",
+					query.target
+				)));
+			}
+
+			let target_exists = |row_id| match target_sheet.with().language(language).row(row_id) {
+				Ok(_) => Ok(true),
+				Err(ironworks::Error::NotFound(ironworks::ErrorValue::Row { .. })) => Ok(false),
+				Err(error) => Err(Error::Other(error.into())),
+			};
+
+			let base_rows = read_sheet_rows(
+				&excel,
+				schema.as_ref(),
+				&read,
+				&path.sheet,
+				query.rows,
+				query.after,
+				query.limit,
+				None,
+				None,
+				language,
+				&filter,
+				&config,
+				&access,
+				&cancel,
+			)?;
+
+			base_rows
+				.into_iter()
+				.map(|row| {
+					let value = base_sheet
+						.with()
+						.language(language)
+						.subrow(row.row_id, row.subrow_id.unwrap_or(0))
+						.and_then(|base_row| base_row.field(&column))
+						.anyhow()?;
+
+					let joined = match join_row_id(value) {
+						Some(target_id) if target_exists(target_id)? => {
+							let fields = read.read(
+								&excel,
+								schema.as_ref(),
+								&query.target,
+								target_id,
+								0,
+								language,
+								&target_filter,
+								config.limit.depth,
+								&|sheet| access.allows(sheet),
+								&cancel,
+							)?;
+
+							Some(RowResult {
+								row_id: target_id,
+								subrow_id: None,
+								fields: ValueString(fields, language),
+							})
+						}
+						_ => None,
+					};
+
+					Ok(JoinResult { row, joined })
+				})
+				.collect::<Result<Vec<_>>>()
+		})
+		.await??;
+
+	let response = JoinResponse {
+		schema: response_specifier,
+		rows,
+	};
+
+	Ok(Encoded(format, response))
+}
+
+fn not_found(error: ironworks::Error) -> Error {
+	match error {
+		ironworks::Error::NotFound(ironworks::ErrorValue::Sheet(..)) => {
+			Error::NotFound(error.to_string())
+		}
+		other => Error::Other(other.into()),
+	}
+}
+
+/// Interpret the value of a join column as a row ID, if it can hold one.
+/// Negative values are used to signify the absence of a link.
+fn join_row_id(field: excel::Field) -> Option<u32> {
+	use excel::Field as F;
+	let value: i64 = match field {
+		F::I8(value) => value.into(),
+		F::I16(value) => value.into(),
+		F::I32(value) => value.into(),
+		F::I64(value) => value,
+		F::U8(value) => value.into(),
+		F::U16(value) => value.into(),
+		F::U32(value) => value.into(),
+		F::U64(value) => i64::try_from(value).ok()?,
+		F::String(_) | F::Bool(_) | F::F32(_) => return None,
+	};
+
+	u32::try_from(value).ok()
+}
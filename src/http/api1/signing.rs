@@ -0,0 +1,296 @@
+use std::{
+	sync::Arc,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use aide::{
+	axum::{routing::get_with, ApiRouter, IntoApiResponse},
+	transform::TransformOperation,
+};
+use axum::{
+	debug_handler,
+	extract::{Request, State},
+	middleware::Next,
+	response::{IntoResponse, Response},
+	Extension, Json, RequestExt,
+};
+use hmac::{Hmac, Mac};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{config::Validator, http::service, utility::glob::Glob};
+
+use super::{
+	error::{Error, Result},
+	extract::{ApiKey, Query},
+};
+
+/// Minimum length of the signing secret, in bytes.
+const SECRET_LENGTH_MIN: usize = 32;
+
+/// Default lifetime, in seconds, of a signed URL.
+const EXPIRY_DEFAULT: u64 = 60 * 60;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+	/// Secret URLs are signed with. Signed URLs are rejected if unset.
+	secret: Option<String>,
+
+	/// Routes, relative to the API root, that may only be requested with one of
+	/// `keys`, or by a signed URL, i.e. `/sheet/*/strings`.
+	#[serde(default)]
+	routes: Vec<Glob>,
+
+	/// API keys that may request restricted routes directly, and sign URLs for
+	/// them.
+	#[serde(default)]
+	keys: Vec<String>,
+
+	/// Maximum lifetime of a signed URL, in seconds.
+	#[serde(default = "default_max_expiry")]
+	max_expiry: u64,
+}
+
+fn default_max_expiry() -> u64 {
+	60 * 60 * 24
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			secret: None,
+			routes: vec![],
+			keys: vec![],
+			max_expiry: default_max_expiry(),
+		}
+	}
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		if let Some(secret) = &self.secret {
+			validator.check(
+				"secret",
+				secret.len() >= SECRET_LENGTH_MIN,
+				format_args!("must be at least {SECRET_LENGTH_MIN} bytes long"),
+			);
+		}
+
+		for route in &self.routes {
+			validator.check(
+				"routes",
+				route.as_str().starts_with('/'),
+				format_args!("route \"{}\" must start with /", route.as_str()),
+			);
+		}
+
+		validator.check("max_expiry", self.max_expiry > 0, "must be greater than 0");
+	}
+}
+
+/// Restricted routes, and the means of signing URLs for them.
+pub struct Signing {
+	secret: Option<String>,
+	routes: Vec<Glob>,
+	keys: Vec<String>,
+	max_expiry: u64,
+}
+
+impl Signing {
+	pub fn new(config: Config) -> Self {
+		Self {
+			secret: config.secret,
+			routes: config.routes,
+			keys: config.keys,
+			max_expiry: config.max_expiry,
+		}
+	}
+
+	fn restricted(&self, path: &str) -> bool {
+		self.routes.iter().any(|route| route.is_match(path))
+	}
+
+	fn trusted(&self, key: Option<&str>) -> bool {
+		key.map_or(false, |key| self.keys.iter().any(|trusted| trusted == key))
+	}
+
+	fn mac(&self, path: &str, query: &str) -> Option<Hmac<Sha256>> {
+		let secret = self.secret.as_ref()?;
+		let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+			.expect("HMAC accepts keys of any length");
+		mac.update(path.as_bytes());
+		mac.update(b"?");
+		mac.update(query.as_bytes());
+		Some(mac)
+	}
+
+	/// Sign a path and query, returning the signed path and its expiry.
+	fn sign(&self, path_and_query: &str, expiry: u64) -> Option<(String, u64)> {
+		let (path, query) = path_and_query
+			.split_once('?')
+			.unwrap_or((path_and_query, ""));
+
+		let expires = now() + expiry;
+		let query = match query {
+			"" => format!("expires={expires}"),
+			query => format!("{query}&expires={expires}"),
+		};
+
+		let signature = self.mac(path, &query)?.finalize().into_bytes();
+		let signature = signature
+			.iter()
+			.map(|byte| format!("{byte:02x}"))
+			.collect::<String>();
+
+		Some((format!("{path}?{query}&signature={signature}"), expires))
+	}
+
+	/// Check the signature of a request's path and query. The signature must be
+	/// the final query parameter, and covers everything preceding it.
+	fn verify(&self, path: &str, query: &str) -> bool {
+		let Some((signed, signature)) = query.rsplit_once("&signature=") else {
+			return false;
+		};
+
+		let expires = signed
+			.split('&')
+			.filter_map(|parameter| parameter.strip_prefix("expires="))
+			.last()
+			.and_then(|expires| expires.parse::<u64>().ok());
+		if expires.map_or(true, |expires| expires < now()) {
+			return false;
+		}
+
+		let (Some(signature), Some(mac)) = (decode_hex(signature), self.mac(path, signed)) else {
+			return false;
+		};
+
+		mac.verify_slice(&signature).is_ok()
+	}
+}
+
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|duration| duration.as_secs())
+		.unwrap_or(0)
+}
+
+fn decode_hex(input: &str) -> Option<Vec<u8>> {
+	if input.len() % 2 != 0 {
+		return None;
+	}
+
+	(0..input.len())
+		.step_by(2)
+		.map(|index| u8::from_str_radix(input.get(index..index + 2)?, 16).ok())
+		.collect()
+}
+
+/// Middleware rejecting requests to restricted routes, unless made with a
+/// trusted API key or a valid signed URL.
+pub async fn restrict(
+	State(signing): State<Arc<Signing>>,
+	mut request: Request,
+	next: Next,
+) -> Response {
+	let uri = request.uri().clone();
+	if !signing.restricted(uri.path()) {
+		return next.run(request).await;
+	}
+
+	let ApiKey(key) = match request.extract_parts::<ApiKey>().await {
+		Ok(key) => key,
+		Err(infallible) => match infallible {},
+	};
+	if signing.trusted(key.as_deref()) {
+		return next.run(request).await;
+	}
+
+	match uri.query() {
+		Some(query) if signing.verify(uri.path(), query) => next.run(request).await,
+		Some(query) if query.contains("signature=") => {
+			Error::Forbidden("signed URL is invalid or has expired".into()).into_response()
+		}
+		_ => Error::Forbidden(format!(
+			"route \"{}\" requires a trusted API key or signed URL",
+			uri.path()
+		))
+		.into_response(),
+	}
+}
+
+pub fn router(signing: Arc<Signing>) -> ApiRouter<service::State> {
+	ApiRouter::new()
+		.api_route("/", get_with(sign, sign_docs))
+		.layer(Extension(signing))
+}
+
+/// Query parameters accepted by the sign endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct SignQuery {
+	/// Path to sign, relative to the API root, including any query string. i.e.
+	/// `/sheet/Item/strings?format=csv`.
+	path: String,
+
+	/// Lifetime of the signed URL, in seconds. Defaults to one hour, and may not
+	/// exceed the maximum configured for this deployment.
+	expires_in: Option<u64>,
+}
+
+/// Response structure for the sign endpoint.
+#[derive(Serialize, JsonSchema)]
+struct SignResponse {
+	/// Signed path and query, relative to the API root.
+	url: String,
+
+	/// Time the URL expires, in seconds since the unix epoch.
+	expires: u64,
+}
+
+fn sign_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("sign a URL")
+		.description("Sign a URL granting temporary access to a restricted route, such as exports. Signed URLs may be handed out to clients without exposing an API key. Requires a trusted API key, and signing to be enabled on this deployment. The signed path and query must be used as-is - any modification invalidates the signature.")
+		.response_with::<200, Json<SignResponse>, _>(|response| {
+			response.example(SignResponse {
+				url: "/sheet/Item/strings?format=csv&expires=1700003600&signature=4f2e...".into(),
+				expires: 1700003600,
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn sign(
+	ApiKey(key): ApiKey,
+	Query(query): Query<SignQuery>,
+	Extension(signing): Extension<Arc<Signing>>,
+) -> Result<impl IntoApiResponse> {
+	if !signing.trusted(key.as_deref()) {
+		return Err(Error::Forbidden(
+			"signing URLs requires a trusted API key".into(),
+		));
+	}
+
+	if !query.path.starts_with('/') {
+		return Err(Error::Invalid("path must start with /".into()));
+	}
+	if query.path.contains("expires=") || query.path.contains("signature=") {
+		return Err(Error::Invalid("path is already signed".into()));
+	}
+
+	let expiry = query.expires_in.unwrap_or(EXPIRY_DEFAULT);
+	if expiry == 0 || expiry > signing.max_expiry {
+		return Err(Error::Invalid(format!(
+			"expires_in must be between 1 and {} seconds",
+			signing.max_expiry
+		)));
+	}
+
+	let (url, expires) = signing
+		.sign(&query.path, expiry)
+		.ok_or_else(|| Error::NotFound("URL signing is not enabled on this deployment".into()))?;
+
+	Ok(Json(SignResponse { url, expires }))
+}
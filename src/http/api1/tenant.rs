@@ -0,0 +1,275 @@
+use std::{
+	collections::HashMap,
+	convert::Infallible,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use aide::OperationIo;
+use axum::{
+	async_trait,
+	extract::{FromRequestParts, Request, State},
+	http::{header, request::Parts},
+	middleware::Next,
+	response::{IntoResponse, Response},
+	RequestExt,
+};
+use serde::Deserialize;
+
+use crate::{config::Validator, schema};
+
+use super::{error::Error, extract::ApiKey};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenantConfig {
+	/// API keys that identify requests as belonging to this tenant.
+	#[serde(default)]
+	keys: Vec<String>,
+
+	/// Hostnames that identify requests as belonging to this tenant. Keys take
+	/// precedence over hostnames.
+	#[serde(default)]
+	hosts: Vec<String>,
+
+	/// Version name used when a request does not specify one.
+	version: Option<String>,
+
+	/// Schema used when a request does not specify one.
+	schema: Option<schema::Specifier>,
+
+	/// Maximum request rate, shared across all requests for the tenant.
+	rate_limit: Option<RateLimitConfig>,
+
+	/// Path prefixes, relative to the API root, that the tenant may request. All
+	/// endpoints are allowed if unset.
+	endpoints: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RateLimitConfig {
+	/// Number of requests allowed per period.
+	requests: u32,
+
+	/// Length of the period, in seconds.
+	period: u64,
+}
+
+pub fn validate(tenants: &HashMap<String, TenantConfig>, validator: &mut Validator) {
+	let mut keys = HashMap::new();
+	let mut hosts = HashMap::new();
+
+	for (name, tenant) in tenants {
+		validator.scope(name, |validator| {
+			validator.check(
+				"keys",
+				!(tenant.keys.is_empty() && tenant.hosts.is_empty()),
+				"at least one key or host is required to identify the tenant",
+			);
+
+			for key in &tenant.keys {
+				if let Some(other) = keys.insert(key, name) {
+					validator.problem("keys", format!("key is already used by tenant {other:?}"));
+				}
+			}
+
+			for host in &tenant.hosts {
+				if let Some(other) = hosts.insert(host.to_ascii_lowercase(), name) {
+					validator.problem(
+						"hosts",
+						format!("host {host:?} is already used by tenant {other:?}"),
+					);
+				}
+			}
+
+			if let Some(rate_limit) = &tenant.rate_limit {
+				validator.check(
+					"rate_limit.requests",
+					rate_limit.requests > 0,
+					"must be at least 1",
+				);
+				validator.check(
+					"rate_limit.period",
+					rate_limit.period > 0,
+					"must be at least 1",
+				);
+			}
+
+			if let Some(endpoints) = &tenant.endpoints {
+				for endpoint in endpoints {
+					validator.check(
+						"endpoints",
+						endpoint.starts_with('/'),
+						format_args!("endpoint {endpoint:?} must start with /"),
+					);
+				}
+			}
+		});
+	}
+}
+
+/// Configuration applied to requests from a single tenant.
+#[derive(Debug)]
+pub struct Tenant {
+	pub name: String,
+	version: Option<String>,
+	schema: Option<schema::Specifier>,
+	endpoints: Option<Vec<String>>,
+	limiter: Option<RateLimiter>,
+}
+
+impl Tenant {
+	fn allows(&self, path: &str) -> bool {
+		let Some(endpoints) = &self.endpoints else {
+			return true;
+		};
+
+		// Prefixes only match on segment boundaries - `/sheet` should not allow `/sheets`.
+		endpoints.iter().any(|endpoint| {
+			let prefix = endpoint.trim_end_matches('/');
+			path.strip_prefix(prefix)
+				.map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+		})
+	}
+}
+
+#[derive(Debug)]
+struct RateLimiter {
+	requests: u32,
+	period: Duration,
+	window: Mutex<(Instant, u32)>,
+}
+
+impl RateLimiter {
+	fn new(config: RateLimitConfig) -> Self {
+		Self {
+			requests: config.requests,
+			period: Duration::from_secs(config.period),
+			window: Mutex::new((Instant::now(), 0)),
+		}
+	}
+
+	/// Count a request against the limit, returning the time until the limit
+	/// resets if it has been exceeded.
+	fn check(&self) -> Result<(), Duration> {
+		let mut window = self.window.lock().expect("poisoned");
+		let (start, count) = &mut *window;
+
+		let elapsed = start.elapsed();
+		if elapsed >= self.period {
+			*start = Instant::now();
+			*count = 0;
+		}
+
+		if *count >= self.requests {
+			return Err(self.period.saturating_sub(elapsed));
+		}
+
+		*count += 1;
+		Ok(())
+	}
+}
+
+/// Tenants configured for the API, indexed by their identifying keys and hosts.
+pub struct Tenants {
+	keys: HashMap<String, Arc<Tenant>>,
+	hosts: HashMap<String, Arc<Tenant>>,
+}
+
+impl Tenants {
+	pub fn new(config: HashMap<String, TenantConfig>) -> Self {
+		let mut keys = HashMap::new();
+		let mut hosts = HashMap::new();
+
+		for (name, config) in config {
+			let tenant = Arc::new(Tenant {
+				name,
+				version: config.version,
+				schema: config.schema,
+				endpoints: config.endpoints,
+				limiter: config.rate_limit.map(RateLimiter::new),
+			});
+
+			for key in config.keys {
+				keys.insert(key, tenant.clone());
+			}
+			for host in config.hosts {
+				hosts.insert(host.to_ascii_lowercase(), tenant.clone());
+			}
+		}
+
+		Self { keys, hosts }
+	}
+
+	fn find(&self, key: Option<&str>, host: Option<&str>) -> Option<Arc<Tenant>> {
+		key.and_then(|key| self.keys.get(key))
+			.or_else(|| host.and_then(|host| self.hosts.get(&host.to_ascii_lowercase())))
+			.cloned()
+	}
+}
+
+/// Middleware identifying the tenant of a request, and enforcing its restrictions.
+pub async fn resolve(
+	State(tenants): State<Arc<Tenants>>,
+	mut request: Request,
+	next: Next,
+) -> Response {
+	let ApiKey(key) = match request.extract_parts::<ApiKey>().await {
+		Ok(key) => key,
+		Err(infallible) => match infallible {},
+	};
+
+	let host = request
+		.headers()
+		.get(header::HOST)
+		.and_then(|value| value.to_str().ok())
+		.map(|host| host.split(':').next().unwrap_or(host));
+
+	let Some(tenant) = tenants.find(key.as_deref(), host) else {
+		return next.run(request).await;
+	};
+
+	if !tenant.allows(request.uri().path()) {
+		return Error::Forbidden(format!(
+			"endpoint is not available to tenant \"{}\"",
+			tenant.name
+		))
+		.into_response();
+	}
+
+	if let Some(Err(retry_after)) = tenant.limiter.as_ref().map(RateLimiter::check) {
+		return Error::TooManyRequests(format!(
+			"rate limit exceeded, retry in {} seconds",
+			retry_after.as_secs().max(1)
+		))
+		.into_response();
+	}
+
+	request.extensions_mut().insert(tenant);
+
+	next.run(request).await
+}
+
+/// Tenant of the current request, if it was identified as belonging to one.
+#[derive(OperationIo)]
+pub struct CurrentTenant(pub Option<Arc<Tenant>>);
+
+impl CurrentTenant {
+	/// Version name to use for the request, falling back to the tenant default.
+	pub fn version<'a>(&'a self, requested: Option<&'a str>) -> Option<&'a str> {
+		requested.or_else(|| self.0.as_ref()?.version.as_deref())
+	}
+
+	/// Schema to use for the request, falling back to the tenant default.
+	pub fn schema(&self, requested: Option<schema::Specifier>) -> Option<schema::Specifier> {
+		requested.or_else(|| self.0.as_ref()?.schema.clone())
+	}
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CurrentTenant {
+	type Rejection = Infallible;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		Ok(Self(parts.extensions.get::<Arc<Tenant>>().cloned()))
+	}
+}
@@ -0,0 +1,197 @@
+use std::{
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use axum::{
+	body::{self, Body, Bytes},
+	extract::{Request, State},
+	http::{header, HeaderMap, Method, Uri},
+	middleware::Next,
+	response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::config::Validator;
+
+use super::error::Error;
+
+/// Default maximum size, in bytes, of request bodies that may be forwarded.
+const BODY_LIMIT_DEFAULT: usize = 1024 * 1024;
+
+/// Default number of seconds the upstream's version list is cached for.
+const LIST_TTL_DEFAULT: u64 = 60;
+
+/// Headers describing a single connection, which must not be forwarded.
+const HOP_HEADERS: &[header::HeaderName] = &[
+	header::CONNECTION,
+	header::CONTENT_LENGTH,
+	header::HOST,
+	header::TRANSFER_ENCODING,
+	header::UPGRADE,
+];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+	/// Base URL of an upstream boilmaster's API, i.e. `https://example.com/api/1`.
+	/// Requests for versions unknown to this instance are forwarded to it, and
+	/// its version names are included in this instance's version list.
+	url: Option<String>,
+
+	/// Maximum size, in bytes, of request bodies that may be forwarded. Defaults
+	/// to 1MiB.
+	body_limit: Option<usize>,
+
+	/// Number of seconds the upstream's version list is cached for. Defaults to 60.
+	list_ttl: Option<u64>,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		if let Some(url) = &self.url {
+			validator.url("url", url);
+		}
+		if let Some(body_limit) = self.body_limit {
+			validator.check("body_limit", body_limit > 0, "must be at least 1");
+		}
+	}
+}
+
+/// Marker added to responses that failed due to a version unknown to this
+/// instance, which may be served by the upstream instead.
+#[derive(Clone, Copy)]
+pub struct UnknownVersion;
+
+pub struct Upstream {
+	url: Option<String>,
+	body_limit: usize,
+	list_ttl: Duration,
+	client: reqwest::Client,
+	names: Mutex<Option<(Instant, Vec<String>)>>,
+}
+
+impl Upstream {
+	pub fn new(config: Config) -> Self {
+		Self {
+			url: config.url.map(|url| url.trim_end_matches('/').to_string()),
+			body_limit: config.body_limit.unwrap_or(BODY_LIMIT_DEFAULT),
+			list_ttl: Duration::from_secs(config.list_ttl.unwrap_or(LIST_TTL_DEFAULT)),
+			client: reqwest::Client::new(),
+			names: Default::default(),
+		}
+	}
+
+	/// Get the names of the versions known to the upstream. Failures to reach the
+	/// upstream are logged, and treated as the upstream knowing no versions.
+	pub async fn names(&self) -> Vec<String> {
+		let Some(url) = &self.url else {
+			return vec![];
+		};
+
+		if let Some((fetched, names)) = &*self.names.lock().expect("poisoned") {
+			if fetched.elapsed() < self.list_ttl {
+				return names.clone();
+			}
+		}
+
+		let result = async {
+			self.client
+				.get(format!("{url}/version"))
+				.send()
+				.await?
+				.error_for_status()?
+				.json::<Vec<String>>()
+				.await
+		}
+		.await;
+
+		match result {
+			Ok(names) => {
+				*self.names.lock().expect("poisoned") = Some((Instant::now(), names.clone()));
+				names
+			}
+			Err(error) => {
+				tracing::warn!(?error, "failed to fetch upstream version list");
+				vec![]
+			}
+		}
+	}
+
+	async fn forward(
+		&self,
+		url: &str,
+		method: Method,
+		uri: &Uri,
+		mut headers: HeaderMap,
+		body: Bytes,
+	) -> reqwest::Result<Response> {
+		let path = uri.path_and_query().map_or("/", |path| path.as_str());
+		for name in HOP_HEADERS {
+			headers.remove(name);
+		}
+
+		let upstream_response = self
+			.client
+			.request(method, format!("{url}{path}"))
+			.headers(headers)
+			.body(body)
+			.send()
+			.await?;
+
+		let status = upstream_response.status();
+		let mut headers = upstream_response.headers().clone();
+		for name in HOP_HEADERS {
+			headers.remove(name);
+		}
+		let body = upstream_response.bytes().await?;
+
+		Ok((status, headers, body).into_response())
+	}
+}
+
+/// Middleware forwarding requests for versions unknown to this instance to the
+/// configured upstream. Requests are handled locally first - only those that
+/// fail due to an unknown version are forwarded.
+pub async fn forward(
+	State(upstream): State<Arc<Upstream>>,
+	mut request: Request,
+	next: Next,
+) -> Response {
+	request.extensions_mut().insert(upstream.clone());
+
+	let Some(url) = &upstream.url else {
+		return next.run(request).await;
+	};
+
+	// The body is needed for both the local attempt and the upstream, buffer it.
+	let (parts, body) = request.into_parts();
+	let body = match body::to_bytes(body, upstream.body_limit).await {
+		Ok(body) => body,
+		Err(_) => {
+			return Error::Invalid(format!(
+				"request body exceeds the limit of {} bytes",
+				upstream.body_limit
+			))
+			.into_response()
+		}
+	};
+
+	let method = parts.method.clone();
+	let uri = parts.uri.clone();
+	let headers = parts.headers.clone();
+
+	let response = next
+		.run(Request::from_parts(parts, Body::from(body.clone())))
+		.await;
+	if response.extensions().get::<UnknownVersion>().is_none() {
+		return response;
+	}
+
+	match upstream.forward(url, method, &uri, headers, body).await {
+		Ok(upstream_response) => upstream_response,
+		Err(error) => {
+			tracing::warn!(?error, "failed to forward request to upstream");
+			response
+		}
+	}
+}
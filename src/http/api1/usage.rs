@@ -0,0 +1,150 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aide::{
+	axum::{routing::get_with, ApiRouter, IntoApiResponse},
+	transform::TransformOperation,
+};
+use axum::{
+	debug_handler,
+	extract::{MatchedPath, RawPathParams, Request, State},
+	middleware::Next,
+	response::Response,
+	Json, RequestExt,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::http::service;
+
+use super::{
+	error::{Error, Result},
+	extract::{ApiKey, Query, API_KEY_HEADER},
+};
+
+/// Default range, in seconds, of usage returned when not otherwise specified.
+const RANGE_DEFAULT: u64 = 60 * 60 * 24;
+
+pub fn router() -> ApiRouter<service::State> {
+	ApiRouter::new().api_route("/", get_with(usage, usage_docs))
+}
+
+/// Middleware recording the route and sheet of requests made with an API key.
+pub async fn record(
+	State(analytics): State<service::Analytics>,
+	mut request: Request,
+	next: Next,
+) -> Response {
+	if analytics.enabled() {
+		let ApiKey(key) = match request.extract_parts::<ApiKey>().await {
+			Ok(key) => key,
+			Err(infallible) => match infallible {},
+		};
+		let route = request.extensions().get::<MatchedPath>().cloned();
+
+		if let (Some(key), Some(route)) = (key, route) {
+			let params = request.extract_parts::<RawPathParams>().await.ok();
+			let sheet = params
+				.as_ref()
+				.and_then(|params| params.iter().find(|(name, _)| *name == "sheet"))
+				.map(|(_, value)| value);
+			analytics.record(&key, route.as_str(), sheet);
+		}
+	}
+
+	next.run(request).await
+}
+
+/// Query parameters accepted by the usage endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct UsageQuery {
+	/// Start of the range to return usage for, in seconds since the unix epoch. Defaults to 24 hours before `until`.
+	since: Option<u64>,
+
+	/// End of the range to return usage for, in seconds since the unix epoch. Defaults to the current time.
+	until: Option<u64>,
+}
+
+/// Response structure for the usage endpoint.
+#[derive(Serialize, JsonSchema)]
+struct UsageResponse {
+	/// Request counts, per hour, route, and sheet.
+	usage: Vec<Usage>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Usage {
+	/// Start of the hour the requests were made within, in seconds since the unix epoch.
+	hour: u64,
+
+	/// Route that was requested.
+	route: String,
+
+	/// Sheet that was requested, for routes that read a sheet.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	sheet: Option<String>,
+
+	/// Number of requests made.
+	count: u64,
+}
+
+impl From<crate::analytics::Usage> for Usage {
+	fn from(usage: crate::analytics::Usage) -> Self {
+		Self {
+			hour: usage.hour,
+			route: usage.route,
+			sheet: usage.sheet,
+			count: usage.count,
+		}
+	}
+}
+
+fn usage_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("api key usage")
+		.description(format!("Request counts for the API key provided in the `{API_KEY_HEADER}` header, rolled up hourly. Only keys configured for analytics are recorded. Returns no usage if analytics are disabled on this deployment."))
+		.response_with::<200, Json<UsageResponse>, _>(|response| {
+			response.example(UsageResponse {
+				usage: vec![Usage {
+					hour: 1700000000,
+					route: "/api/1/sheet/:sheet".into(),
+					sheet: Some("Item".into()),
+					count: 42,
+				}],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn usage(
+	ApiKey(key): ApiKey,
+	Query(query): Query<UsageQuery>,
+	State(analytics): State<service::Analytics>,
+) -> Result<impl IntoApiResponse> {
+	let key = key.ok_or_else(|| {
+		Error::Invalid(format!(
+			"an API key must be provided in the `{API_KEY_HEADER}` header"
+		))
+	})?;
+	if analytics.enabled() && !analytics.tracks(&key) {
+		return Err(Error::Forbidden(
+			"usage is not recorded for this API key".into(),
+		));
+	}
+
+	let until = query.until.unwrap_or_else(|| {
+		SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |duration| duration.as_secs())
+	});
+	let since = query
+		.since
+		.unwrap_or_else(|| until.saturating_sub(RANGE_DEFAULT));
+
+	let usage = tokio::task::spawn_blocking(move || analytics.usage(&key, since, until))
+		.await
+		.map_err(anyhow::Error::from)??;
+
+	Ok(Json(UsageResponse {
+		usage: usage.into_iter().map(Usage::from).collect(),
+	}))
+}
@@ -33,9 +33,11 @@ fn valuestring_schema(_generator: &mut SchemaGenerator) -> Schema {
 	})
 }
 
-struct ValueReference<'a> {
-	value: &'a read::Value,
-	language: excel::Language,
+/// Borrowed counterpart to [`ValueString`], for serializing values that are
+/// shared rather than owned by the response.
+pub struct ValueReference<'a> {
+	pub value: &'a read::Value,
+	pub language: excel::Language,
 }
 
 impl Serialize for ValueReference<'_> {
@@ -73,8 +75,7 @@ impl ValueReference<'_> {
 	where
 		S: serde::Serializer,
 	{
-		let group = (id / 1000) * 1000;
-		let icon_path = format!("ui/icon/{group:0>6}/{id:0>6}");
+		let icon_path = read::icon_path(id);
 
 		let mut state = serializer.serialize_struct("Icon", 3)?;
 		state.serialize_field("id", &id)?;
@@ -98,17 +99,42 @@ impl ValueReference<'_> {
 				state.end()
 			}
 
+			read::Reference::Shallow {
+				value,
+				sheet,
+				row_id,
+				display,
+				circular,
+			} => {
+				// Binary formats write the field count up front - it must match the
+				// fields actually written, excluding those skipped.
+				let length = 3 + usize::from(display.is_some()) + usize::from(*circular);
+				let mut state = serializer.serialize_struct("Reference", length)?;
+				state.serialize_field("value", value)?;
+				state.serialize_field("sheet", sheet)?;
+				state.serialize_field("row_id", row_id)?;
+				self.serialize_display(&mut state, display)?;
+				match circular {
+					true => state.serialize_field("circular", circular)?,
+					false => state.skip_field("circular")?,
+				}
+				state.end()
+			}
+
 			read::Reference::Populated {
 				value,
 				sheet,
 				row_id,
+				display,
 				fields,
 			} => {
 				// TODO: this should be merged with RowResult for consistency
-				let mut state = serializer.serialize_struct("Reference", 4)?;
+				let length = 4 + usize::from(display.is_some());
+				let mut state = serializer.serialize_struct("Reference", length)?;
 				state.serialize_field("value", value)?;
 				state.serialize_field("sheet", sheet)?;
 				state.serialize_field("row_id", row_id)?;
+				self.serialize_display(&mut state, display)?;
 				state.serialize_field(
 					"fields",
 					&ValueReference {
@@ -121,6 +147,26 @@ impl ValueReference<'_> {
 		}
 	}
 
+	fn serialize_display<S>(
+		&self,
+		state: &mut S,
+		display: &Option<Box<read::Value>>,
+	) -> Result<(), S::Error>
+	where
+		S: SerializeStruct,
+	{
+		match display {
+			Some(value) => state.serialize_field(
+				"display",
+				&ValueReference {
+					value,
+					language: self.language,
+				},
+			),
+			None => state.skip_field("display"),
+		}
+	}
+
 	fn serialize_scalar<S>(&self, serializer: S, field: &excel::Field) -> Result<S::Ok, S::Error>
 	where
 		S: serde::Serializer,
@@ -177,3 +223,80 @@ impl ValueReference<'_> {
 		map.end()
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use pretty_assertions::assert_eq;
+
+	use super::*;
+
+	fn test_value(value: &read::Value) -> ValueReference<'_> {
+		ValueReference {
+			value,
+			language: excel::Language::English,
+		}
+	}
+
+	fn test_shallow(display: Option<read::Value>, circular: bool) -> read::Value {
+		read::Value::Reference(read::Reference::Shallow {
+			value: 1,
+			sheet: "Item".into(),
+			row_id: 1,
+			display: display.map(Box::new),
+			circular,
+		})
+	}
+
+	fn test_populated(display: Option<read::Value>) -> read::Value {
+		read::Value::Reference(read::Reference::Populated {
+			value: 1,
+			sheet: "Item".into(),
+			row_id: 1,
+			display: display.map(Box::new),
+			fields: Box::new(read::Value::Scalar(excel::Field::U32(2))),
+		})
+	}
+
+	fn test_cases() -> Vec<read::Value> {
+		let display = || read::Value::Scalar(excel::Field::U8(3));
+		vec![
+			test_shallow(None, false),
+			test_shallow(None, true),
+			test_shallow(Some(display()), false),
+			test_shallow(Some(display()), true),
+			test_populated(None),
+			test_populated(Some(display())),
+		]
+	}
+
+	#[test]
+	fn msgpack_round_trip() {
+		for value in test_cases() {
+			let expected = serde_json::to_value(test_value(&value)).unwrap();
+
+			let bytes = rmp_serde::to_vec_named(&test_value(&value)).unwrap();
+			let mut reader = bytes.as_slice();
+			let decoded = rmp_serde::from_read::<_, serde_json::Value>(&mut reader)
+				.expect("msgpack should decode");
+
+			assert_eq!(decoded, expected);
+			assert!(reader.is_empty(), "msgpack should have no trailing bytes");
+		}
+	}
+
+	#[test]
+	fn cbor_round_trip() {
+		for value in test_cases() {
+			let expected = serde_json::to_value(test_value(&value)).unwrap();
+
+			let mut bytes = vec![];
+			ciborium::into_writer(&test_value(&value), &mut bytes).unwrap();
+			let mut reader = bytes.as_slice();
+			let decoded = ciborium::from_reader::<serde_json::Value, _>(&mut reader)
+				.expect("cbor should decode");
+
+			assert_eq!(decoded, expected);
+			assert!(reader.is_empty(), "cbor should have no trailing bytes");
+		}
+	}
+}
@@ -1,13 +1,44 @@
+use std::{collections::BTreeMap, sync::Arc, time::SystemTime};
+
 use aide::{
-	axum::{routing::get_with, ApiRouter, IntoApiResponse},
+	axum::{
+		routing::{get_with, post_with},
+		ApiRouter, IntoApiResponse,
+	},
 	transform::TransformOperation,
 };
-use axum::{debug_handler, extract::State, Json};
+use axum::{debug_handler, extract::State, Extension, Json};
+use ironworks::{excel, file::exh};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-use crate::http::service;
+use crate::{
+	data,
+	http::service,
+	read,
+	utility::anyhow::Anyhow,
+	version::{self, VersionKey},
+};
+
+use super::{
+	acl::SheetAccess,
+	error::{Error, Result},
+	extract::{JsonBody, Path, Query},
+	upstream::Upstream,
+};
 
 pub fn router() -> ApiRouter<service::State> {
-	ApiRouter::new().api_route("/", get_with(versions, versions_docs))
+	ApiRouter::new()
+		.api_route("/", get_with(versions, versions_docs))
+		.api_route("/status", get_with(status, status_docs))
+		.api_route("/key", post_with(key_lookup, key_lookup_docs))
+		.api_route("/:version/key", get_with(key, key_docs))
+		.api_route("/:version/summary", get_with(summary, summary_docs))
+		.api_route("/:version/sheets", get_with(sheets, sheets_docs))
+		.api_route(
+			"/:version/validation",
+			get_with(validation, validation_docs),
+		)
 }
 
 fn versions_docs(operation: TransformOperation) -> TransformOperation {
@@ -20,8 +51,710 @@ fn versions_docs(operation: TransformOperation) -> TransformOperation {
 }
 
 #[debug_handler(state = service::State)]
-async fn versions(State(version): State<service::Version>) -> impl IntoApiResponse {
+async fn versions(
+	State(version): State<service::Version>,
+	upstream: Option<Extension<Arc<Upstream>>>,
+) -> impl IntoApiResponse {
 	let mut names = version.all_names();
+
+	// Versions held by an upstream are served through this instance as well.
+	if let Some(Extension(upstream)) = upstream {
+		names.extend(upstream.names().await);
+	}
+
 	names.sort_unstable();
+	names.dedup();
 	Json(names)
 }
+
+/// Response structure for the version status endpoint.
+#[derive(Serialize, JsonSchema)]
+struct StatusResponse {
+	/// Whether an update check is currently running. Known versions continue to
+	/// be served while updates are checked for and downloaded.
+	updating: bool,
+
+	/// Known versions, and whether each is ready to serve requests. Versions
+	/// persisted from a previous run become ready shortly after startup,
+	/// regardless of any update in progress.
+	versions: Vec<VersionStatusResponse>,
+
+	/// Unix timestamp, in seconds, of the most recent successful update check.
+	last_success: Option<u64>,
+
+	/// Details of the current run of failed updates. Omitted if the most recent
+	/// update succeeded.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	failure: Option<FailureResponse>,
+
+	/// Patch downloads currently in progress.
+	downloads: Vec<DownloadResponse>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct VersionStatusResponse {
+	/// Key of the version.
+	#[schemars(with = "String")]
+	key: VersionKey,
+
+	/// Names the version is known by.
+	names: Vec<String>,
+
+	/// Whether the version's data has been prepared, and can be read.
+	ready: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct FailureResponse {
+	/// Number of consecutive failed update attempts.
+	attempts: u32,
+
+	/// Description of the most recent error.
+	error: String,
+
+	/// Unix timestamp, in seconds, of the most recent failed attempt.
+	failed_at: u64,
+
+	/// Unix timestamp, in seconds, at which the update will next be attempted.
+	next_attempt: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DownloadResponse {
+	/// Name of the repository the patch belongs to.
+	repository: String,
+
+	/// Name of the patch.
+	patch: String,
+
+	/// Number of bytes downloaded so far.
+	bytes: u64,
+
+	/// Total size of the patch, in bytes.
+	total: u64,
+
+	/// Average download rate, in bytes per second.
+	rate: u64,
+
+	/// Estimated number of seconds until the download completes. Omitted until
+	/// the download has made progress.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	eta: Option<u64>,
+}
+
+fn status_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("version update status")
+		.description("Status of the version update process, including the readiness of each known version and the progress of any patch downloads. Known versions are served while updates run in the background. Failed updates are retried automatically with exponential backoff.")
+		.response_with::<200, Json<StatusResponse>, _>(|response| {
+			response.example(StatusResponse {
+				updating: true,
+				versions: vec![VersionStatusResponse {
+					key: VersionKey::from_patches(["2024.05.31.0000.0000"]),
+					names: vec!["latest".into(), "6.58".into()],
+					ready: true,
+				}],
+				last_success: Some(1718841600),
+				failure: Some(FailureResponse {
+					attempts: 2,
+					error: "failed to fetch patch list for repository 4e9a232b".into(),
+					failed_at: 1718845200,
+					next_attempt: 1718845260,
+				}),
+				downloads: vec![DownloadResponse {
+					repository: "4e9a232b".into(),
+					patch: "H2024.05.31.0000.0000a".into(),
+					bytes: 536870912,
+					total: 1572864000,
+					rate: 10485760,
+					eta: Some(98),
+				}],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn status(
+	State(data): State<service::Data>,
+	State(version): State<service::Version>,
+) -> impl IntoApiResponse {
+	let status = version.status();
+
+	let mut versions = version
+		.keys()
+		.into_iter()
+		.map(|key| {
+			let mut names = version.names(key).unwrap_or_default();
+			names.sort_unstable();
+			VersionStatusResponse {
+				key,
+				names,
+				ready: data.version(key).is_ok(),
+			}
+		})
+		.collect::<Vec<_>>();
+	versions.sort_unstable_by_key(|version| version.key);
+
+	Json(StatusResponse {
+		updating: status.updating,
+		versions,
+		last_success: status.last_success.map(unix_seconds),
+		failure: status.failure.map(FailureResponse::from),
+		downloads: status
+			.downloads
+			.into_iter()
+			.map(DownloadResponse::from)
+			.collect(),
+	})
+}
+
+impl From<version::UpdateFailure> for FailureResponse {
+	fn from(failure: version::UpdateFailure) -> Self {
+		Self {
+			attempts: failure.attempts,
+			error: failure.error,
+			failed_at: unix_seconds(failure.failed_at),
+			next_attempt: unix_seconds(failure.next_attempt),
+		}
+	}
+}
+
+impl From<version::DownloadProgress> for DownloadResponse {
+	fn from(download: version::DownloadProgress) -> Self {
+		Self {
+			repository: download.repository,
+			patch: download.patch,
+			bytes: download.bytes,
+			total: download.total,
+			rate: download.rate,
+			eta: download.eta.map(|eta| eta.as_secs()),
+		}
+	}
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+	time.duration_since(SystemTime::UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_secs())
+}
+
+/// Path variables accepted by endpoints for a single version.
+#[derive(Deserialize, JsonSchema)]
+struct VersionPath {
+	/// Name or key of the version.
+	version: String,
+}
+
+/// Response structure for the version key endpoint.
+#[derive(Serialize, JsonSchema)]
+struct KeyResponse {
+	/// Key of the version.
+	#[schemars(with = "String")]
+	key: VersionKey,
+
+	/// Latest patch of each repository in the version, in the order they
+	/// contribute to the key.
+	patches: Vec<KeyPatch>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct KeyPatch {
+	/// Name of the repository.
+	repository: String,
+
+	/// Name of the repository's latest patch.
+	patch: String,
+}
+
+fn key_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("explain a version key")
+		.description("List the patches a version's key is derived from. Keys are a SeaHash of the latest patch name of each repository, in order - the key lookup endpoint can be used to compute keys for arbitrary patch lists.")
+		.response_with::<200, Json<KeyResponse>, _>(|response| {
+			response.example(KeyResponse {
+				key: "0123456789abcdef".parse().expect("valid version key"),
+				patches: vec![KeyPatch {
+					repository: "4e9a232b".into(),
+					patch: "H2024.05.31.0000.0000a".into(),
+				}],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn key(
+	Path(path): Path<VersionPath>,
+	State(version): State<service::Version>,
+) -> Result<impl IntoApiResponse> {
+	let version_key = resolve_version(&version, &path.version)?;
+	let repositories = version
+		.version(version_key)
+		.ok_or_else(|| Error::NotFound(format!("unknown version \"{}\"", path.version)))?
+		.repositories;
+
+	Ok(Json(KeyResponse {
+		key: version_key,
+		patches: repositories
+			.into_iter()
+			.map(|repository| KeyPatch {
+				patch: repository.latest().name.clone(),
+				repository: repository.name,
+			})
+			.collect(),
+	}))
+}
+
+/// Request body accepted by the version key lookup endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct KeyLookupRequest {
+	/// Latest patch name of each repository, in repository order.
+	patches: Vec<String>,
+}
+
+/// Response structure for the version key lookup endpoint.
+#[derive(Serialize, JsonSchema)]
+struct KeyLookupResponse {
+	/// Key derived from the provided patches.
+	#[schemars(with = "String")]
+	key: VersionKey,
+
+	/// Whether a version with this key is known.
+	known: bool,
+
+	/// Names of the version, if it is known.
+	names: Vec<String>,
+}
+
+fn key_lookup_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("derive a version key")
+		.description("Compute the version key for a list of patch names, and check if it corresponds to a known version.")
+		.response_with::<200, Json<KeyLookupResponse>, _>(|response| {
+			response.example(KeyLookupResponse {
+				key: "0123456789abcdef".parse().expect("valid version key"),
+				known: true,
+				names: vec!["latest".into(), "7.0".into()],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn key_lookup(
+	State(version): State<service::Version>,
+	JsonBody(request): JsonBody<KeyLookupRequest>,
+) -> Result<impl IntoApiResponse> {
+	if request.patches.is_empty() {
+		return Err(Error::Invalid("at least one patch is required".into()));
+	}
+
+	let key = VersionKey::from_patches(request.patches.iter().map(String::as_str));
+	let names = version.names(key);
+
+	Ok(Json(KeyLookupResponse {
+		key,
+		known: names.is_some(),
+		names: names
+			.map(|mut names| {
+				names.sort_unstable();
+				names
+			})
+			.unwrap_or_default(),
+	}))
+}
+
+/// Response structure for the version summary endpoint.
+#[derive(Serialize, JsonSchema)]
+struct SummaryResponse {
+	/// Key of the version this summary describes.
+	#[schemars(with = "String")]
+	key: VersionKey,
+
+	/// Number of sheets present in the version.
+	sheet_count: usize,
+
+	/// Total number of rows across all sheets. Subrows are not counted individually.
+	row_count: u64,
+
+	/// Estimated size, in bytes, of fixed-size row data across all sheets and languages.
+	data_size: u64,
+
+	/// Languages provided by at least one sheet in the version.
+	languages: Vec<String>,
+
+	/// Per-sheet breakdown of the above, keyed by sheet name.
+	sheets: BTreeMap<String, SheetSummary>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SheetSummary {
+	/// Whether the sheet contains default rows, or subrows.
+	kind: &'static str,
+
+	/// Languages provided by this sheet.
+	languages: Vec<String>,
+
+	/// Number of columns in the sheet.
+	column_count: usize,
+
+	/// Number of rows in the sheet.
+	row_count: u32,
+
+	/// Size, in bytes, of the fixed-size portion of a single row.
+	row_size: u16,
+}
+
+fn summary_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("summarise a version")
+		.description("Aggregate metadata about the sheets present in a version, including row counts and languages. Summaries are built once per version and cached.")
+		.response_with::<200, Json<SummaryResponse>, _>(|response| {
+			response.example(SummaryResponse {
+				key: "0123456789abcdef".parse().expect("valid version key"),
+				sheet_count: 1,
+				row_count: 100,
+				data_size: 1600,
+				languages: vec!["en".into(), "ja".into()],
+				sheets: BTreeMap::from([(
+					"Item".into(),
+					SheetSummary {
+						kind: "default",
+						languages: vec!["en".into(), "ja".into()],
+						column_count: 4,
+						row_count: 100,
+						row_size: 8,
+					},
+				)]),
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn summary(
+	Path(path): Path<VersionPath>,
+	access: SheetAccess,
+	State(data): State<service::Data>,
+	State(version): State<service::Version>,
+) -> Result<impl IntoApiResponse> {
+	let version_key = resolve_version(&version, &path.version)?;
+	let data_version = data.version(version_key)?;
+
+	let summary = tokio::task::spawn_blocking(move || data_version.summary())
+		.await
+		.map_err(anyhow::Error::from)??;
+
+	let response = SummaryResponse {
+		key: version_key,
+		sheet_count: summary.sheets.len(),
+		row_count: summary.row_count(),
+		data_size: summary.data_size(),
+		languages: language_strings(summary.languages()),
+		sheets: summary
+			.sheets
+			.iter()
+			.filter(|sheet| access.allows(&sheet.name))
+			.map(|sheet| (sheet.name.clone(), SheetSummary::from(sheet)))
+			.collect(),
+	};
+
+	Ok(Json(response))
+}
+
+impl From<&data::SheetSummary> for SheetSummary {
+	fn from(sheet: &data::SheetSummary) -> Self {
+		Self {
+			kind: match sheet.kind {
+				exh::SheetKind::Subrows => "subrows",
+				_ => "default",
+			},
+			languages: language_strings(sheet.languages.iter().copied()),
+			column_count: sheet.column_count,
+			row_count: sheet.row_count,
+			row_size: sheet.row_size,
+		}
+	}
+}
+
+fn language_strings(languages: impl IntoIterator<Item = excel::Language>) -> Vec<String> {
+	let mut strings = languages
+		.into_iter()
+		.map(|language| read::LanguageString::from(language).to_string())
+		.collect::<Vec<_>>();
+	strings.sort_unstable();
+	strings
+}
+
+/// Query parameters accepted by the version sheets endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct SheetsQuery {
+	/// If `true`, include a hash of each sheet's data. Hashes are stable across
+	/// versions, and can be compared to detect which sheets have changed. The
+	/// first request for hashes of a version reads every sheet, and may be slow.
+	#[serde(default)]
+	hash: bool,
+}
+
+/// Response structure for the version sheets endpoint.
+#[derive(Serialize, JsonSchema)]
+struct SheetsResponse {
+	/// Key of the version the sheets are present in.
+	#[schemars(with = "String")]
+	key: VersionKey,
+
+	/// Sheets present in the version, ordered by name.
+	sheets: Vec<SheetEntry>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SheetEntry {
+	/// Name of the sheet.
+	name: String,
+
+	/// Hash of the sheet's data, as a hexadecimal string. Only present if
+	/// requested with the `hash` query parameter.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	hash: Option<String>,
+}
+
+fn sheets_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("list sheets in a version")
+		.description("List the sheets present in a version, optionally with a hash of each sheet's data. Hashes are built once per version and cached.")
+		.response_with::<200, Json<SheetsResponse>, _>(|response| {
+			response.example(SheetsResponse {
+				key: "0123456789abcdef".parse().expect("valid version key"),
+				sheets: vec![SheetEntry {
+					name: "Item".into(),
+					hash: Some("4f2e8a1c9b3d7e60".into()),
+				}],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn sheets(
+	Path(path): Path<VersionPath>,
+	Query(query): Query<SheetsQuery>,
+	access: SheetAccess,
+	State(data): State<service::Data>,
+	State(version): State<service::Version>,
+) -> Result<impl IntoApiResponse> {
+	let version_key = resolve_version(&version, &path.version)?;
+	let data_version = data.version(version_key)?;
+
+	let mut sheets = data
+		.blocking(move || -> Result<_> {
+			let sheets: Vec<SheetEntry> = match query.hash {
+				true => data_version
+					.sheet_hashes()?
+					.iter()
+					.map(|(name, hash)| SheetEntry {
+						name: name.clone(),
+						hash: Some(format!("{hash:016x}")),
+					})
+					.collect(),
+				false => {
+					let list = data_version.excel().list().anyhow()?;
+					let mut names = list
+						.iter()
+						.map(|name| name.into_owned())
+						.collect::<Vec<_>>();
+					names.sort();
+					names
+						.into_iter()
+						.map(|name| SheetEntry { name, hash: None })
+						.collect()
+				}
+			};
+			Ok(sheets)
+		})
+		.await??;
+	sheets.retain(|sheet| access.allows(&sheet.name));
+
+	Ok(Json(SheetsResponse {
+		key: version_key,
+		sheets,
+	}))
+}
+
+/// Response structure for the version validation endpoint.
+#[derive(Serialize, JsonSchema)]
+struct ValidationResponse {
+	/// Key of the version that was validated.
+	#[schemars(with = "String")]
+	key: VersionKey,
+
+	/// Canonical specifier of the schema the version was validated against.
+	schema: String,
+
+	/// Time validation completed, in seconds since the unix epoch.
+	completed: u64,
+
+	/// Number of sheets with a schema that were checked.
+	sheet_count: usize,
+
+	/// Problems found in the version, ordered by sheet.
+	issues: Vec<ValidationIssue>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ValidationIssue {
+	/// Name of the sheet the issue was found in.
+	sheet: String,
+
+	/// Path of the schema field the issue was found on, if specific to a field.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	field: Option<String>,
+
+	#[serde(flatten)]
+	kind: ValidationIssueKind,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ValidationIssueKind {
+	/// The sheet schema does not fit the columns of the sheet.
+	Structure { reason: String },
+
+	/// A reference or icon field is defined over a column that can't hold an ID.
+	ColumnKind {
+		/// Offset of the column.
+		column: u16,
+		/// Kind of the column.
+		found: String,
+	},
+
+	/// A reference targets a sheet that does not exist.
+	MissingSheet { target: String },
+
+	/// Reference values that do not match a row in any target sheet. Values of 0
+	/// or less are treated as empty, and are not checked.
+	DanglingReference {
+		/// Number of rows with a dangling value.
+		count: u64,
+		/// Number of dangling values beyond the last row of every target sheet.
+		out_of_range: u64,
+		/// A sample of rows with dangling values.
+		examples: Vec<DanglingExample>,
+	},
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DanglingExample {
+	row_id: u32,
+	subrow_id: u16,
+	value: i32,
+}
+
+impl From<&read::ValidationIssue> for ValidationIssue {
+	fn from(issue: &read::ValidationIssue) -> Self {
+		use read::IssueKind as IK;
+		let kind = match &issue.kind {
+			IK::Structure { reason } => ValidationIssueKind::Structure {
+				reason: reason.clone(),
+			},
+			IK::ColumnKind { column, found } => ValidationIssueKind::ColumnKind {
+				column: *column,
+				found: found.clone(),
+			},
+			IK::MissingSheet { target } => ValidationIssueKind::MissingSheet {
+				target: target.clone(),
+			},
+			IK::DanglingReference {
+				count,
+				out_of_range,
+				examples,
+			} => ValidationIssueKind::DanglingReference {
+				count: *count,
+				out_of_range: *out_of_range,
+				examples: examples
+					.iter()
+					.map(|&(row_id, subrow_id, value)| DanglingExample {
+						row_id,
+						subrow_id,
+						value,
+					})
+					.collect(),
+			},
+		};
+
+		Self {
+			sheet: issue.sheet.clone(),
+			field: issue.field.clone(),
+			kind,
+		}
+	}
+}
+
+fn validation_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("validation report for a version")
+		.description("Problems found when cross-checking the sheets of a version against the default schema, such as dangling references and fields over unexpected column kinds. Versions are validated in the background once prepared, if enabled on this deployment.")
+		.response_with::<200, Json<ValidationResponse>, _>(|response| {
+			response.example(ValidationResponse {
+				key: "0123456789abcdef".parse().expect("valid version key"),
+				schema: "exdschema@2:rev:0123456789abcdef".into(),
+				completed: 1700000000,
+				sheet_count: 1,
+				issues: vec![ValidationIssue {
+					sheet: "Item".into(),
+					field: Some("ItemUICategory".into()),
+					kind: ValidationIssueKind::DanglingReference {
+						count: 1,
+						out_of_range: 1,
+						examples: vec![DanglingExample {
+							row_id: 1,
+							subrow_id: 0,
+							value: 999,
+						}],
+					},
+				}],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn validation(
+	Path(path): Path<VersionPath>,
+	access: SheetAccess,
+	State(validation): State<service::Validation>,
+	State(version): State<service::Version>,
+) -> Result<impl IntoApiResponse> {
+	if !validation.enabled() {
+		return Err(Error::NotFound(
+			"validation is not enabled on this deployment".into(),
+		));
+	}
+
+	let version_key = resolve_version(&version, &path.version)?;
+	let report = validation.report(version_key).ok_or_else(|| {
+		Error::NotFound(format!(
+			"version \"{}\" has not been validated yet",
+			path.version
+		))
+	})?;
+
+	Ok(Json(ValidationResponse {
+		key: version_key,
+		schema: report.schema.to_string(),
+		completed: report.completed,
+		sheet_count: report.report.sheets,
+		issues: report
+			.report
+			.issues
+			.iter()
+			.filter(|issue| access.allows(&issue.sheet))
+			.map(ValidationIssue::from)
+			.collect(),
+	}))
+}
+
+// Versions in paths may be specified by either a name, or their raw key.
+fn resolve_version(version: &service::Version, name: &str) -> Result<VersionKey> {
+	if let Some(key) = version.resolve(Some(name)) {
+		return Ok(key);
+	}
+
+	name.parse::<VersionKey>()
+		.ok()
+		.filter(|key| version.version(*key).is_some())
+		.ok_or_else(|| Error::NotFound(format!("unknown version \"{name}\"")))
+}
@@ -0,0 +1,184 @@
+use aide::{
+	axum::{routing::get_with, ApiRouter, IntoApiResponse},
+	transform::TransformOperation,
+};
+use axum::{debug_handler, extract::State, Json};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{http::service, schema, utility::anyhow::Anyhow, version::VersionKey};
+
+use super::{
+	acl::SheetAccess,
+	error::{Error, Result},
+	extract::Path,
+	value::ValueReference,
+};
+
+pub fn router() -> ApiRouter<service::State> {
+	ApiRouter::new()
+		.api_route("/", get_with(list, list_docs))
+		.api_route("/:view", get_with(view, view_docs))
+}
+
+/// Summary of a configured view.
+#[derive(Serialize, JsonSchema)]
+struct ViewSummary {
+	/// Name of the view.
+	name: String,
+
+	/// Sheet the view is read from.
+	sheet: String,
+
+	/// Key of the version the view was last read from. Absent if the view has
+	/// not been refreshed yet.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
+	version: Option<VersionKey>,
+
+	/// Time the view was last refreshed, in seconds since the unix epoch.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	refreshed: Option<u64>,
+
+	/// Number of rows in the view, as of the last refresh.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	row_count: Option<usize>,
+}
+
+fn list_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("list views")
+		.description("List the materialized views configured on this deployment, and when each was last refreshed.")
+		.response_with::<200, Json<Vec<ViewSummary>>, _>(|response| {
+			response.example(vec![ViewSummary {
+				name: "mounts".into(),
+				sheet: "Mount".into(),
+				version: Some("0123456789abcdef".parse().expect("valid version key")),
+				refreshed: Some(1700000000),
+				row_count: Some(300),
+			}])
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn list(access: SheetAccess, State(view): State<service::View>) -> impl IntoApiResponse {
+	let summaries = view
+		.list()
+		.into_iter()
+		.filter(|summary| access.allows(&summary.sheet))
+		.map(|summary| ViewSummary {
+			name: summary.name,
+			sheet: summary.sheet,
+			version: summary.version,
+			refreshed: summary.refreshed,
+			row_count: summary.rows,
+		})
+		.collect::<Vec<_>>();
+
+	Json(summaries)
+}
+
+/// Path variables accepted by the view endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct ViewPath {
+	/// Name of the view.
+	view: String,
+}
+
+/// Response structure for the view endpoint.
+#[derive(Serialize, JsonSchema)]
+struct ViewResponse {
+	/// Name of the view.
+	name: String,
+
+	/// Key of the version the view was read from.
+	#[schemars(with = "String")]
+	version: VersionKey,
+
+	/// The canonical specifier for the schema used to read the view.
+	schema: schema::CanonicalSpecifier,
+
+	/// Time the view was last refreshed, in seconds since the unix epoch. Rows
+	/// reflect the game data as of this time.
+	refreshed: u64,
+
+	/// Rows in the view, in row order.
+	rows: Vec<ViewRow>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ViewRow {
+	/// ID of this row.
+	row_id: u32,
+
+	/// Subrow ID of this row, when relevant.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	subrow_id: Option<u16>,
+
+	/// Field values for this row, as of the last refresh.
+	fields: serde_json::Value,
+}
+
+fn view_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("read a view")
+		.description("Read the rows of a materialized view. Views are pre-computed from sheets in the background and refreshed periodically, so responses are served from memory and may trail the latest game data until the next refresh.")
+		.response_with::<200, Json<ViewResponse>, _>(|response| {
+			response.example(ViewResponse {
+				name: "mounts".into(),
+				version: "0123456789abcdef".parse().expect("valid version key"),
+				schema: schema::CanonicalSpecifier {
+					source: "source".into(),
+					version: "version".into(),
+				},
+				refreshed: 1700000000,
+				rows: vec![ViewRow {
+					row_id: 1,
+					subrow_id: None,
+					fields: serde_json::json!({ "Singular": "company chocobo" }),
+				}],
+			})
+		})
+}
+
+#[debug_handler(state = service::State)]
+async fn view(
+	Path(path): Path<ViewPath>,
+	access: SheetAccess,
+	State(view): State<service::View>,
+) -> Result<impl IntoApiResponse> {
+	let sheet = view
+		.sheet(&path.view)
+		.ok_or_else(|| Error::NotFound(format!("unknown view \"{}\"", path.view)))?;
+	access.check(sheet)?;
+
+	let materialized = view.get(&path.view).ok_or_else(|| {
+		Error::NotFound(format!("view \"{}\" has not been refreshed yet", path.view))
+	})?;
+
+	let rows = materialized
+		.rows
+		.iter()
+		.map(|row| -> Result<_> {
+			let fields = serde_json::to_value(ValueReference {
+				value: &row.value,
+				language: materialized.language,
+			})
+			.anyhow()?;
+
+			Ok(ViewRow {
+				row_id: row.row_id,
+				subrow_id: (row.subrow_id != 0).then_some(row.subrow_id),
+				fields,
+			})
+		})
+		.collect::<Result<Vec<_>>>()?;
+
+	Ok(Json(ViewResponse {
+		name: path.view,
+		version: materialized.version,
+		schema: materialized.schema.clone(),
+		refreshed: materialized.refreshed,
+		rows,
+	}))
+}
@@ -0,0 +1,341 @@
+use std::{
+	collections::HashMap,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use aide::{
+	axum::{routing::get_with, ApiRouter, IntoApiResponse},
+	transform::TransformOperation,
+};
+use anyhow::anyhow;
+use axum::{debug_handler, extract::State, Json};
+use ironworks::excel;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{http::service, read, schema, utility::eorzea};
+
+use super::{
+	acl::SheetAccess,
+	deadline::Cancellation,
+	error::{Error, Result},
+	extract::{Path, Query, VersionQuery},
+	filter::FilterString,
+	tenant::CurrentTenant,
+	value::ValueString,
+};
+
+/// Default number of weather periods included in a forecast.
+const FORECAST_COUNT_DEFAULT: u32 = 5;
+/// Maximum number of weather periods included in a forecast.
+const FORECAST_COUNT_MAX: u32 = 100;
+
+pub fn router() -> ApiRouter<service::State> {
+	ApiRouter::new().api_route("/:territory", get_with(weather, weather_docs))
+}
+
+/// Path variables accepted by the weather endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct WeatherPath {
+	/// Row ID of the TerritoryType to forecast the weather of.
+	territory: u32,
+}
+
+/// Query parameters accepted by the weather endpoint.
+#[derive(Deserialize, JsonSchema)]
+struct WeatherQuery {
+	/// Time to start the forecast at, in seconds since the unix epoch. Defaults
+	/// to the current time.
+	at: Option<u64>,
+
+	/// Number of weather periods to forecast.
+	count: Option<u32>,
+
+	/// Language to use for weather data.
+	language: Option<read::LanguageString>,
+
+	/// Schema that weather data should be read with.
+	schema: Option<schema::Specifier>,
+
+	/// Fields of the Weather sheet to read for each forecast weather.
+	fields: Option<FilterString>,
+}
+
+/// Response structure for the weather endpoint.
+#[derive(Serialize, JsonSchema)]
+struct WeatherResponse {
+	/// The canonical specifier for the schema used in this response.
+	#[schemars(with = "String")]
+	schema: schema::CanonicalSpecifier,
+
+	/// Row ID of the WeatherRate used by the territory.
+	weather_rate: u32,
+
+	/// Weather of each period, starting with the period containing the requested time.
+	forecast: Vec<WeatherPeriod>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct WeatherPeriod {
+	/// Start of the period, in seconds since the unix epoch.
+	start: u64,
+
+	/// End of the period, in seconds since the unix epoch.
+	end: u64,
+
+	/// Row ID of the Weather.
+	weather_id: u32,
+
+	/// Field values of the Weather, according to the current schema.
+	fields: ValueString,
+}
+
+fn weather_docs(operation: TransformOperation) -> TransformOperation {
+	operation
+		.summary("forecast weather")
+		.description("Forecast the weather of a territory, derived from its WeatherRate and the current Eorzean time. Weather changes every 8 Eorzean hours (1400 real seconds).")
+		.response_with::<200, Json<WeatherResponse>, _>(|response| {
+			response.example(WeatherResponse {
+				schema: schema::CanonicalSpecifier {
+					source: "source".into(),
+					version: "version".into(),
+				},
+				weather_rate: 14,
+				forecast: vec![WeatherPeriod {
+					start: 1700000400,
+					end: 1700001800,
+					weather_id: 2,
+					fields: ValueString(
+						read::Value::Struct(HashMap::from([(
+							read::StructKey {
+								name: "Name".into(),
+								language: excel::Language::English,
+							},
+							read::Value::Scalar(excel::Field::String("Fair Skies".into())),
+						)])),
+						excel::Language::English,
+					),
+				}],
+			})
+		})
+}
+
+#[allow(clippy::too_many_arguments)]
+#[debug_handler(state = service::State)]
+async fn weather(
+	Path(path): Path<WeatherPath>,
+	VersionQuery(version_key): VersionQuery,
+	Query(query): Query<WeatherQuery>,
+	tenant: CurrentTenant,
+	access: SheetAccess,
+	Cancellation(cancel): Cancellation,
+	State(data): State<service::Data>,
+	State(read): State<service::Read>,
+	State(schema_provider): State<service::Schema>,
+) -> Result<impl IntoApiResponse> {
+	for sheet in ["TerritoryType", "WeatherRate", "Weather"] {
+		access.check(sheet)?;
+	}
+
+	let excel = data.version(version_key)?.excel();
+
+	let language = query
+		.language
+		.map(excel::Language::from)
+		.unwrap_or_else(|| read.default_language());
+
+	let filter = query
+		.fields
+		.map(|fields| fields.to_filter(language))
+		.unwrap_or(Ok(read::Filter::All))?;
+	let territory_filter = "WeatherRate".parse::<FilterString>()?.to_filter(language)?;
+	let rate_filter = "Weather,Rate"
+		.parse::<FilterString>()?
+		.to_filter(language)?;
+
+	let count = query
+		.count
+		.unwrap_or(FORECAST_COUNT_DEFAULT)
+		.min(FORECAST_COUNT_MAX);
+	let at = query.at.unwrap_or_else(|| {
+		SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |duration| duration.as_secs())
+	});
+
+	// Reject forecasts that would run past the representable range of time,
+	// rather than wrapping partway through.
+	let first_start = eorzea::weather_period_start(at);
+	u64::from(count)
+		.checked_mul(eorzea::SECONDS_PER_WEATHER_PERIOD)
+		.and_then(|duration| first_start.checked_add(duration))
+		.ok_or_else(|| Error::Invalid("at is out of the supported range".into()))?;
+
+	let schema_specifier =
+		schema_provider.canonicalize(tenant.schema(query.schema), version_key)?;
+
+	let response_specifier = schema_specifier.clone();
+	let (weather_rate, forecast) = data
+		.blocking(move || -> Result<_> {
+			let schema = schema_provider.schema(schema_specifier)?;
+
+			let territory = read.read(
+				&excel,
+				schema.as_ref(),
+				"TerritoryType",
+				path.territory,
+				0,
+				language,
+				&territory_filter,
+				0,
+				&|sheet| access.allows(sheet),
+				&cancel,
+			)?;
+			let weather_rate = struct_field(&territory, "WeatherRate", language)
+				.and_then(reference_id)
+				.ok_or_else(|| {
+					Error::NotFound(format!("territory {} has no weather rate", path.territory))
+				})?;
+
+			let rate = read.read(
+				&excel,
+				schema.as_ref(),
+				"WeatherRate",
+				weather_rate,
+				0,
+				language,
+				&rate_filter,
+				0,
+				&|sheet| access.allows(sheet),
+				&cancel,
+			)?;
+			let rates = weather_rates(&rate, language).ok_or_else(|| {
+				Error::Other(anyhow!(
+					"weather rate {weather_rate} does not have weather and rate arrays"
+				))
+			})?;
+
+			let forecast = (0..u64::from(count))
+				.map(|index| {
+					let start = first_start + index * eorzea::SECONDS_PER_WEATHER_PERIOD;
+					let weather_id = forecast_weather(&rates, eorzea::forecast_target(start));
+
+					let fields = read.read(
+						&excel,
+						schema.as_ref(),
+						"Weather",
+						weather_id,
+						0,
+						language,
+						&filter,
+						0,
+						&|sheet| access.allows(sheet),
+						&cancel,
+					)?;
+
+					Ok(WeatherPeriod {
+						start,
+						end: start + eorzea::SECONDS_PER_WEATHER_PERIOD,
+						weather_id,
+						fields: ValueString(fields, language),
+					})
+				})
+				.collect::<Result<Vec<_>>>()?;
+
+			Ok((weather_rate, forecast))
+		})
+		.await??;
+
+	Ok(Json(WeatherResponse {
+		schema: response_specifier,
+		weather_rate,
+		forecast,
+	}))
+}
+
+/// Pair up the weather and rate arrays of a weather rate row, as read through
+/// the schema. Rates are the chance of each weather, in percent.
+fn weather_rates(row: &read::Value, language: excel::Language) -> Option<Vec<(u32, u8)>> {
+	let read::Value::Array(weathers) = struct_field(row, "Weather", language)? else {
+		return None;
+	};
+	let read::Value::Array(rates) = struct_field(row, "Rate", language)? else {
+		return None;
+	};
+
+	let pairs = weathers
+		.iter()
+		.zip(rates)
+		.map(|(weather, rate)| {
+			(
+				reference_id(weather).unwrap_or(0),
+				reference_id(rate)
+					.and_then(|rate| u8::try_from(rate).ok())
+					.unwrap_or(0),
+			)
+		})
+		.collect();
+
+	Some(pairs)
+}
+
+/// Pick the weather of a forecast target, being the first weather whose
+/// cumulative rate exceeds the target.
+fn forecast_weather(rates: &[(u32, u8)], target: u8) -> u32 {
+	let mut cumulative = 0u32;
+	rates
+		.iter()
+		.find(|(_, rate)| {
+			cumulative += u32::from(*rate);
+			u32::from(target) < cumulative
+		})
+		.map_or(0, |(weather_id, _)| *weather_id)
+}
+
+fn field_number(field: excel::Field) -> i64 {
+	use excel::Field as F;
+	match field {
+		F::I8(value) => value.into(),
+		F::I16(value) => value.into(),
+		F::I32(value) => value.into(),
+		F::I64(value) => value,
+		F::U8(value) => value.into(),
+		F::U16(value) => value.into(),
+		F::U32(value) => value.into(),
+		F::U64(value) => i64::try_from(value).unwrap_or(-1),
+		F::String(_) | F::Bool(_) | F::F32(_) => -1,
+	}
+}
+
+/// Get a field of a struct value, falling back to the unlocalised field.
+fn struct_field<'a>(
+	value: &'a read::Value,
+	name: &str,
+	language: excel::Language,
+) -> Option<&'a read::Value> {
+	let read::Value::Struct(fields) = value else {
+		return None;
+	};
+
+	[language, excel::Language::None]
+		.into_iter()
+		.find_map(|language| {
+			fields.get(&read::StructKey {
+				name: name.into(),
+				language,
+			})
+		})
+}
+
+fn reference_id(value: &read::Value) -> Option<u32> {
+	match value {
+		read::Value::Reference(reference) => match reference {
+			read::Reference::Scalar(value) => u32::try_from(*value).ok(),
+			read::Reference::Shallow { value, .. } | read::Reference::Populated { value, .. } => {
+				Some(*value)
+			}
+		},
+		read::Value::Scalar(field) => u32::try_from(field_number(field.clone())).ok(),
+		_ => None,
+	}
+}
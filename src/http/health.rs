@@ -1,4 +1,4 @@
-use axum::{debug_handler, extract::State, response::IntoResponse, routing::get, Router};
+use axum::{debug_handler, extract::State, response::IntoResponse, routing::get, Json, Router};
 use reqwest::StatusCode;
 
 use super::service;
@@ -7,6 +7,8 @@ pub fn router() -> Router<service::State> {
 	Router::new()
 		.route("/live", get(live))
 		.route("/ready", get(ready))
+		.route("/pool", get(pool))
+		.route("/cache", get(cache))
 }
 
 #[debug_handler]
@@ -27,3 +29,13 @@ async fn ready(
 		false => (StatusCode::SERVICE_UNAVAILABLE, "PENDING"),
 	}
 }
+
+#[debug_handler(state = service::State)]
+async fn pool(State(data): State<service::Data>) -> impl IntoResponse {
+	Json(data.pool_metrics())
+}
+
+#[debug_handler(state = service::State)]
+async fn cache(State(cache): State<service::Cache>) -> impl IntoResponse {
+	Json(cache.metrics())
+}
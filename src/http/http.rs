@@ -1,12 +1,15 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
 use anyhow::Result;
-use axum::Router;
+use axum::{extract::Request, middleware, Router};
+use sentry::integrations::tower::{NewSentryLayer, SentryHttpLayer};
 use serde::Deserialize;
 use tokio::net::TcpListener;
 use tokio_util::sync::CancellationToken;
 use tower_http::trace::TraceLayer;
 
+use crate::config::Validator;
+
 use super::{
 	admin,
 	api1,
@@ -24,15 +27,27 @@ pub struct Config {
 	port: u16,
 }
 
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.scope("admin", |validator| self.admin.validate(validator));
+		validator.scope("api1", |validator| self.api1.validate(validator));
+	}
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn serve(
 	cancel: CancellationToken,
 	config: Config,
+	analytics: service::Analytics,
 	asset: service::Asset,
+	cache: service::Cache,
 	data: service::Data,
 	read: service::Read,
 	schema: service::Schema,
 	// search: service::Search,
+	validation: service::Validation,
 	version: service::Version,
+	view: service::View,
 ) -> Result<()> {
 	let bind_address = SocketAddr::new(
 		config.address.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
@@ -41,20 +56,46 @@ pub async fn serve(
 
 	tracing::info!("http binding to {bind_address:?}");
 
+	let state = service::State {
+		analytics,
+		asset,
+		cache,
+		data,
+		read,
+		schema,
+		// search,
+		validation,
+		version,
+		view,
+	};
+
 	let router = Router::new()
 		.nest("/admin", admin::router(config.admin))
-		.nest("/api/1", api1::router(config.api1))
+		.nest(
+			"/api/1",
+			api1::router(config.api1).route_layer(middleware::from_fn_with_state(
+				state.clone(),
+				api1::record_usage,
+			)),
+		)
 		.nest("/health", health::router())
 		// .nest("/search", search::router())
-		.layer(TraceLayer::new_for_http())
-		.with_state(service::State {
-			asset,
-			data,
-			read,
-			schema,
-			// search,
-			version,
-		});
+		.layer(
+			// Matches the default span, with room for the version key of the request.
+			TraceLayer::new_for_http().make_span_with(|request: &Request| {
+				tracing::debug_span!(
+					"request",
+					method = %request.method(),
+					uri = %request.uri(),
+					version = ?request.version(),
+					version_key = tracing::field::Empty,
+				)
+			}),
+		)
+		// Give each request its own Sentry scope, carrying the request's details.
+		.layer(SentryHttpLayer::new())
+		.layer(NewSentryLayer::<Request>::new_from_top())
+		.with_state(state);
 
 	let listener = TcpListener::bind(bind_address).await.unwrap();
 	axum::serve(listener, router)
@@ -3,27 +3,39 @@ use std::sync::Arc;
 use axum::extract::FromRef;
 
 use crate::{
+	analytics,
 	asset,
+	cache,
 	data,
 	read,
 	schema,
 	// search,
+	validation,
 	version,
+	view,
 };
 
+pub type Analytics = Arc<analytics::Analytics>;
 pub type Asset = Arc<asset::Service>;
+pub type Cache = Arc<cache::Caches>;
 pub type Data = Arc<data::Data>;
 pub type Read = Arc<read::Read>;
 pub type Schema = Arc<schema::Provider>;
 // pub type Search = Arc<search::Search>;
+pub type Validation = Arc<validation::Validation>;
 pub type Version = Arc<version::Manager>;
+pub type View = Arc<view::Views>;
 
 #[derive(Clone, FromRef)]
 pub struct State {
+	pub analytics: Analytics,
 	pub asset: Asset,
+	pub cache: Cache,
 	pub data: Data,
 	pub read: Read,
 	pub schema: Schema,
 	// pub search: Search,
+	pub validation: Validation,
 	pub version: Version,
+	pub view: View,
 }
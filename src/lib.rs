@@ -1,12 +1,25 @@
 #![allow(clippy::module_inception)]
 
-// TODO: probably take these non-public and expose an explicit interface here? or is it not worth it given this is the entry point
+//! Library consumers wanting to read game data without the HTTP server should
+//! start with [`Stack`], which assembles the required services from configuration.
+//! The full server, HTTP API included, is available as [`Server`].
+
+pub mod analytics;
 pub mod asset;
+pub mod cache;
+pub mod config;
 pub mod data;
+pub mod error;
 pub mod http;
 pub mod read;
 pub mod schema;
 // pub mod search;
+pub mod server;
+pub mod stack;
 pub mod tracing;
 mod utility;
+pub mod validation;
 pub mod version;
+pub mod view;
+
+pub use {server::Server, stack::Stack};
@@ -1,42 +1,42 @@
-use std::sync::Arc;
+use std::env;
 
 use anyhow::Context;
 use boilmaster::{
-	asset,
-	data,
-	http,
-	read,
-	schema,
-	// search,
+	config::{Problems, Validator},
+	server::{Config, Server},
 	tracing,
-	version,
 };
 use figment::{
 	providers::{Env, Format, Toml},
+	value::{Uncased, UncasedStr},
 	Figment,
 };
-use futures::TryFutureExt;
-use serde::Deserialize;
 use tokio::signal;
 use tokio_util::sync::CancellationToken;
 
-#[derive(Debug, Deserialize)]
-struct Config {
-	// tracing: tracing::Config, - read individually.
-	http: http::Config,
-	read: read::Config,
-	version: version::Config,
-	schema: schema::Config,
-	// search: search::Config,
-}
+const CONFIG_PATH: &str = "boilmaster.toml";
+const OVERRIDE_PATH_DEFAULT: &str = "boilmaster.local.toml";
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-	// Prepare the configuration hierarchy.
-	// TODO: is it worth having a cli flag to specify the config path or is that just immense overkill?
-	let figment = Figment::new()
-		.merge(Toml::file("boilmaster.toml"))
-		.merge(Env::prefixed("BM_").split("_"));
+	let figment = figment();
+
+	// `check-config` reports every configuration problem, then exits without
+	// starting any services.
+	if std::env::args().nth(1).as_deref() == Some("check-config") {
+		let mut validator = Validator::default();
+		if let Some(config) = validator.extract::<tracing::Config>(&figment, "tracing") {
+			validator.scope("tracing", |validator| config.validate(validator));
+		}
+		match load_config(&figment, validator) {
+			Ok(_) => println!("configuration ok"),
+			Err(problems) => {
+				eprint!("{problems}");
+				std::process::exit(1);
+			}
+		}
+		return Ok(());
+	}
 
 	// Initialise tracing before getting too far into bootstrapping the rest of
 	// the application. We extract only the tracing configuration first, so that
@@ -45,52 +45,93 @@ async fn main() -> anyhow::Result<()> {
 	let tracing_config = figment
 		.extract_inner::<tracing::Config>("tracing")
 		.context("failed to initialize tracing config")?;
-	tracing::init(tracing_config);
+	let _tracing_guard = tracing::init(tracing_config).context("failed to initialize tracing")?;
 
 	// Load the rest of the configuration.
-	let config = figment
-		.extract::<Config>()
-		.context("failed to extract config")?;
-
-	let version = Arc::new(
-		version::Manager::new(config.version).context("failed to create version manager")?,
-	);
-	let data = Arc::new(data::Data::new());
-	let asset = Arc::new(asset::Service::new(data.clone()));
-	let read = Arc::new(read::Read::new(config.read));
-	let schema = Arc::new(
-		schema::Provider::new(config.schema, data.clone())
-			.context("failed to create schema provider")?,
-	);
-	// let search = Arc::new(search::Search::new(config.search, data.clone()).expect("TODO"));
+	let config = load_config(&figment, Validator::default()).context("failed to load config")?;
 
 	// Set up a cancellation token that will fire when a shutdown signal is recieved.
 	let shutdown_token = shutdown_token();
 
-	tokio::try_join!(
-		version.start(shutdown_token.clone()),
-		data.start(shutdown_token.clone(), &version)
-			.map_err(anyhow::Error::from),
-		schema
-			.start(shutdown_token.clone())
-			.map_err(anyhow::Error::from),
-		// search
-		// 	.start(shutdown_token.child_token())
-		// 	.map_err(anyhow::Error::from),
-		http::serve(
-			shutdown_token,
-			config.http,
-			asset,
-			data.clone(),
-			read,
-			schema.clone(),
-			// search.clone(),
-			version.clone(),
-		),
+	Server::builder(config).serve(shutdown_token).await
+}
+
+fn figment() -> Figment {
+	// Prepare the configuration hierarchy. The optional override file allows
+	// deployments to change settings without modifying the base config, and can
+	// be moved with `BOILMASTER_CONFIG` to suit mounted volumes.
+	let override_path =
+		env::var("BOILMASTER_CONFIG").unwrap_or_else(|_| OVERRIDE_PATH_DEFAULT.to_string());
+
+	Figment::new()
+		.merge(Toml::file(CONFIG_PATH))
+		.merge(Toml::file(override_path))
+		.merge(Env::prefixed("BM_").map(env_key))
+}
+
+/// Map an environment variable name to its configuration path. Underscores
+/// separate path segments, while a doubled underscore stands for an underscore
+/// within a key, i.e. `BM_VERSION_PATCH_USER__AGENT` -> `version.patch.user_agent`.
+fn env_key(key: &UncasedStr) -> Uncased<'_> {
+	key.as_str()
+		.split("__")
+		.map(|part| part.replace('_', "."))
+		.collect::<Vec<_>>()
+		.join("_")
+		.into()
+}
+
+fn load_config(figment: &Figment, mut validator: Validator) -> Result<Config, Problems> {
+	// Sections are extracted individually so that errors in each are reported
+	// together. Sections that existing configs may predate fall back to defaults.
+	let analytics = validator.extract_or_default(figment, "analytics");
+	let asset = validator.extract_or_default(figment, "asset");
+	let cache = validator.extract_or_default(figment, "cache");
+	let data = validator.extract_or_default(figment, "data");
+	let http = validator.extract(figment, "http");
+	let read = validator.extract(figment, "read");
+	let version = validator.extract(figment, "version");
+	let schema = validator.extract(figment, "schema");
+	let validation = validator.extract_or_default(figment, "validation");
+	let view = validator.extract_or_default(figment, "view");
+
+	let (
+		Some(analytics),
+		Some(asset),
+		Some(cache),
+		Some(data),
+		Some(http),
+		Some(read),
+		Some(version),
+		Some(schema),
+		Some(validation),
+		Some(view),
+	) = (
+		analytics, asset, cache, data, http, read, version, schema, validation, view,
 	)
-	.context("failed to start server")?;
+	else {
+		// Extraction failures are recorded, there's nothing further to validate.
+		return Err(validator
+			.finish()
+			.expect_err("failed extraction should record a problem"));
+	};
+
+	let config = Config {
+		analytics,
+		asset,
+		cache,
+		data,
+		http,
+		read,
+		version,
+		schema,
+		validation,
+		view,
+	};
+	config.validate(&mut validator);
+	validator.finish()?;
 
-	Ok(())
+	Ok(config)
 }
 
 fn shutdown_token() -> CancellationToken {
@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+
+use ironworks::excel::Language;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Locale-aware ordering of strings in a game language.
+///
+/// Strings are compared in three levels - base letters first, then accents,
+/// then case (and for Japanese, script). This is a deliberately small subset of
+/// the Unicode collation algorithm, covering the rules that matter for the
+/// languages the game ships with. Chinese text falls back to code point order.
+#[derive(Debug, Clone, Copy)]
+pub struct Collator {
+	language: Language,
+}
+
+impl Collator {
+	pub fn new(language: Language) -> Self {
+		Self { language }
+	}
+
+	/// Build a key for the given string. Keys of the same collator order the same
+	/// as their strings would under `compare`, and are cheaper to sort repeatedly.
+	pub fn key(&self, value: &str) -> CollationKey {
+		let mut key = CollationKey {
+			primary: Vec::with_capacity(value.len()),
+			secondary: Vec::new(),
+			tertiary: Vec::new(),
+			original: value.to_string(),
+		};
+
+		// Decomposing splits accented letters into their base letter followed by
+		// combining marks, which are then weighted at the secondary level.
+		for character in value.nfd() {
+			if is_combining_mark(character) {
+				if let Some(marks) = key.secondary.last_mut() {
+					marks.push(character);
+				}
+				continue;
+			}
+
+			key.secondary.push(Vec::new());
+			key.tertiary.push(self.variant(character));
+
+			match (self.language, character) {
+				(Language::German, 'ß' | 'ẞ') => key.primary.extend(['s', 's']),
+				(Language::Japanese, character) => key.primary.push(fold_kana(character)),
+				(_, character) => key.primary.extend(character.to_lowercase()),
+			}
+		}
+
+		key
+	}
+
+	pub fn compare(&self, a: &str, b: &str) -> Ordering {
+		self.key(a).cmp(&self.key(b))
+	}
+
+	/// Tertiary weight of a character. Lowercase sorts before uppercase, and in
+	/// Japanese, hiragana before katakana.
+	fn variant(&self, character: char) -> u8 {
+		let case = u8::from(character.is_uppercase());
+		let script = match self.language {
+			Language::Japanese => u8::from(is_katakana(character)),
+			_ => 0,
+		};
+		(script << 1) | case
+	}
+}
+
+/// Sort key for a string, built by a `Collator`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CollationKey {
+	primary: Vec<char>,
+	secondary: Vec<Vec<char>>,
+	tertiary: Vec<u8>,
+	// Strings that are equal at every level are ordered by code point, keeping
+	// the ordering total.
+	original: String,
+}
+
+const KATAKANA_OFFSET: u32 = 'ア' as u32 - 'あ' as u32;
+
+fn is_katakana(character: char) -> bool {
+	('ァ'..='ヶ').contains(&character)
+}
+
+/// Fold katakana onto the equivalent hiragana, so both scripts share primary weights.
+fn fold_kana(character: char) -> char {
+	if !is_katakana(character) {
+		return character;
+	}
+
+	char::from_u32(character as u32 - KATAKANA_OFFSET).unwrap_or(character)
+}
+
+#[cfg(test)]
+mod test {
+	use pretty_assertions::assert_eq;
+
+	use super::*;
+
+	fn sorted(language: Language, values: &[&'static str]) -> Vec<&'static str> {
+		let collator = Collator::new(language);
+		let mut values = values.to_vec();
+		values.sort_by(|a, b| collator.compare(a, b));
+		values
+	}
+
+	#[test]
+	fn english_orders_case_after_letters() {
+		assert_eq!(
+			sorted(Language::English, &["banana", "Apple", "cherry", "apple"]),
+			vec!["apple", "Apple", "banana", "cherry"]
+		);
+	}
+
+	#[test]
+	fn german_orders_umlauts_and_eszett() {
+		assert_eq!(
+			sorted(
+				Language::German,
+				&["Zucker", "Straße", "Birne", "Äpfel", "Strasze", "Apfel"]
+			),
+			vec!["Apfel", "Äpfel", "Birne", "Straße", "Strasze", "Zucker"]
+		);
+	}
+
+	#[test]
+	fn french_orders_accents_after_letters() {
+		assert_eq!(
+			sorted(Language::French, &["élan", "Éclair", "ecole", "eclair"]),
+			vec!["eclair", "Éclair", "ecole", "élan"]
+		);
+	}
+
+	#[test]
+	fn japanese_folds_kana() {
+		assert_eq!(
+			sorted(Language::Japanese, &["が", "カ", "ア", "か", "あ"]),
+			vec!["あ", "ア", "か", "カ", "が"]
+		);
+	}
+
+	#[test]
+	fn chinese_orders_by_code_point() {
+		assert_eq!(
+			sorted(Language::ChineseSimplified, &["二", "中", "一"]),
+			vec!["一", "中", "二"]
+		);
+	}
+
+	#[test]
+	fn distinct_strings_never_compare_equal() {
+		let collator = Collator::new(Language::German);
+		assert_eq!(collator.compare("Straße", "Straße"), Ordering::Equal);
+		assert_ne!(collator.compare("Straße", "Strasse"), Ordering::Equal);
+	}
+}
@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, HashMap};
+
+use ironworks::excel;
+use ironworks_schema as schema;
+use tokio_util::sync::CancellationToken;
+
+use super::{
+	error::Result,
+	filter::Filter,
+	language::LanguageString,
+	read::Read,
+	value::{Reference, Value},
+};
+
+/// Structural differences between a sample of rows read under two schemas.
+#[derive(Debug)]
+pub struct SchemaDiff {
+	/// Number of rows that were sampled.
+	pub rows: usize,
+	/// Field paths only present under the target schema.
+	pub added: Vec<String>,
+	/// Field paths only present under the base schema.
+	pub removed: Vec<String>,
+	/// Field paths whose value shape differs between the schemas.
+	pub changed: Vec<FieldChange>,
+	/// Removed fields whose values match exactly one added field across the
+	/// sample, and have likely been renamed or moved.
+	pub moved: Vec<FieldMove>,
+}
+
+#[derive(Debug)]
+pub struct FieldChange {
+	pub path: String,
+	pub from: &'static str,
+	pub to: &'static str,
+}
+
+#[derive(Debug)]
+pub struct FieldMove {
+	pub from: String,
+	pub to: String,
+}
+
+/// Shape and sampled values of a single field path.
+struct FieldSample {
+	kind: &'static str,
+	/// Values of leaf fields, keyed by the index of the row they were read from.
+	values: BTreeMap<usize, String>,
+}
+
+type FieldSamples = BTreeMap<String, FieldSample>;
+
+impl Read {
+	/// Read a sample of rows from a sheet under two schemas, and report the
+	/// structural differences between the results. Rows are sampled evenly across
+	/// the sheet. References are not followed, and are only resolved to sheets
+	/// that `access` permits.
+	#[allow(clippy::too_many_arguments)]
+	pub fn compare_schemas(
+		&self,
+		excel: &excel::Excel,
+		base: &dyn schema::Schema,
+		target: &dyn schema::Schema,
+		sheet_name: &str,
+		sample: usize,
+		language: excel::Language,
+		access: &dyn Fn(&str) -> bool,
+		cancel: &CancellationToken,
+	) -> Result<SchemaDiff> {
+		let sheet = excel.sheet(sheet_name)?;
+
+		let languages = sheet.languages()?;
+		let row_language = [language, excel::Language::None]
+			.into_iter()
+			.find(|language| languages.contains(language))
+			.unwrap_or(language);
+
+		let rows = sheet
+			.with()
+			.language(row_language)
+			.iter()
+			.map(|row| (row.row_id(), row.subrow_id()))
+			.collect::<Vec<_>>();
+		let step = (rows.len() / sample.max(1)).max(1);
+		let rows = rows.into_iter().step_by(step).take(sample);
+
+		let mut base_fields = FieldSamples::new();
+		let mut target_fields = FieldSamples::new();
+		let mut sampled = 0;
+
+		for (index, (row_id, subrow_id)) in rows.enumerate() {
+			for (schema, fields) in [(base, &mut base_fields), (target, &mut target_fields)] {
+				let value = self.read(
+					excel,
+					schema,
+					sheet_name,
+					row_id,
+					subrow_id,
+					language,
+					&Filter::All,
+					0,
+					access,
+					cancel,
+				)?;
+				collect_fields(&value, String::new(), index, language, fields);
+			}
+			sampled += 1;
+		}
+
+		Ok(diff_fields(sampled, base_fields, target_fields))
+	}
+}
+
+fn collect_fields(
+	value: &Value,
+	path: String,
+	index: usize,
+	language: excel::Language,
+	fields: &mut FieldSamples,
+) {
+	let (kind, leaf) = match value {
+		Value::Array(..) => ("array", None),
+		Value::Icon(id) => ("icon", Some(id.to_string())),
+		Value::Reference(Reference::Scalar(value)) => ("reference", Some(value.to_string())),
+		Value::Reference(Reference::Shallow { value, .. } | Reference::Populated { value, .. }) => {
+			("reference", Some(value.to_string()))
+		}
+		Value::Scalar(field) => ("scalar", Some(format!("{field:?}"))),
+		Value::Struct(..) => ("struct", None),
+	};
+
+	// The root struct is the row itself, and has no path of its own.
+	if !path.is_empty() {
+		let sample = fields.entry(path.clone()).or_insert_with(|| FieldSample {
+			kind,
+			values: BTreeMap::new(),
+		});
+		if let Some(leaf) = leaf {
+			sample.values.insert(index, leaf);
+		}
+	}
+
+	match value {
+		Value::Array(values) => {
+			for (offset, value) in values.iter().enumerate() {
+				collect_fields(value, format!("{path}[{offset}]"), index, language, fields);
+			}
+		}
+		Value::Struct(entries) => {
+			for (key, value) in entries {
+				let name = match key.language == language || key.language == excel::Language::None {
+					true => key.name.clone(),
+					false => format!("{}@{}", key.name, LanguageString::from(key.language)),
+				};
+				let child = match path.is_empty() {
+					true => name,
+					false => format!("{path}.{name}"),
+				};
+				collect_fields(value, child, index, language, fields);
+			}
+		}
+		_ => {}
+	}
+}
+
+fn diff_fields(rows: usize, mut base: FieldSamples, mut target: FieldSamples) -> SchemaDiff {
+	let mut changed = vec![];
+	let shared = base
+		.keys()
+		.filter(|path| target.contains_key(*path))
+		.cloned()
+		.collect::<Vec<_>>();
+	for path in shared {
+		let from = base.remove(&path).expect("shared path").kind;
+		let to = target.remove(&path).expect("shared path").kind;
+		if from != to {
+			changed.push(FieldChange { path, from, to });
+		}
+	}
+
+	// Anything remaining is only present in one of the schemas. Match up leaves
+	// with identical values - only unambiguous matches are considered moves, as
+	// columns of zeroes and similar would otherwise match each other freely.
+	let mut matches = HashMap::<&BTreeMap<usize, String>, (Vec<&str>, Vec<&str>)>::new();
+	for (path, sample) in &base {
+		if !sample.values.is_empty() {
+			matches.entry(&sample.values).or_default().0.push(path);
+		}
+	}
+	for (path, sample) in &target {
+		if let Some(entry) = matches.get_mut(&sample.values) {
+			entry.1.push(path);
+		}
+	}
+
+	let mut moved = matches
+		.into_values()
+		.filter_map(|(from, to)| match (from.as_slice(), to.as_slice()) {
+			([from], [to]) => Some(FieldMove {
+				from: from.to_string(),
+				to: to.to_string(),
+			}),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	moved.sort_unstable_by(|a, b| a.from.cmp(&b.from));
+
+	for field_move in &moved {
+		base.remove(&field_move.from);
+		target.remove(&field_move.to);
+	}
+
+	SchemaDiff {
+		rows,
+		added: target.into_keys().collect(),
+		removed: base.into_keys().collect(),
+		changed,
+		moved,
+	}
+}
@@ -0,0 +1,421 @@
+use std::{collections::HashMap, str::FromStr};
+
+use ironworks::excel;
+use nohash_hasher::IntMap;
+use nom::{
+	branch::alt,
+	bytes::complete::take_while1,
+	character::complete::{char, multispace0, one_of},
+	combinator::{all_consuming, map, map_res},
+	error::convert_error,
+	multi::many0,
+	number::complete::recognize_float,
+	sequence::{delimited, pair, preceded},
+	Finish,
+};
+use serde::{de, Deserialize};
+
+use super::{
+	filter::{Filter, Language},
+	value::{StructKey, Value},
+};
+
+type IResult<'a, O> = nom::IResult<&'a str, O, nom::error::VerboseError<&'a str>>;
+
+/// Arithmetic expression over the fields of a row, i.e. `DamagePhys * 1000 / DelayMs`.
+#[derive(Debug, Clone)]
+pub enum Expression {
+	Number(f64),
+	Field(String),
+	Negate(Box<Expression>),
+	Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Operator {
+	Add,
+	Subtract,
+	Multiply,
+	Divide,
+}
+
+impl Expression {
+	/// Evaluate the expression against the fields of a struct. Fields are looked
+	/// up in the given language, falling back to `None` for unlocalised fields.
+	/// Returns `None` if a field is missing or non-numeric, or the result is not
+	/// a finite number.
+	fn evaluate(
+		&self,
+		fields: &HashMap<StructKey, Value>,
+		language: excel::Language,
+	) -> Option<f64> {
+		let result = match self {
+			Self::Number(number) => *number,
+			Self::Field(name) => [language, excel::Language::None]
+				.into_iter()
+				.find_map(|language| {
+					fields.get(&StructKey {
+						name: name.clone(),
+						language,
+					})
+				})
+				.and_then(Value::numeric)?,
+			Self::Negate(inner) => -inner.evaluate(fields, language)?,
+			Self::Binary(left, operator, right) => {
+				let left = left.evaluate(fields, language)?;
+				let right = right.evaluate(fields, language)?;
+				match operator {
+					Operator::Add => left + right,
+					Operator::Subtract => left - right,
+					Operator::Multiply => left * right,
+					Operator::Divide => left / right,
+				}
+			}
+		};
+
+		result.is_finite().then_some(result)
+	}
+
+	/// Names of the fields the expression reads.
+	fn fields(&self) -> Vec<&str> {
+		let mut fields = vec![];
+		self.collect_fields(&mut fields);
+		fields
+	}
+
+	fn collect_fields<'a>(&'a self, fields: &mut Vec<&'a str>) {
+		match self {
+			Self::Number(_) => {}
+			Self::Field(name) => {
+				if !fields.contains(&name.as_str()) {
+					fields.push(name);
+				}
+			}
+			Self::Negate(inner) => inner.collect_fields(fields),
+			Self::Binary(left, _, right) => {
+				left.collect_fields(fields);
+				right.collect_fields(fields);
+			}
+		}
+	}
+}
+
+/// Widen a filter to include the fields that requested derived fields depend
+/// on, so they can be read even when not requested themselves. Returns the
+/// widened filter and the keys it added, which should be removed with [`strip`]
+/// once derived fields have been applied. Returns `None` if the filter already
+/// covers every dependency.
+pub fn widen_filter(
+	derived: &HashMap<String, Expression>,
+	filter: &Filter,
+) -> Option<(Filter, Vec<StructKey>)> {
+	let Filter::Struct(filter_fields) = filter else {
+		return None;
+	};
+
+	let mut widened = filter_fields.clone();
+	let mut added = vec![];
+
+	for (name, expression) in derived {
+		let Some(languages) = filter_fields.get(name) else {
+			continue;
+		};
+
+		for dependency in expression.fields() {
+			let dependency_languages = widened.entry(dependency.to_string()).or_default();
+			for Language(language) in languages.keys() {
+				if dependency_languages.contains_key(&Language(*language)) {
+					continue;
+				}
+				dependency_languages.insert(Language(*language), Filter::All);
+				added.push(StructKey {
+					name: dependency.to_string(),
+					language: *language,
+				});
+			}
+		}
+	}
+
+	match added.is_empty() {
+		true => None,
+		false => Some((Filter::Struct(widened), added)),
+	}
+}
+
+/// Remove fields added to a struct value by a widened filter.
+pub fn strip(value: &mut Value, added: &[StructKey]) {
+	let Value::Struct(fields) = value else {
+		return;
+	};
+
+	for key in added {
+		fields.remove(key);
+	}
+}
+
+/// Add derived fields to a row's struct value. Derived fields are only added
+/// where the filter would include a field of the same name, and are skipped if
+/// the fields they depend on are missing or non-numeric, or a real field
+/// already holds the name.
+pub fn apply(
+	derived: &HashMap<String, Expression>,
+	value: &mut Value,
+	filter: &Filter,
+	language: excel::Language,
+) {
+	let Value::Struct(fields) = value else {
+		return;
+	};
+
+	let mut all = IntMap::default();
+	all.insert(Language(language), Filter::All);
+
+	for (name, expression) in derived {
+		let languages = match filter {
+			Filter::All => &all,
+			Filter::Struct(filter_fields) => match filter_fields.get(name) {
+				Some(languages) => languages,
+				None => continue,
+			},
+			Filter::Array(_) => return,
+		};
+
+		for Language(language) in languages.keys() {
+			let key = StructKey {
+				name: name.clone(),
+				language: *language,
+			};
+			if fields.contains_key(&key) {
+				tracing::warn!(?key, "derived field collides with a real field");
+				continue;
+			}
+
+			if let Some(result) = expression.evaluate(fields, *language) {
+				// Excel only deals in single precision floats, derived values follow suit.
+				fields.insert(key, Value::Scalar(excel::Field::F32(result as f32)));
+			}
+		}
+	}
+}
+
+impl FromStr for Expression {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		let (_, expression) = all_consuming(delimited(multispace0, sum, multispace0))(input)
+			.finish()
+			.map_err(|error| convert_error(input, error))?;
+
+		Ok(expression)
+	}
+}
+
+impl<'de> Deserialize<'de> for Expression {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		let raw = String::deserialize(deserializer)?;
+		raw.parse().map_err(de::Error::custom)
+	}
+}
+
+fn sum(input: &str) -> IResult<Expression> {
+	let (input, first) = product(input)?;
+	let (input, rest) = many0(pair(token(one_of("+-")), product))(input)?;
+	Ok((input, fold_binary(first, rest)))
+}
+
+fn product(input: &str) -> IResult<Expression> {
+	let (input, first) = unary(input)?;
+	let (input, rest) = many0(pair(token(one_of("*/")), unary))(input)?;
+	Ok((input, fold_binary(first, rest)))
+}
+
+fn unary(input: &str) -> IResult<Expression> {
+	alt((
+		map(preceded(token(char('-')), unary), |inner| {
+			Expression::Negate(Box::new(inner))
+		}),
+		atom,
+	))(input)
+}
+
+fn atom(input: &str) -> IResult<Expression> {
+	alt((
+		delimited(token(char('(')), sum, token(char(')'))),
+		map(
+			map_res(token(recognize_float), str::parse),
+			Expression::Number,
+		),
+		map(token(identifier), |name: &str| {
+			Expression::Field(name.to_string())
+		}),
+	))(input)
+}
+
+fn identifier(input: &str) -> IResult<&str> {
+	take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_')(input)
+}
+
+fn token<'a, O>(
+	parser: impl FnMut(&'a str) -> IResult<'a, O>,
+) -> impl FnMut(&'a str) -> IResult<'a, O> {
+	delimited(multispace0, parser, multispace0)
+}
+
+fn fold_binary(first: Expression, rest: Vec<(char, Expression)>) -> Expression {
+	rest.into_iter().fold(first, |left, (operator, right)| {
+		let operator = match operator {
+			'+' => Operator::Add,
+			'-' => Operator::Subtract,
+			'*' => Operator::Multiply,
+			_ => Operator::Divide,
+		};
+		Expression::Binary(Box::new(left), operator, Box::new(right))
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use pretty_assertions::assert_eq;
+
+	use super::*;
+
+	fn key(name: &str, language: excel::Language) -> StructKey {
+		StructKey {
+			name: name.into(),
+			language,
+		}
+	}
+
+	fn number(value: u16) -> Value {
+		Value::Scalar(excel::Field::U16(value))
+	}
+
+	fn evaluate(input: &str, fields: &HashMap<StructKey, Value>) -> Option<f64> {
+		input
+			.parse::<Expression>()
+			.expect("expression should parse")
+			.evaluate(fields, excel::Language::English)
+	}
+
+	fn struct_filter(names: &[&str]) -> Filter {
+		Filter::Struct(
+			names
+				.iter()
+				.map(|name| {
+					let mut languages = IntMap::default();
+					languages.insert(Language(excel::Language::English), Filter::All);
+					(name.to_string(), languages)
+				})
+				.collect(),
+		)
+	}
+
+	/// Names and numeric values of a struct's fields, in name order.
+	fn fields(value: &Value) -> Vec<(String, Option<f64>)> {
+		let Value::Struct(fields) = value else {
+			panic!("expected struct, got {value:?}");
+		};
+		let mut fields = fields
+			.iter()
+			.map(|(key, value)| (key.name.clone(), value.numeric()))
+			.collect::<Vec<_>>();
+		fields.sort_by(|a, b| a.0.cmp(&b.0));
+		fields
+	}
+
+	fn derived(name: &str, expression: &str) -> HashMap<String, Expression> {
+		HashMap::from([(name.to_string(), expression.parse().unwrap())])
+	}
+
+	#[test]
+	fn parses_with_precedence() {
+		let fields = HashMap::new();
+		assert_eq!(evaluate("1 + 2 * 3", &fields), Some(7.0));
+		assert_eq!(evaluate("(1 + 2) * 3", &fields), Some(9.0));
+		assert_eq!(evaluate("10 - 4 - 3", &fields), Some(3.0));
+		assert_eq!(evaluate("12 / 3 / 2", &fields), Some(2.0));
+		assert_eq!(evaluate("-2 * -(3 - 1)", &fields), Some(4.0));
+		assert_eq!(evaluate("  1.5*2  ", &fields), Some(3.0));
+	}
+
+	#[test]
+	fn rejects_malformed_expressions() {
+		for input in ["", "1 +", "(1 + 2", "1 $ 2", "a b"] {
+			assert!(input.parse::<Expression>().is_err(), "{input:?} parsed");
+		}
+	}
+
+	#[test]
+	fn evaluates_fields() {
+		let fields = HashMap::from([
+			(key("DamagePhys", excel::Language::None), number(50)),
+			(key("DelayMs", excel::Language::None), number(2000)),
+			(key("Level", excel::Language::English), number(90)),
+		]);
+		assert_eq!(evaluate("DamagePhys * 1000 / DelayMs", &fields), Some(25.0));
+		assert_eq!(evaluate("Level + 1", &fields), Some(91.0));
+	}
+
+	#[test]
+	fn skips_unusable_values() {
+		let fields = HashMap::from([
+			(key("Zero", excel::Language::None), number(0)),
+			(key("Names", excel::Language::None), Value::Array(vec![])),
+		]);
+		assert_eq!(evaluate("Missing + 1", &fields), None);
+		assert_eq!(evaluate("Names + 1", &fields), None);
+		assert_eq!(evaluate("1 / Zero", &fields), None);
+	}
+
+	#[test]
+	fn widens_filter_with_dependencies() {
+		let derived = derived("Dps", "DamagePhys * 1000 / DelayMs");
+		let (widened, mut added) = widen_filter(&derived, &struct_filter(&["Dps", "DelayMs"]))
+			.expect("filter should widen");
+
+		assert_eq!(widened, struct_filter(&["Dps", "DelayMs", "DamagePhys"]));
+		added.sort_by(|a, b| a.name.cmp(&b.name));
+		assert_eq!(added, vec![key("DamagePhys", excel::Language::English)]);
+	}
+
+	#[test]
+	fn leaves_covering_filters_alone() {
+		let derived = derived("Dps", "DamagePhys * 1000 / DelayMs");
+		assert_eq!(widen_filter(&derived, &Filter::All), None);
+		assert_eq!(widen_filter(&derived, &struct_filter(&["DelayMs"])), None);
+		assert_eq!(
+			widen_filter(&derived, &struct_filter(&["Dps", "DamagePhys", "DelayMs"])),
+			None
+		);
+	}
+
+	#[test]
+	fn applies_requested_fields_only() {
+		let english = excel::Language::English;
+		let derived = derived("Dps", "DamagePhys * 1000 / DelayMs");
+		let filter = struct_filter(&["Dps"]);
+		let (_, added) = widen_filter(&derived, &filter).expect("filter should widen");
+
+		let mut value = Value::Struct(HashMap::from([
+			(key("DamagePhys", english), number(50)),
+			(key("DelayMs", english), number(2000)),
+		]));
+		apply(&derived, &mut value, &filter, english);
+		strip(&mut value, &added);
+
+		assert_eq!(fields(&value), vec![("Dps".to_string(), Some(25.0))]);
+	}
+
+	#[test]
+	fn keeps_real_fields_on_collision() {
+		let english = excel::Language::English;
+		let derived = derived("Level", "Level + 1");
+		let mut value = Value::Struct(HashMap::from([(key("Level", english), number(90))]));
+		apply(&derived, &mut value, &Filter::All, english);
+
+		assert_eq!(fields(&value), vec![("Level".to_string(), Some(90.0))]);
+	}
+}
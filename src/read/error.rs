@@ -1,3 +1,5 @@
+use crate::error::{classify_failure, Classify, ErrorKind};
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
 	/// The requested resource could not be found.
@@ -15,6 +17,10 @@ pub enum Error {
 	#[error("schema <-> game mismatch on {}: {}", .0.field, .0.reason)]
 	SchemaGameMismatch(MismatchError),
 
+	/// The read was cancelled before it completed.
+	#[error("read cancelled")]
+	Cancelled,
+
 	#[error(transparent)]
 	Failure(#[from] anyhow::Error),
 }
@@ -48,6 +54,19 @@ impl From<ironworks_schema::Error> for Error {
 	}
 }
 
+impl Classify for Error {
+	fn kind(&self) -> ErrorKind {
+		match self {
+			Self::NotFound(..) => ErrorKind::NotFound,
+			Self::InvalidLanguage(..)
+			| Self::FilterSchemaMismatch(..)
+			| Self::SchemaGameMismatch(..) => ErrorKind::Invalid,
+			Self::Cancelled => ErrorKind::Unavailable,
+			Self::Failure(inner) => classify_failure(inner),
+		}
+	}
+}
+
 macro_rules! impl_to_failure {
 	($source:ty) => {
 		impl From<$source> for Error {
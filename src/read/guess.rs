@@ -0,0 +1,181 @@
+use std::ops::Range;
+
+use ironworks::{excel, file::exh, Ironworks};
+
+use super::{error::Result, read::Read, stats::SheetStats, value::icon_path};
+
+/// Number of values sampled from a column when checking a guess.
+const SAMPLE_SIZE: usize = 16;
+
+/// Maximum number of sheets checked as reference targets for a single column.
+const REFERENCE_CANDIDATES: usize = 32;
+
+/// Maximum number of reference targets reported for a single column.
+const REFERENCE_GUESSES: usize = 3;
+
+/// A guess at the purpose of a column, based on the values it contains.
+#[derive(Debug)]
+pub enum ColumnGuess {
+	/// Every sampled value is the ID of an existing icon.
+	Icon,
+	/// Every sampled value is the ID of a row in the sheet.
+	Reference(String),
+}
+
+struct SheetRange {
+	name: String,
+	rows: Range<i64>,
+}
+
+impl Read {
+	/// Guess at the purpose of each column in the provided statistics. Guesses
+	/// are returned in the same order as the statistics columns.
+	///
+	/// This checks the row ranges of every sheet in the version, and may be slow.
+	pub fn guess_columns(
+		&self,
+		ironworks: &Ironworks,
+		excel: &excel::Excel,
+		stats: &SheetStats,
+	) -> Result<Vec<Vec<ColumnGuess>>> {
+		// Only built if there's a column that could plausibly be a reference.
+		let mut ranges = None;
+
+		stats
+			.columns
+			.iter()
+			.map(|column| {
+				// Zero and negative values are typically used as "no value".
+				let values = match &column.integers {
+					Some(integers) => integers.range(1..).copied().collect::<Vec<_>>(),
+					None => return Ok(vec![]),
+				};
+				if values.len() < 2 {
+					return Ok(vec![]);
+				}
+
+				let sample = sample(&values);
+				let mut guesses = vec![];
+
+				if is_icon_column(ironworks, &sample) {
+					guesses.push(ColumnGuess::Icon);
+				}
+
+				let ranges = match &mut ranges {
+					Some(ranges) => ranges,
+					None => ranges.insert(sheet_ranges(ironworks, excel)?),
+				};
+				guesses.extend(
+					reference_guesses(excel, ranges, &values, &sample)?
+						.into_iter()
+						.map(ColumnGuess::Reference),
+				);
+
+				Ok(guesses)
+			})
+			.collect()
+	}
+}
+
+// Pick values spread evenly across the (sorted) values.
+fn sample(values: &[i64]) -> Vec<i64> {
+	let step = (values.len() / SAMPLE_SIZE).max(1);
+	values
+		.iter()
+		.step_by(step)
+		.take(SAMPLE_SIZE)
+		.copied()
+		.collect()
+}
+
+fn is_icon_column(ironworks: &Ironworks, sample: &[i64]) -> bool {
+	sample.iter().all(|value| {
+		u32::try_from(*value).map_or(false, |id| {
+			ironworks
+				.file::<Vec<u8>>(&format!("{}.tex", icon_path(id)))
+				.is_ok()
+		})
+	})
+}
+
+fn sheet_ranges(ironworks: &Ironworks, excel: &excel::Excel) -> Result<Vec<SheetRange>> {
+	let list = excel.list()?;
+
+	let mut ranges = vec![];
+	for name in list.iter() {
+		let header = ironworks.file::<exh::ExcelHeader>(&format!("exd/{name}.exh"))?;
+
+		// References can't target subrows, skip them as candidates.
+		if header.kind() == exh::SheetKind::Subrows {
+			continue;
+		}
+
+		let pages = header.pages();
+		let (Some(first), Some(last)) = (pages.first(), pages.last()) else {
+			continue;
+		};
+
+		ranges.push(SheetRange {
+			name: name.to_string(),
+			rows: i64::from(first.start_id())
+				..i64::from(last.start_id()) + i64::from(last.row_count()),
+		});
+	}
+
+	Ok(ranges)
+}
+
+fn reference_guesses(
+	excel: &excel::Excel,
+	ranges: &[SheetRange],
+	values: &[i64],
+	sample: &[i64],
+) -> Result<Vec<String>> {
+	let (Some(min), Some(max)) = (values.first(), values.last()) else {
+		return Ok(vec![]);
+	};
+
+	// Prefer sheets whose row range most tightly fits the column's values.
+	let mut candidates = ranges
+		.iter()
+		.filter(|range| range.rows.start <= *min && range.rows.end > *max)
+		.collect::<Vec<_>>();
+	candidates.sort_by_key(|range| (range.rows.end - max, max - range.rows.start));
+
+	let mut guesses = vec![];
+	for candidate in candidates.into_iter().take(REFERENCE_CANDIDATES) {
+		if guesses.len() >= REFERENCE_GUESSES {
+			break;
+		}
+
+		if rows_exist(excel, &candidate.name, sample)? {
+			guesses.push(candidate.name.clone());
+		}
+	}
+
+	Ok(guesses)
+}
+
+fn rows_exist(excel: &excel::Excel, sheet: &str, sample: &[i64]) -> Result<bool> {
+	let sheet = excel.sheet(sheet)?;
+
+	// Language doesn't matter for existence, but must be one the sheet provides.
+	let languages = sheet.languages()?;
+	let Some(language) = languages.into_iter().next() else {
+		return Ok(false);
+	};
+
+	for value in sample {
+		let Ok(row_id) = u32::try_from(*value) else {
+			return Ok(false);
+		};
+
+		match sheet.with().language(language).row(row_id) {
+			Ok(_) => {}
+			Err(ironworks::Error::NotFound(ironworks::ErrorValue::Row { .. })) => return Ok(false),
+			Err(error) => Err(error)?,
+		}
+	}
+
+	Ok(true)
+}
@@ -5,7 +5,7 @@ use schemars::{
 	gen::SchemaGenerator,
 	schema::{InstanceType, Metadata, Schema, SchemaObject},
 };
-use serde::de;
+use serde::{de, ser};
 
 use crate::utility::jsonschema::impl_jsonschema;
 
@@ -78,6 +78,15 @@ impl<'de> de::Deserialize<'de> for LanguageString {
 	}
 }
 
+impl ser::Serialize for LanguageString {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		serializer.collect_str(self)
+	}
+}
+
 impl_jsonschema!(LanguageString, languagestring_schema);
 fn languagestring_schema(_generator: &mut SchemaGenerator) -> Schema {
 	// TODO: keep this up to date with the full list.
@@ -1,13 +1,28 @@
+mod collate;
+mod compare;
+mod derived;
 mod error;
 mod filter;
+mod guess;
 mod language;
 mod read;
+mod stats;
+mod strings;
+mod transform;
+mod validate;
 mod value;
 
 pub use {
+	collate::{CollationKey, Collator},
+	compare::{FieldChange, FieldMove, SchemaDiff},
 	error::Error,
 	filter::{Filter, Language},
+	guess::ColumnGuess,
 	language::LanguageString,
 	read::{Config, Read},
-	value::{Reference, StructKey, Value},
+	stats::{ColumnStats, SheetStats},
+	strings::{LanguageStrings, SheetStrings, StringChange, StringRow},
+	transform::{Transform, TransformContext, Transforms},
+	validate::{IssueKind, ValidationIssue, ValidationReport},
+	value::{icon_path, Reference, StructKey, Value},
 };
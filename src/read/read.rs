@@ -10,19 +10,29 @@ use ironworks::{excel, file::exh};
 use ironworks_schema as schema;
 use nohash_hasher::IntMap;
 use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
 
-use crate::read::Language;
+use crate::{config::Validator, read::Language};
 
 use super::{
+	derived::{self, Expression},
 	error::{Error, MismatchError, Result},
 	filter::Filter,
 	language::LanguageString,
+	transform::{TransformContext, Transforms},
 	value::{Reference, StructKey, Value},
 };
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
 	language: LanguageConfig,
+
+	#[serde(default)]
+	display: DisplayConfig,
+
+	/// Fields computed from other fields of a row, by sheet name and field name.
+	#[serde(default)]
+	derived: HashMap<String, HashMap<String, Expression>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -31,9 +41,54 @@ struct LanguageConfig {
 	exclude: Vec<LanguageString>,
 }
 
+#[derive(Debug, Default, Deserialize)]
+struct DisplayConfig {
+	/// Candidate display field names, used for sheets without an explicit
+	/// display field. The first name present in the sheet's schema is used.
+	#[serde(default)]
+	default: Vec<String>,
+
+	/// Display field names for specific sheets.
+	#[serde(default)]
+	sheet: HashMap<String, String>,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		let language = &self.language;
+		validator.check(
+			"language.default",
+			!language.exclude.contains(&language.default),
+			format_args!("default language {} is excluded", language.default),
+		);
+
+		let display = &self.display;
+		for (sheet, field) in &display.sheet {
+			validator.check(
+				&format!("display.sheet.{sheet}"),
+				!field.is_empty(),
+				"display field name must not be empty",
+			);
+		}
+
+		for (sheet, fields) in &self.derived {
+			for name in fields.keys() {
+				validator.check(
+					&format!("derived.{sheet}"),
+					!name.is_empty(),
+					"derived field name must not be empty",
+				);
+			}
+		}
+	}
+}
+
 pub struct Read {
 	default_language: excel::Language,
-	excluded_languages: HashSet<excel::Language>,
+	pub(super) excluded_languages: HashSet<excel::Language>,
+	display: DisplayConfig,
+	derived: HashMap<String, HashMap<String, Expression>>,
+	transforms: Transforms,
 }
 
 impl Read {
@@ -46,13 +101,25 @@ impl Read {
 				.into_iter()
 				.map(|language| language.into())
 				.collect(),
+			display: config.display,
+			derived: config.derived,
+			transforms: Transforms::default(),
 		}
 	}
 
+	/// Install custom value transforms, applied to every row read.
+	pub fn with_transforms(mut self, transforms: Transforms) -> Self {
+		self.transforms = transforms;
+		self
+	}
+
 	pub fn default_language(&self) -> excel::Language {
 		self.default_language
 	}
 
+	/// Read a row. References to sheets that `access` does not permit are left
+	/// unresolved. Reading stops early with [`Error::Cancelled`] once `cancel`
+	/// is triggered.
 	pub fn read(
 		&self,
 		excel: &excel::Excel,
@@ -66,6 +133,8 @@ impl Read {
 
 		filter: &Filter,
 		depth: u8,
+		access: &dyn Fn(&str) -> bool,
+		cancel: &CancellationToken,
 	) -> Result<Value> {
 		let value = read_sheet(ReaderContext {
 			read: self,
@@ -82,15 +151,58 @@ impl Read {
 			rows: &mut HashMap::new(),
 			columns: &[],
 			depth,
+			display: true,
+			visited: &[(sheet_name, row_id)],
+			access,
+			cancel,
 
 			path: &[],
 		})?;
 
 		Ok(value)
 	}
+
+	fn display_enabled(&self) -> bool {
+		!self.display.default.is_empty() || !self.display.sheet.is_empty()
+	}
+
+	/// Get the name of the field used to label references to the given sheet.
+	fn display_field(&self, schema: &dyn schema::Schema, sheet: &str) -> Result<Option<String>> {
+		if let Some(field) = self.display.sheet.get(sheet) {
+			return Ok(Some(field.clone()));
+		}
+
+		if self.display.default.is_empty() {
+			return Ok(None);
+		}
+
+		let fields = match schema.sheet(sheet) {
+			Ok(schema::Sheet {
+				node: schema::Node::Struct(fields),
+				..
+			}) => fields,
+			Ok(_) | Err(schema::Error::NotFound(_)) => return Ok(None),
+			Err(error) => Err(error)?,
+		};
+
+		let field = self
+			.display
+			.default
+			.iter()
+			.find(|candidate| fields.iter().any(|field| &field.name == *candidate))
+			.cloned();
+
+		Ok(field)
+	}
 }
 
 fn read_sheet(context: ReaderContext) -> Result<Value> {
+	// Every row read passes through here, including references - a cheap point
+	// to bail out if nobody is waiting on the result any more.
+	if context.cancel.is_cancelled() {
+		return Err(Error::Cancelled);
+	}
+
 	let sheet_name = context.sheet;
 	let sheet_data = context.excel.sheet(sheet_name)?;
 
@@ -106,19 +218,47 @@ fn read_sheet(context: ReaderContext) -> Result<Value> {
 
 	let columns = get_sorted_columns(&sheet_schema, &sheet_data)?;
 
-	let value = read_node(
+	let (read, filter, language) = (context.read, context.filter, context.language);
+	let (row_id, subrow_id) = (context.row_id, context.subrow_id);
+
+	// Derived fields may depend on fields that weren't requested - read those
+	// too, and drop them again once the derived values are in place.
+	let derived = read.derived.get(sheet_name);
+	let widened = derived.and_then(|derived| derived::widen_filter(derived, filter));
+
+	let mut value = read_node(
 		&sheet_schema.node,
 		ReaderContext {
 			columns: &columns,
+			filter: widened.as_ref().map_or(filter, |(filter, _)| filter),
 
 			..context
 		},
 	)?;
 
+	if let Some(derived) = derived {
+		derived::apply(derived, &mut value, filter, language);
+	}
+
+	if let Some((_, added)) = &widened {
+		derived::strip(&mut value, added);
+	}
+
+	read.transforms.apply(
+		&TransformContext {
+			sheet: sheet_name,
+			row_id,
+			subrow_id,
+			language,
+			filter,
+		},
+		&mut value,
+	);
+
 	Ok(value)
 }
 
-fn get_sorted_columns(
+pub(super) fn get_sorted_columns(
 	schema: &schema::Sheet,
 	data: &excel::Sheet<'_, &str>,
 ) -> Result<Vec<exh::ColumnDefinition>> {
@@ -171,11 +311,15 @@ fn read_scalar_reference(
 
 	let mut reference = Reference::Scalar(target_value);
 
+	// Once out of recursion depth, references are only resolved far enough to
+	// read their display field. We avoid this if following an active reference chain.
+	let shallow = context.depth == 0 && context.filter == &Filter::All;
+
 	// A target less than 0 (typically -1) is usually used to signify that a link
-	// is not present on this row. Also ensure that we've not run out of recursion
-	// depth. We avoid early return if following an active reference chain.
+	// is not present on this row. Also bail if we're shallow, and there's no
+	// display field to read.
 	// TODO: would be neat to halt recursion later, but target checking does have a cost that needs to be considered.
-	if target_value < 0 || (context.depth == 0 && context.filter == &Filter::All) {
+	if target_value < 0 || (shallow && !(context.display && context.read.display_enabled())) {
 		return Ok(Value::Reference(reference));
 	}
 	let target_value = u32::try_from(target_value)
@@ -244,16 +388,59 @@ fn read_scalar_reference(
 			other => other,
 		}?;
 
+		// The reference is to a sheet the reader may not access - leave it unresolved.
+		if !(context.access)(&target.sheet) {
+			break;
+		}
+
 		let row_id = row_data.row_id();
 		let subrow_id = row_data.subrow_id();
 
+		// If the target row is already being read further up this branch, reading
+		// it again would recurse indefinitely - truncate the reference instead.
+		let circular = context.visited.contains(&(target.sheet.as_str(), row_id));
+
+		let mut rows = HashMap::from([(context.language, row_data)]);
+
+		let display = match context.display && context.read.display_enabled() {
+			false => None,
+			true => read_display(ReaderContext {
+				sheet: &target.sheet,
+				row_id,
+				subrow_id,
+
+				rows: &mut rows,
+
+				..context
+			})?,
+		};
+
+		if shallow || circular {
+			reference = Reference::Shallow {
+				value: target_value,
+				sheet: target.sheet.to_string(),
+				row_id,
+				display: display.map(Box::new),
+				circular,
+			};
+			continue;
+		}
+
+		let visited = context
+			.visited
+			.iter()
+			.copied()
+			.chain([(target.sheet.as_str(), row_id)])
+			.collect::<Vec<_>>();
+
 		let child_data = read_sheet(ReaderContext {
 			sheet: &target.sheet,
 			row_id,
 			subrow_id,
 
-			rows: &mut HashMap::from([(context.language, row_data)]),
+			rows: &mut rows,
 			depth: context.depth.max(1) - 1,
+			visited: &visited,
 
 			..context
 		})?;
@@ -262,6 +449,7 @@ fn read_scalar_reference(
 			value: target_value,
 			sheet: target.sheet.to_string(),
 			row_id,
+			display: display.map(Box::new),
 			fields: child_data.into(),
 		}
 	}
@@ -269,7 +457,37 @@ fn read_scalar_reference(
 	Ok(Value::Reference(reference))
 }
 
-fn convert_reference_value(field: excel::Field) -> Result<i32> {
+fn read_display(context: ReaderContext) -> Result<Option<Value>> {
+	let Some(field) = context.read.display_field(context.schema, context.sheet)? else {
+		return Ok(None);
+	};
+
+	let mut language_map = IntMap::default();
+	language_map.insert(Language(context.language), Filter::All);
+	let language = context.language;
+
+	// Display fields are read without resolving further display fields, so a
+	// display field that is itself a reference can't chain indefinitely.
+	let data = read_sheet(ReaderContext {
+		filter: &Filter::Struct(HashMap::from([(field.clone(), language_map)])),
+		depth: 0,
+		display: false,
+		rows: &mut *context.rows,
+		..context
+	})?;
+
+	let value = match data {
+		Value::Struct(mut map) => map.remove(&StructKey {
+			name: field,
+			language,
+		}),
+		_ => None,
+	};
+
+	Ok(value)
+}
+
+pub(super) fn convert_reference_value(field: excel::Field) -> Result<i32> {
 	use excel::Field as F;
 	let result = match field {
 		F::I8(value) => i32::from(value),
@@ -423,7 +641,7 @@ fn read_node_struct(fields: &[schema::StructField], mut context: ReaderContext)
 }
 
 // TODO: this is fairly gnarly - look into a crate for generators, i.e. genawaiter?
-fn iterate_struct_fields<'s, 'c>(
+pub(super) fn iterate_struct_fields<'s, 'c>(
 	fields: &'s [schema::StructField],
 	columns: &'c [exh::ColumnDefinition],
 ) -> Result<impl Iterator<Item = (Cow<'s, str>, &'s schema::Node, &'c [exh::ColumnDefinition])>> {
@@ -517,6 +735,12 @@ struct ReaderContext<'a> {
 	columns: &'a [exh::ColumnDefinition],
 	rows: &'a mut HashMap<excel::Language, excel::Row>,
 	depth: u8,
+	display: bool,
+	/// Sheet rows being read along the current reference chain.
+	visited: &'a [(&'a str, u32)],
+	/// Whether references to the given sheet may be resolved.
+	access: &'a dyn Fn(&str) -> bool,
+	cancel: &'a CancellationToken,
 
 	path: &'a [&'a str],
 }
@@ -0,0 +1,189 @@
+use std::collections::{BTreeSet, HashMap};
+
+use ironworks::{excel, file::exh};
+
+use super::{
+	error::{Error, Result},
+	language::LanguageString,
+	read::Read,
+};
+
+/// Maximum number of distinct values tracked per column. Columns with more
+/// distinct values than this report a lower bound.
+const DISTINCT_LIMIT: usize = 65536;
+
+/// Number of most common values reported per column.
+const TOP_VALUES: usize = 10;
+
+/// Statistics for every column of a sheet, from a single language.
+#[derive(Debug)]
+pub struct SheetStats {
+	pub language: excel::Language,
+	pub row_count: u64,
+	/// Per-column statistics, ordered by column offset.
+	pub columns: Vec<ColumnStats>,
+}
+
+#[derive(Debug)]
+pub struct ColumnStats {
+	pub offset: u16,
+	pub kind: exh::ColumnKind,
+	/// Number of distinct values in the column.
+	pub distinct: usize,
+	/// Whether the distinct count stopped being tracked, and is a lower bound.
+	pub distinct_capped: bool,
+	/// Minimum and maximum values of numeric columns.
+	pub min: Option<f64>,
+	pub max: Option<f64>,
+	/// Number of zero, false, or empty string values.
+	pub zero_count: u64,
+	/// Most common values, as strings, with their number of occurrences.
+	pub top: Vec<(String, u64)>,
+	/// Every distinct value of integer columns, if not capped.
+	pub(super) integers: Option<BTreeSet<i64>>,
+}
+
+#[derive(Default)]
+struct Accumulator {
+	counts: HashMap<String, u64>,
+	capped: bool,
+	min: Option<f64>,
+	max: Option<f64>,
+	zero_count: u64,
+}
+
+impl Accumulator {
+	fn add(&mut self, field: excel::Field) {
+		use excel::Field as F;
+		let (number, key) = match field {
+			F::String(value) => (None, value.to_string()),
+			F::Bool(value) => (Some(f64::from(u8::from(value))), value.to_string()),
+			F::I8(value) => (Some(f64::from(value)), value.to_string()),
+			F::I16(value) => (Some(f64::from(value)), value.to_string()),
+			F::I32(value) => (Some(f64::from(value)), value.to_string()),
+			F::I64(value) => (Some(value as f64), value.to_string()),
+			F::U8(value) => (Some(f64::from(value)), value.to_string()),
+			F::U16(value) => (Some(f64::from(value)), value.to_string()),
+			F::U32(value) => (Some(f64::from(value)), value.to_string()),
+			F::U64(value) => (Some(value as f64), value.to_string()),
+			F::F32(value) => (Some(f64::from(value)), value.to_string()),
+		};
+
+		match number {
+			Some(number) => {
+				self.min = Some(self.min.map_or(number, |min| min.min(number)));
+				self.max = Some(self.max.map_or(number, |max| max.max(number)));
+				if number == 0.0 {
+					self.zero_count += 1;
+				}
+			}
+			None if key.is_empty() => self.zero_count += 1,
+			None => {}
+		}
+
+		match self.counts.get_mut(&key) {
+			Some(count) => *count += 1,
+			None if self.counts.len() < DISTINCT_LIMIT => {
+				self.counts.insert(key, 1);
+			}
+			None => self.capped = true,
+		}
+	}
+
+	fn finish(self, column: &exh::ColumnDefinition) -> ColumnStats {
+		let distinct = self.counts.len();
+
+		let integers = match is_integer(column.kind()) && !self.capped {
+			false => None,
+			true => Some(
+				self.counts
+					.keys()
+					.filter_map(|key| key.parse::<i64>().ok())
+					.collect(),
+			),
+		};
+
+		let mut top = self.counts.into_iter().collect::<Vec<_>>();
+		top.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+		top.truncate(TOP_VALUES);
+
+		ColumnStats {
+			offset: column.offset(),
+			kind: column.kind(),
+			distinct,
+			distinct_capped: self.capped,
+			min: self.min,
+			max: self.max,
+			zero_count: self.zero_count,
+			top,
+			integers,
+		}
+	}
+}
+
+pub(super) fn is_integer(kind: exh::ColumnKind) -> bool {
+	use exh::ColumnKind as CK;
+	matches!(
+		kind,
+		CK::Int8
+			| CK::UInt8
+			| CK::Int16
+			| CK::UInt16
+			| CK::Int32
+			| CK::UInt32
+			| CK::Int64
+			| CK::UInt64
+	)
+}
+
+impl Read {
+	/// Compute statistics for every column of a sheet, with a single pass over
+	/// its rows. Sheets without the requested language fall back to the
+	/// language-less variant, if available.
+	pub fn stats(
+		&self,
+		excel: &excel::Excel,
+		sheet_name: &str,
+		language: excel::Language,
+	) -> Result<SheetStats> {
+		if self.excluded_languages.contains(&language) {
+			return Err(Error::InvalidLanguage(
+				LanguageString::from(language).to_string(),
+			));
+		}
+
+		let sheet = excel.sheet(sheet_name)?;
+
+		let languages = sheet.languages()?;
+		let language = [language, excel::Language::None]
+			.into_iter()
+			.find(|language| languages.contains(language))
+			.unwrap_or(language);
+
+		let mut columns = sheet.columns()?;
+		columns.sort_by_key(|column| column.offset());
+
+		let mut accumulators = columns
+			.iter()
+			.map(|_| Accumulator::default())
+			.collect::<Vec<_>>();
+		let mut row_count = 0;
+
+		for row in sheet.with().language(language).iter() {
+			row_count += 1;
+			for (column, accumulator) in columns.iter().zip(&mut accumulators) {
+				accumulator.add(row.field(column)?);
+			}
+		}
+
+		Ok(SheetStats {
+			language,
+			row_count,
+			columns: columns
+				.iter()
+				.zip(accumulators)
+				.map(|(column, accumulator)| accumulator.finish(column))
+				.collect(),
+		})
+	}
+}
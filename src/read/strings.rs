@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use ironworks::{excel, file::exh};
+
+use super::{
+	error::{Error, Result},
+	language::LanguageString,
+	read::Read,
+};
+
+/// String column data of a sheet, for a single language.
+#[derive(Debug)]
+pub struct LanguageStrings {
+	pub language: excel::Language,
+	pub rows: Vec<StringRow>,
+}
+
+#[derive(Debug)]
+pub struct StringRow {
+	pub row_id: u32,
+	pub subrow_id: u16,
+	/// String values, in the same order as the sheet's string columns.
+	pub values: Vec<String>,
+}
+
+/// Strings of a sheet across all of its readable languages.
+#[derive(Debug)]
+pub struct SheetStrings {
+	/// Offsets of the string columns, in the order values are reported.
+	pub columns: Vec<u16>,
+	pub languages: Vec<LanguageStrings>,
+}
+
+impl Read {
+	/// Read every string column of a sheet, for each language the sheet
+	/// provides. Excluded languages are skipped. If a language is specified, only
+	/// it is read - sheets without the language fall back to the language-less
+	/// variant, if available.
+	pub fn strings(
+		&self,
+		excel: &excel::Excel,
+		sheet_name: &str,
+		language: Option<excel::Language>,
+	) -> Result<SheetStrings> {
+		if let Some(language) = language {
+			if self.excluded_languages.contains(&language) {
+				return Err(Error::InvalidLanguage(
+					LanguageString::from(language).to_string(),
+				));
+			}
+		}
+
+		let sheet = excel.sheet(sheet_name)?;
+
+		let mut columns = sheet
+			.columns()?
+			.into_iter()
+			.filter(|column| column.kind() == exh::ColumnKind::String)
+			.collect::<Vec<_>>();
+		columns.sort_by_key(|column| column.offset());
+
+		let sheet_languages = sheet.languages()?;
+		let read_languages = match language {
+			Some(language) => [language, excel::Language::None]
+				.into_iter()
+				.find(|language| sheet_languages.contains(language))
+				.into_iter()
+				.collect::<Vec<_>>(),
+			None => sheet_languages
+				.into_iter()
+				.filter(|language| !self.excluded_languages.contains(language))
+				.collect(),
+		};
+
+		let languages = read_languages
+			.into_iter()
+			.map(|language| {
+				let rows = sheet
+					.with()
+					.language(language)
+					.iter()
+					.map(|row| {
+						let values = columns
+							.iter()
+							.map(|column| match row.field(column)? {
+								excel::Field::String(value) => Ok(value.to_string()),
+								other => {
+									Err(anyhow::anyhow!("expected string, got {other:?}").into())
+								}
+							})
+							.collect::<Result<Vec<_>>>()?;
+
+						Ok(StringRow {
+							row_id: row.row_id(),
+							subrow_id: row.subrow_id(),
+							values,
+						})
+					})
+					.collect::<Result<Vec<_>>>()?;
+
+				Ok(LanguageStrings { language, rows })
+			})
+			.collect::<Result<Vec<_>>>()?;
+
+		Ok(SheetStrings {
+			columns: columns.iter().map(|column| column.offset()).collect(),
+			languages,
+		})
+	}
+}
+
+/// A single string that differs between two reads of a sheet.
+#[derive(Debug)]
+pub struct StringChange {
+	pub row_id: u32,
+	pub subrow_id: u16,
+	pub column: u16,
+	pub language: excel::Language,
+	/// Value before the change. `None` if the string did not previously exist.
+	pub before: Option<String>,
+	/// Value after the change. `None` if the string no longer exists.
+	pub after: Option<String>,
+}
+
+impl SheetStrings {
+	/// Compare these strings against a later read of the same sheet. Columns are
+	/// matched by offset, and rows by ID.
+	pub fn diff(&self, after: &SheetStrings) -> Vec<StringChange> {
+		let before_map = self.string_map();
+		let after_map = after.string_map();
+
+		let keys = before_map
+			.keys()
+			.chain(after_map.keys())
+			.collect::<BTreeSet<_>>();
+
+		keys.into_iter()
+			.filter_map(|key| {
+				let before = before_map.get(key).copied();
+				let after = after_map.get(key).copied();
+				if before == after {
+					return None;
+				}
+
+				let (row_id, subrow_id, column, language) = *key;
+				Some(StringChange {
+					row_id,
+					subrow_id,
+					column,
+					language: language.0,
+					before: before.map(str::to_string),
+					after: after.map(str::to_string),
+				})
+			})
+			.collect()
+	}
+
+	fn string_map(&self) -> BTreeMap<(u32, u16, u16, OrdLanguage), &str> {
+		self.languages
+			.iter()
+			.flat_map(|language| {
+				language.rows.iter().flat_map(move |row| {
+					self.columns
+						.iter()
+						.zip(&row.values)
+						// Empty strings are treated as absent, to avoid reporting noise
+						// when a column or row is added with no content.
+						.filter(|(_, value)| !value.is_empty())
+						.map(move |(column, value)| {
+							let key = (
+								row.row_id,
+								row.subrow_id,
+								*column,
+								OrdLanguage(language.language),
+							);
+							(key, value.as_str())
+						})
+				})
+			})
+			.collect()
+	}
+}
+
+// Language doesn't implement Ord - order it by its numeric value for use in keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct OrdLanguage(excel::Language);
+
+impl PartialOrd for OrdLanguage {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for OrdLanguage {
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		(self.0 as u8).cmp(&(other.0 as u8))
+	}
+}
@@ -0,0 +1,90 @@
+use std::{collections::HashMap, sync::Arc};
+
+use ironworks::excel;
+
+use super::{
+	filter::Filter,
+	value::{StructKey, Value},
+};
+
+/// Custom transform applied to values as they are read. Transforms are run
+/// after derived fields are computed, and before the value is serialised, so
+/// any changes are visible to every response format.
+pub trait Transform: Send + Sync {
+	/// Transform a value in place. For sheet transforms, `value` is the row's
+	/// root struct - for field transforms, it is the value of the field.
+	fn transform(&self, context: &TransformContext, value: &mut Value);
+}
+
+/// Context of the value being transformed.
+pub struct TransformContext<'a> {
+	pub sheet: &'a str,
+	pub row_id: u32,
+	pub subrow_id: u16,
+	pub language: excel::Language,
+	/// Filter the row was read with. Transforms adding fields should respect it.
+	pub filter: &'a Filter,
+}
+
+/// Registry of custom value transforms, by sheet and optionally field name.
+/// Transforms are installed at startup, via the server or stack builders, and
+/// run in the order they were registered.
+#[derive(Default, Clone)]
+pub struct Transforms {
+	sheets: HashMap<String, Vec<Arc<dyn Transform>>>,
+	fields: HashMap<String, Vec<(String, Arc<dyn Transform>)>>,
+}
+
+impl Transforms {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Register a transform applied to every row read from the given sheet.
+	pub fn sheet(
+		&mut self,
+		sheet: impl Into<String>,
+		transform: impl Transform + 'static,
+	) -> &mut Self {
+		self.sheets
+			.entry(sheet.into())
+			.or_default()
+			.push(Arc::new(transform));
+		self
+	}
+
+	/// Register a transform applied to the value of a top-level field of the
+	/// given sheet, in every language it is read in.
+	pub fn field(
+		&mut self,
+		sheet: impl Into<String>,
+		field: impl Into<String>,
+		transform: impl Transform + 'static,
+	) -> &mut Self {
+		self.fields
+			.entry(sheet.into())
+			.or_default()
+			.push((field.into(), Arc::new(transform)));
+		self
+	}
+
+	pub(super) fn apply(&self, context: &TransformContext, value: &mut Value) {
+		if let (Some(fields), Value::Struct(entries)) =
+			(self.fields.get(context.sheet), &mut *value)
+		{
+			for (name, transform) in fields {
+				for (StructKey { name: key, .. }, field) in entries.iter_mut() {
+					if key == name {
+						transform.transform(context, field);
+					}
+				}
+			}
+		}
+
+		if let Some(transforms) = self.sheets.get(context.sheet) {
+			for transform in transforms {
+				transform.transform(context, value);
+			}
+		}
+	}
+}
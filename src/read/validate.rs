@@ -0,0 +1,327 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use anyhow::anyhow;
+use ironworks::{excel, file::exh};
+use ironworks_schema as schema;
+use serde::Serialize;
+
+use super::{
+	error::{Error, Result},
+	read::{convert_reference_value, get_sorted_columns, iterate_struct_fields, Read},
+	stats::is_integer,
+};
+
+/// Maximum number of example rows recorded for each dangling reference issue.
+const EXAMPLE_LIMIT: usize = 10;
+
+/// Problems found while cross-checking the sheets of a version against a schema.
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationReport {
+	/// Number of sheets with a schema that were checked.
+	pub sheets: usize,
+	pub issues: Vec<ValidationIssue>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationIssue {
+	pub sheet: String,
+	/// Path of the schema field the issue was found on, if specific to a field.
+	pub field: Option<String>,
+	#[serde(flatten)]
+	pub kind: IssueKind,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IssueKind {
+	/// The sheet schema does not fit the columns of the sheet.
+	Structure { reason: String },
+
+	/// A reference or icon field is defined over a column that can't hold an ID.
+	ColumnKind { column: u16, found: String },
+
+	/// A reference targets a sheet that does not exist.
+	MissingSheet { target: String },
+
+	/// Reference values that do not match a row in any of the target sheets.
+	/// Values of 0 or less are treated as empty, and are not checked.
+	DanglingReference {
+		count: u64,
+		/// Number of the dangling values that lie beyond the last row of every target.
+		out_of_range: u64,
+		/// Example rows with dangling values, as row ID, subrow ID, and value.
+		examples: Vec<(u32, u16, i32)>,
+	},
+}
+
+/// A schema field whose column is expected to hold an ID.
+struct IdField<'a> {
+	path: String,
+	column: exh::ColumnDefinition,
+	targets: &'a [schema::ReferenceTarget],
+}
+
+/// Row IDs of sheets targeted by references, loaded as needed.
+#[derive(Default)]
+struct RowIds {
+	sheets: HashMap<String, BTreeSet<u32>>,
+}
+
+impl Read {
+	/// Validate every sheet of a version against a schema. This reads every row of
+	/// every sheet with a schema, as well as the sheets they reference.
+	///
+	/// Conditional reference targets are treated as unconditional, so references
+	/// matching the wrong target may go unreported.
+	pub fn validate(
+		&self,
+		excel: &excel::Excel,
+		schema: &dyn schema::Schema,
+	) -> Result<ValidationReport> {
+		let list = excel.list()?;
+		let names = list
+			.iter()
+			.map(|name| name.to_string())
+			.collect::<HashSet<_>>();
+
+		let mut report = ValidationReport::default();
+		let mut row_ids = RowIds::default();
+
+		let mut sorted_names = names.iter().collect::<Vec<_>>();
+		sorted_names.sort_unstable();
+
+		for name in sorted_names {
+			let sheet_schema = match schema.sheet(name) {
+				Err(schema::Error::NotFound(_)) => continue,
+				other => other?,
+			};
+
+			report.sheets += 1;
+			self.validate_sheet(excel, &names, &sheet_schema, &mut row_ids, &mut report)?;
+		}
+
+		Ok(report)
+	}
+
+	fn validate_sheet(
+		&self,
+		excel: &excel::Excel,
+		names: &HashSet<String>,
+		sheet_schema: &schema::Sheet,
+		row_ids: &mut RowIds,
+		report: &mut ValidationReport,
+	) -> Result<()> {
+		let sheet_name = sheet_schema.name.as_str();
+		let sheet = excel.sheet(sheet_name)?;
+		let columns = get_sorted_columns(sheet_schema, &sheet)?;
+
+		let issue = |field: Option<&str>, kind| ValidationIssue {
+			sheet: sheet_name.to_string(),
+			field: field.map(str::to_string),
+			kind,
+		};
+
+		let mut fields = vec![];
+		if let Err(reason) = collect_id_fields(&sheet_schema.node, &columns, "", &mut fields) {
+			report
+				.issues
+				.push(issue(None, IssueKind::Structure { reason }));
+			return Ok(());
+		}
+
+		// Check the shape of each field, keeping those that can be checked against row data.
+		let mut checked = vec![];
+		for field in fields {
+			if !is_integer(field.column.kind()) {
+				let kind = IssueKind::ColumnKind {
+					column: field.column.offset(),
+					found: format!("{:?}", field.column.kind()),
+				};
+				report.issues.push(issue(Some(&field.path), kind));
+				continue;
+			}
+
+			let mut targets = vec![];
+			for target in field.targets {
+				match names.contains(&target.sheet) {
+					true => targets.push(target.sheet.as_str()),
+					false => report.issues.push(issue(
+						Some(&field.path),
+						IssueKind::MissingSheet {
+							target: target.sheet.clone(),
+						},
+					)),
+				}
+			}
+
+			if !targets.is_empty() {
+				for target in &targets {
+					row_ids.load(self, excel, target)?;
+				}
+				checked.push((field, targets, DanglingCounter::default()));
+			}
+		}
+
+		if checked.is_empty() {
+			return Ok(());
+		}
+
+		let language = self.scan_language(&sheet)?;
+		for row in sheet.with().language(language).iter() {
+			for (field, targets, counter) in &mut checked {
+				let value = convert_reference_value(row.field(&field.column)?)?;
+				if value <= 0 {
+					continue;
+				}
+
+				let id = u32::try_from(value)?;
+				if targets.iter().any(|target| row_ids.contains(target, id)) {
+					continue;
+				}
+
+				let out_of_range = targets.iter().all(|target| row_ids.is_beyond(target, id));
+				counter.add(row.row_id(), row.subrow_id(), value, out_of_range);
+			}
+		}
+
+		for (field, _targets, counter) in checked {
+			if counter.count > 0 {
+				report
+					.issues
+					.push(issue(Some(&field.path), counter.into_issue()));
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Pick a language to read a sheet's rows in. IDs are not localised, so any
+	/// readable language will do.
+	fn scan_language(&self, sheet: &excel::Sheet<&str>) -> Result<excel::Language> {
+		let languages = sheet.languages()?;
+		[self.default_language(), excel::Language::None]
+			.into_iter()
+			.chain(languages.iter().copied())
+			.find(|language| {
+				languages.contains(language) && !self.excluded_languages.contains(language)
+			})
+			.ok_or_else(|| {
+				Error::Failure(anyhow!("no readable language for sheet {}", sheet.name()))
+			})
+	}
+}
+
+impl RowIds {
+	fn load(&mut self, read: &Read, excel: &excel::Excel, sheet_name: &str) -> Result<()> {
+		if self.sheets.contains_key(sheet_name) {
+			return Ok(());
+		}
+
+		let sheet = excel.sheet(sheet_name)?;
+		let language = read.scan_language(&sheet)?;
+		let ids = sheet
+			.with()
+			.language(language)
+			.iter()
+			.map(|row| row.row_id())
+			.collect();
+
+		self.sheets.insert(sheet_name.to_string(), ids);
+		Ok(())
+	}
+
+	fn contains(&self, sheet_name: &str, id: u32) -> bool {
+		self.sheets
+			.get(sheet_name)
+			.map_or(false, |ids| ids.contains(&id))
+	}
+
+	fn is_beyond(&self, sheet_name: &str, id: u32) -> bool {
+		self.sheets
+			.get(sheet_name)
+			.and_then(|ids| ids.last())
+			.map_or(true, |last| id > *last)
+	}
+}
+
+#[derive(Default)]
+struct DanglingCounter {
+	count: u64,
+	out_of_range: u64,
+	examples: Vec<(u32, u16, i32)>,
+}
+
+impl DanglingCounter {
+	fn add(&mut self, row_id: u32, subrow_id: u16, value: i32, out_of_range: bool) {
+		self.count += 1;
+		if out_of_range {
+			self.out_of_range += 1;
+		}
+		if self.examples.len() < EXAMPLE_LIMIT {
+			self.examples.push((row_id, subrow_id, value));
+		}
+	}
+
+	fn into_issue(self) -> IssueKind {
+		IssueKind::DanglingReference {
+			count: self.count,
+			out_of_range: self.out_of_range,
+			examples: self.examples,
+		}
+	}
+}
+
+/// Walk a schema node, collecting the fields that are expected to hold IDs.
+/// Returns the reason the schema does not fit if the columns run out.
+fn collect_id_fields<'a>(
+	node: &'a schema::Node,
+	columns: &[exh::ColumnDefinition],
+	path: &str,
+	fields: &mut Vec<IdField<'a>>,
+) -> Result<(), String> {
+	use schema::Node as N;
+	match node {
+		N::Scalar(scalar) => {
+			let column = columns
+				.first()
+				.ok_or_else(|| format!("no column available for field {path}"))?;
+
+			let targets: &[schema::ReferenceTarget] = match scalar {
+				schema::Scalar::Reference(targets) => targets.as_slice(),
+				schema::Scalar::Icon => &[],
+				_ => return Ok(()),
+			};
+
+			fields.push(IdField {
+				path: path.to_string(),
+				column: column.clone(),
+				targets,
+			});
+		}
+
+		N::Array { count, node } => {
+			let size = usize::try_from(node.size()).map_err(|error| error.to_string())?;
+			for index in 0..usize::try_from(*count).map_err(|error| error.to_string())? {
+				let start = index * size;
+				let element_columns = columns
+					.get(start..start + size)
+					.ok_or_else(|| format!("insufficient columns to satisfy array {path}"))?;
+				collect_id_fields(node, element_columns, &format!("{path}[{index}]"), fields)?;
+			}
+		}
+
+		N::Struct(struct_fields) => {
+			let items =
+				iterate_struct_fields(struct_fields, columns).map_err(|error| error.to_string())?;
+			for (name, node, field_columns) in items {
+				let path = match path.is_empty() {
+					true => name.to_string(),
+					false => format!("{path}.{name}"),
+				};
+				collect_id_fields(node, field_columns, &path, fields)?;
+			}
+		}
+	}
+
+	Ok(())
+}
@@ -11,13 +11,55 @@ pub enum Value {
 	Struct(HashMap<StructKey, Value>),
 }
 
+impl Value {
+	/// Numeric interpretation of the value, if it has one. Strings, arrays, and
+	/// structs are not numeric.
+	pub fn numeric(&self) -> Option<f64> {
+		use excel::Field as F;
+		let number = match self {
+			Value::Scalar(field) => match field {
+				F::String(_) => return None,
+				F::Bool(value) => f64::from(u8::from(*value)),
+				F::I8(value) => (*value).into(),
+				F::I16(value) => (*value).into(),
+				F::I32(value) => (*value).into(),
+				F::I64(value) => *value as f64,
+				F::U8(value) => (*value).into(),
+				F::U16(value) => (*value).into(),
+				F::U32(value) => (*value).into(),
+				F::U64(value) => *value as f64,
+				F::F32(value) => (*value).into(),
+			},
+			Value::Icon(id) => (*id).into(),
+			Value::Reference(Reference::Scalar(value)) => (*value).into(),
+			Value::Reference(
+				Reference::Shallow { value, .. } | Reference::Populated { value, .. },
+			) => (*value).into(),
+			Value::Array(_) | Value::Struct(_) => return None,
+		};
+
+		Some(number)
+	}
+}
+
 #[derive(Debug)]
 pub enum Reference {
 	Scalar(i32),
+	/// Reference resolved to its target row, without reading the row's fields.
+	Shallow {
+		value: u32,
+		sheet: String,
+		row_id: u32,
+		display: Option<Box<Value>>,
+		/// Whether the target row was not read as it is already being read
+		/// further up the reference chain.
+		circular: bool,
+	},
 	Populated {
 		value: u32,
 		sheet: String,
 		row_id: u32,
+		display: Option<Box<Value>>,
 		fields: Box<Value>,
 	},
 }
@@ -27,3 +69,9 @@ pub struct StructKey {
 	pub name: String,
 	pub language: excel::Language,
 }
+
+/// Game path of an icon texture, excluding extension.
+pub fn icon_path(id: u32) -> String {
+	let group = (id / 1000) * 1000;
+	format!("ui/icon/{group:0>6}/{id:0>6}")
+}
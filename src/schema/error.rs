@@ -1,3 +1,5 @@
+use crate::error::{classify_failure, Classify, ErrorKind};
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
 	#[error("unknown schema source \"{0}\"")]
@@ -6,6 +8,19 @@ pub enum Error {
 	#[error("invalid schema version \"{0}\"")]
 	InvalidVersion(String),
 
+	/// The requested schema does not describe the game version being read. Reading
+	/// with it would produce incorrect field names.
+	#[error(
+		"schema version \"{schema}\" does not support game version {game_version}{}",
+		suggestion.as_ref().map(|suggestion| format!(", try \"{suggestion}\"")).unwrap_or_default()
+	)]
+	Incompatible {
+		schema: String,
+		game_version: String,
+		/// Nearest schema version that supports the game version, if any.
+		suggestion: Option<String>,
+	},
+
 	#[error(transparent)]
 	Failure(#[from] anyhow::Error),
 }
@@ -21,4 +36,15 @@ impl From<ironworks_schema::Error> for Error {
 	}
 }
 
+impl Classify for Error {
+	fn kind(&self) -> ErrorKind {
+		match self {
+			Self::UnknownSource(..) | Self::InvalidVersion(..) | Self::Incompatible { .. } => {
+				ErrorKind::Invalid
+			}
+			Self::Failure(inner) => classify_failure(inner),
+		}
+	}
+}
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -1,10 +1,10 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{path::Path, sync::Arc};
 
 use anyhow::anyhow;
 use ironworks_schema::exdschema;
 use serde::Deserialize;
 
-use crate::{data, utility::anyhow::Anyhow, version::VersionKey};
+use crate::{config::Validator, data, utility::anyhow::Anyhow, version::VersionKey};
 
 use super::{
 	error::{Error, Result},
@@ -18,6 +18,13 @@ pub struct Config {
 	directory: String,
 }
 
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.check("remote", !self.remote.is_empty(), "must not be empty");
+		validator.writable_directory("directory", Path::new(&self.directory));
+	}
+}
+
 pub struct ExdSchema {
 	data: Arc<data::Data>,
 
@@ -42,6 +49,75 @@ impl ExdSchema {
 	}
 }
 
+impl ExdSchema {
+	/// Resolve the specifier for a reference at a requested game version, if it
+	/// is compatible with the game version being read.
+	fn compatible_specifier(
+		&self,
+		reference: &str,
+		requested_game_version: &str,
+		game_version: &str,
+	) -> Result<Option<exdschema::Specifier>> {
+		resolve_compatible(
+			|game_version| self.provider.specifier(reference, game_version),
+			|specifier| specifier.game_version().to_string(),
+			requested_game_version,
+			game_version,
+		)
+	}
+
+	/// Find the nearest schema version supporting a game version - preferring the
+	/// requested reference, falling back to the default.
+	fn suggest(&self, reference: &str, game_version: &str) -> Option<String> {
+		let default_reference = self
+			.default
+			.split_once('-')
+			.map_or(self.default.as_str(), |(reference, _)| reference);
+
+		[reference, default_reference]
+			.into_iter()
+			.find_map(|reference| {
+				self.compatible_specifier(reference, game_version, game_version)
+					.ok()
+					.flatten()
+			})
+			.map(|specifier| format!("{}-{}", specifier.reference(), specifier.game_version()))
+	}
+}
+
+/// Resolve the schema for a requested game version, if it is the schema that
+/// applies to the game version being read. Game versions resolve to the schema
+/// of the nearest version at or before them, so a request naming a different
+/// game version is only compatible if both resolve to the same schema. A
+/// schema for any other game version will silently produce incorrect field
+/// names.
+fn resolve_compatible<S>(
+	resolve: impl Fn(&str) -> Result<S, ironworks_schema::Error>,
+	schema_game_version: impl Fn(&S) -> String,
+	requested_game_version: &str,
+	game_version: &str,
+) -> Result<Option<S>> {
+	use ironworks_schema::Error as SE;
+	use ironworks_schema::ErrorValue as SEV;
+	let resolve = |game_version: &str| match resolve(game_version) {
+		Ok(specifier) => Ok(Some(specifier)),
+		Err(SE::NotFound(SEV::Version(_))) => Ok(None),
+		Err(error) => Err(Error::from(error)),
+	};
+
+	let Some(current) = resolve(game_version)? else {
+		return Ok(None);
+	};
+	if requested_game_version == game_version {
+		return Ok(Some(current));
+	}
+
+	let requested = resolve(requested_game_version)?
+		.filter(|requested| schema_game_version(requested) == schema_game_version(&current));
+
+	Ok(requested)
+}
+
 impl Source for ExdSchema {
 	fn ready(&self) -> bool {
 		// The backing git repository is cloned as part of `::new`, so if this is
@@ -63,24 +139,29 @@ impl Source for ExdSchema {
 	) -> Result<String> {
 		let schema_version = schema_version.unwrap_or(&self.default);
 
-		let split = schema_version.splitn(2, '-').collect::<Vec<_>>();
-		let (reference, game_version) = match split[..] {
-			[reference, game_version] => (reference, Cow::Borrowed(game_version)),
-			// Errors here are effectively a full failure, we need the game version to resolve within the schema
-			[reference] => (
-				reference,
-				self.data
-					.version(version_key)
-					.anyhow()?
-					.excel()
-					.version()
-					.anyhow()?
-					.into(),
-			),
-			_ => unreachable!("splitn should ensure this vec contains 1 or 2 entries"),
+		// Errors here are effectively a full failure, we need the game version to resolve within the schema
+		let game_version = self
+			.data
+			.version(version_key)
+			.anyhow()?
+			.excel()
+			.version()
+			.anyhow()?;
+
+		let (reference, requested_game_version) = match schema_version.split_once('-') {
+			Some((reference, requested_game_version)) => (reference, requested_game_version),
+			None => (schema_version, game_version.as_str()),
 		};
 
-		let specifier = self.provider.specifier(reference, &game_version)?;
+		let Some(specifier) =
+			self.compatible_specifier(reference, requested_game_version, &game_version)?
+		else {
+			return Err(Error::Incompatible {
+				schema: schema_version.to_string(),
+				suggestion: self.suggest(reference, &game_version),
+				game_version,
+			});
+		};
 
 		Ok(format!(
 			"{}-{}",
@@ -98,3 +179,67 @@ impl Source for ExdSchema {
 		Ok(Box::new(schema))
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use ironworks_schema::{Error as SE, ErrorValue as SEV};
+	use pretty_assertions::assert_eq;
+
+	use super::*;
+
+	// Schema changes were made at each of these game versions.
+	const SCHEMA_VERSIONS: &[&str] = &["2024.01.01", "2024.03.01"];
+
+	fn test_resolve(requested: &str, current: &str) -> Option<String> {
+		resolve_compatible(
+			|game_version| {
+				SCHEMA_VERSIONS
+					.iter()
+					.rev()
+					.find(|version| **version <= game_version)
+					.map(|version| version.to_string())
+					.ok_or_else(|| SE::NotFound(SEV::Version(game_version.into())))
+			},
+			|version| version.clone(),
+			requested,
+			current,
+		)
+		.expect("resolution should not fail")
+	}
+
+	#[test]
+	fn same_game_version() {
+		assert_eq!(
+			test_resolve("2024.02.15", "2024.02.15"),
+			Some("2024.01.01".to_string())
+		);
+	}
+
+	#[test]
+	fn earlier_game_version_sharing_schema() {
+		assert_eq!(
+			test_resolve("2024.01.20", "2024.02.15"),
+			Some("2024.01.01".to_string())
+		);
+	}
+
+	#[test]
+	fn later_game_version_sharing_schema() {
+		assert_eq!(
+			test_resolve("2024.02.15", "2024.01.20"),
+			Some("2024.01.01".to_string())
+		);
+	}
+
+	#[test]
+	fn game_version_across_schema_change() {
+		assert_eq!(test_resolve("2024.02.15", "2024.03.05"), None);
+		assert_eq!(test_resolve("2024.03.05", "2024.02.15"), None);
+	}
+
+	#[test]
+	fn game_version_before_any_schema() {
+		assert_eq!(test_resolve("2023.12.01", "2023.12.01"), None);
+		assert_eq!(test_resolve("2024.01.20", "2023.12.01"), None);
+	}
+}
@@ -6,7 +6,7 @@ use serde::Deserialize;
 use tokio::{select, time};
 use tokio_util::sync::CancellationToken;
 
-use crate::{data, version::VersionKey};
+use crate::{config::Validator, data, version::VersionKey};
 
 use super::{
 	error::{Error, Result},
@@ -34,6 +34,18 @@ pub struct Config {
 	exdschema: exdschema::Config,
 }
 
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.check("interval", self.interval > 0, "must be at least 1");
+		validator.check(
+			"default",
+			self.default.source == "exdschema",
+			format_args!("unknown schema source {:?}", self.default.source),
+		);
+		validator.scope("exdschema", |validator| self.exdschema.validate(validator));
+	}
+}
+
 // TODO: need a way to handle updating the repo
 // TODO: look into moving sources into a channel so i'm not leaning on send+sync for other shit
 pub struct Provider {
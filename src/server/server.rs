@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use futures::TryFutureExt;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+	analytics,
+	asset,
+	cache,
+	config::Validator,
+	data,
+	http,
+	read,
+	schema,
+	// search,
+	stack::Stack,
+	validation,
+	version,
+	view,
+};
+
+/// Configuration for the full server, matching the sections of the server's
+/// configuration file of the same names.
+#[derive(Debug)]
+pub struct Config {
+	// tracing: tracing::Config, - read individually.
+	pub analytics: analytics::Config,
+	pub asset: asset::Config,
+	pub cache: cache::Config,
+	pub data: data::Config,
+	pub http: http::Config,
+	pub read: read::Config,
+	pub version: version::Config,
+	pub schema: schema::Config,
+	// pub search: search::Config,
+	pub validation: validation::Config,
+	pub view: view::Config,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.scope("analytics", |validator| self.analytics.validate(validator));
+		validator.scope("asset", |validator| self.asset.validate(validator));
+		validator.scope("cache", |validator| self.cache.validate(validator));
+		validator.scope("data", |validator| self.data.validate(validator));
+		validator.scope("http", |validator| self.http.validate(validator));
+		validator.scope("read", |validator| self.read.validate(validator));
+		validator.scope("version", |validator| self.version.validate(validator));
+		validator.scope("schema", |validator| self.schema.validate(validator));
+		validator.scope("validation", |validator| {
+			self.validation.validate(validator)
+		});
+		validator.scope("view", |validator| self.view.validate(validator));
+	}
+}
+
+pub struct ServerBuilder {
+	config: Config,
+	transforms: read::Transforms,
+}
+
+impl ServerBuilder {
+	/// Install custom value transforms, applied to every row read.
+	pub fn transforms(mut self, transforms: read::Transforms) -> Self {
+		self.transforms = transforms;
+		self
+	}
+
+	/// Build the server's services, and run them until cancelled.
+	pub async fn serve(self, shutdown_token: CancellationToken) -> anyhow::Result<()> {
+		let config = self.config;
+
+		let version = Arc::new(
+			version::Manager::new(config.version).context("failed to create version manager")?,
+		);
+		let cache = Arc::new(cache::Caches::new(config.cache));
+		let data = Arc::new(data::Data::new(config.data, &cache).context("failed to create data")?);
+		let asset = Arc::new(
+			asset::Service::new(config.asset, &cache, data.clone())
+				.context("failed to create asset service")?,
+		);
+		let analytics = Arc::new(
+			analytics::Analytics::new(config.analytics).context("failed to create analytics")?,
+		);
+		let read = Arc::new(read::Read::new(config.read).with_transforms(self.transforms));
+		let schema = Arc::new(
+			schema::Provider::new(config.schema, data.clone())
+				.context("failed to create schema provider")?,
+		);
+		// let search = Arc::new(search::Search::new(config.search, data.clone()).expect("TODO"));
+		let validation = Arc::new(validation::Validation::new(
+			config.validation,
+			data.clone(),
+			read.clone(),
+			schema.clone(),
+		));
+		let view = Arc::new(view::Views::new(
+			config.view,
+			Stack {
+				version: version.clone(),
+				data: data.clone(),
+				read: read.clone(),
+				schema: schema.clone(),
+			},
+		));
+
+		tokio::try_join!(
+			version.start(shutdown_token.clone()),
+			data.start(shutdown_token.clone(), &version)
+				.map_err(anyhow::Error::from),
+			schema
+				.start(shutdown_token.clone())
+				.map_err(anyhow::Error::from),
+			analytics.start(shutdown_token.clone()),
+			validation.start(shutdown_token.clone()),
+			view.start(shutdown_token.clone()),
+			// search
+			// 	.start(shutdown_token.child_token())
+			// 	.map_err(anyhow::Error::from),
+			http::serve(
+				shutdown_token,
+				config.http,
+				analytics.clone(),
+				asset,
+				cache,
+				data.clone(),
+				read,
+				schema.clone(),
+				// search.clone(),
+				validation.clone(),
+				version.clone(),
+				view.clone(),
+			),
+		)
+		.context("failed to start server")?;
+
+		Ok(())
+	}
+}
+
+/// The full boilmaster server, including the HTTP API and its background
+/// services. Embedders may use this in place of the binary to customise
+/// services, such as installing value transforms.
+pub struct Server;
+
+impl Server {
+	pub fn builder(config: Config) -> ServerBuilder {
+		ServerBuilder {
+			config,
+			transforms: read::Transforms::default(),
+		}
+	}
+}
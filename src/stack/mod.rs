@@ -0,0 +1,7 @@
+mod request;
+mod stack;
+
+pub use {
+	request::{RowRequest, RowResponse, SheetRequest, SheetResponse, SheetRow},
+	stack::{Config, Stack, StackBuilder},
+};
@@ -0,0 +1,104 @@
+use ironworks::excel;
+
+use crate::{read, schema, version::VersionKey};
+
+/// Default depth references are followed to when reading rows.
+const DEPTH_DEFAULT: u8 = 2;
+
+/// Default maximum number of rows read by a sheet request.
+const LIMIT_DEFAULT: usize = 100;
+
+/// Request to read a single row.
+#[derive(Debug, Clone)]
+pub struct RowRequest {
+	/// Name of the sheet to read.
+	pub sheet: String,
+	pub row_id: u32,
+	pub subrow_id: u16,
+	/// Name of the version to read from. Defaults to the latest version.
+	pub version: Option<String>,
+	/// Schema to read the row with. Defaults to the configured default schema.
+	pub schema: Option<schema::Specifier>,
+	/// Language to read. Defaults to the configured default language.
+	pub language: Option<excel::Language>,
+	/// Fields to read.
+	pub filter: read::Filter,
+	/// Depth references are followed to.
+	pub depth: u8,
+}
+
+impl RowRequest {
+	pub fn new(sheet: impl Into<String>, row_id: u32) -> Self {
+		Self {
+			sheet: sheet.into(),
+			row_id,
+			subrow_id: 0,
+			version: None,
+			schema: None,
+			language: None,
+			filter: read::Filter::All,
+			depth: DEPTH_DEFAULT,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct RowResponse {
+	/// Key of the version the row was read from.
+	pub version: VersionKey,
+	/// Schema the row was read with.
+	pub schema: schema::CanonicalSpecifier,
+	pub value: read::Value,
+}
+
+/// Request to read a page of rows from a sheet, in row order.
+#[derive(Debug, Clone)]
+pub struct SheetRequest {
+	/// Name of the sheet to read.
+	pub sheet: String,
+	/// Name of the version to read from. Defaults to the latest version.
+	pub version: Option<String>,
+	/// Schema to read rows with. Defaults to the configured default schema.
+	pub schema: Option<schema::Specifier>,
+	/// Language to read. Defaults to the configured default language.
+	pub language: Option<excel::Language>,
+	/// Fields to read.
+	pub filter: read::Filter,
+	/// Depth references are followed to.
+	pub depth: u8,
+	/// Only read rows after this row and subrow ID.
+	pub after: Option<(u32, u16)>,
+	/// Maximum number of rows to read.
+	pub limit: usize,
+}
+
+impl SheetRequest {
+	pub fn new(sheet: impl Into<String>) -> Self {
+		Self {
+			sheet: sheet.into(),
+			version: None,
+			schema: None,
+			language: None,
+			filter: read::Filter::All,
+			depth: DEPTH_DEFAULT,
+			after: None,
+			limit: LIMIT_DEFAULT,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct SheetResponse {
+	/// Key of the version the rows were read from.
+	pub version: VersionKey,
+	/// Schema the rows were read with.
+	pub schema: schema::CanonicalSpecifier,
+	pub rows: Vec<SheetRow>,
+}
+
+#[derive(Debug)]
+pub struct SheetRow {
+	pub row_id: u32,
+	pub subrow_id: u16,
+	pub value: read::Value,
+}
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use futures::TryFutureExt;
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+	cache::{self, Caches},
+	config::Validator,
+	data::{self, Data},
+	error::{Error, ErrorKind, Result},
+	read::{self, Read},
+	schema,
+	version::{self, VersionKey},
+};
+
+use super::request::{RowRequest, RowResponse, SheetRequest, SheetResponse, SheetRow};
+
+/// Configuration for the services required to read game data, matching the
+/// sections of the server's configuration file of the same names.
+#[derive(Debug, Deserialize)]
+pub struct Config {
+	#[serde(default)]
+	pub cache: cache::Config,
+	pub data: data::Config,
+	pub read: read::Config,
+	pub schema: schema::Config,
+	pub version: version::Config,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.scope("cache", |validator| self.cache.validate(validator));
+		validator.scope("data", |validator| self.data.validate(validator));
+		validator.scope("read", |validator| self.read.validate(validator));
+		validator.scope("schema", |validator| self.schema.validate(validator));
+		validator.scope("version", |validator| self.version.validate(validator));
+	}
+}
+
+pub struct StackBuilder {
+	config: Config,
+	transforms: read::Transforms,
+}
+
+impl StackBuilder {
+	/// Install custom value transforms, applied to every row read.
+	pub fn transforms(mut self, transforms: read::Transforms) -> Self {
+		self.transforms = transforms;
+		self
+	}
+
+	pub fn build(self) -> anyhow::Result<Stack> {
+		let config = self.config;
+
+		let version = Arc::new(
+			version::Manager::new(config.version).context("failed to create version manager")?,
+		);
+		let caches = Caches::new(config.cache);
+		let data = Arc::new(Data::new(config.data, &caches).context("failed to create data")?);
+		let read = Arc::new(Read::new(config.read).with_transforms(self.transforms));
+		let schema = Arc::new(
+			schema::Provider::new(config.schema, data.clone())
+				.context("failed to create schema provider")?,
+		);
+
+		Ok(Stack {
+			version,
+			data,
+			read,
+			schema,
+		})
+	}
+}
+
+/// The services required to read game data, for use of boilmaster as a library
+/// without the HTTP server.
+pub struct Stack {
+	pub version: Arc<version::Manager>,
+	pub data: Arc<Data>,
+	pub read: Arc<Read>,
+	pub schema: Arc<schema::Provider>,
+}
+
+impl Stack {
+	pub fn builder(config: Config) -> StackBuilder {
+		StackBuilder {
+			config,
+			transforms: read::Transforms::default(),
+		}
+	}
+
+	/// Run the stack's background services until cancelled. Versions are only
+	/// readable once they have been prepared by these services.
+	pub async fn start(&self, cancel: CancellationToken) -> anyhow::Result<()> {
+		tokio::try_join!(
+			self.version.start(cancel.clone()),
+			self.data
+				.start(cancel.clone(), &self.version)
+				.map_err(anyhow::Error::from),
+			self.schema
+				.start(cancel.clone())
+				.map_err(anyhow::Error::from),
+		)?;
+
+		Ok(())
+	}
+
+	/// Read a single row. Errors are classified by [`ErrorKind`], to inform
+	/// how they are reported and whether the read is worth retrying.
+	pub async fn read_row(&self, request: RowRequest) -> Result<RowResponse> {
+		let version_key = self.resolve_version(request.version.as_deref())?;
+		let excel = self.data.version(version_key)?.excel();
+		let specifier = self.schema.canonicalize(request.schema, version_key)?;
+		let language = request
+			.language
+			.unwrap_or_else(|| self.read.default_language());
+
+		let (read, schema_provider) = (self.read.clone(), self.schema.clone());
+		let task_specifier = specifier.clone();
+		let value = self
+			.data
+			.blocking(move || -> Result<_> {
+				let schema = schema_provider.schema(task_specifier)?;
+				let value = read.read(
+					&excel,
+					schema.as_ref(),
+					&request.sheet,
+					request.row_id,
+					request.subrow_id,
+					language,
+					&request.filter,
+					request.depth,
+					&|_| true,
+					&CancellationToken::new(),
+				)?;
+				Ok(value)
+			})
+			.await??;
+
+		Ok(RowResponse {
+			version: version_key,
+			schema: specifier,
+			value,
+		})
+	}
+
+	/// Read a page of rows from a sheet.
+	pub async fn read_sheet(&self, request: SheetRequest) -> Result<SheetResponse> {
+		let version_key = self.resolve_version(request.version.as_deref())?;
+		let excel = self.data.version(version_key)?.excel();
+		let specifier = self.schema.canonicalize(request.schema, version_key)?;
+		let language = request
+			.language
+			.unwrap_or_else(|| self.read.default_language());
+
+		let (read, schema_provider) = (self.read.clone(), self.schema.clone());
+		let task_specifier = specifier.clone();
+		let rows = self
+			.data
+			.blocking(move || -> Result<_> {
+				let schema = schema_provider.schema(task_specifier)?;
+				let cancel = CancellationToken::new();
+
+				let sheet = excel
+					.sheet(request.sheet.as_str())
+					.map_err(read::Error::from)?;
+				let mut builder = sheet.with();
+				builder.language(language);
+
+				builder
+					.iter()
+					.map(|row| (row.row_id(), row.subrow_id()))
+					.filter(|specifier| Some(*specifier) > request.after)
+					.take(request.limit)
+					.map(|(row_id, subrow_id)| {
+						let value = read.read(
+							&excel,
+							schema.as_ref(),
+							&request.sheet,
+							row_id,
+							subrow_id,
+							language,
+							&request.filter,
+							request.depth,
+							&|_| true,
+							&cancel,
+						)?;
+						Ok(SheetRow {
+							row_id,
+							subrow_id,
+							value,
+						})
+					})
+					.collect::<Result<Vec<_>>>()
+			})
+			.await??;
+
+		Ok(SheetResponse {
+			version: version_key,
+			schema: specifier,
+			rows,
+		})
+	}
+
+	fn resolve_version(&self, name: Option<&str>) -> Result<VersionKey> {
+		self.version.resolve(name).ok_or_else(|| {
+			Error::new(
+				ErrorKind::NotFound,
+				anyhow!("unknown version \"{}\"", name.unwrap_or("latest")),
+			)
+		})
+	}
+}
@@ -0,0 +1,190 @@
+use std::{
+	fs,
+	io::{self, Write},
+	path::{Path, PathBuf},
+	sync::Mutex,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::config::Validator;
+
+#[derive(Debug, Deserialize)]
+pub struct RotationConfig {
+	/// Rotate the file once it would exceed this size, in bytes.
+	size: Option<u64>,
+
+	/// Rotate the file at the start of each interval.
+	interval: Option<RotationInterval>,
+
+	/// Number of rotated files to keep alongside the active file.
+	#[serde(default = "default_keep")]
+	keep: usize,
+}
+
+impl Default for RotationConfig {
+	fn default() -> Self {
+		Self {
+			size: None,
+			interval: None,
+			keep: default_keep(),
+		}
+	}
+}
+
+fn default_keep() -> usize {
+	5
+}
+
+impl RotationConfig {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.check("size", self.size != Some(0), "must be at least 1");
+		validator.check("keep", self.keep > 0, "must be at least 1");
+	}
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RotationInterval {
+	Hourly,
+	Daily,
+}
+
+impl RotationInterval {
+	fn seconds(self) -> u64 {
+		match self {
+			Self::Hourly => 60 * 60,
+			Self::Daily => 60 * 60 * 24,
+		}
+	}
+}
+
+/// Log file writer, rotating the file by size and/or time. Rotated files are
+/// suffixed with a number, from `.1` for the most recent.
+pub struct RollingFile {
+	path: PathBuf,
+	rotation: RotationConfig,
+	state: Mutex<State>,
+}
+
+struct State {
+	file: fs::File,
+	size: u64,
+	period: Option<u64>,
+}
+
+impl RollingFile {
+	pub fn new(path: PathBuf, rotation: RotationConfig) -> io::Result<Self> {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		let file = open(&path)?;
+		let metadata = file.metadata()?;
+
+		// Pick up the period of any existing file, so a restart after an interval
+		// boundary still rotates it.
+		let period = rotation.interval.map(|interval| {
+			let modified = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+			period(modified, interval)
+		});
+
+		Ok(Self {
+			path,
+			state: Mutex::new(State {
+				file,
+				size: metadata.len(),
+				period,
+			}),
+			rotation,
+		})
+	}
+
+	fn should_rotate(&self, state: &State, incoming: usize) -> Option<Option<u64>> {
+		let period = self
+			.rotation
+			.interval
+			.map(|interval| period(SystemTime::now(), interval));
+
+		let size_exceeded = self.rotation.size.map_or(false, |limit| {
+			state.size > 0 && state.size + u64::try_from(incoming).unwrap_or(u64::MAX) > limit
+		});
+
+		match size_exceeded || period != state.period {
+			true => Some(period),
+			false => None,
+		}
+	}
+
+	fn rotate(&self, state: &mut State) -> io::Result<()> {
+		state.file.flush()?;
+
+		// Shift existing rotations up by one, dropping the oldest.
+		let keep = self.rotation.keep;
+		let _ = fs::remove_file(self.rotated_path(keep));
+		for index in (1..keep).rev() {
+			let from = self.rotated_path(index);
+			if from.exists() {
+				fs::rename(from, self.rotated_path(index + 1))?;
+			}
+		}
+		fs::rename(&self.path, self.rotated_path(1))?;
+
+		state.file = open(&self.path)?;
+		state.size = 0;
+
+		Ok(())
+	}
+
+	fn rotated_path(&self, index: usize) -> PathBuf {
+		let mut path = self.path.clone().into_os_string();
+		path.push(format!(".{index}"));
+		path.into()
+	}
+}
+
+impl io::Write for &RollingFile {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let mut state = self.state.lock().expect("poisoned");
+
+		if let Some(period) = self.should_rotate(&state, buf.len()) {
+			// Failing to rotate shouldn't lose the log line - keep writing to the
+			// current file, and try again on the next write.
+			match self.rotate(&mut state) {
+				Ok(()) => state.period = period,
+				Err(error) => eprintln!("failed to rotate log file {:?}: {error}", self.path),
+			}
+		}
+
+		let written = state.file.write(buf)?;
+		state.size += u64::try_from(written).unwrap_or(u64::MAX);
+
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.state.lock().expect("poisoned").file.flush()
+	}
+}
+
+impl<'a> MakeWriter<'a> for RollingFile {
+	type Writer = &'a RollingFile;
+
+	fn make_writer(&'a self) -> Self::Writer {
+		self
+	}
+}
+
+fn open(path: &Path) -> io::Result<fs::File> {
+	fs::OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn period(time: SystemTime, interval: RotationInterval) -> u64 {
+	let seconds = time
+		.duration_since(UNIX_EPOCH)
+		.map(|duration| duration.as_secs())
+		.unwrap_or(0);
+	seconds / interval.seconds()
+}
@@ -0,0 +1,5 @@
+mod file;
+mod sentry;
+mod tracing;
+
+pub use self::tracing::{init, Config, Guard};
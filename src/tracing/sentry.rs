@@ -0,0 +1,49 @@
+use anyhow::{Context, Result};
+use git_version::git_version;
+use sentry::{types::Dsn, ClientInitGuard, ClientOptions};
+use serde::Deserialize;
+use tracing::Subscriber;
+use tracing_subscriber::{registry::LookupSpan, Layer};
+
+use crate::config::Validator;
+
+const RELEASE: &str = git_version!(prefix = "boilmaster@", fallback = "unknown");
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SentryConfig {
+	/// Sentry DSN to send error reports to. Reporting is disabled if unset.
+	dsn: Option<String>,
+
+	/// Environment name attached to reports.
+	environment: Option<String>,
+}
+
+impl SentryConfig {
+	pub fn validate(&self, validator: &mut Validator) {
+		if let Some(Err(error)) = self.dsn.as_deref().map(str::parse::<Dsn>) {
+			validator.problem("dsn", format!("invalid DSN: {error}"));
+		}
+	}
+}
+
+/// Start the Sentry client, returning a layer that reports error events to it.
+/// Lower level events are attached to reports as breadcrumbs, and panics are
+/// reported by the client itself. Reports still pending when the guard is
+/// dropped are flushed.
+pub fn init<S>(config: SentryConfig) -> Result<Option<(ClientInitGuard, impl Layer<S>)>>
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	let Some(dsn) = config.dsn else {
+		return Ok(None);
+	};
+
+	let guard = sentry::init(ClientOptions {
+		dsn: Some(dsn.parse().context("invalid DSN")?),
+		release: Some(RELEASE.into()),
+		environment: config.environment.map(Into::into),
+		..Default::default()
+	});
+
+	Ok(Some((guard, sentry::integrations::tracing::layer())))
+}
@@ -0,0 +1,145 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use anyhow::{Context, Result};
+use figment::value::magic::RelativePathBuf;
+use serde::{de, Deserialize};
+use tracing::metadata::LevelFilter;
+use tracing_subscriber::{filter, layer::SubscriberExt, util::SubscriberInitExt, Layer, Registry};
+
+use crate::config::Validator;
+
+use super::{
+	file::{RollingFile, RotationConfig},
+	sentry::{self, SentryConfig},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+	filters: TracingFilters,
+
+	/// Additional log files, keyed by name, each with their own filters.
+	#[serde(default)]
+	files: HashMap<String, FileConfig>,
+
+	/// Sentry error reporting.
+	#[serde(default)]
+	sentry: SentryConfig,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileConfig {
+	path: RelativePathBuf,
+	filters: TracingFilters,
+	#[serde(default)]
+	rotation: RotationConfig,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		for (name, file) in &self.files {
+			validator.scope("files", |validator| {
+				validator.scope(name, |validator| {
+					let path = file.path.relative();
+					if let Some(parent) = path.parent() {
+						validator.writable_directory("path", parent);
+					}
+					validator.scope("rotation", |validator| file.rotation.validate(validator));
+				})
+			});
+		}
+
+		validator.scope("sentry", |validator| self.sentry.validate(validator));
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct TracingFilters {
+	default: ConfigLevelFilter,
+
+	#[serde(flatten)]
+	targets: HashMap<String, ConfigLevelFilter>,
+}
+
+#[repr(transparent)]
+struct ConfigLevelFilter(LevelFilter);
+
+impl From<ConfigLevelFilter> for LevelFilter {
+	fn from(filter: ConfigLevelFilter) -> Self {
+		filter.0
+	}
+}
+
+impl fmt::Debug for ConfigLevelFilter {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.0.fmt(f)
+	}
+}
+
+impl<'de> Deserialize<'de> for ConfigLevelFilter {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		let string = String::deserialize(deserializer)?;
+		let level_filter = LevelFilter::from_str(&string).map_err(de::Error::custom)?;
+		Ok(Self(level_filter))
+	}
+}
+
+/// Keeps error reporting running. Pending reports are flushed when dropped.
+pub struct Guard {
+	_sentry: Option<::sentry::ClientInitGuard>,
+}
+
+pub fn init(config: Config) -> Result<Guard> {
+	// TODO: consider enabling this with a config flag or something tracing.console?
+	let console_filter = filter::Targets::new()
+		.with_target("tokio", LevelFilter::TRACE)
+		.with_target("runtime", LevelFilter::TRACE);
+
+	let tracing_filter = filter::Targets::new()
+		.with_default(config.filters.default)
+		.with_targets(config.filters.targets);
+
+	let mut layers = config
+		.files
+		.into_iter()
+		.map(|(name, file)| {
+			let path = file.path.relative();
+			let writer = RollingFile::new(path.clone(), file.rotation)
+				.with_context(|| format!("failed to open {name} log file {path:?}"))?;
+
+			let filter = filter::Targets::new()
+				.with_default(file.filters.default)
+				.with_targets(file.filters.targets);
+
+			let layer = tracing_subscriber::fmt::layer()
+				.with_ansi(false)
+				.with_writer(writer)
+				.with_filter(filter)
+				.boxed();
+
+			Ok(layer)
+		})
+		.collect::<Result<Vec<Box<dyn Layer<Registry> + Send + Sync>>>>()?;
+
+	let sentry_guard = match sentry::init(config.sentry).context("failed to set up Sentry")? {
+		Some((guard, layer)) => {
+			layers.push(layer.boxed());
+			Some(guard)
+		}
+		None => None,
+	};
+
+	// TODO: env filter (will need feature enabled). consider enabling pulling from log! too.
+	// TODO: now that i have config working, is it worth using env filter here or should i handle it via config env?
+	tracing_subscriber::registry()
+		.with(layers)
+		.with(console_subscriber::spawn().with_filter(console_filter))
+		.with(tracing_subscriber::fmt::layer().with_filter(tracing_filter))
+		.init();
+
+	Ok(Guard {
+		_sentry: sentry_guard,
+	})
+}
@@ -0,0 +1,112 @@
+/// Real seconds per Eorzean hour. Eorzean time passes 3600/175 times faster
+/// than real time.
+pub const SECONDS_PER_HOUR: u64 = 175;
+
+/// Real seconds per weather period. Weather changes every 8 Eorzean hours.
+pub const SECONDS_PER_WEATHER_PERIOD: u64 = SECONDS_PER_HOUR * 8;
+
+/// Get the start of the weather period containing a unix timestamp.
+pub fn weather_period_start(unix_seconds: u64) -> u64 {
+	unix_seconds - unix_seconds % SECONDS_PER_WEATHER_PERIOD
+}
+
+/// Calculate the forecast target for the weather period containing a unix
+/// timestamp, in the range `0..100`. The weather of a zone is the first entry
+/// of its weather rate whose cumulative rate exceeds the target.
+pub fn forecast_target(unix_seconds: u64) -> u8 {
+	// The game performs this calculation with wrapping 32-bit arithmetic.
+	let bell = unix_seconds / SECONDS_PER_HOUR;
+	let increment = ((bell + 8 - (bell % 8)) % 24) as u32;
+	let total_days = (unix_seconds / (SECONDS_PER_HOUR * 24)) as u32;
+
+	let base = total_days.wrapping_mul(100).wrapping_add(increment);
+	let step1 = (base << 11) ^ base;
+	let step2 = (step1 >> 8) ^ step1;
+
+	(step2 % 100) as u8
+}
+
+/// Eorzean seconds per Eorzean day.
+pub const EORZEA_SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+// Eorzean time passes at 3600/175 = 144/7 times the rate of real time.
+const EORZEA_RATE_NUMERATOR: u64 = 144;
+const EORZEA_RATE_DENOMINATOR: u64 = 7;
+
+/// Point in Eorzean time, broken down into calendar units. Eorzean years are
+/// 12 moons of 32 suns (days), each 24 bells (hours) long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EorzeaTime {
+	/// Seconds since the Eorzean epoch.
+	pub timestamp: u64,
+	pub year: u64,
+	pub moon: u8,
+	pub sun: u8,
+	pub bell: u8,
+	pub minute: u8,
+}
+
+impl EorzeaTime {
+	pub fn from_timestamp(timestamp: u64) -> Self {
+		let minutes = timestamp / 60;
+		let bells = minutes / 60;
+		let suns = bells / 24;
+		let moons = suns / 32;
+
+		Self {
+			timestamp,
+			year: moons / 12 + 1,
+			moon: (moons % 12 + 1) as u8,
+			sun: (suns % 32 + 1) as u8,
+			bell: (bells % 24) as u8,
+			minute: (minutes % 60) as u8,
+		}
+	}
+}
+
+/// Convert a unix timestamp into an Eorzean timestamp. Returns `None` if the
+/// result is out of range.
+pub fn to_eorzea(unix_seconds: u64) -> Option<u64> {
+	Some(unix_seconds.checked_mul(EORZEA_RATE_NUMERATOR)? / EORZEA_RATE_DENOMINATOR)
+}
+
+/// Convert an Eorzean timestamp into the earliest unix timestamp at which that
+/// Eorzean time has been reached. Returns `None` if the result is out of range.
+pub fn to_real(eorzea_seconds: u64) -> Option<u64> {
+	Some(
+		eorzea_seconds
+			.checked_mul(EORZEA_RATE_DENOMINATOR)?
+			.div_ceil(EORZEA_RATE_NUMERATOR),
+	)
+}
+
+/// Find the next occurrence of a daily window of Eorzean time, as unix
+/// timestamps. Window bounds are Eorzean seconds since midnight - windows
+/// ending at or before their start span midnight. A window in progress at
+/// `unix_seconds` is considered the next occurrence. Returns `None` if the
+/// window is out of range.
+pub fn next_window(unix_seconds: u64, start: u64, end: u64) -> Option<(u64, u64)> {
+	let start = start % EORZEA_SECONDS_PER_DAY;
+	let duration = match (end % EORZEA_SECONDS_PER_DAY + EORZEA_SECONDS_PER_DAY - start)
+		% EORZEA_SECONDS_PER_DAY
+	{
+		0 => EORZEA_SECONDS_PER_DAY,
+		duration => duration,
+	};
+
+	let now = to_eorzea(unix_seconds)?;
+	let midnight = now - now % EORZEA_SECONDS_PER_DAY;
+
+	// Start from yesterday's window, as it may span midnight into today.
+	let mut window_start = midnight
+		.checked_add(start)?
+		.saturating_sub(EORZEA_SECONDS_PER_DAY);
+	while window_start.checked_add(duration)? <= now {
+		window_start = window_start.checked_add(EORZEA_SECONDS_PER_DAY)?;
+	}
+
+	Some((
+		to_real(window_start)?,
+		to_real(window_start.checked_add(duration)?)?,
+	))
+}
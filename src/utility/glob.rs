@@ -0,0 +1,104 @@
+use std::{fmt, str::FromStr};
+
+use regex::Regex;
+use serde::{de, Deserialize};
+
+/// A simple glob pattern for matching sheet names and similar paths.
+///
+/// `*` matches any sequence of characters (including `/`), and `?` matches any
+/// single character. All other characters match literally.
+#[derive(Clone)]
+pub struct Glob {
+	pattern: String,
+	regex: Regex,
+}
+
+impl Glob {
+	pub fn is_match(&self, input: &str) -> bool {
+		self.regex.is_match(input)
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.pattern
+	}
+}
+
+impl fmt::Debug for Glob {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		formatter.debug_tuple("Glob").field(&self.pattern).finish()
+	}
+}
+
+impl FromStr for Glob {
+	type Err = regex::Error;
+
+	fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+		let mut expression = String::from("^");
+		for character in pattern.chars() {
+			match character {
+				'*' => expression.push_str(".*"),
+				'?' => expression.push('.'),
+				other => expression.push_str(&regex::escape(other.encode_utf8(&mut [0; 4]))),
+			}
+		}
+		expression.push('$');
+
+		Ok(Self {
+			pattern: pattern.to_string(),
+			regex: Regex::new(&expression)?,
+		})
+	}
+}
+
+impl<'de> Deserialize<'de> for Glob {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		let raw = String::deserialize(deserializer)?;
+		raw.parse().map_err(de::Error::custom)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn glob(pattern: &str) -> Glob {
+		pattern.parse().expect("pattern should parse")
+	}
+
+	#[test]
+	fn literal() {
+		assert!(glob("Item").is_match("Item"));
+		assert!(!glob("Item").is_match("ItemFood"));
+	}
+
+	#[test]
+	fn prefix() {
+		let pattern = glob("quest/*");
+		assert!(pattern.is_match("quest/000/ClsHrv001_00000"));
+		assert!(!pattern.is_match("Quest"));
+	}
+
+	#[test]
+	fn suffix() {
+		let pattern = glob("*Transient");
+		assert!(pattern.is_match("ActionTransient"));
+		assert!(!pattern.is_match("ActionTransientData"));
+	}
+
+	#[test]
+	fn single_character() {
+		let pattern = glob("Item?");
+		assert!(pattern.is_match("Item2"));
+		assert!(!pattern.is_match("Item"));
+	}
+
+	#[test]
+	fn escapes_regex_characters() {
+		let pattern = glob("custom/(a).b");
+		assert!(pattern.is_match("custom/(a).b"));
+		assert!(!pattern.is_match("custom/a_b"));
+	}
+}
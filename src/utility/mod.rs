@@ -1,4 +1,6 @@
 pub mod anyhow;
+pub mod eorzea;
 pub mod field;
+pub mod glob;
 pub mod jsonschema;
 pub mod warnings;
@@ -0,0 +1,3 @@
+mod validation;
+
+pub use validation::{Config, Report, Validation};
@@ -0,0 +1,190 @@
+use std::{
+	collections::HashMap,
+	fs,
+	path::PathBuf,
+	sync::{Arc, RwLock},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use figment::value::magic::RelativePathBuf;
+use serde::{Deserialize, Serialize};
+use tokio::{select, time};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+	config::Validator,
+	data::Data,
+	read::{Read, ValidationReport},
+	schema::{self, CanonicalSpecifier},
+	version::VersionKey,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+	/// Whether versions should be validated against the default schema once
+	/// they have been prepared.
+	#[serde(default)]
+	enabled: bool,
+
+	/// Directory reports are written to, as `<version key>.json`. Reports are
+	/// only held in memory if unset.
+	#[serde(default)]
+	directory: Option<RelativePathBuf>,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		if let (true, Some(directory)) = (self.enabled, &self.directory) {
+			validator.writable_directory("directory", &directory.relative());
+		}
+	}
+}
+
+/// Validation report for a single version.
+#[derive(Debug, Serialize)]
+pub struct Report {
+	pub key: VersionKey,
+	/// Schema the version was validated against.
+	pub schema: CanonicalSpecifier,
+	/// Time the report was completed, in seconds since the unix epoch.
+	pub completed: u64,
+	#[serde(flatten)]
+	pub report: ValidationReport,
+}
+
+/// Cross-checks prepared versions against the default schema, publishing a
+/// report of any problems found for each.
+pub struct Validation {
+	enabled: bool,
+	directory: Option<PathBuf>,
+
+	data: Arc<Data>,
+	read: Arc<Read>,
+	schema: Arc<schema::Provider>,
+
+	reports: RwLock<HashMap<VersionKey, Arc<Report>>>,
+}
+
+impl Validation {
+	pub fn new(
+		config: Config,
+		data: Arc<Data>,
+		read: Arc<Read>,
+		schema: Arc<schema::Provider>,
+	) -> Self {
+		Self {
+			enabled: config.enabled,
+			directory: config.directory.map(|directory| directory.relative()),
+			data,
+			read,
+			schema,
+			reports: Default::default(),
+		}
+	}
+
+	pub fn enabled(&self) -> bool {
+		self.enabled
+	}
+
+	/// Get the report for a version, if it has been validated.
+	pub fn report(&self, key: VersionKey) -> Option<Arc<Report>> {
+		self.reports.read().expect("poisoned").get(&key).cloned()
+	}
+
+	pub async fn start(&self, cancel: CancellationToken) -> Result<()> {
+		if !self.enabled {
+			return Ok(());
+		}
+
+		let mut receiver = self.data.subscribe();
+
+		// Versions can't be validated until the schema is available.
+		while !self.schema.ready() {
+			select! {
+				_ = time::sleep(Duration::from_secs(1)) => {},
+				_ = cancel.cancelled() => return Ok(()),
+			}
+		}
+
+		loop {
+			let keys = receiver.borrow_and_update().clone();
+			for key in keys {
+				if self.report(key).is_some() {
+					continue;
+				}
+
+				select! {
+					result = self.validate_version(key) => {
+						if let Err(error) = result {
+							tracing::warn!(%key, ?error, "failed to validate version");
+						}
+					}
+					_ = cancel.cancelled() => return Ok(()),
+				}
+			}
+
+			select! {
+				result = receiver.changed() => {
+					if result.is_err() {
+						break;
+					}
+				}
+				_ = cancel.cancelled() => break,
+			}
+		}
+
+		Ok(())
+	}
+
+	async fn validate_version(&self, key: VersionKey) -> Result<()> {
+		tracing::info!(%key, "validating version");
+
+		let excel = self.data.version(key)?.excel();
+		let specifier = self.schema.canonicalize(None, key)?;
+
+		// Validation reads every row of the version - it's run outside the data
+		// pool to avoid starving requests of it.
+		let read = self.read.clone();
+		let schema_provider = self.schema.clone();
+		let schema_specifier = specifier.clone();
+		let report = tokio::task::spawn_blocking(move || -> Result<_> {
+			let schema = schema_provider.schema(schema_specifier)?;
+			Ok(read.validate(&excel, schema.as_ref())?)
+		})
+		.await??;
+
+		let completed = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |duration| duration.as_secs());
+
+		let report = Report {
+			key,
+			schema: specifier,
+			completed,
+			report,
+		};
+
+		tracing::info!(
+			%key,
+			sheets = report.report.sheets,
+			issues = report.report.issues.len(),
+			"version validated"
+		);
+
+		if let Some(directory) = &self.directory {
+			fs::create_dir_all(directory)?;
+			let path = directory.join(format!("{key}.json"));
+			let file = fs::File::create(&path)
+				.with_context(|| format!("failed to create {}", path.display()))?;
+			serde_json::to_writer_pretty(file, &report)?;
+		}
+
+		self.reports
+			.write()
+			.expect("poisoned")
+			.insert(key, Arc::new(report));
+
+		Ok(())
+	}
+}
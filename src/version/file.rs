@@ -0,0 +1,60 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{Context, Result};
+use figment::value::magic::RelativePathBuf;
+use futures::future::BoxFuture;
+use nonempty::NonEmpty;
+use serde::Deserialize;
+
+use crate::config::Validator;
+
+use super::provider::{Patch, VersionProvider};
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+	path: RelativePathBuf,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.exists("path", &self.path.relative());
+	}
+}
+
+/// Version provider reading patch lists from a local JSON file, for use in
+/// environments without access to thaliak. The file maps repository names to
+/// their patches, oldest-first, and is re-read on every update.
+pub struct Provider {
+	path: PathBuf,
+}
+
+impl Provider {
+	pub fn new(config: Config) -> Self {
+		Self {
+			path: config.path.relative(),
+		}
+	}
+
+	#[tracing::instrument(level = "debug", skip(self))]
+	async fn read_patch_list(&self, repository: String) -> Result<NonEmpty<Patch>> {
+		let bytes = tokio::fs::read(&self.path)
+			.await
+			.with_context(|| format!("failed to read patch list file {:?}", self.path))?;
+
+		let mut repositories = serde_json::from_slice::<HashMap<String, Vec<Patch>>>(&bytes)
+			.with_context(|| format!("failed to parse patch list file {:?}", self.path))?;
+
+		let patches = repositories
+			.remove(&repository)
+			.ok_or_else(|| anyhow::anyhow!("no patches listed for repository \"{repository}\""))?;
+
+		NonEmpty::from_vec(patches)
+			.ok_or_else(|| anyhow::anyhow!("patch list for {repository} is empty"))
+	}
+}
+
+impl VersionProvider for Provider {
+	fn patch_list(&self, repository: String) -> BoxFuture<'_, Result<NonEmpty<Patch>>> {
+		Box::pin(self.read_patch_list(repository))
+	}
+}
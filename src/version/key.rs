@@ -13,22 +13,33 @@ use super::version::Version;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct VersionKey(u64);
 
-impl From<&Version> for VersionKey {
-	fn from(version: &Version) -> Self {
+impl VersionKey {
+	/// Derive a key from patch names. Each name is fed, in order, into a
+	/// default-seeded SeaHash hasher via Rust's `Hash` implementation for `str`.
+	/// Versions use the latest patch of each of their repositories, ordered by
+	/// repository slot (boot, ffxiv, ex1, ...).
+	pub fn from_patches<'a>(patches: impl IntoIterator<Item = &'a str>) -> Self {
 		let mut hasher = SeaHasher::new();
 
-		for patch in version
-			.repositories
-			.iter()
-			.map(|repository| repository.latest())
-		{
-			patch.name.hash(&mut hasher);
+		for patch in patches {
+			patch.hash(&mut hasher);
 		}
 
 		Self(hasher.finish())
 	}
 }
 
+impl From<&Version> for VersionKey {
+	fn from(version: &Version) -> Self {
+		Self::from_patches(
+			version
+				.repositories
+				.iter()
+				.map(|repository| repository.latest().name.as_str()),
+		)
+	}
+}
+
 impl fmt::Display for VersionKey {
 	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
 		formatter.write_fmt(format_args!("{:016x}", self.0))
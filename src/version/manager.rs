@@ -4,9 +4,10 @@ use std::{
 	io::{self, Read},
 	path::{Path, PathBuf},
 	sync::RwLock,
+	time::{Duration, SystemTime},
 };
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use figment::value::magic::RelativePathBuf;
 use fs4::FileExt;
 use futures::future::{join_all, try_join_all};
@@ -15,22 +16,131 @@ use serde::{Deserialize, Serialize};
 use tokio::{select, sync::broadcast, time};
 use tokio_util::sync::CancellationToken;
 
+use crate::config::Validator;
+
 use super::{
+	file,
 	key::VersionKey,
-	patcher, thaliak,
-	version::{Repository, Version},
+	patcher,
+	provider::VersionProvider,
+	thaliak,
+	version::{self, Repository, Slot, Version},
 };
 
 const TAG_LATEST: &str = "latest";
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
+	#[serde(default)]
+	provider: ProviderKind,
 	thaliak: thaliak::Config,
+	/// Required if the file provider is selected.
+	#[serde(default)]
+	file: Option<file::Config>,
 	patch: patcher::Config,
 
 	interval: u64,
 	directory: RelativePathBuf,
-	repositories: Vec<String>,
+
+	/// Repository IDs, by the game repository they provide patches for. A list
+	/// of IDs is also accepted for compatibility, mapped onto slots in order
+	/// from ffxiv.
+	#[serde(deserialize_with = "deserialize_repositories")]
+	repositories: BTreeMap<Slot, String>,
+	/// Repositories to skip, even if configured above. Excluded repositories are
+	/// neither downloaded, nor included in version keys.
+	#[serde(default)]
+	exclude: Vec<Slot>,
+
+	#[serde(default)]
+	retry: RetryConfig,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.check("interval", self.interval > 0, "must be at least 1");
+		validator.writable_directory("directory", &self.directory.relative());
+		validator.check(
+			"repositories",
+			self.repositories
+				.keys()
+				.any(|slot| !self.exclude.contains(slot)),
+			"at least one repository is required",
+		);
+
+		let retry = &self.retry;
+		validator.check("retry.initial", retry.initial > 0, "must be at least 1");
+		validator.check(
+			"retry.max",
+			retry.max >= retry.initial,
+			format_args!("must be at least retry.initial ({})", retry.initial),
+		);
+
+		// Only the selected provider's configuration is relevant.
+		match self.provider {
+			ProviderKind::Thaliak => {
+				validator.scope("thaliak", |validator| self.thaliak.validate(validator))
+			}
+			ProviderKind::File => match &self.file {
+				Some(file) => validator.scope("file", |validator| file.validate(validator)),
+				None => validator.problem("file", "must be configured for the file provider"),
+			},
+		}
+
+		validator.scope("patch", |validator| self.patch.validate(validator));
+	}
+}
+
+fn deserialize_repositories<'de, D>(deserializer: D) -> Result<BTreeMap<Slot, String>, D::Error>
+where
+	D: serde::Deserializer<'de>,
+{
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Repositories {
+		Slots(BTreeMap<Slot, String>),
+		Legacy(Vec<String>),
+	}
+
+	match Repositories::deserialize(deserializer)? {
+		Repositories::Slots(repositories) => Ok(repositories),
+		Repositories::Legacy(repositories) => {
+			if repositories.len() > Slot::LEGACY_ORDER.len() {
+				return Err(serde::de::Error::custom(format!(
+					"a list of repositories may contain at most {} entries - configure boot by slot instead",
+					Slot::LEGACY_ORDER.len()
+				)));
+			}
+
+			Ok(Slot::LEGACY_ORDER.into_iter().zip(repositories).collect())
+		}
+	}
+}
+
+/// Source of patch lists for the configured repositories.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ProviderKind {
+	#[default]
+	Thaliak,
+	File,
+}
+
+#[derive(Debug, Deserialize)]
+struct RetryConfig {
+	/// Delay, in seconds, before the first retry of a failed update.
+	initial: u64,
+	/// Upper bound, in seconds, of the delay between retries.
+	max: u64,
+}
+
+impl Default for RetryConfig {
+	fn default() -> Self {
+		Self {
+			initial: 30,
+			max: 15 * 60,
+		}
+	}
 }
 
 /// Messgages that may be broadcast by the version system.
@@ -47,13 +157,63 @@ pub enum VersionMessage {
 	Changed(VersionKey),
 }
 
+/// Current state of the version update process.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateStatus {
+	/// Whether an update check is currently running. Versions that are already
+	/// known continue to be served while an update is in progress.
+	pub updating: bool,
+	/// Time of the most recent successful update check.
+	pub last_success: Option<SystemTime>,
+	/// Details of the current run of failed updates, if the last update failed.
+	pub failure: Option<UpdateFailure>,
+	/// Patch downloads currently in flight.
+	pub downloads: Vec<patcher::DownloadProgress>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateFailure {
+	/// Number of consecutive failed update attempts.
+	pub attempts: u32,
+	/// Description of the most recent error.
+	pub error: String,
+	/// Time of the most recent failed attempt.
+	pub failed_at: SystemTime,
+	/// Time at which the update will next be attempted.
+	pub next_attempt: SystemTime,
+}
+
+/// What an update would entail, were it run now.
+pub struct UpdatePlan {
+	/// Key of the version the update would produce.
+	pub key: VersionKey,
+	/// Whether the version is already known, in which case the update is a no-op.
+	pub known: bool,
+	/// The version the update would produce. Patches that need downloading are
+	/// not yet available at their paths.
+	pub version: Version,
+	/// Patches that would need to be downloaded.
+	pub downloads: Vec<PlannedDownload>,
+}
+
+#[derive(Debug)]
+pub struct PlannedDownload {
+	pub repository: String,
+	pub patch: String,
+	/// Size of the patch, in bytes.
+	pub size: u64,
+}
+
 pub struct Manager {
-	provider: thaliak::Provider,
+	provider: Box<dyn VersionProvider>,
 	patcher: patcher::Patcher,
 
 	update_interval: u64,
+	retry: RetryConfig,
 	directory: PathBuf,
-	repositories: Vec<String>,
+	repositories: Vec<(Slot, String)>,
+
+	status: RwLock<UpdateStatus>,
 
 	versions: RwLock<HashMap<VersionKey, Version>>,
 	names: RwLock<HashMap<String, VersionKey>>,
@@ -69,13 +229,29 @@ impl Manager {
 		// Realistically; we're only going to signal one version at a time - 10 should be more than enough for our use cases.
 		let (sender, _receiver) = broadcast::channel(10);
 
+		let provider: Box<dyn VersionProvider> = match config.provider {
+			ProviderKind::Thaliak => Box::new(thaliak::Provider::new(config.thaliak)),
+			ProviderKind::File => Box::new(file::Provider::new(
+				config
+					.file
+					.context("file provider selected without file configuration")?,
+			)),
+		};
+
 		Ok(Self {
-			provider: thaliak::Provider::new(config.thaliak),
+			provider,
 			patcher: patcher::Patcher::new(config.patch),
 
 			update_interval: config.interval,
+			retry: config.retry,
 			directory,
-			repositories: config.repositories,
+			repositories: config
+				.repositories
+				.into_iter()
+				.filter(|(slot, _)| !config.exclude.contains(slot))
+				.collect(),
+
+			status: Default::default(),
 
 			versions: Default::default(),
 			names: Default::default(),
@@ -90,6 +266,14 @@ impl Manager {
 		self.versions.read().expect("poisoned").len() > 0
 	}
 
+	/// Get the current status of the update process.
+	pub fn status(&self) -> UpdateStatus {
+		UpdateStatus {
+			downloads: self.patcher.downloads(),
+			..self.status.read().expect("poisoned").clone()
+		}
+	}
+
 	/// Subscribe to changes to the version list.
 	pub fn subscribe(&self) -> broadcast::Receiver<VersionMessage> {
 		self.channel.subscribe()
@@ -173,31 +357,83 @@ impl Manager {
 	}
 
 	async fn start_inner(&self) -> Result<()> {
-		// Hydrate from disk.
+		// Hydrate from disk. Hydrated versions are announced immediately, so they
+		// can be prepared and served while the first update check runs.
 		self.hydrate().await?;
 
-		// Set up an interval to check for updates.
-		let mut interval = time::interval(time::Duration::from_secs(self.update_interval));
-		interval.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+		let hydrated = self.versions.read().expect("poisoned").len();
+		if hydrated > 0 {
+			tracing::info!(hydrated, "serving hydrated versions during update check");
+		}
 
+		// Check for updates on an interval, retrying more eagerly if an update fails.
 		loop {
-			interval.tick().await;
+			self.status.write().expect("poisoned").updating = true;
+			let result = self.update().await;
+			self.status.write().expect("poisoned").updating = false;
 
-			if let Err(error) = self.update().await {
-				tracing::error!(?error, "update failed");
-			}
+			let delay = match result {
+				Ok(()) => self.record_success(),
+				Err(error) => {
+					tracing::error!(?error, "update failed");
+					self.record_failure(&error)
+				}
+			};
+
+			time::sleep(delay).await;
 		}
 	}
 
+	fn record_success(&self) -> Duration {
+		let mut status = self.status.write().expect("poisoned");
+		status.last_success = Some(SystemTime::now());
+		status.failure = None;
+
+		Duration::from_secs(self.update_interval)
+	}
+
+	fn record_failure(&self, error: &anyhow::Error) -> Duration {
+		let mut status = self.status.write().expect("poisoned");
+		let attempts = status
+			.failure
+			.as_ref()
+			.map_or(0, |failure| failure.attempts)
+			.saturating_add(1);
+
+		// Exponential backoff, capped to the configured maximum. Retries never wait
+		// longer than a regular update would.
+		let delay = self
+			.retry
+			.initial
+			.saturating_mul(1 << (attempts - 1).min(32))
+			.min(self.retry.max)
+			.min(self.update_interval);
+		let delay = Duration::from_secs(delay);
+
+		let now = SystemTime::now();
+		status.failure = Some(UpdateFailure {
+			attempts,
+			error: format!("{error:#}"),
+			failed_at: now,
+			next_attempt: now + delay,
+		});
+
+		tracing::info!(attempts, ?delay, "scheduled update retry");
+
+		delay
+	}
+
 	// TODO: There should only be one update pass running at a time - two would result in races.
 	async fn update(&self) -> Result<()> {
 		tracing::info!("checking for version updates");
 
-		// Get a fresh view of the repositories.
+		// Get a fresh view of the repositories. Patches that were fully downloaded
+		// by a previous, failed, attempt are already available locally, so a retry
+		// will only re-request the patches that are still missing.
 		let pending_repositories = self
 			.repositories
 			.iter()
-			.map(|repository| self.fetch_repository(repository));
+			.map(|(slot, repository)| self.fetch_repository(*slot, repository));
 		let repositories = try_join_all(pending_repositories).await?;
 
 		// Build a version struct and it's associated key and save it to the versions map.
@@ -230,7 +466,13 @@ impl Manager {
 			return Ok(());
 		}
 
-		tracing::info!(%key, "new or updated version");
+		// Log the patches the key was derived from, so it can be reproduced externally.
+		let patches = version
+			.repositories
+			.iter()
+			.map(|repository| format!("{}:{}", repository.name, repository.latest().name))
+			.collect::<Vec<_>>();
+		tracing::info!(%key, ?patches, "new or updated version");
 
 		// Update latest tag.
 		// TODO: This might need to be moved to manual-only for now? If there's any long-running ingestion tasks (i.e. search) hanging off versions, then setting latest _now_ would leave end-consumers pointing at an uningested tag.
@@ -253,20 +495,97 @@ impl Manager {
 		Ok(())
 	}
 
-	async fn fetch_repository(&self, repository: &str) -> Result<Repository> {
+	/// Resolve what an update would entail without executing it. Patch lists are
+	/// fetched, but no patches are downloaded.
+	pub async fn plan(&self) -> Result<UpdatePlan> {
+		let pending_repositories = self
+			.repositories
+			.iter()
+			.map(|(slot, repository)| self.plan_repository(*slot, repository));
+		let planned = try_join_all(pending_repositories).await?;
+
+		let mut repositories = Vec::with_capacity(planned.len());
+		let mut downloads = Vec::new();
+		for (repository, repository_downloads) in planned {
+			repositories.push(repository);
+			downloads.extend(repository_downloads);
+		}
+
+		let version = Version { repositories };
+		let key = VersionKey::from(&version);
+		let known = self.versions.read().expect("poisoned").contains_key(&key);
+
+		Ok(UpdatePlan {
+			key,
+			known,
+			version,
+			downloads,
+		})
+	}
+
+	async fn plan_repository(
+		&self,
+		slot: Slot,
+		repository: &str,
+	) -> Result<(Repository, Vec<PlannedDownload>)> {
+		let patch_list = self
+			.provider
+			.patch_list(repository.to_string())
+			.await
+			.with_context(|| format!("failed to fetch patch list for repository {repository}"))?;
+
+		let mut downloads = Vec::new();
+		let mut patches = Vec::with_capacity(patch_list.len());
+		for patch in patch_list {
+			if self.patcher.requires_download(repository, &patch)? {
+				downloads.push(PlannedDownload {
+					repository: repository.to_string(),
+					patch: patch.name.clone(),
+					size: patch.size,
+				});
+			}
+
+			patches.push(version::Patch {
+				path: self.patcher.patch_path(repository, &patch.name),
+				name: patch.name,
+			});
+		}
+
+		let patches =
+			NonEmpty::from_vec(patches).expect("non-empty list is guaranteed by provider");
+
+		Ok((
+			Repository {
+				name: repository.to_string(),
+				slot,
+				patches,
+			},
+			downloads,
+		))
+	}
+
+	async fn fetch_repository(&self, slot: Slot, repository: &str) -> Result<Repository> {
 		// a failure to fetch the patch list for a repo is pretty unrecoverable i think?
-		let patch_list = self.provider.patch_list(repository.to_string()).await?;
+		let patch_list = self
+			.provider
+			.patch_list(repository.to_string())
+			.await
+			.with_context(|| format!("failed to fetch patch list for repository {repository}"))?;
 
 		// todo: is a failure here meaningful? i imagine retries and so on should be done at the patcher
 		// note: would use nonempty::map but i need asyncnessnessness
 		let pending_patches = patch_list
 			.into_iter()
 			.map(|patch| self.patcher.to_local_patch(repository, patch));
-		let patches = NonEmpty::from_vec(try_join_all(pending_patches).await?)
-			.expect("non-empty list is guaranteed by provider");
+		let patches = try_join_all(pending_patches)
+			.await
+			.with_context(|| format!("failed to download patches for repository {repository}"))?;
+		let patches =
+			NonEmpty::from_vec(patches).expect("non-empty list is guaranteed by provider");
 
 		Ok(Repository {
 			name: repository.to_string(),
+			slot,
 			patches,
 		})
 	}
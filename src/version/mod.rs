@@ -1,11 +1,16 @@
+mod file;
 mod key;
 mod manager;
 mod patcher;
+mod provider;
 mod thaliak;
 mod version;
 
 pub use {
 	key::VersionKey,
-	manager::{Config, Manager, VersionMessage},
-	version::{Patch, Repository, Version},
+	manager::{
+		Config, Manager, PlannedDownload, UpdateFailure, UpdatePlan, UpdateStatus, VersionMessage,
+	},
+	patcher::DownloadProgress,
+	version::{Patch, Repository, Slot, Version},
 };
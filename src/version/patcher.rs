@@ -1,17 +1,25 @@
 use std::{
 	collections::HashMap,
 	fs,
-	io::{self, Write},
+	hash::Hasher,
+	io::{self, BufReader, Read, Write},
 	path::{Path, PathBuf},
 	sync::{Arc, Mutex},
+	time::Duration,
 };
 
 use anyhow::{Context, Result};
 use figment::value::magic::RelativePathBuf;
+use seahash::SeaHasher;
 use serde::Deserialize;
-use tokio::sync::{broadcast, Semaphore};
+use tokio::{
+	sync::{broadcast, Semaphore},
+	time::{self, Instant},
+};
+
+use crate::config::Validator;
 
-use super::{thaliak, version};
+use super::{provider, version};
 
 enum State {
 	Pending(broadcast::Receiver<version::Patch>),
@@ -21,40 +29,142 @@ enum State {
 #[derive(Debug, Deserialize)]
 pub struct Config {
 	directory: RelativePathBuf,
+	/// Maximum number of patches downloaded at once, across all repositories.
 	concurrency: usize,
+	/// Maximum number of downloaded patches verified and deduplicated at once.
+	/// Verification runs alongside further downloads.
+	verify_concurrency: usize,
 	user_agent: String,
+
+	/// Maximum combined bandwidth of all patch downloads, in bytes per second.
+	/// Unlimited if not set.
+	bandwidth: Option<u64>,
+
+	/// If set, downloaded patches with content identical to an existing patch
+	/// will be replaced with a hard link to that patch.
+	#[serde(default)]
+	deduplicate: bool,
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.writable_directory("directory", &self.directory.relative());
+		validator.check("concurrency", self.concurrency > 0, "must be at least 1");
+		validator.check(
+			"verify_concurrency",
+			self.verify_concurrency > 0,
+			"must be at least 1",
+		);
+		if let Some(bandwidth) = self.bandwidth {
+			validator.check("bandwidth", bandwidth > 0, "must be at least 1");
+		}
+		validator.check(
+			"user_agent",
+			!self.user_agent.is_empty(),
+			"must not be empty",
+		);
+	}
+}
+
+/// Progress of a patch download that is currently in flight.
+#[derive(Debug, Clone)]
+pub struct DownloadProgress {
+	pub repository: String,
+	pub patch: String,
+	/// Number of bytes downloaded so far.
+	pub bytes: u64,
+	/// Total size of the patch, in bytes.
+	pub total: u64,
+	/// Average download rate since the download started, in bytes per second.
+	pub rate: u64,
+	/// Estimated time remaining at the average rate, if any progress has been made.
+	pub eta: Option<Duration>,
+}
+
+struct Download {
+	repository: String,
+	patch: String,
+	bytes: u64,
+	total: u64,
+	started: Instant,
 }
 
+type Downloads = Arc<Mutex<HashMap<PathBuf, Download>>>;
+
 pub struct Patcher {
 	directory: PathBuf,
+	deduplicate: bool,
 	semaphore: Arc<Semaphore>,
+	verify_semaphore: Arc<Semaphore>,
+	throttle: Option<Arc<Throttle>>,
 	client: reqwest::Client,
 	patch_states: Arc<Mutex<HashMap<PathBuf, State>>>,
+	downloads: Downloads,
 }
 
 impl Patcher {
 	pub fn new(config: Config) -> Self {
 		Self {
 			directory: config.directory.relative(),
+			deduplicate: config.deduplicate,
 			semaphore: Arc::new(Semaphore::new(config.concurrency)),
+			verify_semaphore: Arc::new(Semaphore::new(config.verify_concurrency)),
+			throttle: config
+				.bandwidth
+				.map(|bandwidth| Arc::new(Throttle::new(bandwidth))),
 			client: reqwest::Client::builder()
 				.user_agent(config.user_agent)
 				.build()
 				.expect("failed to build reqwest client"),
 			patch_states: Default::default(),
+			downloads: Default::default(),
 		}
 	}
 
+	/// Get the progress of all patch downloads currently in flight.
+	pub fn downloads(&self) -> Vec<DownloadProgress> {
+		let downloads = self.downloads.lock().expect("poisoned");
+		let mut progress = downloads
+			.values()
+			.map(|download| {
+				let elapsed = download.started.elapsed().as_secs_f64();
+				let rate = match elapsed > 0.0 {
+					true => (download.bytes as f64 / elapsed) as u64,
+					false => 0,
+				};
+				let eta = (rate > 0).then(|| {
+					Duration::from_secs(download.total.saturating_sub(download.bytes) / rate)
+				});
+
+				DownloadProgress {
+					repository: download.repository.clone(),
+					patch: download.patch.clone(),
+					bytes: download.bytes,
+					total: download.total,
+					rate,
+					eta,
+				}
+			})
+			.collect::<Vec<_>>();
+		progress.sort_by(|a, b| (&a.repository, &a.patch).cmp(&(&b.repository, &b.patch)));
+		progress
+	}
+
 	pub fn patch_path(&self, repository: &str, patch: &str) -> PathBuf {
 		self.directory.join(repository).join(patch)
 	}
 
+	/// Check if a remote patch would need to be downloaded to be made available.
+	pub fn requires_download(&self, repository: &str, patch: &provider::Patch) -> Result<bool> {
+		self.should_fetch_patch(patch, &self.patch_path(repository, &patch.name))
+	}
+
 	pub async fn to_local_patch(
 		&self,
 		repository: &str,
-		thaliak_patch: thaliak::Patch,
+		remote_patch: provider::Patch,
 	) -> Result<version::Patch> {
-		let patch_path = self.patch_path(repository, &thaliak_patch.name);
+		let patch_path = self.patch_path(repository, &remote_patch.name);
 
 		// TODO: It seems wasteful to call this hundreds of times every update when it'll do something less than 10 times ever.
 		let repository_directory = patch_path
@@ -87,7 +197,7 @@ impl Patcher {
 				drop(patch_states);
 
 				let patch = self
-					.maybe_download_patch(thaliak_patch, patch_path.clone())
+					.maybe_download_patch(repository, remote_patch, patch_path.clone())
 					.await?;
 
 				// Download is complete - relock to insert, and broadcast the value to
@@ -108,23 +218,69 @@ impl Patcher {
 
 	async fn maybe_download_patch(
 		&self,
-		thaliak_patch: thaliak::Patch,
+		repository: &str,
+		remote_patch: provider::Patch,
 		patch_path: PathBuf,
 	) -> Result<version::Patch> {
-		let patch_name = thaliak_patch.name.clone();
+		let patch_name = remote_patch.name.clone();
 
 		// If we need to fetch the patch, wait for a permit then spin off a task to handle the download.
-		if self.should_fetch_patch(&thaliak_patch, &patch_path)? {
+		if self.should_fetch_patch(&remote_patch, &patch_path)? {
 			let permit = self.semaphore.clone().acquire_owned().await.unwrap();
 
 			let client = self.client.clone();
+			let throttle = self.throttle.clone();
+			let expected_size = remote_patch.size;
 			let patch_path = patch_path.clone();
+
+			// Track the download's progress for as long as the task is running.
+			let downloads = self.downloads.clone();
+			downloads.lock().expect("poisoned").insert(
+				patch_path.clone(),
+				Download {
+					repository: repository.to_string(),
+					patch: patch_name.clone(),
+					bytes: 0,
+					total: expected_size,
+					started: Instant::now(),
+				},
+			);
+
 			let handle = tokio::spawn(async move {
-				let result = fetch_patch(client, &thaliak_patch, &patch_path).await;
+				let result = fetch_patch(
+					client,
+					throttle.as_deref(),
+					&downloads,
+					&remote_patch,
+					&patch_path,
+				)
+				.await;
+				downloads.lock().expect("poisoned").remove(&patch_path);
 				drop(permit);
 				result
 			});
 			handle.await??;
+
+			// The download permit has been released, so verification of this patch
+			// overlaps with the download of the next.
+			let permit = self.verify_semaphore.clone().acquire_owned().await.unwrap();
+			let objects = self.deduplicate.then(|| self.directory.join(".objects"));
+			let patch_path = patch_path.clone();
+			let patch_name = patch_name.clone();
+			tokio::task::spawn_blocking(move || -> Result<()> {
+				verify_patch(&patch_path, expected_size)?;
+
+				// Deduplication is an optimisation - the patch itself is fine, so don't fail on this.
+				if let Some(objects) = objects {
+					if let Err(error) = deduplicate_patch(&objects, &patch_path) {
+						tracing::warn!(patch = %patch_name, ?error, "failed to deduplicate patch");
+					}
+				}
+
+				drop(permit);
+				Ok(())
+			})
+			.await??;
 		}
 
 		let patch = version::Patch {
@@ -135,7 +291,7 @@ impl Patcher {
 		Ok(patch)
 	}
 
-	fn should_fetch_patch(&self, patch: &thaliak::Patch, path: &Path) -> Result<bool> {
+	fn should_fetch_patch(&self, patch: &provider::Patch, path: &Path) -> Result<bool> {
 		// If the file doesn't exist, we'll need to download it.
 		let metadata = match path.metadata() {
 			Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(true),
@@ -164,11 +320,22 @@ impl Patcher {
 }
 
 #[tracing::instrument(level = "info", skip_all, fields(url = patch.url))]
-async fn fetch_patch(client: reqwest::Client, patch: &thaliak::Patch, path: &Path) -> Result<()> {
+async fn fetch_patch(
+	client: reqwest::Client,
+	throttle: Option<&Throttle>,
+	downloads: &Downloads,
+	patch: &provider::Patch,
+	path: &Path,
+) -> Result<()> {
 	tracing::info!("fetching patch");
 
+	// Download to a temporary file, and move it into place once complete. The
+	// patch path may be a hard link to a deduplicated object shared with other
+	// patches - writing through it would corrupt every copy.
+	let partial_path = path.with_extension("part");
+
 	// Create the target file before opening any connections.
-	let mut target_file = fs::File::create(path)?;
+	let mut target_file = fs::File::create(&partial_path)?;
 
 	// TODO: both of the below failure conditions may be worth retrying over? consider.
 
@@ -196,7 +363,17 @@ async fn fetch_patch(client: reqwest::Client, patch: &thaliak::Patch, path: &Pat
 		// This is blocking - is it worth trying to use async fs, or is the slowdown from that going to be Problematic:tm:?
 		target_file.write_all(&chunk)?;
 
-		position += u64::try_from(chunk.len()).unwrap();
+		let length = u64::try_from(chunk.len()).unwrap();
+		position += length;
+
+		if let Some(download) = downloads.lock().expect("poisoned").get_mut(path) {
+			download.bytes = position;
+		}
+
+		if let Some(throttle) = throttle {
+			throttle.consume(length).await;
+		}
+
 		let report_pos = f64::round((position as f64 / content_length as f64) * 20.0) * 5.0;
 		if report_pos > last_report {
 			tracing::debug!("{position}/{content_length} ({report_pos}%)");
@@ -204,5 +381,122 @@ async fn fetch_patch(client: reqwest::Client, patch: &thaliak::Patch, path: &Pat
 		}
 	}
 
+	target_file.flush()?;
+	drop(target_file);
+	fs::rename(&partial_path, path)?;
+
 	Ok(())
 }
+
+/// Shared limit on the combined bandwidth of patch downloads. Downloads are
+/// delayed after reading each chunk, which in turn slows the connection.
+struct Throttle {
+	/// Permitted rate, in bytes per second.
+	rate: u64,
+	/// Time at which all bandwidth consumed so far has been paid off.
+	next: Mutex<Instant>,
+}
+
+impl Throttle {
+	fn new(rate: u64) -> Self {
+		Self {
+			rate,
+			next: Mutex::new(Instant::now()),
+		}
+	}
+
+	/// Consume bandwidth for the given number of bytes, waiting until doing so
+	/// would not exceed the permitted rate.
+	async fn consume(&self, bytes: u64) {
+		let cost = Duration::from_secs_f64(bytes as f64 / self.rate as f64);
+		let deadline = {
+			let mut next = self.next.lock().expect("poisoned");
+			// Idle time doesn't accrue credit, to avoid bursts after a pause.
+			*next = (*next).max(Instant::now()) + cost;
+			*next
+		};
+
+		time::sleep_until(deadline).await;
+	}
+}
+
+/// Verify that a downloaded patch was written in full. Patches that fail
+/// verification are removed, so they are fetched again on the next update.
+fn verify_patch(path: &Path, expected_size: u64) -> Result<()> {
+	let size = path.metadata()?.len();
+	if size != expected_size {
+		fs::remove_file(path)?;
+		anyhow::bail!("patch {path:?} is incomplete: expected {expected_size} bytes, got {size}");
+	}
+
+	Ok(())
+}
+
+/// Link the patch at the given path into a content-addressed object store. If
+/// an identical object already exists, the patch is replaced with a hard link
+/// to it.
+fn deduplicate_patch(objects: &Path, path: &Path) -> Result<()> {
+	fs::create_dir_all(objects)
+		.with_context(|| format!("failed to create directory {objects:?}"))?;
+
+	let size = path.metadata()?.len();
+	let mut hasher = SeaHasher::new();
+	let mut reader = BufReader::new(fs::File::open(path)?);
+	let mut buffer = [0u8; 64 * 1024];
+	loop {
+		let count = reader.read(&mut buffer)?;
+		if count == 0 {
+			break;
+		}
+		hasher.write(&buffer[..count]);
+	}
+
+	let object_path = objects.join(format!("{size:x}-{:016x}", hasher.finish()));
+
+	// No existing object with this content - this patch becomes the object.
+	if !object_path.exists() {
+		fs::hard_link(path, &object_path)?;
+		return Ok(());
+	}
+
+	// The hash isn't cryptographic - make sure the content really does match
+	// before throwing anything away.
+	if !files_equal(path, &object_path)? {
+		tracing::warn!(
+			?path,
+			?object_path,
+			"hash collision, skipping deduplication"
+		);
+		return Ok(());
+	}
+
+	// Link alongside the patch and rename over it, so the patch path is never missing.
+	let link_path = path.with_extension("link");
+	let _ = fs::remove_file(&link_path);
+	fs::hard_link(&object_path, &link_path)?;
+	fs::rename(&link_path, path)?;
+
+	tracing::info!(?path, ?object_path, "deduplicated patch");
+
+	Ok(())
+}
+
+fn files_equal(a: &Path, b: &Path) -> Result<bool> {
+	let mut reader_a = BufReader::new(fs::File::open(a)?);
+	let mut reader_b = BufReader::new(fs::File::open(b)?);
+	let mut buffer_a = [0u8; 64 * 1024];
+	let mut buffer_b = [0u8; 64 * 1024];
+
+	loop {
+		let count = reader_a.read(&mut buffer_a)?;
+		if count == 0 {
+			// Ensure the other file is also exhausted.
+			return Ok(reader_b.read(&mut buffer_b)? == 0);
+		}
+
+		reader_b.read_exact(&mut buffer_b[..count])?;
+		if buffer_a[..count] != buffer_b[..count] {
+			return Ok(false);
+		}
+	}
+}
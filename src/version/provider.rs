@@ -0,0 +1,18 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use nonempty::NonEmpty;
+use serde::Deserialize;
+
+/// Source of patch lists for game repositories.
+pub trait VersionProvider: Send + Sync {
+	/// Fetch the full list of patches for a repository, ordered oldest-first.
+	fn patch_list(&self, repository: String) -> BoxFuture<'_, Result<NonEmpty<Patch>>>;
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Patch {
+	pub name: String,
+	pub url: String,
+	pub size: u64,
+	// TODO: hashes (needs fixes @ thaliak)
+}
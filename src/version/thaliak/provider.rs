@@ -1,17 +1,15 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
+use futures::future::BoxFuture;
 use graphql_client::{GraphQLQuery, Response};
 use nonempty::NonEmpty;
 use serde::Deserialize;
 
-#[derive(Debug)]
-pub struct Patch {
-	pub name: String,
-	pub url: String,
-	pub size: u64,
-	// TODO: hashes (needs fixes @ thaliak)
-}
+use crate::{
+	config::Validator,
+	version::provider::{Patch, VersionProvider},
+};
 
 // TODO: As-is this query can only fetch one repository per request. May be possible to programatically merge multiple into one query with a more struct-driven query system like cynic.
 #[derive(GraphQLQuery)]
@@ -29,6 +27,12 @@ pub struct Config {
 	overrides: Option<HashMap<String, HashMap<String, String>>>,
 }
 
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.url("endpoint", &self.endpoint);
+	}
+}
+
 pub struct Provider {
 	endpoint: String,
 	overrides: HashMap<String, HashMap<String, String>>,
@@ -45,7 +49,7 @@ impl Provider {
 	}
 
 	#[tracing::instrument(level = "debug", skip(self))]
-	pub async fn patch_list(&self, repository: String) -> Result<NonEmpty<Patch>> {
+	async fn fetch_patch_list(&self, repository: String) -> Result<NonEmpty<Patch>> {
 		let query = RepositoryQuery::build_query(repository_query::Variables {
 			repository: repository.clone(),
 		});
@@ -151,3 +155,9 @@ impl Provider {
 		})
 	}
 }
+
+impl VersionProvider for Provider {
+	fn patch_list(&self, repository: String) -> BoxFuture<'_, Result<NonEmpty<Patch>>> {
+		Box::pin(self.fetch_patch_list(repository))
+	}
+}
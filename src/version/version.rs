@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use nonempty::NonEmpty;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -20,6 +20,7 @@ impl Version {
 				.iter()
 				.map(|repository| PersistedRepository {
 					name: repository.name.clone(),
+					slot: Some(repository.slot),
 					patches: repository.patches.clone().map(|patch| patch.name),
 				})
 				.collect(),
@@ -39,17 +40,33 @@ impl Version {
 		let PersistedVersion(persisted_repositories) = PersistedVersion::deserialize(deserializer)
 			.map_err(|err| anyhow::anyhow!(err.to_string()))?;
 
+		// Versions persisted before slots were recorded always started at ffxiv,
+		// and never included boot.
+		let mut legacy_slots = Slot::LEGACY_ORDER.into_iter();
 		let repositories = persisted_repositories
 			.into_iter()
-			.map(|persisted_repository| Repository {
-				patches: persisted_repository.patches.map(|patch_name| Patch {
-					// TODO: I should probably fail out if this doesn't point to a file on disk.
-					path: get_path(&persisted_repository.name, &patch_name),
-					name: patch_name,
-				}),
-				name: persisted_repository.name,
+			.map(|persisted_repository| {
+				let slot = match persisted_repository.slot {
+					Some(slot) => slot,
+					None => legacy_slots.next().with_context(|| {
+						format!(
+							"could not infer slot of repository {}",
+							persisted_repository.name
+						)
+					})?,
+				};
+
+				Ok(Repository {
+					slot,
+					patches: persisted_repository.patches.map(|patch_name| Patch {
+						// TODO: I should probably fail out if this doesn't point to a file on disk.
+						path: get_path(&persisted_repository.name, &patch_name),
+						name: patch_name,
+					}),
+					name: persisted_repository.name,
+				})
 			})
-			.collect();
+			.collect::<Result<Vec<_>>>()?;
 
 		Ok(Version { repositories })
 	}
@@ -58,15 +75,71 @@ impl Version {
 #[derive(Clone, PartialEq)]
 pub struct Repository {
 	pub name: String,
+	pub slot: Slot,
 	pub patches: NonEmpty<Patch>,
 }
 
 #[derive(Serialize, Deserialize)]
 struct PersistedRepository {
 	name: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	slot: Option<Slot>,
 	patches: NonEmpty<String>,
 }
 
+/// Game repositories that patches may be provided for. Ordered as they
+/// contribute to version keys.
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Serialize,
+	Deserialize,
+	strum::Display,
+	strum::EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Slot {
+	Boot,
+	Ffxiv,
+	Ex1,
+	Ex2,
+	Ex3,
+	Ex4,
+	Ex5,
+}
+
+impl Slot {
+	pub(super) const LEGACY_ORDER: [Self; 6] = [
+		Self::Ffxiv,
+		Self::Ex1,
+		Self::Ex2,
+		Self::Ex3,
+		Self::Ex4,
+		Self::Ex5,
+	];
+
+	/// Index of the repository within sqpack data. Boot patches update the
+	/// launcher rather than game data, and have no index.
+	pub fn sqpack_index(self) -> Option<u8> {
+		match self {
+			Self::Boot => None,
+			Self::Ffxiv => Some(0),
+			Self::Ex1 => Some(1),
+			Self::Ex2 => Some(2),
+			Self::Ex3 => Some(3),
+			Self::Ex4 => Some(4),
+			Self::Ex5 => Some(5),
+		}
+	}
+}
+
 impl Repository {
 	/// Get the most recent patch in the repository.
 	pub fn latest(&self) -> &Patch {
@@ -0,0 +1,402 @@
+use std::{
+	collections::HashMap,
+	sync::{Arc, RwLock},
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use ironworks::excel;
+use serde::{Deserialize, Serialize};
+use tokio::{select, time};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+	config::Validator,
+	read,
+	schema::{self, CanonicalSpecifier},
+	stack::{SheetRequest, SheetRow, Stack},
+	version::VersionKey,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+	/// Default interval between refreshes of each view, in seconds.
+	#[serde(default = "default_refresh")]
+	refresh: u64,
+
+	/// Views to materialize, keyed by the name they are served under.
+	#[serde(default)]
+	views: HashMap<String, ViewConfig>,
+}
+
+fn default_refresh() -> u64 {
+	60 * 60
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			refresh: default_refresh(),
+			views: HashMap::new(),
+		}
+	}
+}
+
+impl Config {
+	pub fn validate(&self, validator: &mut Validator) {
+		validator.check("refresh", self.refresh > 0, "must be greater than 0");
+
+		validator.scope("views", |validator| {
+			for (name, view) in &self.views {
+				validator.check(
+					name,
+					!name.is_empty()
+						&& name
+							.chars()
+							.all(|char| char.is_ascii_alphanumeric() || matches!(char, '-' | '_')),
+					"view names may only contain ASCII letters, digits, '-', and '_'",
+				);
+				validator.scope(name, |validator| view.validate(validator));
+			}
+		});
+	}
+}
+
+/// Definition of a single materialized view.
+#[derive(Debug, Deserialize)]
+struct ViewConfig {
+	/// Sheet the view is read from.
+	sheet: String,
+
+	/// Top-level fields to include in the view. All fields are included if empty.
+	#[serde(default)]
+	fields: Vec<String>,
+
+	/// Fields which must hold a non-default value for a row to be included in
+	/// the view.
+	#[serde(default)]
+	require: Vec<String>,
+
+	/// Version to read from. Defaults to the latest version, following it as
+	/// new versions are prepared.
+	version: Option<String>,
+
+	/// Schema to read with. Defaults to the configured default schema.
+	schema: Option<schema::Specifier>,
+
+	/// Language to read. Defaults to the configured default language.
+	language: Option<read::LanguageString>,
+
+	/// Depth references are followed to. Defaults to the depth used for sheet
+	/// requests.
+	depth: Option<u8>,
+
+	/// Interval between refreshes of this view, in seconds. Defaults to the
+	/// top-level refresh interval.
+	refresh: Option<u64>,
+}
+
+impl ViewConfig {
+	fn validate(&self, validator: &mut Validator) {
+		validator.check("sheet", !self.sheet.is_empty(), "must not be empty");
+
+		if let Some(refresh) = self.refresh {
+			validator.check("refresh", refresh > 0, "must be greater than 0");
+		}
+
+		if !self.fields.is_empty() {
+			for field in &self.require {
+				validator.check(
+					"require",
+					self.fields.contains(field),
+					format!("required field {field:?} is not listed in fields"),
+				);
+			}
+		}
+	}
+}
+
+/// Pre-computed result set for a view, as of its last refresh.
+#[derive(Debug)]
+pub struct Materialized {
+	/// Key of the version the view was read from.
+	pub version: VersionKey,
+	/// Schema the view was read with.
+	pub schema: CanonicalSpecifier,
+	pub language: excel::Language,
+	/// Time the view was last refreshed, in seconds since the unix epoch.
+	pub refreshed: u64,
+	pub rows: Vec<SheetRow>,
+}
+
+/// Overview of a configured view.
+#[derive(Debug, Serialize)]
+pub struct ViewSummary {
+	pub name: String,
+	pub sheet: String,
+	/// Key of the version the view was last read from, if it has been refreshed.
+	pub version: Option<VersionKey>,
+	/// Time the view was last refreshed, in seconds since the unix epoch.
+	pub refreshed: Option<u64>,
+	/// Number of rows in the view, if it has been refreshed.
+	pub rows: Option<usize>,
+}
+
+/// Maintains named, periodically refreshed result sets derived from sheets, so
+/// that popular queries can be served from memory rather than read per request.
+pub struct Views {
+	refresh: Duration,
+	definitions: HashMap<String, ViewConfig>,
+
+	stack: Stack,
+
+	views: RwLock<HashMap<String, Arc<Materialized>>>,
+}
+
+impl Views {
+	pub fn new(config: Config, stack: Stack) -> Self {
+		Self {
+			refresh: Duration::from_secs(config.refresh),
+			definitions: config.views,
+			stack,
+			views: Default::default(),
+		}
+	}
+
+	/// Summaries of all configured views, in name order.
+	pub fn list(&self) -> Vec<ViewSummary> {
+		let views = self.views.read().expect("poisoned");
+		let mut summaries = self
+			.definitions
+			.iter()
+			.map(|(name, definition)| {
+				let view = views.get(name);
+				ViewSummary {
+					name: name.clone(),
+					sheet: definition.sheet.clone(),
+					version: view.map(|view| view.version),
+					refreshed: view.map(|view| view.refreshed),
+					rows: view.map(|view| view.rows.len()),
+				}
+			})
+			.collect::<Vec<_>>();
+		summaries.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+		summaries
+	}
+
+	/// Name of the sheet a view is read from, if the view is configured.
+	pub fn sheet(&self, name: &str) -> Option<&str> {
+		self.definitions
+			.get(name)
+			.map(|definition| definition.sheet.as_str())
+	}
+
+	/// Get the current contents of a view, if it has been refreshed.
+	pub fn get(&self, name: &str) -> Option<Arc<Materialized>> {
+		self.views.read().expect("poisoned").get(name).cloned()
+	}
+
+	pub async fn start(&self, cancel: CancellationToken) -> Result<()> {
+		if self.definitions.is_empty() {
+			return Ok(());
+		}
+
+		let mut receiver = self.stack.data.subscribe();
+
+		// Views can't be read until the schema is available.
+		while !self.stack.schema.ready() {
+			select! {
+				_ = time::sleep(Duration::from_secs(1)) => {},
+				_ = cancel.cancelled() => return Ok(()),
+			}
+		}
+
+		let mut attempts = HashMap::<&str, Instant>::new();
+		let mut changed = Instant::now();
+
+		loop {
+			for (name, definition) in &self.definitions {
+				if !self.due(name, definition, attempts.get(name.as_str()), changed) {
+					continue;
+				}
+
+				attempts.insert(name, Instant::now());
+				select! {
+					result = self.refresh_view(name, definition) => {
+						if let Err(error) = result {
+							tracing::warn!(view = %name, ?error, "failed to refresh view");
+						}
+					}
+					_ = cancel.cancelled() => return Ok(()),
+				}
+			}
+
+			// Sleep until the next view is due, waking early if the available
+			// versions change.
+			let next = self
+				.definitions
+				.iter()
+				.filter_map(|(name, definition)| {
+					let attempt = attempts.get(name.as_str())?;
+					Some(self.interval(definition).saturating_sub(attempt.elapsed()))
+				})
+				.min()
+				.unwrap_or(self.refresh);
+
+			select! {
+				_ = time::sleep(next) => {},
+				result = receiver.changed() => {
+					if result.is_err() {
+						break;
+					}
+					changed = Instant::now();
+				}
+				_ = cancel.cancelled() => break,
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interval(&self, definition: &ViewConfig) -> Duration {
+		definition
+			.refresh
+			.map(Duration::from_secs)
+			.unwrap_or(self.refresh)
+	}
+
+	/// Whether a view should be refreshed. Views are refreshed on their interval,
+	/// and early if the version they target has changed since they were last
+	/// attempted.
+	fn due(
+		&self,
+		name: &str,
+		definition: &ViewConfig,
+		attempt: Option<&Instant>,
+		changed: Instant,
+	) -> bool {
+		let Some(attempt) = attempt else {
+			return true;
+		};
+
+		if attempt.elapsed() >= self.interval(definition) {
+			return true;
+		}
+
+		let current = self.stack.version.resolve(definition.version.as_deref());
+		let stale = self
+			.get(name)
+			.map_or(true, |view| Some(view.version) != current);
+
+		stale && *attempt < changed
+	}
+
+	async fn refresh_view(&self, name: &str, definition: &ViewConfig) -> Result<()> {
+		tracing::info!(view = %name, sheet = %definition.sheet, "refreshing view");
+
+		let language = definition
+			.language
+			.map(excel::Language::from)
+			.unwrap_or_else(|| self.stack.read.default_language());
+
+		let mut request = SheetRequest::new(&definition.sheet);
+		request.version = definition.version.clone();
+		request.schema = definition.schema.clone();
+		request.language = Some(language);
+		request.filter = field_filter(&definition.fields, language);
+		request.limit = usize::MAX;
+		if let Some(depth) = definition.depth {
+			request.depth = depth;
+		}
+
+		let response = self.stack.read_sheet(request).await?;
+
+		let rows = response
+			.rows
+			.into_iter()
+			.filter(|row| {
+				definition
+					.require
+					.iter()
+					.all(|field| field_set(&row.value, field))
+			})
+			.collect::<Vec<_>>();
+
+		tracing::info!(view = %name, version = %response.version, rows = rows.len(), "view refreshed");
+
+		let view = Materialized {
+			version: response.version,
+			schema: response.schema,
+			language,
+			refreshed: SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map(|duration| duration.as_secs())
+				.unwrap_or(0),
+			rows,
+		};
+
+		self.views
+			.write()
+			.expect("poisoned")
+			.insert(name.to_string(), Arc::new(view));
+
+		Ok(())
+	}
+}
+
+/// Build a filter selecting the given top-level fields in full.
+fn field_filter(fields: &[String], language: excel::Language) -> read::Filter {
+	if fields.is_empty() {
+		return read::Filter::All;
+	}
+
+	read::Filter::Struct(
+		fields
+			.iter()
+			.map(|field| {
+				let languages = [(read::Language(language), read::Filter::All)]
+					.into_iter()
+					.collect();
+				(field.clone(), languages)
+			})
+			.collect(),
+	)
+}
+
+/// Whether the named top-level field of a row holds a non-default value.
+fn field_set(value: &read::Value, field: &str) -> bool {
+	let read::Value::Struct(fields) = value else {
+		return false;
+	};
+
+	fields
+		.iter()
+		.filter(|(key, _)| key.name == field)
+		.any(|(_, value)| value_set(value))
+}
+
+fn value_set(value: &read::Value) -> bool {
+	use excel::Field as F;
+	use read::{Reference as R, Value as V};
+
+	match value {
+		V::Array(values) => values.iter().any(value_set),
+		V::Icon(id) => *id != 0,
+		V::Reference(R::Scalar(value)) => *value > 0,
+		V::Reference(R::Shallow { value, .. } | R::Populated { value, .. }) => *value > 0,
+		V::Struct(fields) => fields.values().any(value_set),
+		V::Scalar(field) => match field {
+			F::String(value) => !value.to_string().is_empty(),
+			F::Bool(value) => *value,
+			F::I8(value) => *value != 0,
+			F::I16(value) => *value != 0,
+			F::I32(value) => *value != 0,
+			F::I64(value) => *value != 0,
+			F::U8(value) => *value != 0,
+			F::U16(value) => *value != 0,
+			F::U32(value) => *value != 0,
+			F::U64(value) => *value != 0,
+			F::F32(value) => *value != 0.,
+		},
+	}
+}